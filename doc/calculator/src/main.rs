@@ -190,8 +190,10 @@ pub mod tok9;
 #[test]
 fn calculator9() {
     let input = "22 * pi + 66";
-    let lexer = crate::tok9::Lexer::new(input);
-    let expr = calculator9::ExprParser::new().parse(input, lexer).unwrap();
+    let lexer = crate::tok9::lexer();
+    let expr = calculator9::ExprParser::new()
+        .parse(input, lexer.tokens(input))
+        .unwrap();
     assert_eq!(&format!("{:?}", expr), "((\"22\" * \"pi\") + \"66\")");
 }
 
@@ -0,0 +1,7 @@
+fn main() {
+    lalrpop::Configuration::new()
+        .emit_comments(true)
+        .force_build(true)
+        .process_current_dir()
+        .unwrap();
+}
@@ -0,0 +1,73 @@
+//! Grammars with `#[token_source = "proc_macro2"]`, parsing the tokens
+//! of `lalrpop_procmacro::Tokens` from end to end. Like
+//! `lalrpop-procmacro`, this crate is not part of the workspace.
+
+use lalrpop_util::lalrpop_mod;
+
+lalrpop_mod!(pub function);
+
+#[derive(Debug, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub params: Vec<(String, String)>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::{function::FunctionParser, Function};
+    use lalrpop_procmacro::Tokens;
+    use lalrpop_util::ParseError;
+    use proc_macro2::TokenStream;
+
+    fn tokens(input: &str) -> Tokens {
+        Tokens::new(input.parse::<TokenStream>().unwrap())
+    }
+
+    #[test]
+    fn function() {
+        let function = FunctionParser::new()
+            .parse(tokens("fn add(x: i32, y: i32) {}"))
+            .unwrap();
+        assert_eq!(
+            function,
+            Function {
+                name: "add".to_string(),
+                params: vec![
+                    ("x".to_string(), "i32".to_string()),
+                    ("y".to_string(), "i32".to_string()),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn keyword_is_not_an_ident() {
+        // `fn` is the terminal `"fn"`, so it cannot name the function;
+        // the location is the position of the token in the stream
+        match FunctionParser::new().parse(tokens("fn fn() {}")) {
+            Err(ParseError::UnrecognizedToken {
+                token: (1, token, 2),
+                expected,
+            }) => {
+                assert_eq!(token.to_string(), "fn");
+                assert_eq!(expected, [r#""Ident""#]);
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn missing_comma() {
+        // the tokens of a group come between the tokens of its delimiters
+        match FunctionParser::new().parse(tokens("fn f(x: i32 y: i32) {}")) {
+            Err(ParseError::UnrecognizedToken {
+                token: (6, token, 7),
+                expected,
+            }) => {
+                assert_eq!(token.to_string(), "y");
+                assert_eq!(expected, [r#"")""#, r#"",""#]);
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+}
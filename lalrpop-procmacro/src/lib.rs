@@ -0,0 +1,238 @@
+//! Tokens of a `proc_macro2::TokenStream`, for parsing the input of a
+//! procedural macro with a parser generated by LALRPOP.
+//!
+//! A grammar annotated with `#[token_source = "proc_macro2"]` takes the
+//! [`Token`]s of [`Tokens`], which walks a token stream and breaks its
+//! groups up into their delimiters and their contents. The terminals of
+//! the grammar are then quoted strings that stand for these tokens:
+//!
+//! - `"Ident"` for any identifier, unless the grammar has a terminal
+//!   spelled like it: with a terminal `"fn"`, the identifier `fn` is
+//!   that terminal instead;
+//! - `"Literal"` for any literal;
+//! - a single character like `"+"` or `","` for that punctuation;
+//! - `"("`, `")"`, `"["`, `"]"`, `"{"` and `"}"` for the delimiters of a
+//!   group.
+//!
+//! Groups without delimiters, which come from the fragments of
+//! `macro_rules!` macros, are walked into without a token of their own.
+//!
+//! ```ignore
+//! let tokens = lalrpop_procmacro::Tokens::new(input);
+//! let item = grammar::ItemParser::new().parse(tokens)?;
+//! ```
+//!
+//! A token stream knows no offsets, so the locations the parser reports
+//! are the positions of the tokens in the stream. [`Token::source_span`]
+//! gives the `Span` to report an error at instead.
+
+use std::fmt;
+
+use proc_macro2::{token_stream, Delimiter, Ident, Literal, Punct, Span, TokenStream, TokenTree};
+
+/// The terminal that stands for any identifier.
+pub const IDENT: &str = "Ident";
+
+/// The terminal that stands for any literal.
+pub const LITERAL: &str = "Literal";
+
+/// A token of a token stream, as handed to the parser by [`Tokens`].
+#[derive(Clone, Debug)]
+pub struct Token {
+    pub kind: TokenKind,
+    /// The position of the token in the stream, counting from 0.
+    pub index: usize,
+}
+
+#[derive(Clone, Debug)]
+pub enum TokenKind {
+    Ident(Ident),
+    Punct(Punct),
+    Literal(Literal),
+    /// The opening delimiter of a group, with its span.
+    Open(Delimiter, Span),
+    /// The closing delimiter of a group, with its span.
+    Close(Delimiter, Span),
+}
+
+impl Token {
+    /// Whether this token stands for `terminal`, a terminal of the
+    /// grammar without its quotes. The generated parser tries `"Ident"`
+    /// last, after the terminals spelled like identifiers.
+    pub fn matches(&self, terminal: &str) -> bool {
+        match &self.kind {
+            TokenKind::Ident(ident) => {
+                terminal == IDENT || (terminal != LITERAL && *ident == terminal)
+            }
+            TokenKind::Punct(punct) => {
+                let mut chars = terminal.chars();
+                chars.next() == Some(punct.as_char()) && chars.next().is_none()
+            }
+            TokenKind::Literal(_) => terminal == LITERAL,
+            TokenKind::Open(delimiter, _) => {
+                delimiters(*delimiter).is_some_and(|(open, _)| open == terminal)
+            }
+            TokenKind::Close(delimiter, _) => {
+                delimiters(*delimiter).is_some_and(|(_, close)| close == terminal)
+            }
+        }
+    }
+
+    /// The span of the token in the macro input.
+    pub fn source_span(&self) -> Span {
+        match &self.kind {
+            TokenKind::Ident(ident) => ident.span(),
+            TokenKind::Punct(punct) => punct.span(),
+            TokenKind::Literal(literal) => literal.span(),
+            TokenKind::Open(_, span) | TokenKind::Close(_, span) => *span,
+        }
+    }
+}
+
+/// The token as it is written, for the messages of `ParseError`.
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            TokenKind::Ident(ident) => write!(f, "{}", ident),
+            TokenKind::Punct(punct) => write!(f, "{}", punct),
+            TokenKind::Literal(literal) => write!(f, "{}", literal),
+            TokenKind::Open(delimiter, _) => {
+                write!(f, "{}", delimiters(*delimiter).map_or("", |(open, _)| open))
+            }
+            TokenKind::Close(delimiter, _) => {
+                write!(
+                    f,
+                    "{}",
+                    delimiters(*delimiter).map_or("", |(_, close)| close)
+                )
+            }
+        }
+    }
+}
+
+impl lalrpop_util::token::Token for Token {
+    /// The class of the token, like `"Ident"`, or its delimiter. Use
+    /// [`Token::matches`] to compare it with a terminal.
+    fn terminal_name(&self) -> &str {
+        match &self.kind {
+            TokenKind::Ident(_) => IDENT,
+            TokenKind::Punct(_) => "Punct",
+            TokenKind::Literal(_) => LITERAL,
+            TokenKind::Open(delimiter, _) => delimiters(*delimiter).map_or("", |(open, _)| open),
+            TokenKind::Close(delimiter, _) => delimiters(*delimiter).map_or("", |(_, close)| close),
+        }
+    }
+
+    fn span(&self) -> (usize, usize) {
+        (self.index, self.index + 1)
+    }
+}
+
+/// The terminals of the opening and the closing delimiter, for the
+/// groups that have delimiters.
+fn delimiters(delimiter: Delimiter) -> Option<(&'static str, &'static str)> {
+    match delimiter {
+        Delimiter::Parenthesis => Some(("(", ")")),
+        Delimiter::Brace => Some(("{", "}")),
+        Delimiter::Bracket => Some(("[", "]")),
+        Delimiter::None => None,
+    }
+}
+
+/// The tokens of a token stream, in order, with the contents of each
+/// group between the tokens of its delimiters.
+pub struct Tokens {
+    /// The rest of each group being walked, innermost last, with its
+    /// closing delimiter, which is produced once the group is done.
+    groups: Vec<(token_stream::IntoIter, Option<(Delimiter, Span)>)>,
+    index: usize,
+}
+
+impl Tokens {
+    pub fn new(stream: TokenStream) -> Self {
+        Tokens {
+            groups: vec![(stream.into_iter(), None)],
+            index: 0,
+        }
+    }
+}
+
+impl Iterator for Tokens {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        let kind = loop {
+            let (trees, close) = self.groups.last_mut()?;
+            match trees.next() {
+                Some(TokenTree::Group(group)) => {
+                    let delimiter = group.delimiter();
+                    let trees = group.stream().into_iter();
+                    if delimiter == Delimiter::None {
+                        self.groups.push((trees, None));
+                    } else {
+                        self.groups
+                            .push((trees, Some((delimiter, group.span_close()))));
+                        break TokenKind::Open(delimiter, group.span_open());
+                    }
+                }
+                Some(TokenTree::Ident(ident)) => break TokenKind::Ident(ident),
+                Some(TokenTree::Punct(punct)) => break TokenKind::Punct(punct),
+                Some(TokenTree::Literal(literal)) => break TokenKind::Literal(literal),
+                None => {
+                    let close = close.take();
+                    self.groups.pop();
+                    if let Some((delimiter, span)) = close {
+                        break TokenKind::Close(delimiter, span);
+                    }
+                }
+            }
+        };
+        let index = self.index;
+        self.index += 1;
+        Some(Token { kind, index })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Token, Tokens};
+    use proc_macro2::TokenStream;
+
+    fn tokens(input: &str) -> Vec<Token> {
+        Tokens::new(input.parse::<TokenStream>().unwrap()).collect()
+    }
+
+    #[test]
+    fn groups_are_flattened() {
+        let tokens = tokens("f(x, [1]) {}");
+        let terminals = [
+            "Ident", "(", "Ident", ",", "[", "Literal", "]", ")", "{", "}",
+        ];
+        assert_eq!(tokens.len(), terminals.len());
+        for (token, terminal) in tokens.iter().zip(terminals) {
+            assert!(token.matches(terminal), "{:?} is not {}", token, terminal);
+        }
+        let indices: Vec<_> = tokens.iter().map(|token| token.index).collect();
+        assert_eq!(indices, (0..terminals.len()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn keywords() {
+        let tokens = tokens("fn Literal");
+        assert!(tokens[0].matches("fn"));
+        assert!(tokens[0].matches("Ident"));
+        assert!(!tokens[0].matches("let"));
+        // an identifier is never a literal, whatever it is called
+        assert!(!tokens[1].matches("Literal"));
+        assert!(tokens[1].matches("Ident"));
+    }
+
+    #[test]
+    fn punctuation() {
+        let tokens = tokens("=>");
+        assert_eq!(tokens.len(), 2);
+        assert!(tokens[0].matches("="));
+        assert!(!tokens[0].matches("=>"));
+        assert!(tokens[1].matches(">"));
+    }
+}
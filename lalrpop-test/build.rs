@@ -4,6 +4,19 @@ fn main() {
         .force_build(true)
         .unit_test()
         .log_debug()
-        .process_current_dir()
+        .use_cargo_dir_conventions()
+        .process()
+        .unwrap();
+
+    // Only the grammars that test table serialization get the extra
+    // `tables()` API, so they live outside of `src`
+    lalrpop::Configuration::new()
+        .emit_comments(true)
+        .serialize_tables(true)
+        .force_build(true)
+        .unit_test()
+        .log_debug()
+        .set_in_dir("serialize_tables")
+        .process()
         .unwrap();
 }
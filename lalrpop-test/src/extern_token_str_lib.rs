@@ -0,0 +1,84 @@
+use crate::extern_token_str::SumParser;
+use lalrpop_util::ParseError;
+
+/// A token of `extern token` type: its `AsRef<str>` names the terminal
+/// it stands for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Token<'input> {
+    pub kind: &'static str,
+    pub text: &'input str,
+}
+
+impl AsRef<str> for Token<'_> {
+    fn as_ref(&self) -> &str {
+        self.kind
+    }
+}
+
+fn tokenize(input: &str) -> Vec<(usize, Token<'_>, usize)> {
+    let mut tokens = vec![];
+    let mut offset = 0;
+    for text in input.split(' ') {
+        let kind = match text {
+            "+" => "+",
+            "-" => "-",
+            "(" => "(",
+            ")" => ")",
+            _ => "Num",
+        };
+        tokens.push((offset, Token { kind, text }, offset + text.len()));
+        offset += text.len() + 1;
+    }
+    tokens
+}
+
+#[test]
+fn extern_token_str_partial() {
+    let parser = SumParser::new();
+    let (result, stopped_at, rest) = parser.parse_partial(tokenize("1 + 2 ( 3")).unwrap();
+    assert_eq!(result, 3);
+    assert_eq!(
+        stopped_at,
+        Some((
+            6,
+            Token {
+                kind: "(",
+                text: "("
+            },
+            7
+        ))
+    );
+    assert_eq!(
+        rest.collect::<Vec<_>>(),
+        [(
+            8,
+            Token {
+                kind: "Num",
+                text: "3"
+            },
+            9
+        )]
+    );
+}
+
+#[test]
+fn extern_token_str() {
+    let parser = SumParser::new();
+    assert_eq!(parser.parse(tokenize("1 + ( 20 + 300 )")), Ok(321));
+
+    let expected = vec![r#""+""#.to_string()];
+    assert_eq!(
+        parser.parse(tokenize("1 - 2")),
+        Err(ParseError::UnrecognizedToken {
+            token: (
+                2,
+                Token {
+                    kind: "-",
+                    text: "-"
+                },
+                3
+            ),
+            expected,
+        })
+    );
+}
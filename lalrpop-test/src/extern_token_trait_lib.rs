@@ -0,0 +1,77 @@
+use crate::extern_token_trait::SumParser;
+use lalrpop_util::ParseError;
+
+/// A token that implements `lalrpop_util::token::Token`, so the lexer
+/// can yield it without its locations.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Token<'input> {
+    pub kind: &'static str,
+    pub text: &'input str,
+    pub start: usize,
+}
+
+impl lalrpop_util::token::Token for Token<'_> {
+    fn terminal_name(&self) -> &str {
+        self.kind
+    }
+
+    fn span(&self) -> (usize, usize) {
+        (self.start, self.start + self.text.len())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LexicalError {
+    pub location: usize,
+}
+
+fn tokenize(input: &str) -> Vec<Result<Token<'_>, LexicalError>> {
+    let mut tokens = vec![];
+    let mut start = 0;
+    for text in input.split(' ') {
+        let kind = match text {
+            "+" => "+",
+            "-" => "-",
+            "(" => "(",
+            ")" => ")",
+            _ if text.chars().all(|c| c.is_ascii_digit()) => "Num",
+            _ => {
+                tokens.push(Err(LexicalError { location: start }));
+                break;
+            }
+        };
+        tokens.push(Ok(Token { kind, text, start }));
+        start += text.len() + 1;
+    }
+    tokens
+}
+
+#[test]
+fn extern_token_trait() {
+    let parser = SumParser::new();
+    assert_eq!(parser.parse(tokenize("1 + ( 20 + 300 )")), Ok(321));
+
+    let expected = vec![r#""+""#.to_string()];
+    assert_eq!(
+        parser.parse(tokenize("1 - 2")),
+        Err(ParseError::UnrecognizedToken {
+            token: (
+                2,
+                Token {
+                    kind: "-",
+                    text: "-",
+                    start: 2
+                },
+                3
+            ),
+            expected,
+        })
+    );
+
+    assert_eq!(
+        parser.parse(tokenize("1 + x")),
+        Err(ParseError::User {
+            error: LexicalError { location: 4 }
+        })
+    );
+}
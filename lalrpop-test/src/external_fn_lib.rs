@@ -0,0 +1,28 @@
+use crate::external_fn::ExprParser;
+use lalrpop_util::ParseError;
+
+pub fn sub(l: i32, r: i32) -> i32 {
+    l - r
+}
+
+pub fn div<L, T>(l: i32, _: &str, r: i32) -> Result<i32, ParseError<L, T, &'static str>> {
+    l.checked_div(r).ok_or(ParseError::User {
+        error: "division by zero",
+    })
+}
+
+pub fn num(digits: &str) -> i32 {
+    digits.parse().unwrap()
+}
+
+#[test]
+fn external_fn_actions() {
+    let parser = ExprParser::new();
+    assert_eq!(parser.parse("22 - 8 / 2 - 3").unwrap(), 15);
+    assert_eq!(
+        parser.parse("1 / 0"),
+        Err(ParseError::User {
+            error: "division by zero"
+        })
+    );
+}
@@ -0,0 +1,52 @@
+use crate::folder::{ExprParser, Folder, LetsParser};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Expr {
+    Num(i32),
+    Var(String),
+    Add(Box<Expr>, Box<Expr>),
+}
+
+/// Adds up constants and upper-cases the names being defined. `Expr` is
+/// our own type, so we have to rebuild it ourselves.
+struct ConstFold;
+
+impl Folder<'_> for ConstFold {
+    fn fold_term(&mut self, node: Expr) -> Expr {
+        match node {
+            Expr::Add(l, r) => match (self.fold_term(*l), self.fold_term(*r)) {
+                (Expr::Num(l), Expr::Num(r)) => Expr::Num(l + r),
+                (l, r) => Expr::Add(Box::new(l), Box::new(r)),
+            },
+            node => node,
+        }
+    }
+
+    fn fold_name(&mut self, node: String) -> String {
+        node.to_uppercase()
+    }
+}
+
+#[test]
+fn folder_rebuilds_box() {
+    let expr = ExprParser::new().parse("x + (1 + 2)").unwrap();
+    assert_eq!(
+        ConstFold.fold_expr(expr),
+        Box::new(Expr::Add(
+            Box::new(Expr::Var("x".to_string())),
+            Box::new(Expr::Num(3))
+        ))
+    );
+}
+
+#[test]
+fn folder_rebuilds_vec_tuple_and_option() {
+    let lets = LetsParser::new().parse("let a = 1 + 2; let b;").unwrap();
+    assert_eq!(
+        ConstFold.fold_lets(lets),
+        vec![
+            ("A".to_string(), Some(Box::new(Expr::Num(3)))),
+            ("B".to_string(), None)
+        ]
+    );
+}
@@ -35,12 +35,134 @@ lalrpop_mod_test!(sub);
 lalrpop_mod_test!(sub_ascent);
 lalrpop_mod_test!(sub_table);
 
+/// test saving and loading the parse tables (built by their own
+/// configuration in build.rs)
+lalrpop_mod_test!(serialize_tables);
+lalrpop_mod_test!(serialize_tables_other);
+
 /// more interesting demonstration of parsing full expressions
 lalrpop_mod_test!(expr);
 
 /// more interesting demonstration of parsing full expressions, using LALR not LR
 lalrpop_mod_test!(expr_lalr);
 
+/// grammar that is LR(1) but not LALR(1), built with canonical LR(1)
+lalrpop_mod_test!(canonical_lr1);
+
+/// ambiguous grammar parsed with GLR, yielding every parse
+lalrpop_mod_test!(ambiguous);
+
+/// expressions parsed by an `AsyncParser` from a token stream that is
+/// not always ready
+lalrpop_mod_test!(async_parser);
+
+/// grammar that takes some of its nonterminals from another file
+lalrpop_mod_test!(import);
+
+/// grammar importing a nonterminal whose type is given by `%type`
+lalrpop_mod_test!(import_type_decl);
+
+/// grammar with `(?=X)` and `(?!X)` lookahead assertions
+lalrpop_mod_test!(lookahead_assertion);
+
+/// grammar with a generated `Visitor` trait
+lalrpop_mod_test!(visitor);
+mod visitor_lib;
+
+/// grammar with a generated `Folder` trait
+lalrpop_mod_test!(folder);
+mod folder_lib;
+
+/// grammar with `#[inline]` and `#[cold]` on alternatives
+lalrpop_mod_test!(alternative_attributes);
+
+/// grammar whose parser stack has a fixed maximum depth
+lalrpop_mod_test!(stack_depth);
+
+/// expression grammar using `%left` and `%right` instead of one nonterminal per level
+lalrpop_mod_test!(precedence_decl);
+
+/// grammar with an `IncrementalParser` that reuses the trees of earlier parses
+lalrpop_mod_test!(incremental);
+
+/// grammar whose actions call functions defined elsewhere
+lalrpop_mod_test!(external_fn);
+mod external_fn_lib;
+
+/// grammar using `@L`, `@R` and `@span` in action code
+lalrpop_mod_test!(span);
+
+/// grammar with `#[test] parse "..." as X => pattern;` items
+lalrpop_mod_test!(grammar_tests);
+
+/// alternatives with `#[if { expr }]` guards
+lalrpop_mod_test!(guards);
+
+/// `#[fallible]` action code, using `?` on the grammar's error type
+lalrpop_mod_test!(fallible);
+
+/// the error type given as `grammar<E = ...>`
+lalrpop_mod_test!(error_type);
+
+/// `#[emit_cst]` parsers, returning a parse tree next to the value
+lalrpop_mod_test!(emit_cst);
+
+/// uses the macros of the prelude
+lalrpop_mod_test!(prelude);
+
+/// entry points declared with `%start`
+lalrpop_mod_test!(start_decl);
+
+/// symbols named with `@x:X` only for documentation
+lalrpop_mod_test!(doc_name);
+
+/// Unicode character classes and terminals in the generated lexer
+lalrpop_mod_test!(unicode);
+
+/// Unicode identifiers as nonterminal, macro argument and binding names
+lalrpop_mod_test!(unicode_ident);
+
+/// `#[shared_action => code]` on nonterminals
+lalrpop_mod_test!(shared_action);
+
+/// types declared with `%type`
+lalrpop_mod_test!(type_decl);
+
+/// repeats with a count, like `X{2}` and `X{0,2}`
+lalrpop_mod_test!(repeat_count);
+
+/// `#[precedence(level = N, assoc = "...")]` on alternatives
+lalrpop_mod_test!(precedence_annot);
+
+/// groups with alternatives, like `("+" | "-")`
+lalrpop_mod_test!(choice);
+
+/// `#[deprecated]` on public nonterminals
+lalrpop_mod_test!(deprecated);
+
+/// `parse_partial`, which parses a prefix of the input
+lalrpop_mod_test!(partial);
+
+/// alternatives of macros without action code
+lalrpop_mod_test!(default_action);
+
+/// macros given types as arguments, like `Parse<u32>`
+lalrpop_mod_test!(macro_type_arg);
+
+/// `extern token T;`, matching tokens by their `AsRef<str>`
+lalrpop_mod_test!(extern_token_str);
+mod extern_token_str_lib;
+
+/// `extern token T: Token;`, matching tokens by their `Token` impl
+lalrpop_mod_test!(extern_token_trait);
+mod extern_token_trait_lib;
+
+/// `extern token` tokens split by `lalrpop_util::lexer::RegexLexer`
+lalrpop_mod_test!(regex_lexer);
+
+/// cuts (`^`) naming what an unexpected token should have followed
+lalrpop_mod_test!(cut);
+
 /// more interesting demonstration of parsing full expressions, using intern tok
 lalrpop_mod_test!(expr_intern_tok);
 
@@ -97,6 +219,7 @@ lalrpop_mod_test!(use_super);
 lalrpop_mod_test!(use_super_internal_tok);
 
 mod pub_in;
+mod pub_super;
 
 /// Custom error type (issue #113)
 #[derive(Debug, PartialEq)]
@@ -133,6 +256,7 @@ lalrpop_mod_test!(inline_fallible);
 
 /// test for unit action code
 lalrpop_mod_test!(unit);
+lalrpop_mod_test!(unit_ascent);
 
 /// test for match section
 lalrpop_mod_test!(match_section);
@@ -191,6 +315,16 @@ pub fn use_cfg_created_parser() {
     cfg::AlwaysCreatedParser::new();
 }
 
+#[test]
+fn cfg_alternatives() {
+    let parser = cfg::AlternativesParser::new();
+    assert_eq!(parser.parse("d"), Ok(4));
+    #[cfg(feature = "test-set")]
+    assert_eq!(parser.parse("a"), Ok(1));
+    assert!(parser.parse("b").is_err());
+    assert!(parser.parse("c").is_err());
+}
+
 /// This constant is here so that some of the generator parsers can
 /// refer to it in order to test `super::` handling in action code.
 const ZERO: i32 = 0;
@@ -236,6 +370,666 @@ fn expr_test5() {
     );
 }
 
+#[test]
+fn canonical_lr1_test() {
+    let parser = canonical_lr1::SParser::new();
+    assert_eq!(parser.parse("a e c").unwrap(), "aEc");
+    assert_eq!(parser.parse("a e d").unwrap(), "aFd");
+    assert_eq!(parser.parse("b e c").unwrap(), "bFc");
+    assert_eq!(parser.parse("b e d").unwrap(), "bEd");
+    assert!(parser.parse("a e").is_err());
+}
+
+#[test]
+fn ambiguous_test() {
+    let parser = ambiguous::ExprParser::new();
+    assert_eq!(parser.parse("1").unwrap(), vec!["1"]);
+    assert_eq!(parser.parse("1 + 2").unwrap(), vec!["(1 + 2)"]);
+    assert_eq!(
+        parser.parse("1 + 2 * 3").unwrap(),
+        vec!["(1 + (2 * 3))", "((1 + 2) * 3)"]
+    );
+    assert_eq!(parser.parse("1 + 2 + 3 + 4").unwrap().len(), 5);
+    assert!(parser.parse("1 +").is_err());
+    assert!(parser.parse("1 2").is_err());
+}
+
+#[test]
+fn lookahead_assertion_test() {
+    let parser = lookahead_assertion::StmtParser::new();
+    assert_eq!(parser.parse("x").unwrap(), "x");
+    assert_eq!(parser.parse("if a then x").unwrap(), "(if a x)");
+    assert_eq!(
+        parser.parse("if a then if b then x else y").unwrap(),
+        "(if a (if b x y))"
+    );
+
+    let parser = lookahead_assertion::WordParser::new();
+    assert_eq!(parser.parse("w;").unwrap(), "w");
+    assert!(parser.parse("w").is_err());
+}
+
+#[test]
+fn alternative_attributes_test() {
+    let parser = alternative_attributes::ExprParser::new();
+    assert_eq!(parser.parse("(1 + 2) + 3 + 4").unwrap(), 10);
+    assert_eq!(parser.parse("!").unwrap(), -1);
+}
+
+#[test]
+fn stack_depth_test() {
+    let parser = stack_depth::ParensParser::new();
+    assert_eq!(parser.parse("").unwrap(), 0);
+    assert_eq!(parser.parse("((((()))))").unwrap(), 5);
+    // the innermost `)` does not fit
+    assert_eq!(
+        parser.parse("(((((())))))"),
+        Err(ParseError::StackOverflow { location: 6 })
+    );
+    // nor does the innermost (empty) `Parens`
+    assert_eq!(
+        parser.parse("((((((())))))))"),
+        Err(ParseError::StackOverflow { location: 7 })
+    );
+}
+
+#[test]
+fn precedence_decl_test() {
+    let parser = precedence_decl::ExprParser::new();
+    assert_eq!(parser.parse("10 - 3 - 2").unwrap(), 5);
+    assert_eq!(parser.parse("1 + 2 * 3 - 4 / 2").unwrap(), 5);
+    assert_eq!(parser.parse("2 ^ 3 ^ 2").unwrap(), 512);
+    assert_eq!(parser.parse("2 * 3 ^ 2").unwrap(), 18);
+    assert_eq!(parser.parse("(1 + 2) * 3").unwrap(), 9);
+    assert_eq!(parser.parse("1 + 1 == 2").unwrap(), 1);
+    assert!(parser.parse("1 == 1 == 1").is_err());
+}
+
+/// A token stream that is `Pending` before every token, as if each had
+/// to be awaited.
+struct SlowTokens<'input> {
+    tokens: std::collections::VecDeque<Tok<'input>>,
+    ready: bool,
+}
+
+impl<'input> lalrpop_util::state_machine::TokenStream for SlowTokens<'input> {
+    type Item = Tok<'input>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.ready = !self.ready;
+        if self.ready {
+            cx.waker().wake_by_ref();
+            std::task::Poll::Pending
+        } else {
+            std::task::Poll::Ready(self.tokens.pop_front())
+        }
+    }
+}
+
+fn noop_waker() -> std::task::Waker {
+    use std::task::{RawWaker, RawWakerVTable, Waker};
+
+    fn clone(_: *const ()) -> RawWaker {
+        RawWaker::new(std::ptr::null(), &VTABLE)
+    }
+    fn noop(_: *const ()) {}
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+    unsafe { Waker::from_raw(clone(std::ptr::null())) }
+}
+
+/// Parses `input` with an `AsyncParser`, polling it until it is ready,
+/// and counts how often it was pending.
+fn async_parse(input: &str) -> (Result<i32, ParseError<(), Tok<'_>, &'static str>>, usize) {
+    let waker = noop_waker();
+    let mut cx = std::task::Context::from_waker(&waker);
+    let mut tokens = SlowTokens {
+        tokens: util::tok::tokenize(input)
+            .into_iter()
+            .map(|t| t.1)
+            .collect(),
+        ready: false,
+    };
+    let mut parser = async_parser::ExprAsyncParser::new(2);
+    let mut pending = 0;
+    loop {
+        match parser.poll_parse(&mut cx, &mut tokens) {
+            std::task::Poll::Ready(result) => return (result, pending),
+            std::task::Poll::Pending => pending += 1,
+        }
+    }
+}
+
+#[test]
+fn async_parser_test() {
+    // the parser waits once for each token, and once for the end
+    assert_eq!(async_parse("22 - (3 + 5)"), (Ok(2 * (22 - (3 + 5))), 8));
+    // the parse ends the same as with the `Parser`, also when the
+    // recovery from an error drops tokens it had to wait for
+    for input in ["1 + (* 7) - 3", "1 +", "(1 2) * 3", ""] {
+        let tokens = util::tok::tokenize(input).into_iter().map(|t| t.1);
+        let expected = async_parser::ExprParser::new().parse(2, tokens);
+        assert_eq!(async_parse(input).0, expected, "{:?}", input);
+    }
+}
+
+#[test]
+fn incremental_test() {
+    use lalrpop_util::incremental::Edit;
+
+    let parser = incremental::ItemsIncrementalParser::new();
+    let mut text = String::from("a = 1 + b; c = [1, (2 + 3), d]; e = f;");
+    let mut tree = parser.parse(&text).unwrap();
+    assert_eq!(tree.name(), "Items");
+    assert_eq!(tree.text(&text), text);
+
+    let edits = [
+        // change a token in the middle
+        Edit {
+            start: 24,
+            deleted: 1,
+            inserted: "7",
+        },
+        // add an item at the end
+        Edit {
+            start: 38,
+            deleted: 0,
+            inserted: " g = h;",
+        },
+        // make a name longer
+        Edit {
+            start: 0,
+            deleted: 0,
+            inserted: "xy",
+        },
+        // remove the list
+        Edit {
+            start: 17,
+            deleted: 15,
+            inserted: "0",
+        },
+        // empty the input
+        Edit {
+            start: 0,
+            deleted: 33,
+            inserted: "",
+        },
+    ];
+    for edit in &edits {
+        let new_text = edit.apply(&text);
+        let new_tree = parser.reparse(&new_text, &tree, edit).unwrap();
+        assert_eq!(new_tree, parser.parse(&new_text).unwrap(), "{:?}", new_text);
+        text = new_text;
+        tree = new_tree;
+    }
+    assert_eq!(text, "");
+    assert!(incremental::ItemsParser::new().parse(&text).is_ok());
+
+    let edit = Edit {
+        start: 0,
+        deleted: 0,
+        inserted: "a = ;",
+    };
+    assert!(parser.reparse(&edit.apply(&text), &tree, &edit).is_err());
+}
+
+#[test]
+fn span_test() {
+    use lalrpop_util::Span;
+
+    assert_eq!(
+        span::ItemsParser::new().parse("ab (c d) <>").unwrap(),
+        vec![
+            (Span::new(0, 2), "ab"),
+            (Span::new(3, 8), "list"),
+            (Span::new(10, 10), "empty"),
+        ]
+    );
+}
+
+#[test]
+fn guards_test() {
+    let parser = guards::IdentParser::new();
+    assert_eq!(parser.parse("foo").unwrap(), "foo");
+    assert_eq!(
+        parser.parse("let").unwrap_err(),
+        ParseError::InvalidToken { location: 0 }
+    );
+
+    let parser = guards::KeywordParser::new();
+    assert_eq!(parser.parse("let").unwrap(), "let");
+    assert_eq!(
+        parser.parse("foo").unwrap_err(),
+        ParseError::InvalidToken { location: 0 }
+    );
+
+    let parser = guards::DigitsParser::new();
+    assert_eq!(parser.parse("1,2,3").unwrap(), vec![1, 2, 3]);
+    assert_eq!(
+        parser.parse("1,2,3,4").unwrap_err(),
+        ParseError::InvalidToken { location: 0 }
+    );
+    assert_eq!(
+        parser.parse("1,0").unwrap_err(),
+        ParseError::InvalidToken { location: 2 }
+    );
+}
+
+#[test]
+fn fallible_test() {
+    let parser = fallible::SumParser::new();
+    assert_eq!(parser.parse("1 + 2 + 3").unwrap(), 6);
+    assert_eq!(
+        parser.parse("1 + 300").unwrap_err(),
+        ParseError::User {
+            error: "300".parse::<u8>().unwrap_err()
+        }
+    );
+    assert_eq!(
+        parser.parse("200 + 100").unwrap_err(),
+        ParseError::User {
+            error: "256".parse::<u8>().unwrap_err()
+        }
+    );
+
+    let parser = fallible::NumsParser::new();
+    assert_eq!(parser.parse("1, 2, 3").unwrap(), vec![1, 2, 3]);
+    assert!(parser.parse("1, 256").is_err());
+}
+
+#[test]
+fn error_type_test() {
+    let parser = error_type::NumParser::new();
+    assert_eq!(parser.parse("42"), Ok(42));
+    assert_eq!(
+        parser.parse("300").unwrap_err(),
+        ParseError::User {
+            error: "300".parse::<u8>().unwrap_err()
+        }
+    );
+}
+
+#[test]
+fn emit_cst_test() {
+    use lalrpop_util::{ParseTreeNode, Span};
+
+    fn terminal(token: &'static str, start: usize, end: usize) -> ParseTreeNode {
+        ParseTreeNode::Terminal {
+            token,
+            span: Span::new(start, end),
+        }
+    }
+
+    fn nonterminal(
+        name: &'static str,
+        children: Vec<ParseTreeNode>,
+        start: usize,
+        end: usize,
+    ) -> ParseTreeNode {
+        ParseTreeNode::Nonterminal {
+            name,
+            children,
+            span: Span::new(start, end),
+        }
+    }
+
+    let parser = emit_cst::SumParser::new();
+    let (value, node) = parser.parse("1 + (2)").unwrap();
+    assert_eq!(value, 3);
+    assert_eq!(
+        node,
+        nonterminal(
+            "Sum",
+            vec![
+                nonterminal(
+                    "Sum",
+                    vec![nonterminal(
+                        "Num",
+                        vec![terminal(r##"r#"[0-9]+"#"##, 0, 1)],
+                        0,
+                        1
+                    )],
+                    0,
+                    1
+                ),
+                terminal(r#""+""#, 2, 3),
+                nonterminal(
+                    "Num",
+                    vec![
+                        terminal(r#""(""#, 4, 5),
+                        nonterminal(
+                            "Sum",
+                            vec![nonterminal(
+                                "Num",
+                                vec![terminal(r##"r#"[0-9]+"#"##, 5, 6)],
+                                5,
+                                6
+                            )],
+                            5,
+                            6
+                        ),
+                        terminal(r#"")""#, 6, 7),
+                        nonterminal(r#""!"?"#, vec![], 7, 7),
+                    ],
+                    4,
+                    7
+                ),
+            ],
+            0,
+            7
+        )
+    );
+
+    // the text of the leaves, read back from the input
+    fn leaves<'a>(node: &ParseTreeNode, input: &'a str, out: &mut Vec<&'a str>) {
+        match node {
+            ParseTreeNode::Terminal { .. } => out.push(node.text(input)),
+            ParseTreeNode::Nonterminal { .. } => {
+                for child in node.children() {
+                    leaves(child, input, out);
+                }
+            }
+        }
+    }
+    let mut texts = vec![];
+    leaves(&node, "1 + (2)", &mut texts);
+    assert_eq!(texts, ["1", "+", "(", "2", ")"]);
+    assert_eq!(node.children()[2].text("1 + (2)"), "(2)");
+
+    let (value, node) = parser.parse("(3)!").unwrap();
+    assert_eq!(value, 3);
+    let ParseTreeNode::Nonterminal { children, .. } = node else {
+        panic!("expected a nonterminal");
+    };
+    let ParseTreeNode::Nonterminal { children, .. } = &children[0] else {
+        panic!("expected a nonterminal");
+    };
+    assert_eq!(
+        children[3],
+        nonterminal(r#""!"?"#, vec![terminal(r#""!""#, 3, 4)], 3, 4)
+    );
+
+    assert_eq!(
+        parser.parse("99999999999").unwrap_err(),
+        ParseError::User { error: "too big" }
+    );
+}
+
+#[test]
+fn prelude_test() {
+    let parser = prelude::ListParser::new();
+    assert_eq!(parser.parse("[]").unwrap(), vec![]);
+    assert_eq!(parser.parse("[1, 2]").unwrap(), vec![1, 2]);
+    assert!(parser.parse("[1, 2,]").is_err());
+
+    let parser = prelude::TupleParser::new();
+    assert_eq!(parser.parse("()").unwrap(), vec![]);
+    assert_eq!(parser.parse("(1, 2,)").unwrap(), vec![1, 2]);
+
+    let parser = prelude::LetParser::new();
+    assert!(parser.parse("let x").unwrap());
+    assert!(!parser.parse("x").unwrap());
+
+    let parser = prelude::WordsParser::new();
+    assert_eq!(parser.parse(":a").unwrap(), (vec![], vec!["a"]));
+    assert_eq!(
+        parser.parse("a b : c").unwrap(),
+        (vec!["a", "b"], vec!["c"])
+    );
+    assert!(parser.parse("a :").is_err());
+}
+
+#[test]
+fn start_decl_test() {
+    assert_eq!(start_decl::ExprParser::new().parse("1 + 2"), Ok(3));
+    assert_eq!(
+        start_decl::StatementParser::new().parse("1 + 2; 3;"),
+        Ok(vec![3, 3])
+    );
+}
+
+#[test]
+fn doc_name_test() {
+    assert_eq!(doc_name::PairParser::new().parse("1, 2"), Ok((1, ",", 2)));
+    assert_eq!(doc_name::SumParser::new().parse("1 + 2"), Ok(1));
+
+    // the expected tokens that start a named symbol carry its name
+    let error = doc_name::PairParser::new().parse(",").unwrap_err();
+    assert_eq!(error.expected_tokens(), [r###"r#"[0-9]+"# (called lhs)"###]);
+    let error = doc_name::PairParser::new().parse("1, ,").unwrap_err();
+    assert_eq!(error.expected_tokens(), [r###"r#"[0-9]+"# (called rhs)"###]);
+    let error = doc_name::PairParser::new().parse("1").unwrap_err();
+    assert_eq!(error.expected_tokens(), [r#"",""#]);
+}
+
+#[test]
+fn regex_lexer_test() {
+    use lalrpop_util::lexer::{LexError, RegexLexer};
+
+    let lexer = RegexLexer::new(&[
+        (r"\s+", ""),
+        (r"[0-9]+", "Num"),
+        (r"\+", "+"),
+        (r"\(", "("),
+        (r"\)", ")"),
+    ])
+    .unwrap();
+    let parser = regex_lexer::SumParser::new();
+    let parse = |input| parser.parse(input, lexer.tokens(input));
+    assert_eq!(parse("1 + (20 + 300)"), Ok(321));
+    assert_eq!(
+        parse("1 + x"),
+        Err(ParseError::User {
+            error: LexError { location: 4 }
+        })
+    );
+    assert_eq!(
+        parse("1 2"),
+        Err(ParseError::UnrecognizedToken {
+            token: (2, "Num", 3),
+            expected: vec![r#""+""#.to_string()],
+        })
+    );
+
+    // the longest match wins, then the first rule
+    let lexer = RegexLexer::new(&[("if", "If"), ("[a-z]+", "Id")]).unwrap();
+    let names = |text| {
+        lexer
+            .tokens(text)
+            .map(|token| token.unwrap().1)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names("if"), ["If"]);
+    assert_eq!(names("iffy"), ["Id"]);
+}
+
+#[test]
+fn unicode_test() {
+    let parser = unicode::AssignParser::new();
+    assert_eq!(parser.parse("λ ← ٤٢").unwrap(), ("λ", "٤٢"));
+    assert_eq!(parser.parse("Über←3").unwrap(), ("Über", "3"));
+    assert!(parser.parse("λ ← x").is_err());
+}
+
+#[test]
+fn unicode_ident_test() {
+    let parser = unicode_ident::GrößeParser::new();
+    assert_eq!(parser.parse("1, 22, 3").unwrap(), vec![1, 22, 3]);
+}
+
+#[test]
+fn shared_action_test() {
+    let parser = shared_action::StmtParser::new();
+    assert_eq!(parser.parse("let 4;").unwrap(), ("let", 40, 0, 6));
+    assert_eq!(parser.parse("return (2);").unwrap(), ("return", 200, 0, 11));
+    assert_eq!(parser.parse(" 7 ;").unwrap(), ("expr", 70, 1, 4));
+    assert_eq!(parser.parse("assert 1;").unwrap(), ("assert", 10, 0, 9));
+    assert!(parser.parse("assert 0;").is_err());
+}
+
+#[test]
+fn type_decl_test() {
+    let parser = type_decl::NumsParser::new();
+    assert_eq!(parser.parse("1, 22, 3").unwrap(), vec![1, 22, 3]);
+    assert_eq!(type_decl::SumParser::new().parse("1, 22, 3").unwrap(), 26);
+}
+
+#[test]
+fn repeat_count_test() {
+    let parser = repeat_count::PairParser::new();
+    assert_eq!(parser.parse("1 2").unwrap(), vec![1, 2]);
+    assert!(parser.parse("1").is_err());
+    assert!(parser.parse("1 2 3").is_err());
+
+    let parser = repeat_count::VersionParser::new();
+    assert_eq!(parser.parse("1").unwrap(), (1, vec![]));
+    assert_eq!(parser.parse("1.2.3").unwrap(), (1, vec![2, 3]));
+    assert!(parser.parse("1.2.3.4").is_err());
+
+    let parser = repeat_count::LettersParser::new();
+    assert_eq!(parser.parse("a b c d").unwrap(), vec!["a", "b", "c", "d"]);
+    assert!(parser.parse("a b").is_err());
+}
+
+#[test]
+fn choice_test() {
+    let parser = choice::DeclParser::new();
+    assert_eq!(parser.parse("let 1;").unwrap(), ("let", 1, ";"));
+    assert_eq!(parser.parse("var 2;").unwrap(), ("var", 2, ";"));
+    assert!(parser.parse("const 3;").is_err());
+
+    let parser = choice::TermsParser::new();
+    assert_eq!(
+        parser.parse("1 + 2 - 3").unwrap(),
+        (1, vec![("+", 2), ("-", 3)])
+    );
+
+    let parser = choice::ValueParser::new();
+    assert_eq!(parser.parse("(1)").unwrap(), 1);
+    assert_eq!(parser.parse("2").unwrap(), 2);
+    assert_eq!(parser.parse("# 3").unwrap(), 3);
+}
+
+#[test]
+#[allow(deprecated)]
+fn deprecated_test() {
+    assert_eq!(deprecated::NumParser::new().parse("12").unwrap(), 12);
+    assert_eq!(deprecated::OldNumParser::new().parse("34").unwrap(), 34);
+}
+
+#[test]
+fn partial_test() {
+    let parser = partial::SumParser::new();
+    assert_eq!(parser.parse_partial("1 + 2 3 + 4"), Ok((3, "3 + 4")));
+    assert_eq!(parser.parse_partial("1 + 2"), Ok((3, "")));
+    assert_eq!(
+        parser.parse_partial("1 +"),
+        Err(ParseError::UnrecognizedEof {
+            location: 3,
+            expected: vec![r###"r#"[0-9]+"#"###.to_string()],
+        })
+    );
+}
+
+#[test]
+fn default_action_test() {
+    assert_eq!(default_action::ChosenParser::new().parse("w 1"), Ok(1));
+    assert_eq!(
+        default_action::TupleParser::new().parse("1, 2"),
+        Ok((1, ",", 2))
+    );
+    assert_eq!(default_action::UnitParser::new().parse("1;"), Ok(()));
+    assert_eq!(default_action::EmptyParser::new().parse("w"), Ok(()));
+    assert_eq!(default_action::OptionalParser::new().parse("w"), Ok(None));
+    assert_eq!(
+        default_action::OptionalParser::new().parse("w 3"),
+        Ok(Some(3))
+    );
+    assert_eq!(default_action::SpannedParser::new().parse("w 45"), Ok(45));
+    assert_eq!(default_action::SelectedParser::new().parse("6"), Ok(6));
+    assert_eq!(default_action::RejectedParser::new().parse("x 7"), Ok(7));
+    assert!(default_action::RejectedParser::new().parse("7").is_err());
+    assert_eq!(
+        default_action::NestedParser::new().parse("w 8, w 9"),
+        Ok((8, ",", 9))
+    );
+}
+
+#[test]
+fn macro_type_arg_test() {
+    assert_eq!(macro_type_arg::ByteParser::new().parse("255"), Ok(255u8));
+    assert_eq!(
+        macro_type_arg::WideParser::new().parse("4294967296"),
+        Ok(4294967296u64)
+    );
+    assert_eq!(
+        macro_type_arg::NumbersParser::new().parse("[1, 2, 3,]"),
+        Ok(vec![1, 2, 3])
+    );
+    assert_eq!(
+        macro_type_arg::NestedParser::new().parse("[]"),
+        Ok(Vec::<Vec<String>>::new())
+    );
+}
+
+#[test]
+fn precedence_annot_test() {
+    let parser = precedence_annot::ExprParser::new();
+    assert_eq!(parser.parse("1 + 2 * 3").unwrap(), 7);
+    assert_eq!(parser.parse("10 - 4 - 3").unwrap(), 3);
+    assert_eq!(parser.parse("2 ^ 3 ^ 2").unwrap(), 512);
+    assert_eq!(parser.parse("2 * 3 ^ 2").unwrap(), 18);
+    assert_eq!(parser.parse("(1 + 2) * 3").unwrap(), 9);
+}
+
+#[test]
+fn cut_test() {
+    let parser = cut::StmtsParser::new();
+    assert_eq!(
+        parser.parse("let x = 1; print 2;").unwrap(),
+        vec![("x".to_string(), 1), ("print".to_string(), 2)]
+    );
+
+    // past the cut, the error names the keyword
+    match parser.parse("let x 1;") {
+        Err(ParseError::UnrecognizedAfter {
+            after,
+            token: Some((6, _, 7)),
+            location: 6,
+            expected,
+        }) => {
+            assert_eq!(after, r#""let""#);
+            assert_eq!(expected, vec![r#""=""#.to_string()]);
+        }
+        r => panic!("unexpected result: {:?}", r),
+    }
+    assert_eq!(
+        parser.parse("print 1").unwrap_err().to_string(),
+        "Unrecognized EOF found at 7 after `\"print\"`\nExpected one of \";\""
+    );
+
+    // before it, the error is the usual one
+    assert!(matches!(
+        parser.parse("x = 1;"),
+        Err(ParseError::UnrecognizedToken { .. })
+    ));
+}
+
+#[test]
+fn import_test() {
+    let parser = import::SumParser::new();
+    assert_eq!(parser.parse("1 + 22 + 3").unwrap(), 26);
+    assert!(parser.parse("1 +").is_err());
+}
+
+#[test]
+fn import_type_decl_test() {
+    let parser = import_type_decl::SumParser::new();
+    assert_eq!(parser.parse("1, 22, 3").unwrap(), 26);
+}
+
 #[test]
 fn expr_intern_tok_test1() {
     assert_eq!(
@@ -413,6 +1207,28 @@ fn sub_table_test1() {
     util::test(|t| sub_table::SParser::new().parse(t), "22 - 3", 22 - 3);
 }
 
+#[test]
+fn serialize_tables_test() {
+    util::test(
+        |t| serialize_tables::EParser::new().parse(t),
+        "22 - 3",
+        22 - 3,
+    );
+
+    let path = std::env::temp_dir().join(format!("serialize_tables_{}.tables", std::process::id()));
+    let tables = serialize_tables::EParser::tables();
+    tables.save_to_path(&path).unwrap();
+    assert_eq!(
+        serialize_tables::EParser::load_tables_from_path(&path).unwrap(),
+        tables
+    );
+
+    // the tables of another grammar are rejected
+    let error = serialize_tables_other::ParensParser::load_tables_from_path(&path).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    std::fs::remove_file(&path).unwrap();
+}
+
 #[test]
 fn expr_arena_test1() {
     use crate::expr_arena_ast::*;
@@ -958,6 +1774,12 @@ fn unit_test2() {
     assert!(unit::ExprParser::new().parse("3 + +").is_err());
 }
 
+#[test]
+fn unit_ascent_test() {
+    assert!(unit_ascent::ExprParser::new().parse("3 + 4 * 5").is_ok());
+    assert!(unit_ascent::ExprParser::new().parse("3 + +").is_err());
+}
+
 #[test]
 fn generics_issue_104_test1() {
     // The real thing `generics_issue_104` is testing is that the code
@@ -0,0 +1,32 @@
+//! Test for `pub(super)` and `pub(in super::...)` on nonterminals,
+//! whose paths are relative to the generated module, and for `pub(self)`.
+
+mod outer {
+    mod inner {
+        use crate::lalrpop_mod;
+        lalrpop_mod!(pub(super) pub_super);
+
+        #[test]
+        fn pub_super_test() {
+            assert_eq!(pub_super::NumParser::new().parse("3"), Ok(3));
+        }
+    }
+
+    #[test]
+    fn pub_in_super_super_test() {
+        assert_eq!(inner::pub_super::SumParser::new().parse("1 + 2"), Ok(3));
+    }
+}
+
+/// A `pub(self)` parser is only visible in the module the grammar is
+/// generated in, so this one includes it itself.
+mod pub_self {
+    // the other parsers are not used in this copy
+    #![allow(unused_imports)]
+    include!(concat!(env!("OUT_DIR"), "/pub_super.rs"));
+
+    #[test]
+    fn pub_self_test() {
+        assert_eq!(NegParser::new().parse("-3"), Ok(-3));
+    }
+}
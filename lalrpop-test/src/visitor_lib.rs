@@ -0,0 +1,41 @@
+use crate::visitor::{ItemsParser, PairsParser, Visitor};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Item<'input> {
+    Word(&'input str),
+    List(Vec<Item<'input>>),
+}
+
+/// Collects every word; `Item` is our own type, so we have to descend
+/// into it ourselves.
+#[derive(Default)]
+struct Words<'input>(Vec<&'input str>);
+
+impl<'ast, 'input> Visitor<'ast, 'input> for Words<'input> {
+    fn visit_item(&mut self, node: &'ast Item<'input>) {
+        match node {
+            Item::Word(word) => self.visit_word(word),
+            Item::List(items) => self.visit_items(items),
+        }
+    }
+
+    fn visit_word(&mut self, node: &'ast &'input str) {
+        self.0.push(node);
+    }
+}
+
+#[test]
+fn visitor_walks_vec() {
+    let items = ItemsParser::new().parse("a (b (c)) d").unwrap();
+    let mut words = Words::default();
+    words.visit_items(&items);
+    assert_eq!(words.0, ["a", "b", "c", "d"]);
+}
+
+#[test]
+fn visitor_walks_tuple_option_and_box() {
+    let pairs = PairsParser::new().parse("x = (y); z = ;").unwrap();
+    let mut words = Words::default();
+    words.visit_pairs(&pairs);
+    assert_eq!(words.0, ["x", "y", "z"]);
+}
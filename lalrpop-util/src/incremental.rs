@@ -0,0 +1,577 @@
+//! Runtime support for the parsers of `#[incremental]` grammars.
+//!
+//! Such a grammar gets an `IncrementalParser` next to each of its
+//! `Parser`s. Instead of running the actions of the grammar, it builds a
+//! [`ParseTree`] that records which production matched which span of the
+//! input. Given the tree of the previous version of some text and the
+//! [`Edit`] that was made to it, `IncrementalParser::reparse` then
+//! takes every subtree it can from the old tree instead of parsing that
+//! part of the input again.
+//!
+//! A subtree is only reused if the parser is in the same state as when
+//! the subtree was first built, the subtree covers the same tokens, and
+//! it is followed by the same kind of token. Since the parser is
+//! deterministic, it would have built exactly the same subtree again, so
+//! a reparse always yields the same tree as a parse from scratch.
+
+use crate::state_machine::{
+    ParseError, ParserAction, ParserDefinition, SimulatedReduce, TokenTriple,
+};
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::{string::String, vec::Vec};
+
+/// A change to the input: the `deleted` bytes starting at `start` were
+/// replaced with `inserted`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Edit<'a> {
+    pub start: usize,
+    pub deleted: usize,
+    pub inserted: &'a str,
+}
+
+impl<'a> Edit<'a> {
+    /// Returns `text` with the edit applied.
+    pub fn apply(&self, text: &str) -> String {
+        let mut result = String::with_capacity(text.len() - self.deleted + self.inserted.len());
+        result.push_str(&text[..self.start]);
+        result.push_str(self.inserted);
+        result.push_str(&text[self.start + self.deleted..]);
+        result
+    }
+
+    /// Where the span `start..end` of the old input is in the new input,
+    /// or `None` if the edit changed (or is inside) it.
+    fn offset(&self, start: usize, end: usize) -> Option<isize> {
+        if end <= self.start {
+            Some(0)
+        } else if start >= self.start + self.deleted {
+            Some(self.inserted.len() as isize - self.deleted as isize)
+        } else {
+            None
+        }
+    }
+}
+
+/// A concrete syntax tree built by an incremental parser, covering the
+/// input from `start` to `end`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseTree {
+    pub start: usize,
+    pub end: usize,
+    pub node: Node,
+
+    /// the index of the terminal or nonterminal
+    index: usize,
+
+    /// what it takes to reuse this tree, if it is a nonterminal
+    reuse: Option<Reuse>,
+}
+
+/// The names are the ones the parser uses in its error messages, like
+/// `"+"` or `r#"[0-9]+"#` for terminals, and `Expr` or `Expr*` for
+/// nonterminals.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Node {
+    Terminal {
+        name: &'static str,
+    },
+
+    /// A nonterminal, with the trees of the symbols it was reduced from.
+    Nonterminal {
+        name: &'static str,
+        children: Vec<ParseTree>,
+    },
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Reuse {
+    /// the state on top of the stack when the first token was shifted
+    state: i32,
+
+    /// the terminal after the tree (`None` if it ends the input)
+    follow: Option<usize>,
+}
+
+impl ParseTree {
+    /// The name of the terminal or nonterminal.
+    pub fn name(&self) -> &'static str {
+        match self.node {
+            Node::Terminal { name } | Node::Nonterminal { name, .. } => name,
+        }
+    }
+
+    /// The trees this nonterminal was reduced from; empty for a terminal.
+    pub fn children(&self) -> &[ParseTree] {
+        match self.node {
+            Node::Terminal { .. } => &[],
+            Node::Nonterminal { ref children, .. } => children,
+        }
+    }
+
+    /// The part of `input` (the text that was parsed) this tree covers.
+    pub fn text<'input>(&self, input: &'input str) -> &'input str {
+        &input[self.start..self.end]
+    }
+
+    fn terminals<'t>(&'t self, out: &mut Vec<&'t ParseTree>) {
+        match self.node {
+            Node::Terminal { .. } => out.push(self),
+            Node::Nonterminal { ref children, .. } => {
+                for child in children {
+                    child.terminals(out);
+                }
+            }
+        }
+    }
+
+    /// Whether the first thing in this tree is a terminal (and not an
+    /// empty nonterminal). The parser reduces leading empty nonterminals
+    /// before it gets to shift the first token, so trees that start with
+    /// one cannot be reused when it does.
+    fn starts_with_terminal(&self) -> bool {
+        match self.node {
+            Node::Terminal { .. } => true,
+            Node::Nonterminal { ref children, .. } => children
+                .first()
+                .is_some_and(|first| first.starts_with_terminal()),
+        }
+    }
+
+    /// Collects the nonterminals that are not touched by `edit`, by where
+    /// they start in the new input, outermost first.
+    fn candidates<'t>(&'t self, edit: &Edit<'_>, out: &mut BTreeMap<usize, Vec<Candidate<'t>>>) {
+        if let Node::Nonterminal { ref children, .. } = self.node {
+            if self.starts_with_terminal() {
+                if let Some(offset) = edit.offset(self.start, self.end) {
+                    out.entry(self.start.wrapping_add_signed(offset))
+                        .or_default()
+                        .push(Candidate { tree: self, offset });
+                }
+            }
+            for child in children {
+                child.candidates(edit, out);
+            }
+        }
+    }
+
+    /// Clones this tree, moving it by `offset`.
+    fn moved(&self, offset: isize) -> ParseTree {
+        let node = match self.node {
+            Node::Terminal { name } => Node::Terminal { name },
+            Node::Nonterminal { name, ref children } => Node::Nonterminal {
+                name,
+                children: children.iter().map(|child| child.moved(offset)).collect(),
+            },
+        };
+        ParseTree {
+            start: self.start.wrapping_add_signed(offset),
+            end: self.end.wrapping_add_signed(offset),
+            node,
+            index: self.index,
+            reuse: self.reuse,
+        }
+    }
+}
+
+struct Candidate<'t> {
+    tree: &'t ParseTree,
+    offset: isize,
+}
+
+/// Parses `tokens` into a tree. `terminals` and `nonterminals` hold the
+/// name of each `D::TokenIndex` and `D::NonterminalIndex`. If `old` is
+/// given, it is the tree of the input before `edit`, and the subtrees it
+/// shares with the new input are reused.
+pub fn parse<D, I>(
+    definition: D,
+    terminals: &'static [&'static str],
+    nonterminals: &'static [&'static str],
+    tokens: I,
+    old: Option<(&ParseTree, &Edit<'_>)>,
+) -> Result<ParseTree, ParseError<D>>
+where
+    D: ParserDefinition<Location = usize, TokenIndex = usize, NonterminalIndex = usize>,
+    D::StateIndex: Into<i32>,
+    I: Iterator<Item = Result<TokenTriple<D>, ParseError<D>>>,
+{
+    TreeBuilder::new(definition, terminals, nonterminals, tokens, old).parse()
+}
+
+struct TreeBuilder<'t, D, I>
+where
+    D: ParserDefinition,
+{
+    definition: D,
+    terminals: &'static [&'static str],
+    nonterminals: &'static [&'static str],
+    tokens: I,
+
+    /// tokens read ahead to check whether a subtree can be reused
+    buffer: VecDeque<Result<TokenTriple<D>, ParseError<D>>>,
+
+    states: Vec<D::StateIndex>,
+    trees: Vec<ParseTree>,
+    last_location: usize,
+    candidates: BTreeMap<usize, Vec<Candidate<'t>>>,
+
+    /// the number of subtrees taken from the old tree
+    #[cfg(test)]
+    reused: usize,
+}
+
+impl<'t, D, I> TreeBuilder<'t, D, I>
+where
+    D: ParserDefinition<Location = usize, TokenIndex = usize, NonterminalIndex = usize>,
+    D::StateIndex: Into<i32>,
+    I: Iterator<Item = Result<TokenTriple<D>, ParseError<D>>>,
+{
+    fn new(
+        definition: D,
+        terminals: &'static [&'static str],
+        nonterminals: &'static [&'static str],
+        tokens: I,
+        old: Option<(&'t ParseTree, &Edit<'_>)>,
+    ) -> Self {
+        let mut candidates = BTreeMap::new();
+        if let Some((tree, edit)) = old {
+            tree.candidates(edit, &mut candidates);
+        }
+        let start_state = definition.start_state();
+        TreeBuilder {
+            definition,
+            terminals,
+            nonterminals,
+            tokens,
+            buffer: VecDeque::new(),
+            states: alloc::vec![start_state],
+            trees: Vec::new(),
+            last_location: 0,
+            candidates,
+            #[cfg(test)]
+            reused: 0,
+        }
+    }
+
+    fn parse(&mut self) -> Result<ParseTree, ParseError<D>> {
+        loop {
+            let lookahead = match self.peek(0) {
+                Some(Ok(triple)) => Some(triple.clone()),
+                Some(Err(_)) => return Err(self.buffer.pop_front().unwrap().unwrap_err()),
+                None => None,
+            };
+            let terminal = match lookahead {
+                Some(ref triple) => match self.definition.token_to_index(&triple.1) {
+                    Some(index) => Some(index),
+                    None => return Err(self.unrecognized(lookahead)),
+                },
+                None => None,
+            };
+
+            let top_state = *self.states.last().unwrap();
+            let action = match terminal {
+                Some(index) => self.definition.action(top_state, index),
+                None => self.definition.eof_action(top_state),
+            };
+
+            if let Some(target_state) = action.as_shift() {
+                let (start, _, end) = lookahead.unwrap();
+                if self.reuse(start) {
+                    continue;
+                }
+                let index = terminal.unwrap();
+                self.buffer.pop_front();
+                self.states.push(target_state);
+                self.trees.push(ParseTree {
+                    start,
+                    end,
+                    node: Node::Terminal {
+                        name: self.terminals[index],
+                    },
+                    index,
+                    reuse: None,
+                });
+                self.last_location = end;
+            } else if let Some(reduce_index) = action.as_reduce() {
+                match self.definition.simulate_reduce(reduce_index) {
+                    SimulatedReduce::Reduce {
+                        states_to_pop,
+                        nonterminal_produced,
+                    } => self.reduce(states_to_pop, nonterminal_produced, terminal),
+                    SimulatedReduce::Accept => {
+                        return match lookahead {
+                            Some(token) => Err(crate::ParseError::ExtraToken { token }),
+                            None => Ok(self.trees.pop().unwrap()),
+                        };
+                    }
+                }
+            } else {
+                return Err(self.unrecognized(lookahead));
+            }
+        }
+    }
+
+    fn reduce(&mut self, states_to_pop: usize, nonterminal: usize, follow: Option<usize>) {
+        let len = self.trees.len() - states_to_pop;
+        let children: Vec<_> = self.trees.drain(len..).collect();
+        self.states.truncate(self.states.len() - states_to_pop);
+
+        let top_state = *self.states.last().unwrap();
+        let (start, end) = match (children.first(), children.last()) {
+            (Some(first), Some(last)) => (first.start, last.end),
+            _ => (self.last_location, self.last_location),
+        };
+        self.trees.push(ParseTree {
+            start,
+            end,
+            node: Node::Nonterminal {
+                name: self.nonterminals[nonterminal],
+                children,
+            },
+            index: nonterminal,
+            reuse: Some(Reuse {
+                state: top_state.into(),
+                follow,
+            }),
+        });
+        self.states
+            .push(self.definition.goto(top_state, nonterminal));
+    }
+
+    /// Tries to push a subtree of the old tree that starts at `start`
+    /// instead of shifting the next token.
+    fn reuse(&mut self, start: usize) -> bool {
+        let top_state = *self.states.last().unwrap();
+        let candidates = match self.candidates.remove(&start) {
+            Some(candidates) => candidates,
+            None => return false,
+        };
+
+        for candidate in candidates {
+            let reuse = candidate.tree.reuse.unwrap();
+            if reuse.state != top_state.into() {
+                continue;
+            }
+
+            let mut terminals = Vec::new();
+            candidate.tree.terminals(&mut terminals);
+            if !self.matches(&terminals, candidate.offset, reuse.follow) {
+                continue;
+            }
+
+            let tree = candidate.tree.moved(candidate.offset);
+            self.buffer.drain(..terminals.len());
+            self.last_location = tree.end;
+            self.trees.push(tree);
+            self.states
+                .push(self.definition.goto(top_state, candidate.tree.index));
+            #[cfg(test)]
+            {
+                self.reused += 1;
+            }
+            return true;
+        }
+
+        false
+    }
+
+    /// Whether the next tokens are `terminals` (moved by `offset`),
+    /// followed by the terminal `follow`. The edit did not touch the
+    /// text of any of them, so it suffices to compare their spans.
+    fn matches(&mut self, terminals: &[&ParseTree], offset: isize, follow: Option<usize>) -> bool {
+        for (i, old) in terminals.iter().enumerate() {
+            self.peek(i);
+            match self.buffer.get(i) {
+                Some(Ok((start, token, end)))
+                    if *start == old.start.wrapping_add_signed(offset)
+                        && *end == old.end.wrapping_add_signed(offset)
+                        && self.definition.token_to_index(token) == Some(old.index) => {}
+                _ => return false,
+            }
+        }
+
+        self.peek(terminals.len());
+        match self.buffer.get(terminals.len()) {
+            Some(Ok((_, token, _))) => {
+                follow.is_some() && self.definition.token_to_index(token) == follow
+            }
+            Some(Err(_)) => false,
+            None => follow.is_none(),
+        }
+    }
+
+    fn peek(&mut self, i: usize) -> Option<&Result<TokenTriple<D>, ParseError<D>>> {
+        while self.buffer.len() <= i {
+            self.buffer.push_back(self.tokens.next()?);
+        }
+        self.buffer.get(i)
+    }
+
+    fn unrecognized(&self, token: Option<TokenTriple<D>>) -> ParseError<D> {
+        let expected = self.definition.expected_tokens_from_states(&self.states);
+        match token {
+            Some(token) => crate::ParseError::UnrecognizedToken { token, expected },
+            None => crate::ParseError::UnrecognizedEof {
+                location: self.last_location,
+                expected,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_machine::{ParseResult, Stack, SymbolTriple};
+    use alloc::vec;
+
+    const TERMINALS: &[&str] = &[r#""(""#, r#""x""#, r#"")""#];
+    const NONTERMINALS: &[&str] = &["S", "I", "__S"];
+
+    // S = S I | I;
+    // I = "(" "x" ")";
+    //
+    // Actions are encoded like the generated tables: `s + 1` shifts to
+    // state `s`, `-(r + 1)` reduces production `r`, and 0 is an error.
+    const ACTION: &[i8] = &[
+        4, 0, 0, // 0
+        4, 0, 0, // 1
+        -2, 0, 0, // 2
+        0, 6, 0, // 3
+        -1, 0, 0, // 4
+        0, 0, 7, // 5
+        -3, 0, 0, // 6
+    ];
+    const EOF_ACTION: &[i8] = &[0, -4, -2, 0, -1, 0, -3];
+
+    struct Items;
+
+    impl ParserDefinition for Items {
+        type Location = usize;
+        type Error = &'static str;
+        type Token = char;
+        type TokenIndex = usize;
+        type Symbol = ();
+        type Success = ();
+        type StateIndex = i8;
+        type Action = i8;
+        type ReduceIndex = i8;
+        type NonterminalIndex = usize;
+
+        fn start_location(&self) -> usize {
+            0
+        }
+
+        fn start_state(&self) -> i8 {
+            0
+        }
+
+        fn token_to_index(&self, token: &char) -> Option<usize> {
+            "(x)".find(*token)
+        }
+
+        fn action(&self, state: i8, token_index: usize) -> i8 {
+            ACTION[state as usize * 3 + token_index]
+        }
+
+        fn error_action(&self, _: i8) -> i8 {
+            0
+        }
+
+        fn eof_action(&self, state: i8) -> i8 {
+            EOF_ACTION[state as usize]
+        }
+
+        fn goto(&self, state: i8, nt: usize) -> i8 {
+            match (state, nt) {
+                (0, 0) => 1,
+                (0, 1) => 2,
+                (1, 1) => 4,
+                _ => unreachable!(),
+            }
+        }
+
+        fn token_to_symbol(&self, _: usize, _: char) {}
+
+        fn expected_tokens(&self, _: i8) -> Vec<String> {
+            vec![]
+        }
+
+        fn uses_error_recovery(&self) -> bool {
+            false
+        }
+
+        fn error_recovery_symbol(&self, _: crate::state_machine::ErrorRecovery<Self>) {}
+
+        fn reduce(
+            &mut self,
+            _: i8,
+            _: Option<&usize>,
+            _: &mut impl Stack<i8>,
+            _: &mut impl Stack<SymbolTriple<Self>>,
+        ) -> Option<ParseResult<Self>> {
+            unreachable!()
+        }
+
+        fn simulate_reduce(&self, action: i8) -> SimulatedReduce<Self> {
+            let (states_to_pop, nonterminal_produced) = match action {
+                0 => (2, 0),
+                1 => (1, 0),
+                2 => (3, 1),
+                _ => return SimulatedReduce::Accept,
+            };
+            SimulatedReduce::Reduce {
+                states_to_pop,
+                nonterminal_produced,
+            }
+        }
+    }
+
+    fn tokens(
+        text: &str,
+    ) -> impl Iterator<Item = Result<TokenTriple<Items>, ParseError<Items>>> + '_ {
+        text.char_indices().map(|(i, c)| Ok((i, c, i + 1)))
+    }
+
+    /// Reparses `text` after `edit`, returning the new tree and how many
+    /// subtrees were taken from the old one.
+    fn reparse(text: &str, edit: Edit<'_>) -> (ParseTree, usize) {
+        let old = parse(Items, TERMINALS, NONTERMINALS, tokens(text), None).unwrap();
+        let text = edit.apply(text);
+        let fresh = parse(Items, TERMINALS, NONTERMINALS, tokens(&text), None).unwrap();
+
+        let mut builder = TreeBuilder::new(
+            Items,
+            TERMINALS,
+            NONTERMINALS,
+            tokens(&text),
+            Some((&old, &edit)),
+        );
+        let tree = builder.parse().unwrap();
+        assert_eq!(tree, fresh);
+        (tree, builder.reused)
+    }
+
+    #[test]
+    fn reuses_untouched_subtree() {
+        let edit = Edit {
+            start: 4,
+            deleted: 1,
+            inserted: "x",
+        };
+        let (tree, reused) = reparse("(x)(x)", edit);
+        assert_eq!(reused, 1);
+        assert_eq!(tree.children()[0].text("(x)(x)"), "(x)");
+    }
+
+    #[test]
+    fn refuses_subtree_with_other_follow() {
+        // the first item used to be followed by "(", now it ends the input
+        let edit = Edit {
+            start: 3,
+            deleted: 3,
+            inserted: "",
+        };
+        let (_, reused) = reparse("(x)(x)", edit);
+        assert_eq!(reused, 0);
+    }
+}
@@ -1,4 +1,4 @@
-use std::{fmt, marker::PhantomData};
+use std::{convert::Infallible, fmt, marker::PhantomData};
 
 use crate::ParseError;
 
@@ -8,6 +8,9 @@ use regex_automata::nfa::thompson::Config as NfaConfig;
 use regex_automata::util::syntax::Config as SyntaxConfig;
 use regex_automata::{Anchored, Input, MatchKind};
 
+/// A token of the built-in lexer: the index of the terminal it matched
+/// and the text it matched, which borrows from the input rather than
+/// being copied out of it.
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Token<'input>(pub usize, pub &'input str);
 impl<'a> fmt::Display for Token<'a> {
@@ -68,6 +71,9 @@ impl MatcherBuilder {
     }
 }
 
+/// Splits `text` into tokens, yielding each with its start and end byte
+/// offsets. Matching does not allocate, except for the lazily built
+/// states of the DFA, which are cached for the life of the matcher.
 pub struct Matcher<'input, 'builder, E> {
     text: &'input str,
     consumed: usize,
@@ -138,3 +144,75 @@ impl<'input, 'builder, E> Iterator for Matcher<'input, 'builder, E> {
         }
     }
 }
+
+/// A lexer made of `(pattern, name)` rules, for grammars that declare
+/// `extern token &'input str` instead of matching their own regular
+/// expressions. At each point of the input, the rule with the longest
+/// match wins, and of those, the one listed first. The text matched by a
+/// rule whose name is empty, like whitespace, is skipped.
+pub struct RegexLexer {
+    builder: MatcherBuilder,
+    names: Vec<String>,
+}
+
+impl RegexLexer {
+    /// Builds the lexer from its rules, in order of priority: each is a
+    /// regular expression and the name of the token it produces, which
+    /// is the terminal that stands for it in the grammar. Fails if a
+    /// pattern is not a valid regular expression.
+    #[allow(clippy::result_large_err)]
+    pub fn new(rules: &[(&str, &str)]) -> Result<RegexLexer, BuildError> {
+        // `Matcher` prefers the last pattern of those with the longest
+        // match, so the rules are handed over in reverse
+        let builder = MatcherBuilder::new(
+            rules
+                .iter()
+                .rev()
+                .map(|&(pattern, name)| (pattern, name.is_empty())),
+        )?;
+        let names = rules.iter().rev().map(|&(_, name)| name.into()).collect();
+        Ok(RegexLexer { builder, names })
+    }
+
+    /// Splits `text` into `(start, name, end)` triples, where `name` is
+    /// the name of the rule that matched `text[start..end]`.
+    pub fn tokens<'a>(&'a self, text: &'a str) -> RegexTokens<'a> {
+        RegexTokens {
+            matcher: self.builder.matcher(text),
+            names: &self.names,
+        }
+    }
+}
+
+/// No rule of a [`RegexLexer`] matches the input at `location`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LexError {
+    pub location: usize,
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "Invalid token at {}", self.location)
+    }
+}
+
+impl std::error::Error for LexError {}
+
+/// The tokens of a text, as split by [`RegexLexer::tokens`].
+pub struct RegexTokens<'a> {
+    matcher: Matcher<'a, 'a, Infallible>,
+    names: &'a [String],
+}
+
+impl<'a> Iterator for RegexTokens<'a> {
+    type Item = Result<(usize, &'a str, usize), LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = match self.matcher.next()? {
+            Ok((start, Token(index, _), end)) => Ok((start, &*self.names[index], end)),
+            Err(ParseError::InvalidToken { location }) => Err(LexError { location }),
+            Err(_) => unreachable!("the matcher only reports invalid tokens"),
+        };
+        Some(result)
+    }
+}
@@ -2,16 +2,26 @@
 
 extern crate alloc;
 
-use alloc::{string::String, vec::Vec};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
 use core::fmt;
 #[cfg(feature = "std")]
 use std::error::Error;
 
+pub mod incremental;
 #[cfg(feature = "lexer")]
 pub mod lexer;
 pub mod state_machine;
+#[cfg(feature = "serialize-tables")]
+pub mod tables;
+pub mod token;
 
+/// The errors a generated parser can return. New variants may be added,
+/// so a `match` on one needs a wildcard arm.
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[non_exhaustive]
 pub enum ParseError<L, T, E> {
     /// Generated by the parser when it encounters a token (or EOF) it did not
     /// expect.
@@ -39,9 +49,31 @@ pub enum ParseError<L, T, E> {
         expected: Vec<String>,
     },
 
+    /// Generated instead of `UnrecognizedToken` or `UnrecognizedEof` when
+    /// the parser has passed a cut (`^`) in the grammar, and so knows which
+    /// alternative it is in.
+    UnrecognizedAfter {
+        /// The symbols before the cut, as written in the grammar.
+        after: String,
+
+        /// The unexpected token, or `None` if the input ended.
+        token: Option<(L, T, L)>,
+
+        /// The start of `token`, or the end of the final token.
+        location: L,
+
+        /// The set of expected tokens, as for `UnrecognizedToken`.
+        expected: Vec<String>,
+    },
+
     /// Generated by the parser when it encounters additional, unexpected tokens.
     ExtraToken { token: (L, T, L) },
 
+    /// Generated by a parser with a bounded stack (see the
+    /// `#[no_std(stack_depth = "N")]` grammar annotation) when the input
+    /// nests more deeply than the stack allows.
+    StackOverflow { location: L },
+
     /// Custom error type.
     User { error: E },
 }
@@ -66,9 +98,23 @@ impl<L, T, E> ParseError<L, T, E> {
                 token: maptok(token),
                 expected,
             },
+            ParseError::UnrecognizedAfter {
+                after,
+                token,
+                location,
+                expected,
+            } => ParseError::UnrecognizedAfter {
+                after,
+                token: token.map(maptok),
+                location: loc_op(location),
+                expected,
+            },
             ParseError::ExtraToken { token } => ParseError::ExtraToken {
                 token: maptok(token),
             },
+            ParseError::StackOverflow { location } => ParseError::StackOverflow {
+                location: loc_op(location),
+            },
             ParseError::User { error } => ParseError::User {
                 error: err_op(error),
             },
@@ -86,6 +132,49 @@ impl<L, T, E> ParseError<L, T, E> {
     pub fn map_error<EE>(self, op: impl FnOnce(E) -> EE) -> ParseError<L, T, EE> {
         self.map_intern(|x| x, |x| x, op)
     }
+
+    /// The error returned by a fallible action, if this is a `User`
+    /// error. `Error::source` does not return it: that would need
+    /// `E: Error + 'static`, which the default `&str` error type is not,
+    /// so the `Error` impl only asks for a printable error type.
+    pub fn user_error(&self) -> Option<&E> {
+        match self {
+            ParseError::User { error } => Some(error),
+            _ => None,
+        }
+    }
+
+    /// The tokens the parser could have accepted where the error
+    /// happened, named as in the grammar. Only `UnrecognizedToken`,
+    /// `UnrecognizedEof` and `UnrecognizedAfter` errors have any.
+    pub fn expected_tokens(&self) -> &[String] {
+        match self {
+            ParseError::UnrecognizedEof { expected, .. }
+            | ParseError::UnrecognizedToken { expected, .. }
+            | ParseError::UnrecognizedAfter { expected, .. } => expected,
+            ParseError::InvalidToken { .. }
+            | ParseError::ExtraToken { .. }
+            | ParseError::StackOverflow { .. }
+            | ParseError::User { .. } => &[],
+        }
+    }
+}
+
+impl<L, T: fmt::Display, E> ParseError<L, T, E> {
+    /// Turns the token of an error into the `String` it displays as, so
+    /// that the error no longer borrows from the input.
+    pub fn into_owned(self) -> ParseError<L, String, E> {
+        self.map_token(|token| token.to_string())
+    }
+}
+
+impl<T, E> ParseError<usize, T, E> {
+    /// Turns the byte offsets of an error into lines and columns of
+    /// `text`, the input that was parsed.
+    pub fn with_line_col(self, text: &str) -> ParseError<Location, T, E> {
+        let index = LineIndex::new(text);
+        self.map_location(|offset| index.location(offset))
+    }
 }
 
 /// Format a list of expected tokens.
@@ -134,9 +223,30 @@ where
                 )?;
                 fmt_expected(f, expected)
             }
+            UnrecognizedAfter {
+                ref after,
+                ref token,
+                ref location,
+                ref expected,
+            } => {
+                match *token {
+                    Some((ref start, ref token, ref end)) => write!(
+                        f,
+                        "Unrecognized token `{}` found at {}:{} after `{}`",
+                        token, start, end, after
+                    )?,
+                    None => write!(
+                        f,
+                        "Unrecognized EOF found at {} after `{}`",
+                        location, after
+                    )?,
+                }
+                fmt_expected(f, expected)
+            }
             ExtraToken {
                 token: (ref start, ref token, ref end),
             } => write!(f, "Extra token {} found at {}:{}", token, start, end),
+            StackOverflow { ref location } => write!(f, "Parser stack overflow at {}", location),
         }
     }
 }
@@ -154,9 +264,6 @@ where
     T: fmt::Debug + fmt::Display,
     E: fmt::Debug + fmt::Display,
 {
-    fn description(&self) -> &str {
-        "parse error"
-    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -165,6 +272,132 @@ pub struct ErrorRecovery<L, T, E> {
     pub dropped_tokens: Vec<(L, T, L)>,
 }
 
+/// Where something starts and ends in the input of a parser. Writing
+/// `@span` in action code yields the span of the alternative, from its
+/// start (`@L`) to its end (`@R`).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Span<L = usize> {
+    pub start: L,
+    pub end: L,
+}
+
+impl<L> Span<L> {
+    pub fn new(start: L, end: L) -> Self {
+        Span { start, end }
+    }
+}
+
+impl<L: Copy + Ord> Span<L> {
+    /// The smallest span that encloses both `self` and `other`.
+    pub fn merge(self, other: Span<L>) -> Span<L> {
+        Span {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+
+    /// Whether `other` lies within `self`.
+    pub fn contains(&self, other: Span<L>) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+}
+
+/// A node of the concrete syntax tree built by the parsers of
+/// `#[emit_cst]` grammars, which return it next to the value of the
+/// action code.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ParseTreeNode<L = usize> {
+    /// A terminal, named as in the grammar, like `"+"` or `Num`.
+    Terminal { token: &'static str, span: Span<L> },
+
+    /// A nonterminal, with a child for each symbol of the alternative
+    /// that was matched. `@L`, `@R` and lookahead assertions match no
+    /// input and have no node.
+    Nonterminal {
+        name: &'static str,
+        children: Vec<ParseTreeNode<L>>,
+        span: Span<L>,
+    },
+}
+
+impl<L> ParseTreeNode<L> {
+    pub fn span(&self) -> &Span<L> {
+        match self {
+            ParseTreeNode::Terminal { span, .. } | ParseTreeNode::Nonterminal { span, .. } => span,
+        }
+    }
+
+    /// The children of a nonterminal, or none for a terminal.
+    pub fn children(&self) -> &[ParseTreeNode<L>] {
+        match self {
+            ParseTreeNode::Terminal { .. } => &[],
+            ParseTreeNode::Nonterminal { children, .. } => children,
+        }
+    }
+}
+
+impl ParseTreeNode<usize> {
+    /// The text of `input` that the node matched, like the text of a
+    /// token for a terminal, so that a tree can be printed back out
+    /// without keeping the tokens around.
+    pub fn text<'input>(&self, input: &'input str) -> &'input str {
+        let span = self.span();
+        &input[span.start..span.end]
+    }
+}
+
+/// A position in the input of a parser, as a byte offset and as a line
+/// and column. Lines and columns are counted from 1, and columns count
+/// `char`s rather than bytes. It is displayed as `line:col`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Location {
+    pub byte_offset: usize,
+    pub line: usize,
+    pub col: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.col)
+    }
+}
+
+/// The offsets at which the lines of a text start, for turning the byte
+/// offsets used by parsers into `Location`s. Errors can be converted
+/// with `ParseError::with_line_col`.
+#[derive(Clone, Debug)]
+pub struct LineIndex<'text> {
+    text: &'text str,
+    line_starts: Vec<usize>,
+}
+
+impl<'text> LineIndex<'text> {
+    pub fn new(text: &'text str) -> Self {
+        let line_starts = core::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        LineIndex { text, line_starts }
+    }
+
+    /// The location of `byte_offset`, which must be at most the length
+    /// of the text. An offset inside a `char` counts as that `char`.
+    pub fn location(&self, byte_offset: usize) -> Location {
+        let line = self
+            .line_starts
+            .partition_point(|&start| start <= byte_offset);
+        let line_start = self.line_starts[line - 1];
+        let col = self.text[line_start..]
+            .char_indices()
+            .take_while(|&(i, c)| line_start + i + c.len_utf8() <= byte_offset)
+            .count();
+        Location {
+            byte_offset,
+            line,
+            col: col + 1,
+        }
+    }
+}
+
 /// Define a module using the generated parse from a `.lalrpop` file.
 ///
 /// You have to specify the name of the module and the path of the file
@@ -218,5 +451,109 @@ mod tests {
             "Unrecognized token `t0` found at 1:2\n\
              Expected one of t1, t2 or t3"
         );
+        assert_eq!(err.expected_tokens(), ["t1", "t2", "t3"]);
+
+        let eof = ParseError::UnrecognizedEof::<i32, &str, &str> {
+            location: 2,
+            expected: err.expected_tokens().to_vec(),
+        };
+        assert_eq!(
+            format!("{}", eof),
+            "Unrecognized EOF found at 2\n\
+             Expected one of t1, t2 or t3"
+        );
+        assert_eq!(eof.expected_tokens(), err.expected_tokens());
+
+        let err = ParseError::InvalidToken::<i32, &str, &str> { location: 1 };
+        assert!(err.expected_tokens().is_empty());
+    }
+
+    #[test]
+    fn line_index() {
+        let index = LineIndex::new("ab\nçd\n");
+        let line_col = |offset| {
+            let location = index.location(offset);
+            (location.line, location.col)
+        };
+        assert_eq!(line_col(0), (1, 1));
+        assert_eq!(line_col(2), (1, 3));
+        assert_eq!(line_col(3), (2, 1));
+        // `ç` takes two bytes
+        assert_eq!(line_col(5), (2, 2));
+        assert_eq!(line_col(7), (3, 1));
+
+        let err = ParseError::UnrecognizedToken::<usize, &str, &str> {
+            token: (5, "d", 6),
+            expected: vec![],
+        };
+        assert_eq!(
+            format!("{}", err.with_line_col("ab\nçd\n")),
+            "Unrecognized token `d` found at 2:2:2:3"
+        );
+    }
+
+    #[test]
+    fn into_owned() {
+        let err: ParseError<usize, String, &str> = {
+            let input = String::from("ab");
+            ParseError::ExtraToken::<usize, &str, &str> {
+                token: (0, &input[..1], 1),
+            }
+            .into_owned()
+        };
+        assert_eq!(
+            err,
+            ParseError::ExtraToken {
+                token: (0, "a".to_string(), 1),
+            }
+        );
+    }
+
+    #[test]
+    fn span() {
+        let a = Span::new(2, 5);
+        let b = Span::new(4, 9);
+        assert_eq!(a.merge(b), Span::new(2, 9));
+        assert_eq!(b.merge(a), Span::new(2, 9));
+        assert!(a.merge(b).contains(a));
+        assert!(a.contains(a));
+        assert!(a.contains(Span::new(3, 3)));
+        assert!(!a.contains(b));
+    }
+
+    #[test]
+    fn user_error() {
+        let err = ParseError::User::<i32, &str, &str> { error: "too big" };
+        assert_eq!(err.user_error(), Some(&"too big"));
+
+        let err = ParseError::InvalidToken::<i32, &str, &str> { location: 1 };
+        assert!(err.user_error().is_none());
+    }
+
+    // the default error type, `&'static str`, does not implement `Error`
+    #[cfg(feature = "std")]
+    #[test]
+    fn boxed_with_str_error() {
+        fn parse() -> Result<(), Box<dyn Error>> {
+            Err(ParseError::User::<i32, &str, &str> { error: "too big" })?
+        }
+        assert_eq!(parse().unwrap_err().to_string(), "too big");
+    }
+
+    #[test]
+    fn array_stack() {
+        use state_machine::{ArrayStack, Stack};
+
+        let mut stack = ArrayStack::<String, 2>::default();
+        stack.push("a".to_string());
+        stack.push("b".to_string());
+        assert!(stack.is_full());
+        assert_eq!(stack.to_vec(), ["a", "b"]);
+        assert_eq!(stack.last().map(String::as_str), Some("b"));
+
+        stack.truncate(1);
+        assert_eq!((stack.len(), &stack[0][..]), (1, "a"));
+        assert_eq!(stack.pop().as_deref(), Some("a"));
+        assert_eq!(stack.pop(), None);
     }
 }
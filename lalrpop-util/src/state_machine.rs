@@ -1,5 +1,8 @@
 use alloc::{string::String, vec, vec::Vec};
 use core::fmt::Debug;
+use core::ops::Index;
+use core::pin::Pin;
+use core::task::{Context, Poll};
 
 #[cfg(feature = "std")]
 const DEBUG_ENABLED: bool = false;
@@ -13,6 +16,24 @@ macro_rules! debug {
     }
 }
 
+/// Prints an action of the parser to stderr, if it was driven while
+/// `LALRPOP_LOG=trace` was set. Without the `trace` feature, this
+/// compiles to nothing.
+macro_rules! trace {
+    ($parser:expr, $($args:expr),* $(,)*) => {
+        #[cfg(feature = "trace")]
+        if $parser.trace {
+            eprintln!($($args),*);
+        }
+    }
+}
+
+/// Whether the `LALRPOP_LOG` environment variable asks for a trace.
+#[cfg(feature = "trace")]
+fn trace_enabled() -> bool {
+    std::env::var_os("LALRPOP_LOG").is_some_and(|log| log == "trace")
+}
+
 pub trait ParserDefinition: Sized {
     /// Represents a location in the input text. If you are using the
     /// default tokenizer, this will be a `usize`.
@@ -89,6 +110,20 @@ pub trait ParserDefinition: Sized {
     /// not to the user's terminals but to the "special terminal" `$`.
     fn eof_action(&self, state: Self::StateIndex) -> Self::Action;
 
+    /// Returns the actions, beyond the one returned by `action` (or
+    /// `eof_action`, if `token_index` is `None`), that are also valid
+    /// in the given state. Only grammars annotated with `#[ambiguous]`
+    /// have such conflicting actions; they are explored by
+    /// `GlrParser` and ignored by `Parser`.
+    fn conflicting_actions(
+        &self,
+        state: Self::StateIndex,
+        token_index: Option<Self::TokenIndex>,
+    ) -> &[Self::Action] {
+        let _ = (state, token_index);
+        &[]
+    }
+
     /// If we reduce to a nonterminal in the given state, what state
     /// do we go to? This is infallible due to the nature of LR(1)
     /// grammars.
@@ -110,6 +145,14 @@ pub trait ParserDefinition: Sized {
         self.expected_tokens(*states.last().unwrap())
     }
 
+    /// Returns the symbols before the cut (`^`) of the alternative that
+    /// the parser has committed to in the given state, if there is one.
+    /// This is used for error reporting.
+    fn cut_context(&self, state: Self::StateIndex) -> Option<&'static str> {
+        let _ = state;
+        None
+    }
+
     /// True if this grammar supports error recovery.
     fn uses_error_recovery(&self) -> bool;
 
@@ -122,8 +165,8 @@ pub trait ParserDefinition: Sized {
     /// current lookahead that is triggering the reduction (it is
     /// `None` for EOF).
     ///
-    /// The `states` and `symbols` vectors represent the internal
-    /// state machine vectors; they are given to `reduce` so that it
+    /// The `states` and `symbols` stacks represent the internal
+    /// state machine stacks; they are given to `reduce` so that it
     /// can pop off states that no longer apply (and consume their
     /// symbols). At the end, it should also push the new state and
     /// symbol produced.
@@ -142,8 +185,8 @@ pub trait ParserDefinition: Sized {
         &mut self,
         reduce_index: Self::ReduceIndex,
         start_location: Option<&Self::Location>,
-        states: &mut Vec<Self::StateIndex>,
-        symbols: &mut Vec<SymbolTriple<Self>>,
+        states: &mut impl Stack<Self::StateIndex>,
+        symbols: &mut impl Stack<SymbolTriple<Self>>,
     ) -> Option<ParseResult<Self>>;
 
     /// Returns information about how many states will be popped
@@ -185,26 +228,177 @@ pub type Symbol<D> = <D as ParserDefinition>::Symbol;
 
 pub type ParseError<D> = crate::ParseError<Location<D>, Token<D>, Error<D>>;
 pub type ParseResult<D> = Result<Success<D>, ParseError<D>>;
+pub type PartialParseResult<D> = Result<(Success<D>, Option<TokenTriple<D>>), ParseError<D>>;
 pub type TokenTriple<D> = (Location<D>, Token<D>, Location<D>);
 pub type SymbolTriple<D> = (Location<D>, Symbol<D>, Location<D>);
 pub type ErrorRecovery<D> = crate::ErrorRecovery<Location<D>, Token<D>, Error<D>>;
 
-pub struct Parser<D, I>
+/// The storage of one of the stacks of a `Parser`: a `Vec`, which
+/// grows as needed, or an `ArrayStack`, which has a fixed capacity and
+/// never allocates.
+pub trait Stack<T>: Index<usize, Output = T> {
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether there is no room to push another element.
+    fn is_full(&self) -> bool;
+
+    /// Pushes `value` on top of the stack. The caller checks
+    /// `is_full` first.
+    fn push(&mut self, value: T);
+
+    fn pop(&mut self) -> Option<T>;
+
+    /// Pops elements until only `len` are left.
+    fn truncate(&mut self, len: usize);
+
+    fn get(&self, index: usize) -> Option<&T> {
+        (index < self.len()).then(|| &self[index])
+    }
+
+    fn last(&self) -> Option<&T> {
+        self.len().checked_sub(1).map(|index| &self[index])
+    }
+
+    /// Copies the elements, from the bottom of the stack to the top.
+    fn to_vec(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        (0..self.len()).map(|index| self[index].clone()).collect()
+    }
+}
+
+impl<T> Stack<T> for Vec<T> {
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn is_full(&self) -> bool {
+        false
+    }
+
+    fn push(&mut self, value: T) {
+        Vec::push(self, value)
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        Vec::pop(self)
+    }
+
+    fn truncate(&mut self, len: usize) {
+        Vec::truncate(self, len)
+    }
+}
+
+/// A stack of at most `N` elements, stored inline. It is used by
+/// grammars annotated with `#[no_std(stack_depth = "N")]`, so that
+/// parsing needs no heap memory for the stack, which lives wherever the
+/// parser does.
+pub struct ArrayStack<T, const N: usize> {
+    items: [Option<T>; N],
+    len: usize,
+}
+
+impl<T, const N: usize> Default for ArrayStack<T, N> {
+    fn default() -> Self {
+        ArrayStack {
+            items: core::array::from_fn(|_| None),
+            len: 0,
+        }
+    }
+}
+
+impl<T, const N: usize> Index<usize> for ArrayStack<T, N> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        assert!(index < self.len, "stack index out of bounds");
+        self.items[index].as_ref().unwrap()
+    }
+}
+
+impl<T, const N: usize> Stack<T> for ArrayStack<T, N> {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    fn push(&mut self, value: T) {
+        assert!(!self.is_full(), "stack overflow");
+        self.items[self.len] = Some(value);
+        self.len += 1;
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.len = self.len.checked_sub(1)?;
+        self.items[self.len].take()
+    }
+
+    fn truncate(&mut self, len: usize) {
+        while self.len > len {
+            self.pop();
+        }
+    }
+}
+
+/// Where a `Parser` reads its tokens from: an iterator, or the tokens
+/// an `AsyncParser` receives from a `TokenStream`.
+pub trait TokenSource<D: ParserDefinition> {
+    /// The next token, `None` at the end of the input, or
+    /// `Poll::Pending` if the next token has not arrived yet.
+    fn poll_token(&mut self) -> Poll<Option<Result<TokenTriple<D>, ParseError<D>>>>;
+}
+
+impl<D, I> TokenSource<D> for I
 where
     D: ParserDefinition,
     I: Iterator<Item = Result<TokenTriple<D>, ParseError<D>>>,
+{
+    fn poll_token(&mut self) -> Poll<Option<Result<TokenTriple<D>, ParseError<D>>>> {
+        Poll::Ready(self.next())
+    }
+}
+
+pub struct Parser<D, I, S = Vec<<D as ParserDefinition>::StateIndex>, Y = Vec<SymbolTriple<D>>>
+where
+    D: ParserDefinition,
+    I: TokenSource<D>,
 {
     definition: D,
     tokens: I,
-    states: Vec<D::StateIndex>,
-    symbols: Vec<SymbolTriple<D>>,
+    states: S,
+    symbols: Y,
     last_location: D::Location,
+
+    /// Whether to stop, rather than fail, at a token that cannot
+    /// continue the input read so far, if that input is complete.
+    partial: bool,
+
+    /// The token a partial parse stopped at, if any.
+    stopped_at: Option<TokenTriple<D>>,
+
+    /// The error being recovered from and the tokens dropped so far,
+    /// if error recovery was waiting for the next token.
+    recovering: Option<(ParseError<D>, Vec<TokenTriple<D>>)>,
+
+    /// Whether to print each action taken to stderr.
+    #[cfg(feature = "trace")]
+    trace: bool,
 }
 
 enum NextToken<D: ParserDefinition> {
     FoundToken(TokenTriple<D>, D::TokenIndex),
     Eof,
     Done(ParseResult<D>),
+    /// The next token has not arrived yet.
+    Pending,
 }
 
 impl<D, I> Parser<D, I>
@@ -213,32 +407,115 @@ where
     I: Iterator<Item = Result<TokenTriple<D>, ParseError<D>>>,
 {
     pub fn drive(definition: D, tokens: I) -> ParseResult<D> {
+        Self::new(definition, tokens).parse()
+    }
+
+    /// Like `drive`, but the stack is an `ArrayStack`, which never
+    /// holds more than `N` states and does not allocate; input that
+    /// would need a deeper stack yields `ParseError::StackOverflow`.
+    pub fn drive_with_stack_depth<const N: usize>(definition: D, tokens: I) -> ParseResult<D> {
+        BoundedParser::<D, I, N>::new(definition, tokens).parse()
+    }
+
+    /// Like `drive`, but parses only as much of the input as it can:
+    /// at the first token that cannot continue the input read so far,
+    /// it stops, and returns that token alongside the result, if the
+    /// input before it is complete. Tokens after it are not read.
+    pub fn drive_partial(definition: D, tokens: I) -> PartialParseResult<D> {
+        Self::new(definition, tokens).parse_partial()
+    }
+
+    /// `drive_partial` with the stack of `drive_with_stack_depth`.
+    pub fn drive_partial_with_stack_depth<const N: usize>(
+        definition: D,
+        tokens: I,
+    ) -> PartialParseResult<D> {
+        BoundedParser::<D, I, N>::new(definition, tokens).parse_partial()
+    }
+}
+
+/// A `Parser` whose stacks hold at most `N` states.
+type BoundedParser<D, I, const N: usize> = Parser<
+    D,
+    I,
+    ArrayStack<<D as ParserDefinition>::StateIndex, N>,
+    ArrayStack<SymbolTriple<D>, N>,
+>;
+
+impl<D, I, S, Y> Parser<D, I, S, Y>
+where
+    D: ParserDefinition,
+    I: Iterator<Item = Result<TokenTriple<D>, ParseError<D>>>,
+    S: Stack<D::StateIndex> + Default,
+    Y: Stack<SymbolTriple<D>> + Default,
+{
+    fn parse_partial(mut self) -> PartialParseResult<D> {
+        self.partial = true;
+        let success = self.parse()?;
+        Ok((success, self.stopped_at))
+    }
+
+    fn parse(&mut self) -> ParseResult<D> {
+        match self.poll_parse() {
+            Poll::Ready(result) => result,
+            Poll::Pending => unreachable!("an iterator never waits for its next token"),
+        }
+    }
+}
+
+impl<D, I, S, Y> Parser<D, I, S, Y>
+where
+    D: ParserDefinition,
+    I: TokenSource<D>,
+    S: Stack<D::StateIndex> + Default,
+    Y: Stack<SymbolTriple<D>> + Default,
+{
+    fn new(definition: D, tokens: I) -> Self {
         let last_location = definition.start_location();
-        let start_state = definition.start_state();
+        let mut states = S::default();
+        states.push(definition.start_state());
         Parser {
             definition,
             tokens,
-            states: vec![start_state],
-            symbols: vec![],
+            states,
+            symbols: Y::default(),
             last_location,
+            partial: false,
+            stopped_at: None,
+            recovering: None,
+            #[cfg(feature = "trace")]
+            trace: trace_enabled(),
         }
-        .parse()
+    }
+
+    /// Whether there is no room to push one more state. The symbols
+    /// stack always holds one element fewer than the states stack.
+    fn stack_full(&self) -> bool {
+        self.states.is_full()
     }
 
     fn top_state(&self) -> D::StateIndex {
         *self.states.last().unwrap()
     }
 
-    fn parse(&mut self) -> ParseResult<D> {
+    /// Parses until the input ends, or until the next token has not
+    /// arrived yet. In that case, it can be called again once it has,
+    /// and it picks up where it left off.
+    fn poll_parse(&mut self) -> Poll<ParseResult<D>> {
         // Outer loop: each time we continue around this loop, we
         // shift a new token from the input. We break from the loop
         // when the end of the input is reached (we return early if an
         // error occurs).
         'shift: loop {
-            let (mut lookahead, mut token_index) = match self.next_token() {
+            let next = match self.recovering.take() {
+                Some(recovering) => self.resume_error_recovery(recovering),
+                None => self.next_token(),
+            };
+            let (mut lookahead, mut token_index) = match next {
                 NextToken::FoundToken(l, i) => (l, i),
-                NextToken::Eof => return self.parse_eof(),
-                NextToken::Done(e) => return e,
+                NextToken::Eof => return Poll::Ready(self.parse_eof()),
+                NextToken::Done(e) => return Poll::Ready(e),
+                NextToken::Pending => return Poll::Pending,
             };
 
             debug!("+ SHIFT: {:?}", lookahead);
@@ -252,6 +529,19 @@ where
 
                 if let Some(target_state) = action.as_shift() {
                     debug!("\\ shift to: {:?}", target_state);
+                    trace!(
+                        self,
+                        "state {:?}, lookahead {:?}: shift to state {:?}",
+                        top_state,
+                        lookahead.1,
+                        target_state
+                    );
+
+                    if self.stack_full() {
+                        return Poll::Ready(Err(crate::ParseError::StackOverflow {
+                            location: lookahead.0,
+                        }));
+                    }
 
                     // Shift and transition to state `action - 1`
                     let symbol = self.definition.token_to_symbol(token_index, lookahead.1);
@@ -260,16 +550,43 @@ where
                     continue 'shift;
                 } else if let Some(reduce_index) = action.as_reduce() {
                     debug!("\\ reduce to: {:?}", reduce_index);
+                    trace!(
+                        self,
+                        "state {:?}, lookahead {:?}: reduce by rule {:?}",
+                        top_state,
+                        lookahead.1,
+                        reduce_index
+                    );
 
                     if let Some(r) = self.reduce(reduce_index, Some(&lookahead.0)) {
-                        return match r {
+                        return Poll::Ready(match r {
+                            Ok(success) if self.partial => {
+                                self.stopped_at = Some(lookahead);
+                                Ok(success)
+                            }
                             // we reached eof, but still have lookahead
                             Ok(_) => Err(crate::ParseError::ExtraToken { token: lookahead }),
                             Err(e) => Err(e),
-                        };
+                        });
                     }
+                } else if self.partial && self.accepts_eof() {
+                    trace!(
+                        self,
+                        "state {:?}, lookahead {:?}: stop",
+                        top_state,
+                        lookahead.1
+                    );
+                    self.last_location = lookahead.0.clone();
+                    self.stopped_at = Some(lookahead);
+                    return Poll::Ready(self.parse_eof());
                 } else {
                     debug!("\\ error -- initiating error recovery!");
+                    trace!(
+                        self,
+                        "state {:?}, lookahead {:?}: error",
+                        top_state,
+                        lookahead.1
+                    );
 
                     match self.error_recovery(Some(lookahead), Some(token_index)) {
                         NextToken::FoundToken(l, i) => {
@@ -277,8 +594,9 @@ where
                             token_index = i;
                             continue 'inner;
                         }
-                        NextToken::Eof => return self.parse_eof(),
-                        NextToken::Done(e) => return e,
+                        NextToken::Eof => return Poll::Ready(self.parse_eof()),
+                        NextToken::Done(e) => return Poll::Ready(e),
+                        NextToken::Pending => return Poll::Pending,
                     }
                 }
             }
@@ -291,15 +609,21 @@ where
             let top_state = self.top_state();
             let action = self.definition.eof_action(top_state);
             if let Some(reduce_index) = action.as_reduce() {
-                if let Some(result) =
-                    self.definition
-                        .reduce(reduce_index, None, &mut self.states, &mut self.symbols)
-                {
+                trace!(
+                    self,
+                    "state {:?}, lookahead EOF: reduce by rule {:?}",
+                    top_state,
+                    reduce_index
+                );
+                if let Some(result) = self.reduce(reduce_index, None) {
                     return result;
                 }
             } else {
+                trace!(self, "state {:?}, lookahead EOF: error", top_state);
                 match self.error_recovery(None, None) {
-                    NextToken::FoundToken(..) => panic!("cannot find token at EOF"),
+                    NextToken::FoundToken(..) | NextToken::Pending => {
+                        panic!("cannot find token at EOF")
+                    }
                     NextToken::Done(e) => return e,
                     NextToken::Eof => continue,
                 }
@@ -309,8 +633,8 @@ where
 
     fn error_recovery(
         &mut self,
-        mut opt_lookahead: Option<TokenTriple<D>>,
-        mut opt_token_index: Option<D::TokenIndex>,
+        opt_lookahead: Option<TokenTriple<D>>,
+        opt_token_index: Option<D::TokenIndex>,
     ) -> NextToken<D> {
         debug!(
             "\\+ error_recovery(opt_lookahead={:?}, opt_token_index={:?})",
@@ -321,13 +645,11 @@ where
             debug!("\\ error -- no error recovery!");
 
             return NextToken::Done(Err(
-                self.unrecognized_token_error(opt_lookahead, &self.states)
+                self.unrecognized_token_error(opt_lookahead, &self.states.to_vec())
             ));
         }
 
-        let error = self.unrecognized_token_error(opt_lookahead.clone(), &self.states);
-
-        let mut dropped_tokens = vec![];
+        let error = self.unrecognized_token_error(opt_lookahead.clone(), &self.states.to_vec());
 
         // We are going to insert ERROR into the lookahead. So, first,
         // perform all reductions from current state triggered by having
@@ -350,18 +672,48 @@ where
             }
         }
 
-        // Now try to find the recovery state.
-        let states_len = self.states.len();
+        self.find_recovery_state(error, vec![], opt_lookahead, opt_token_index)
+    }
+
+    /// Continues the error recovery that was waiting for the token after
+    /// those it dropped.
+    fn resume_error_recovery(
+        &mut self,
+        (error, dropped_tokens): (ParseError<D>, Vec<TokenTriple<D>>),
+    ) -> NextToken<D> {
+        match self.next_token() {
+            NextToken::FoundToken(lookahead, token_index) => {
+                self.find_recovery_state(error, dropped_tokens, Some(lookahead), Some(token_index))
+            }
+            NextToken::Eof => self.find_recovery_state(error, dropped_tokens, None, None),
+            NextToken::Done(e) => NextToken::Done(e),
+            NextToken::Pending => {
+                self.recovering = Some((error, dropped_tokens));
+                NextToken::Pending
+            }
+        }
+    }
+
+    /// Finds the state to shift the error token in, dropping tokens
+    /// until one of the states can accept the lookahead after it.
+    fn find_recovery_state(
+        &mut self,
+        error: ParseError<D>,
+        mut dropped_tokens: Vec<TokenTriple<D>>,
+        mut opt_lookahead: Option<TokenTriple<D>>,
+        mut opt_token_index: Option<D::TokenIndex>,
+    ) -> NextToken<D> {
+        let states = self.states.to_vec();
+        let states_len = states.len();
         let top = 'find_state: loop {
             // Go backwards through the states...
             debug!(
                 "\\\\+ error_recovery: find_state loop, {:?} states = {:?}",
-                self.states.len(),
-                self.states,
+                states_len, states,
             );
 
             for top in (0..states_len).rev() {
-                let state = self.states[top];
+                let state = states[top];
                 debug!("\\\\\\ top = {:?}, state = {:?}", top, state);
 
                 // ...fetch action for error token...
@@ -370,7 +722,7 @@ where
                 if let Some(error_state) = action.as_shift() {
                     // If action is a shift that takes us into `error_state`,
                     // and `error_state` can accept this lookahead, we are done.
-                    if self.accepts(error_state, &self.states[..=top], opt_token_index) {
+                    if self.accepts(error_state, &states[..=top], opt_token_index) {
                         debug!("\\\\\\ accepted!");
                         break 'find_state top;
                     }
@@ -415,6 +767,10 @@ where
                             debug!("\\\\\\ no more tokens");
                             return NextToken::Done(e);
                         }
+                        NextToken::Pending => {
+                            self.recovering = Some((error, dropped_tokens));
+                            return NextToken::Pending;
+                        }
                     }
                 }
             }
@@ -497,6 +853,10 @@ where
         self.states.truncate(top + 1);
         self.symbols.truncate(top);
 
+        if self.stack_full() {
+            return NextToken::Done(Err(crate::ParseError::StackOverflow { location: start }));
+        }
+
         let recover_state = self.states[top];
         let error_action = self.definition.error_action(recover_state);
         let error_state = error_action.as_shift().unwrap();
@@ -586,11 +946,30 @@ where
         }
     }
 
+    /// Whether the input read so far is complete, that is, whether the
+    /// parse would succeed if the input ended here.
+    fn accepts_eof(&self) -> bool {
+        let states = self.states.to_vec();
+        let (top, states) = states.split_last().unwrap();
+        self.accepts(*top, states, None)
+    }
+
     fn reduce(
         &mut self,
         action: D::ReduceIndex,
         lookahead_start: Option<&D::Location>,
     ) -> Option<ParseResult<D>> {
+        // only reducing an empty production grows the stack
+        if self.stack_full() {
+            if let SimulatedReduce::Reduce {
+                states_to_pop: 0, ..
+            } = self.definition.simulate_reduce(action)
+            {
+                let location = lookahead_start.unwrap_or(&self.last_location).clone();
+                return Some(Err(crate::ParseError::StackOverflow { location }));
+            }
+        }
+
         self.definition
             .reduce(action, lookahead_start, &mut self.states, &mut self.symbols)
     }
@@ -600,35 +979,32 @@ where
         token: Option<TokenTriple<D>>,
         states: &[D::StateIndex],
     ) -> ParseError<D> {
-        match token {
-            Some(token) => crate::ParseError::UnrecognizedToken {
-                token,
-                expected: self.definition.expected_tokens_from_states(states),
-            },
-            None => crate::ParseError::UnrecognizedEof {
-                location: self.last_location.clone(),
-                expected: self.definition.expected_tokens_from_states(states),
-            },
-        }
+        unrecognized_token_error(&self.definition, token, &self.last_location, states)
     }
 
     /// Consume the next token from the input and classify it into a
     /// token index. Classification can fail with an error. If there
     /// are no more tokens, signal EOF.
     fn next_token(&mut self) -> NextToken<D> {
-        let token = match self.tokens.next() {
-            Some(Ok(v)) => v,
-            Some(Err(e)) => return NextToken::Done(Err(e)),
-            None => return NextToken::Eof,
+        let token = match self.tokens.poll_token() {
+            Poll::Ready(Some(Ok(v))) => v,
+            Poll::Ready(Some(Err(e))) => return NextToken::Done(Err(e)),
+            Poll::Ready(None) => return NextToken::Eof,
+            Poll::Pending => return NextToken::Pending,
         };
 
         self.last_location = token.2.clone();
 
         let token_index = match self.definition.token_to_index(&token.1) {
             Some(i) => i,
+            None if self.partial && self.accepts_eof() => {
+                self.last_location = token.0.clone();
+                self.stopped_at = Some(token);
+                return NextToken::Eof;
+            }
             None => {
                 return NextToken::Done(Err(
-                    self.unrecognized_token_error(Some(token), &self.states)
+                    self.unrecognized_token_error(Some(token), &self.states.to_vec())
                 ))
             }
         };
@@ -637,6 +1013,367 @@ where
     }
 }
 
+/// A stream of tokens that may not have the next one yet. It is the
+/// `Stream` trait of the `futures` crate, which lalrpop-util does not
+/// depend on; a `futures` stream can be passed to an `AsyncParser` by
+/// wrapping it in a type whose `poll_next` calls the stream's.
+pub trait TokenStream {
+    type Item;
+
+    /// The next token, `None` at the end of the input, or
+    /// `Poll::Pending` if the next token has not arrived yet, in which
+    /// case `cx` is woken once it has.
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>>;
+}
+
+/// A parser whose tokens arrive asynchronously. It keeps the stack of
+/// the `Parser` between calls of `poll_parse`, so that a parse can stop
+/// when the next token has not arrived yet, and go on once it has.
+///
+/// Its stacks are `Vec`s by default; grammars annotated with
+/// `#[no_std(stack_depth = "N")]` use `ArrayStack`s instead.
+pub struct AsyncParser<
+    D: ParserDefinition,
+    S = Vec<<D as ParserDefinition>::StateIndex>,
+    Y = Vec<SymbolTriple<D>>,
+> {
+    parser: Parser<D, ArrivedToken<D>, S, Y>,
+}
+
+/// The token an `AsyncParser` was given that its parser has not read
+/// yet, if any.
+struct ArrivedToken<D: ParserDefinition>(Option<Option<Result<TokenTriple<D>, ParseError<D>>>>);
+
+impl<D: ParserDefinition> TokenSource<D> for ArrivedToken<D> {
+    fn poll_token(&mut self) -> Poll<Option<Result<TokenTriple<D>, ParseError<D>>>> {
+        match self.0.take() {
+            Some(token) => Poll::Ready(token),
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl<D, S, Y> AsyncParser<D, S, Y>
+where
+    D: ParserDefinition,
+    S: Stack<D::StateIndex> + Default,
+    Y: Stack<SymbolTriple<D>> + Default,
+{
+    pub fn new(definition: D) -> Self {
+        AsyncParser {
+            parser: Parser::new(definition, ArrivedToken(None)),
+        }
+    }
+
+    /// Parses the tokens returned by `next_token`, until the input ends
+    /// or `next_token` returns `Poll::Pending`. In that case, this
+    /// returns `Poll::Pending` too, and it can be called again once the
+    /// next token has arrived. Once it has returned `Poll::Ready`, it
+    /// must not be called again.
+    pub fn poll_parse<F>(&mut self, mut next_token: F) -> Poll<ParseResult<D>>
+    where
+        F: FnMut() -> Poll<Option<Result<TokenTriple<D>, ParseError<D>>>>,
+    {
+        loop {
+            if let Poll::Ready(result) = self.parser.poll_parse() {
+                return Poll::Ready(result);
+            }
+            // the parser is waiting for a token
+            match next_token() {
+                Poll::Ready(token) => self.parser.tokens.0 = Some(token),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// A parser stack explored by `GlrParser`.
+struct GlrStack<D: ParserDefinition> {
+    states: Vec<D::StateIndex>,
+    symbols: Vec<SymbolTriple<D>>,
+}
+
+impl<D> Clone for GlrStack<D>
+where
+    D: ParserDefinition,
+    D::Symbol: Clone,
+{
+    fn clone(&self) -> Self {
+        GlrStack {
+            states: self.states.clone(),
+            symbols: self.symbols.clone(),
+        }
+    }
+}
+
+/// The result of applying a single action to a `GlrStack`.
+enum GlrStep<D: ParserDefinition> {
+    /// The lookahead was shifted; the stack waits for the next token.
+    Shifted(GlrStack<D>),
+    /// A reduction was performed; the stack must be examined again
+    /// with the same lookahead.
+    Reduced(GlrStack<D>),
+    /// The start symbol was reduced at EOF.
+    Accepted(Success<D>),
+    /// This stack cannot continue.
+    Failed(ParseError<D>),
+}
+
+/// A generalized LR parser, used for grammars annotated with
+/// `#[ambiguous]`. Whenever more than one action is possible (see
+/// `ParserDefinition::conflicting_actions`), the stack is copied and
+/// each action is pursued independently; stacks that hit an error are
+/// dropped. Every parse that reaches the end of the input is returned.
+///
+/// This is the simple stack-splitting form of GLR: stacks are never
+/// merged, so highly ambiguous input can take exponential time, and
+/// grammars in which a nonterminal can derive itself (e.g. `A = A`)
+/// do not terminate. User actions run once per stack, so they should
+/// not have side effects.
+pub struct GlrParser<D, I>
+where
+    D: ParserDefinition,
+    I: Iterator<Item = Result<TokenTriple<D>, ParseError<D>>>,
+{
+    definition: D,
+    tokens: I,
+    last_location: D::Location,
+}
+
+impl<D, I> GlrParser<D, I>
+where
+    D: ParserDefinition,
+    D::Symbol: Clone,
+    I: Iterator<Item = Result<TokenTriple<D>, ParseError<D>>>,
+{
+    pub fn drive(definition: D, tokens: I) -> Result<Vec<Success<D>>, ParseError<D>> {
+        let last_location = definition.start_location();
+        GlrParser {
+            definition,
+            tokens,
+            last_location,
+        }
+        .parse()
+    }
+
+    fn parse(&mut self) -> Result<Vec<Success<D>>, ParseError<D>> {
+        let mut stacks = vec![GlrStack {
+            states: vec![self.definition.start_state()],
+            symbols: vec![],
+        }];
+
+        loop {
+            let lookahead = match self.tokens.next() {
+                Some(Ok(v)) => v,
+                Some(Err(e)) => return Err(e),
+                None => break,
+            };
+            self.last_location = lookahead.2.clone();
+
+            let token_index = match self.definition.token_to_index(&lookahead.1) {
+                Some(i) => i,
+                None => {
+                    return Err(self.unrecognized_token_error(Some(lookahead), &stacks[0].states))
+                }
+            };
+
+            debug!("+ GLR SHIFT: {:?} ({} stacks)", lookahead, stacks.len());
+
+            let mut shifted = vec![];
+            let mut error = None;
+            self.explore(stacks, Some((&lookahead, token_index)), |step| match step {
+                GlrStep::Shifted(stack) => shifted.push(stack),
+                GlrStep::Failed(e) => merge_error(&mut error, e),
+                GlrStep::Reduced(_) | GlrStep::Accepted(_) => unreachable!(),
+            });
+
+            if shifted.is_empty() {
+                return Err(error.unwrap());
+            }
+            stacks = shifted;
+        }
+
+        let mut results = vec![];
+        let mut error = None;
+        self.explore(stacks, None, |step| match step {
+            GlrStep::Accepted(result) => results.push(result),
+            GlrStep::Failed(e) => merge_error(&mut error, e),
+            GlrStep::Shifted(_) | GlrStep::Reduced(_) => unreachable!(),
+        });
+
+        if results.is_empty() {
+            Err(error.unwrap())
+        } else {
+            Ok(results)
+        }
+    }
+
+    /// Applies every possible action to each of `stacks`, repeating
+    /// until each copy has either shifted the lookahead, accepted (at
+    /// EOF) or failed. Those outcomes are given to `consume`, in the
+    /// order in which the stacks and their actions were given.
+    fn explore(
+        &mut self,
+        stacks: Vec<GlrStack<D>>,
+        lookahead: Option<(&TokenTriple<D>, D::TokenIndex)>,
+        mut consume: impl FnMut(GlrStep<D>),
+    ) {
+        let mut pending = stacks;
+        pending.reverse();
+
+        while let Some(stack) = pending.pop() {
+            let top = *stack.states.last().unwrap();
+            let token_index = lookahead.map(|(_, i)| i);
+            let primary = match token_index {
+                Some(i) => self.definition.action(top, i),
+                None => self.definition.eof_action(top),
+            };
+            let actions: Vec<D::Action> = Some(primary)
+                .into_iter()
+                .chain(
+                    self.definition
+                        .conflicting_actions(top, token_index)
+                        .iter()
+                        .copied(),
+                )
+                .collect();
+
+            debug!("\\ GLR actions in state {:?}: {:?}", top, actions);
+
+            // Every action but the last gets its own copy of the stack.
+            let (&last, rest) = actions.split_last().unwrap();
+            let mut reduced = vec![];
+            let steps = rest
+                .iter()
+                .map(|&action| self.step(stack.clone(), action, lookahead))
+                .collect::<Vec<_>>();
+            let last_step = self.step(stack, last, lookahead);
+            for step in steps.into_iter().chain(Some(last_step)) {
+                match step {
+                    GlrStep::Reduced(stack) => reduced.push(stack),
+                    step => consume(step),
+                }
+            }
+
+            // Pursue the reduced stacks depth-first, first action first.
+            pending.extend(reduced.into_iter().rev());
+        }
+    }
+
+    fn step(
+        &mut self,
+        mut stack: GlrStack<D>,
+        action: D::Action,
+        lookahead: Option<(&TokenTriple<D>, D::TokenIndex)>,
+    ) -> GlrStep<D> {
+        if let Some(target_state) = action.as_shift() {
+            let (lookahead, token_index) = lookahead.expect("cannot shift EOF");
+            let symbol = self
+                .definition
+                .token_to_symbol(token_index, lookahead.1.clone());
+            stack.states.push(target_state);
+            stack
+                .symbols
+                .push((lookahead.0.clone(), symbol, lookahead.2.clone()));
+            GlrStep::Shifted(stack)
+        } else if let Some(reduce_index) = action.as_reduce() {
+            let start_location = lookahead.map(|(l, _)| &l.0);
+            match self.definition.reduce(
+                reduce_index,
+                start_location,
+                &mut stack.states,
+                &mut stack.symbols,
+            ) {
+                None => GlrStep::Reduced(stack),
+                Some(Ok(result)) => match lookahead {
+                    // we reached eof, but still have lookahead
+                    Some((token, _)) => GlrStep::Failed(crate::ParseError::ExtraToken {
+                        token: token.clone(),
+                    }),
+                    None => GlrStep::Accepted(result),
+                },
+                Some(Err(e)) => GlrStep::Failed(e),
+            }
+        } else {
+            let token = lookahead.map(|(l, _)| l.clone());
+            GlrStep::Failed(self.unrecognized_token_error(token, &stack.states))
+        }
+    }
+
+    fn unrecognized_token_error(
+        &self,
+        token: Option<TokenTriple<D>>,
+        states: &[D::StateIndex],
+    ) -> ParseError<D> {
+        unrecognized_token_error(&self.definition, token, &self.last_location, states)
+    }
+}
+
+/// The error for an unexpected `token` (or EOF, if `None`). If one of
+/// `states`, searching from the top of the stack, is past a cut, the
+/// error says what the token was expected to follow.
+fn unrecognized_token_error<D: ParserDefinition>(
+    definition: &D,
+    token: Option<TokenTriple<D>>,
+    last_location: &D::Location,
+    states: &[D::StateIndex],
+) -> ParseError<D> {
+    let expected = definition.expected_tokens_from_states(states);
+    let after = states
+        .iter()
+        .rev()
+        .find_map(|&state| definition.cut_context(state));
+    match (after, token) {
+        (Some(after), token) => crate::ParseError::UnrecognizedAfter {
+            after: after.into(),
+            location: match token {
+                Some((ref start, _, _)) => start.clone(),
+                None => last_location.clone(),
+            },
+            token,
+            expected,
+        },
+        (None, Some(token)) => crate::ParseError::UnrecognizedToken { token, expected },
+        (None, None) => crate::ParseError::UnrecognizedEof {
+            location: last_location.clone(),
+            expected,
+        },
+    }
+}
+
+/// Records `new` as the error to report if every GLR stack fails. When
+/// several stacks fail on the same token, the tokens they expected are
+/// combined.
+fn merge_error<L, T, E>(
+    error: &mut Option<crate::ParseError<L, T, E>>,
+    new: crate::ParseError<L, T, E>,
+) {
+    match (error.as_mut(), new) {
+        (
+            Some(crate::ParseError::UnrecognizedToken { expected, .. }),
+            crate::ParseError::UnrecognizedToken {
+                expected: new_expected,
+                ..
+            },
+        )
+        | (
+            Some(crate::ParseError::UnrecognizedEof { expected, .. }),
+            crate::ParseError::UnrecognizedEof {
+                expected: new_expected,
+                ..
+            },
+        ) => {
+            for token in new_expected {
+                if !expected.contains(&token) {
+                    expected.push(token);
+                }
+            }
+        }
+        (Some(_), _) => {}
+        (None, new) => *error = Some(new),
+    }
+}
+
 /// In LALRPOP generated rules, we actually use `i32`, `i16`, or `i8`
 /// to represent all of the various indices (we use the smallest one
 /// that will fit). So implement `ParserAction` for each of those.
@@ -0,0 +1,179 @@
+//! A file format for the parse tables of a generated parser.
+//!
+//! Parsers generated with `--serialize-tables` (or
+//! `Configuration::serialize_tables`) get two extra associated
+//! functions: `XParser::tables()` returns the tables the parser was
+//! compiled with, and `XParser::load_tables_from_path` reads back tables
+//! that were written with [`ParseTables::save_to_path`].
+//!
+//! Every file starts with [`MAGIC`] followed by [`VERSION`], so a file
+//! written by a different version of this format is rejected instead of
+//! being misread. All integers are little-endian.
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+/// The first bytes of every table file.
+pub const MAGIC: [u8; 8] = *b"LALRPOP\0";
+
+/// The version of the format, bumped whenever the layout changes.
+pub const VERSION: u32 = 1;
+
+/// The tables that drive a table-driven parser.
+///
+/// `action` has one row per state and one column per terminal, and
+/// `eof_action` one entry per state. A positive entry `n` shifts and
+/// goes to state `n - 1`, a negative entry `-n` reduces production
+/// `n - 1` and zero is an error. `goto` has one row per state and one
+/// column per nonterminal, holding the next state after a reduction to
+/// that nonterminal, or `-1` if there is none.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParseTables {
+    pub terminals: Vec<String>,
+    pub nonterminals: Vec<String>,
+    pub action: Vec<i32>,
+    pub eof_action: Vec<i32>,
+    pub goto: Vec<i32>,
+}
+
+impl ParseTables {
+    /// Collects the tables of a generated parser, whatever integer type
+    /// it uses for its states.
+    pub fn new<S: Copy + Into<i32>>(
+        terminals: &[&str],
+        nonterminals: &[&str],
+        action: &[S],
+        eof_action: &[S],
+        goto: &[S],
+    ) -> Self {
+        let strings = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        let integers = |table: &[S]| table.iter().map(|&entry| entry.into()).collect();
+        ParseTables {
+            terminals: strings(terminals),
+            nonterminals: strings(nonterminals),
+            action: integers(action),
+            eof_action: integers(eof_action),
+            goto: integers(goto),
+        }
+    }
+
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        for names in [&self.terminals, &self.nonterminals] {
+            write_len(&mut writer, names.len())?;
+            for name in names {
+                write_len(&mut writer, name.len())?;
+                writer.write_all(name.as_bytes())?;
+            }
+        }
+        for table in [&self.action, &self.eof_action, &self.goto] {
+            write_len(&mut writer, table.len())?;
+            for entry in table {
+                writer.write_all(&entry.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads tables written by [`ParseTables::write_to`]. Fails with
+    /// [`io::ErrorKind::InvalidData`] if the input does not start with
+    /// [`MAGIC`] and [`VERSION`].
+    pub fn read_from<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(invalid_data("not a LALRPOP table file".to_string()));
+        }
+        let version = read_u32(&mut reader)?;
+        if version != VERSION {
+            return Err(invalid_data(format!(
+                "table file has version {}, expected version {}",
+                version, VERSION
+            )));
+        }
+
+        let mut names = || -> io::Result<Vec<String>> {
+            (0..read_u32(&mut reader)?)
+                .map(|_| {
+                    let mut bytes = vec![0; read_u32(&mut reader)? as usize];
+                    reader.read_exact(&mut bytes)?;
+                    String::from_utf8(bytes).map_err(|e| invalid_data(e.to_string()))
+                })
+                .collect()
+        };
+        let terminals = names()?;
+        let nonterminals = names()?;
+
+        let mut table = || -> io::Result<Vec<i32>> {
+            (0..read_u32(&mut reader)?)
+                .map(|_| read_u32(&mut reader).map(|entry| entry as i32))
+                .collect()
+        };
+        Ok(ParseTables {
+            terminals,
+            nonterminals,
+            action: table()?,
+            eof_action: table()?,
+            goto: table()?,
+        })
+    }
+
+    pub fn save_to_path<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = io::BufWriter::new(fs::File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn load_from_path<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read_from(io::BufReader::new(fs::File::open(path)?))
+    }
+}
+
+fn write_len<W: Write>(writer: &mut W, len: usize) -> io::Result<()> {
+    let len = u32::try_from(len).map_err(|e| invalid_data(e.to_string()))?;
+    writer.write_all(&len.to_le_bytes())
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let tables = ParseTables::new(
+            &[r#""+""#, r##"r#"[0-9]+"#"##],
+            &["Expr"],
+            &[0i8, 2, -1, 0],
+            &[-2i8, 0],
+            &[1i8, -1],
+        );
+        let mut bytes = vec![];
+        tables.write_to(&mut bytes).unwrap();
+        assert_eq!(ParseTables::read_from(&bytes[..]).unwrap(), tables);
+
+        // a file of another version is stale
+        bytes[8] += 1;
+        let error = ParseTables::read_from(&bytes[..]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            error.to_string(),
+            "table file has version 2, expected version 1"
+        );
+
+        bytes[0] = b'X';
+        let error = ParseTables::read_from(&bytes[..]).unwrap_err();
+        assert_eq!(error.to_string(), "not a LALRPOP table file");
+    }
+}
@@ -0,0 +1,18 @@
+//! Tokens that describe themselves, for grammars declaring
+//! `extern token T: Token;`.
+//!
+//! The parser of such a grammar matches each token against the
+//! terminals by its [`Token::terminal_name`], and takes its location from
+//! its [`Token::span`], so a lexer can hand over its tokens, or
+//! `Result`s of them, without first turning them into triples.
+
+/// A token produced by a user-defined lexer.
+pub trait Token {
+    /// The terminal this token stands for, as it is quoted in the
+    /// grammar: a token whose `terminal_name` is `"+"` matches the
+    /// terminal `"+"`.
+    fn terminal_name(&self) -> &str;
+
+    /// The offsets of the start and the end of the token in the input.
+    fn span(&self) -> (usize, usize);
+}
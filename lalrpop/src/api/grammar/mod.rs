@@ -0,0 +1,259 @@
+//! The phases of processing a grammar, for using LALRPOP as a library
+//! rather than from a build script: `Grammar::parse_file` reads a
+//! grammar, `Grammar::validate` checks it, and
+//! `ValidatedGrammar::generate_code` writes its parser.
+
+use crate::build;
+use crate::file_text::FileText;
+use crate::grammar::parse_tree as pt;
+use crate::grammar::repr as r;
+use crate::lr1;
+use crate::message::builder::InlineBuilder;
+use crate::message::Message;
+use crate::normalize;
+use crate::session::Session;
+use crate::tls::Tls;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+
+#[cfg(test)]
+mod test;
+
+/// A grammar that has been parsed, but not checked.
+pub struct Grammar {
+    session: Rc<Session>,
+    file_text: Rc<FileText>,
+    grammar: pt::Grammar,
+}
+
+/// A grammar whose LR tables can be built without conflicts.
+pub struct ValidatedGrammar {
+    session: Rc<Session>,
+    file_text: Rc<FileText>,
+    grammar: r::Grammar,
+}
+
+/// The file of a grammar could not be read or parsed.
+#[derive(Clone, Debug)]
+pub struct ParseError {
+    location: String,
+    message: String,
+}
+
+/// A problem found while checking a grammar, or a warning about it.
+#[derive(Clone, Debug)]
+pub struct ValidationError {
+    location: String,
+    message: String,
+}
+
+/// A cycle of nonterminals that derive no input, found by
+/// `Grammar::cycle_check`.
+#[derive(Clone, Debug)]
+pub struct CycleError {
+    location: String,
+    message: String,
+    nonterminals: Vec<String>,
+}
+
+/// The code of a parser could not be generated.
+#[derive(Debug)]
+pub struct CodegenError {
+    error: io::Error,
+}
+
+impl Grammar {
+    /// Parses the `.lalrpop` file at `path`, along with the files it
+    /// imports.
+    pub fn parse_file<P: AsRef<Path>>(path: P) -> Result<Grammar, ParseError> {
+        let path = path.as_ref();
+        let mut file_text =
+            FileText::from_path(path.to_path_buf()).map_err(|error| ParseError {
+                location: path.display().to_string(),
+                message: error.to_string(),
+            })?;
+        let grammar =
+            build::try_parse_grammar(&mut file_text).map_err(|(span, message)| ParseError {
+                location: file_text.span_str(span),
+                message,
+            })?;
+        Ok(Grammar {
+            session: Rc::new(Session::new()),
+            file_text: Rc::new(file_text),
+            grammar,
+        })
+    }
+
+    /// Checks the grammar, up to and including the construction of the
+    /// LR tables of its public nonterminals. All the conflicts found in
+    /// the tables are reported, but only the first of the other errors.
+    pub fn validate(&self) -> Result<ValidatedGrammar, Vec<ValidationError>> {
+        let _tls = Tls::install(self.session.clone(), self.file_text.clone());
+        let grammar = normalize::normalize(&self.session, self.grammar.clone())
+            .map_err(|error| vec![self.error(error.span, error.message)])?;
+        if grammar.start_nonterminals.is_empty() {
+            let message = "no public symbols declared in grammar".to_string();
+            return Err(vec![self.error(self.grammar.span, message)]);
+        }
+
+        let mut errors = vec![];
+        for start_nt in grammar.start_nonterminals.values() {
+            let _lr1_tls = lr1::Lr1Tls::install(grammar.terminals.clone());
+            if let Err(error) = lr1::build_states(&grammar, start_nt.clone()) {
+                let _ = lr1::report_error(&grammar, &error, |message| {
+                    errors.push(self.conflict(message));
+                    Ok::<_, ()>(())
+                });
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        Ok(ValidatedGrammar {
+            session: self.session.clone(),
+            file_text: self.file_text.clone(),
+            grammar,
+        })
+    }
+
+    /// Checks that no nonterminal is part of a cycle that none of its
+    /// alternatives get out of, like `A = A "x";` or `A = B; B = A;`.
+    /// The parser of such a grammar could never reduce them, and
+    /// `validate` would often report conflicts about them instead. Only
+    /// the first cycle is reported; the other errors of the grammar are
+    /// left to `validate`.
+    pub fn cycle_check(&self) -> Result<(), CycleError> {
+        let _tls = Tls::install(self.session.clone(), self.file_text.clone());
+        let grammar = match normalize::normalize(&self.session, self.grammar.clone()) {
+            Ok(grammar) => grammar,
+            Err(_) => return Ok(()),
+        };
+        match grammar.unproductive_cycles().into_iter().next() {
+            None => Ok(()),
+            Some(cycle) => {
+                let chain = cycle
+                    .iter()
+                    .map(|nonterminal| format!("`{}`", nonterminal))
+                    .collect::<Vec<_>>()
+                    .join(" -> ");
+                Err(CycleError {
+                    location: self
+                        .file_text
+                        .span_str(grammar.nonterminals[&cycle[0]].span),
+                    message: format!("the cycle {} derives no input", chain),
+                    nonterminals: cycle.iter().map(ToString::to_string).collect(),
+                })
+            }
+        }
+    }
+
+    fn error(&self, span: pt::Span, message: String) -> ValidationError {
+        ValidationError {
+            location: self.file_text.span_str(span),
+            message,
+        }
+    }
+
+    /// The text of a conflict, without the location it starts with.
+    fn conflict(&self, message: Message) -> ValidationError {
+        let location = self.file_text.span_str(message.span());
+        let content = InlineBuilder::new().push(Box::new(message)).end();
+        let text = content
+            .emit_to_canvas(80)
+            .to_strings()
+            .iter()
+            .map(|line| line.to_string().trim_end().to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let message = match text.strip_prefix(&format!("{}: ", location)) {
+            Some(text) => text.to_string(),
+            None => text,
+        };
+        ValidationError { location, message }
+    }
+}
+
+impl ValidatedGrammar {
+    /// The warnings about the grammar, like those that a build script
+    /// prints.
+    pub fn warnings(&self) -> Vec<ValidationError> {
+        self.grammar
+            .lint()
+            .into_iter()
+            .map(|lint| ValidationError {
+                location: self
+                    .file_text
+                    .span_str(pt::Span(lint.span.start, lint.span.end)),
+                message: lint.message,
+            })
+            .collect()
+    }
+
+    /// Generates the code of the parsers, as it is written into the
+    /// `.rs` file of the grammar after the header that names the
+    /// version of LALRPOP and the hash of the grammar.
+    pub fn generate_code(&self) -> Result<String, CodegenError> {
+        let _tls = Tls::install(self.session.clone(), self.file_text.clone());
+        // the default session writes no report, graph or conflicts, so
+        // their files are never created
+        let unused = Path::new("");
+        let buffer =
+            build::emit_recursive_ascent(&self.session, &self.grammar, unused, unused, unused)
+                .map_err(|error| CodegenError { error })?;
+        String::from_utf8(buffer).map_err(|error| CodegenError {
+            error: io::Error::new(io::ErrorKind::InvalidData, error),
+        })
+    }
+}
+
+impl CycleError {
+    /// The chain of the cycle, from its first nonterminal back to
+    /// itself.
+    pub fn nonterminals(&self) -> &[String] {
+        &self.nonterminals
+    }
+}
+
+macro_rules! location_error {
+    ($name:ident) => {
+        impl $name {
+            /// Where the problem is, as `file:line:col: line:col`, or
+            /// just the file if it could not be read.
+            pub fn location(&self) -> &str {
+                &self.location
+            }
+
+            pub fn message(&self) -> &str {
+                &self.message
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}: {}", self.location, self.message)
+            }
+        }
+
+        impl Error for $name {}
+    };
+}
+
+location_error!(ParseError);
+location_error!(CycleError);
+location_error!(ValidationError);
+
+impl fmt::Display for CodegenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "could not generate the parser: {}", self.error)
+    }
+}
+
+impl Error for CodegenError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
@@ -0,0 +1,102 @@
+use super::Grammar;
+use std::fs;
+use std::path::PathBuf;
+
+/// Writes `text` into a fresh grammar file and returns its path.
+fn write_grammar(test_name: &str, text: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("lalrpop-api-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(format!("{}.lalrpop", test_name));
+    fs::write(&path, text).unwrap();
+    path
+}
+
+#[test]
+fn phases() {
+    let path = write_grammar(
+        "phases",
+        r#"grammar; pub Num: i32 = r"[0-9]+" => <>.parse().unwrap();"#,
+    );
+    let grammar = Grammar::parse_file(path).unwrap();
+    let validated = grammar.validate().unwrap();
+    assert!(validated.warnings().is_empty());
+    let code = validated.generate_code().unwrap();
+    assert!(code.contains("pub struct NumParser"));
+}
+
+#[test]
+fn missing_file() {
+    let path = std::env::temp_dir().join("lalrpop-api-missing.lalrpop");
+    let error = Grammar::parse_file(&path).err().unwrap();
+    assert_eq!(error.location(), path.display().to_string());
+}
+
+#[test]
+fn syntax_error() {
+    let path = write_grammar("syntax_error", "grammar; pub Num: i32 = ;");
+    let error = Grammar::parse_file(path).err().unwrap();
+    assert!(error
+        .location()
+        .ends_with("syntax_error.lalrpop:1:25: 1:25"));
+    assert_eq!(error.message(), "unexpected token: `;`");
+}
+
+#[test]
+fn normalization_error() {
+    let path = write_grammar("normalization_error", "grammar; pub Num: i32 = Digit;");
+    let errors = Grammar::parse_file(path).unwrap().validate().err().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message(), "no definition found for `Digit`");
+}
+
+#[test]
+fn conflicts() {
+    let path = write_grammar(
+        "conflicts",
+        r#"grammar; pub Expr: () = { Expr "+" Expr, "x" };"#,
+    );
+    let errors = Grammar::parse_file(path).unwrap().validate().err().unwrap();
+    assert!(!errors.is_empty());
+    assert!(errors[0]
+        .location()
+        .ends_with("conflicts.lalrpop:1:27: 1:39"));
+    assert!(errors[0]
+        .message()
+        .starts_with("Ambiguous grammar detected"));
+}
+
+#[test]
+fn unit_values() {
+    let path = write_grammar(
+        "unit_values",
+        r#"grammar; pub Pair: () = Unit Unit; Unit: () = "x";"#,
+    );
+    let code = Grammar::parse_file(path)
+        .unwrap()
+        .validate()
+        .unwrap()
+        .generate_code()
+        .unwrap();
+    // no action of a `()` nonterminal has its value bound to a name
+    assert!(!code.contains("let __nt ="));
+    assert!(code.contains("return Some(Ok(()));"));
+}
+
+#[test]
+fn cycle_check() {
+    let path = write_grammar(
+        "cycle_check",
+        r#"grammar; pub List: () = { Item, "x" }; Item: () = Items ","; Items: () = Item;"#,
+    );
+    let grammar = Grammar::parse_file(path).unwrap();
+    let error = grammar.cycle_check().err().unwrap();
+    assert_eq!(error.nonterminals(), ["Item", "Items", "Item"]);
+    assert!(error.location().ends_with("cycle_check.lalrpop:1:40: 1:43"));
+    assert_eq!(
+        error.message(),
+        "the cycle `Item` -> `Items` -> `Item` derives no input"
+    );
+
+    let path = write_grammar("no_cycle", r#"grammar; pub List: () = { List "x", "x" };"#);
+    assert!(Grammar::parse_file(path).unwrap().cycle_check().is_ok());
+}
@@ -1,6 +1,7 @@
 use crate::build;
 use crate::log::Level;
 use crate::session::{ColorConfig, Session};
+use std::collections::BTreeSet;
 use std::default::Default;
 use std::env;
 use std::env::current_dir;
@@ -8,6 +9,14 @@ use std::error::Error;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
+mod grammar;
+#[cfg(test)]
+mod test;
+
+pub use self::grammar::{
+    CodegenError, CycleError, Grammar, ParseError, ValidatedGrammar, ValidationError,
+};
+
 /// Configure various aspects of how LALRPOP works.
 /// Intended for use within a `build.rs` script.
 /// To get the default configuration, use `Configuration::new`.
@@ -67,6 +76,22 @@ impl Configuration {
         self
     }
 
+    /// Name the generated module `name` instead of after the grammar
+    /// file, so the parser for `src/lang/grammar.lalrpop` can be written
+    /// to `lang_grammar.rs` in the output directory (next to where
+    /// `grammar.rs` would go) and included with
+    /// `lalrpop_mod!(lang_grammar, "/lang/lang_grammar.rs")`. The other
+    /// files emitted for the grammar are named the same way. As all
+    /// grammars would get the same name, this is an error when
+    /// processing a directory with more than one grammar.
+    pub fn with_module_name<S>(&mut self, name: S) -> &mut Configuration
+    where
+        S: Into<String>,
+    {
+        self.session.module_name = Some(name.into());
+        self
+    }
+
     /// Apply `cargo` directory location conventions, by setting the
     /// input directory to `src` and the output directory to
     /// `$OUT_DIR`.
@@ -126,12 +151,125 @@ impl Configuration {
         self
     }
 
+    /// If true, pipes the generated code through `rustfmt` (or the
+    /// program named by the `RUSTFMT` environment variable) before
+    /// writing it, so that it is easier to read. If rustfmt cannot be
+    /// run, the code is written as it is, with a warning. Default is
+    /// false.
+    pub fn format_generated_code(&mut self, val: bool) -> &mut Configuration {
+        self.session.format_generated_code = val;
+        self
+    }
+
+    /// Writes `text` as a comment at the top of each generated file,
+    /// like "This file is generated by lalrpop. Do not edit.", one `//`
+    /// line per line of `text`. It follows the two lines with which
+    /// lalrpop decides whether the file is up to date. It is not a `//!`
+    /// doc comment, since those are not allowed in a file included by
+    /// `lalrpop_mod!`. Default is no comment.
+    pub fn emit_module_comment(&mut self, text: &str) -> &mut Configuration {
+        self.session.module_comment = Some(text.to_string());
+        self
+    }
+
     /// If true, emit report file about generated code.
     pub fn emit_report(&mut self, val: bool) -> &mut Configuration {
         self.session.emit_report = val;
         self
     }
 
+    /// If true, emit a Graphviz DOT file (`<grammar>.dot`) showing the
+    /// LR automaton of each public nonterminal. The file is written
+    /// even if the grammar has conflicts, to help debug them.
+    pub fn emit_dot(&mut self, val: bool) -> &mut Configuration {
+        self.session.emit_dot = val;
+        self
+    }
+
+    /// If true, emit a file (`<grammar>.ast.rs`) with a `mod ast` that
+    /// declares one enum per nonterminal and one variant per
+    /// alternative, as a starting point for the grammar's AST.
+    pub fn emit_rust_types(&mut self, val: bool) -> &mut Configuration {
+        self.session.emit_rust_types = val;
+        self
+    }
+
+    /// If true, emit a JSON file (`<grammar>.conflicts.json`) listing
+    /// the conflicts of the grammar with the spans of the rules
+    /// involved, for editors to highlight. The file is written even if
+    /// there are no conflicts, in which case the list is empty.
+    pub fn emit_conflicts_json(&mut self, val: bool) -> &mut Configuration {
+        self.session.emit_conflicts_json = val;
+        self
+    }
+
+    /// If true, emit a file (`<grammar>.ebnf`) with the grammar in the
+    /// EBNF notation of ISO 14977, with the uses of macros expanded.
+    pub fn emit_ebnf(&mut self, val: bool) -> &mut Configuration {
+        self.session.emit_ebnf = val;
+        self
+    }
+
+    /// If true, emit a file (`<grammar>.bnf`) with the grammar in plain
+    /// BNF, with the uses of macros expanded and the repeats, options
+    /// and groups written as rules of their own.
+    pub fn emit_bnf(&mut self, val: bool) -> &mut Configuration {
+        self.session.emit_bnf = val;
+        self
+    }
+
+    /// If true, emit a file (`<grammar>.railroad.html`) with a railroad
+    /// diagram of each nonterminal, drawn in SVG, with the uses of
+    /// macros expanded.
+    pub fn emit_railroad(&mut self, val: bool) -> &mut Configuration {
+        self.session.emit_railroad = val;
+        self
+    }
+
+    /// If true, give each table-driven parser a `tables()` function
+    /// returning its parse tables and a `load_tables_from_path` function
+    /// to read them back from a file. The generated code needs the
+    /// `serialize-tables` feature of `lalrpop_util`. Default is false.
+    pub fn serialize_tables(&mut self, val: bool) -> &mut Configuration {
+        self.session.serialize_tables = val;
+        self
+    }
+
+    /// If true, only check that grammars are free of errors and
+    /// conflicts: no `.rs` file (or any other file) is written, and
+    /// grammars are checked even if they are up to date. Default is
+    /// false.
+    pub fn check_only(&mut self, val: bool) -> &mut Configuration {
+        self.session.check_only = val;
+        self
+    }
+
+    /// If true, print a warning for each alternative that is never
+    /// reduced by the generated parser, either because its nonterminal
+    /// is not used by any public nonterminal or because other
+    /// alternatives always take precedence over it. Default is false.
+    pub fn warn_unreachable(&mut self, val: bool) -> &mut Configuration {
+        self.session.warn_unreachable = val;
+        self
+    }
+
+    /// If true, the report of a conflict lists all the items of the LR
+    /// state where it arises, with their lookaheads, to show how the
+    /// state came to be. Default is false.
+    pub fn verbose_conflicts(&mut self, val: bool) -> &mut Configuration {
+        self.session.verbose_conflicts = val;
+        self
+    }
+
+    /// If true, print to stderr how long each phase of processing a
+    /// grammar took: parsing it, normalizing it (which includes macro
+    /// expansion), constructing the LR states and resolving their
+    /// conflicts, and generating code. Default is false.
+    pub fn time_phases(&mut self, val: bool) -> &mut Configuration {
+        self.session.timings = val.then(Default::default);
+        self
+    }
+
     /// Minimal logs: only for errors that halt progress.
     pub fn log_quiet(&mut self) -> &mut Configuration {
         self.session.log.set_level(Level::Taciturn);
@@ -168,6 +306,19 @@ impl Configuration {
         self
     }
 
+    /// Sets the configuration options, other than features, that
+    /// `#[cfg(...)]` alternatives are checked against, as `(key, value)`
+    /// pairs like `("target_os", "linux")`, or `("unix", "")` for an
+    /// option without a value.
+    /// (Default: Loaded from `CARGO_CFG_{}` environment variables).
+    pub fn set_cfg<I>(&mut self, iterable: I) -> &mut Configuration
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        self.session.cfg = Some(iterable.into_iter().collect());
+        self
+    }
+
     /// Enables "unit-testing" configuration. This is only for
     /// lalrpop-test.
     #[doc(hidden)]
@@ -195,7 +346,7 @@ impl Configuration {
 
     /// Process all `.lalrpop` files in `path`.
     pub fn process_dir<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
-        let mut session = self.session.clone();
+        let mut session = self.cargo_session();
 
         // If in/out dir are empty, use cargo conventions by default.
         // See https://github.com/lalrpop/lalrpop/issues/280
@@ -210,7 +361,39 @@ impl Configuration {
             session.out_dir = Some(PathBuf::from(out_dir));
         }
 
-        if self.session.features.is_none() {
+        let session = Rc::new(session);
+        build::process_dir(session, path)?;
+        Ok(())
+    }
+
+    /// Process the given `.lalrpop` file.
+    pub fn process_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let session = Rc::new(self.cargo_session());
+        build::process_file(session, path)?;
+        Ok(())
+    }
+
+    /// Process the given `.lalrpop` files, in order, and no others.
+    /// Like `process_file`, this uses the directories set with
+    /// `set_in_dir` and `set_out_dir` (or `use_cargo_dir_conventions`).
+    pub fn process_files<I, P>(&self, paths: I) -> Result<(), Box<dyn Error>>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let session = Rc::new(self.cargo_session());
+        build::process_files(session, paths)?;
+        Ok(())
+    }
+
+    /// The session, with the features and the target configuration that
+    /// cargo gives build scripts, unless they were set explicitly, so
+    /// that `#[cfg(...)]` is decided the same way whichever files are
+    /// processed.
+    fn cargo_session(&self) -> Session {
+        let mut session = self.session.clone();
+
+        if session.features.is_none() {
             // Pick up the features cargo sets for build scripts
             session.features = Some(
                 env::vars()
@@ -224,16 +407,28 @@ impl Configuration {
             );
         }
 
-        let session = Rc::new(session);
-        build::process_dir(session, path)?;
-        Ok(())
-    }
+        if session.cfg.is_none() {
+            // Pick up the configuration of the target that cargo sets
+            // for build scripts, where an option with several values,
+            // like `target_feature`, has them separated by commas
+            let mut cfg = BTreeSet::new();
+            for (cfg_var, values) in env::vars() {
+                let Some(key) = cfg_var.strip_prefix("CARGO_CFG_") else {
+                    continue;
+                };
+                let key = key.to_ascii_lowercase();
+                if values.is_empty() {
+                    cfg.insert((key, values));
+                } else {
+                    for value in values.split(',') {
+                        cfg.insert((key.clone(), value.to_string()));
+                    }
+                }
+            }
+            session.cfg = Some(cfg);
+        }
 
-    /// Process the given `.lalrpop` file.
-    pub fn process_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
-        let session = Rc::new(self.session.clone());
-        build::process_file(session, path)?;
-        Ok(())
+        session
     }
 }
 
@@ -0,0 +1,35 @@
+use super::Configuration;
+use std::env;
+use std::fs;
+
+const GRAMMAR: &str = r#"
+grammar;
+pub Word = {
+    "always",
+    #[cfg(lalrpop_api_test = "on")]
+    "sometimes",
+};
+"#;
+
+#[test]
+fn process_file_loads_target_cfg() {
+    let dir = env::temp_dir().join(format!("lalrpop-api-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let grammar = dir.join("word.lalrpop");
+    fs::write(&grammar, GRAMMAR).unwrap();
+
+    // cargo sets `CARGO_CFG_*` for build scripts; no other test reads
+    // this one
+    env::set_var("CARGO_CFG_LALRPOP_API_TEST", "on");
+    Configuration::new()
+        .set_in_dir(&dir)
+        .log_quiet()
+        .process_file(&grammar)
+        .unwrap();
+    env::remove_var("CARGO_CFG_LALRPOP_API_TEST");
+
+    let parser = fs::read_to_string(dir.join("word.rs")).unwrap();
+    assert!(parser.contains(r####"r###""sometimes""###"####));
+    fs::remove_dir_all(&dir).unwrap();
+}
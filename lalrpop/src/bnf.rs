@@ -0,0 +1,271 @@
+//! Writes a grammar in plain BNF, for tools that accept neither
+//! LALRPOP's syntax nor EBNF. Like the EBNF writer, this expects the
+//! uses of macros to have been expanded (see
+//! `normalize::expand_macro_uses`). BNF has no repeats, options or
+//! groups, so each of them becomes a rule of its own: `X*` becomes
+//! `<X_star> ::= "" | <X_star> <X>`, `X+` becomes
+//! `<X_plus> ::= <X> | <X_plus> <X>`, and `X?` becomes
+//! `<X_opt> ::= "" | <X>`.
+
+use crate::collections::{map, Map};
+use crate::ebnf::{nonterminal_idents, sanitize, terminal_string};
+use crate::grammar::parse_tree::{
+    Grammar, GrammarItem, NonterminalString, RepeatOp, Symbol, SymbolKind,
+};
+use std::fmt::Write;
+
+impl Grammar {
+    /// The grammar in BNF, one rule per nonterminal, followed by the
+    /// rules for its repeats, options and groups.
+    pub fn to_bnf_string(&self) -> String {
+        let nonterminals: Vec<_> = self
+            .items
+            .iter()
+            .filter_map(GrammarItem::as_nonterminal)
+            .collect();
+        let mut writer = BnfWriter {
+            idents: nonterminal_idents(&nonterminals),
+            helpers: map(),
+            rules: vec![],
+        };
+        let mut rules: Vec<(String, Vec<Vec<String>>)> = vec![];
+        for data in &nonterminals {
+            let alternatives = data
+                .alternatives
+                .iter()
+                .map(|alternative| writer.sequence(&alternative.expr.symbols))
+                .collect();
+            rules.push((writer.nonterminal(&data.name), alternatives));
+        }
+        rules.append(&mut writer.rules);
+
+        let mut out = String::new();
+        for (index, (ident, alternatives)) in rules.iter().enumerate() {
+            if index > 0 {
+                out.push('\n');
+            }
+            let lhs = format!("<{}> ::= ", ident);
+            out.push_str(&lhs);
+            for (index, alternative) in alternatives.iter().enumerate() {
+                if index > 0 {
+                    write!(out, "\n{:width$}| ", "", width = lhs.len() - 2).unwrap();
+                }
+                if alternative.is_empty() {
+                    out.push_str("\"\"");
+                } else {
+                    out.push_str(&alternative.join(" "));
+                }
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+struct BnfWriter {
+    idents: Map<NonterminalString, String>,
+    // the rule written for each repeat, option or group, by its text
+    helpers: Map<String, String>,
+    // the rules for the repeats, options and groups, in the order they
+    // were needed
+    rules: Vec<(String, Vec<Vec<String>>)>,
+}
+
+impl BnfWriter {
+    fn nonterminal(&self, name: &NonterminalString) -> String {
+        self.idents
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| sanitize(&name.0))
+    }
+
+    fn sequence(&mut self, symbols: &[Symbol]) -> Vec<String> {
+        symbols
+            .iter()
+            .flat_map(|symbol| self.symbol(symbol))
+            .collect()
+    }
+
+    /// The BNF for `symbol`, which is empty if it matches no input,
+    /// like `@L` or a lookahead assertion.
+    fn symbol(&mut self, symbol: &Symbol) -> Vec<String> {
+        match symbol.kind {
+            SymbolKind::Expr(ref expr) => self.sequence(&expr.symbols),
+            SymbolKind::Choice(ref exprs) => vec![self.helper(symbol, "choice", |writer, _| {
+                exprs
+                    .iter()
+                    .map(|expr| writer.sequence(&expr.symbols))
+                    .collect()
+            })],
+            SymbolKind::Terminal(ref terminal) => vec![terminal_string(terminal)],
+            SymbolKind::Nonterminal(ref name) => vec![format!("<{}>", self.nonterminal(name))],
+            SymbolKind::Repeat(ref repeat) => {
+                let inner = self.symbol(&repeat.symbol);
+                if inner.is_empty() {
+                    return vec![];
+                }
+                match repeat.op {
+                    RepeatOp::Star => vec![self.star(&repeat.symbol, &inner)],
+                    RepeatOp::Plus => vec![self.helper(symbol, "plus", |_, this| {
+                        vec![inner.clone(), [vec![this], inner.clone()].concat()]
+                    })],
+                    RepeatOp::Question => vec![self.optional(&repeat.symbol, &inner)],
+                    RepeatOp::Count { min, max } => {
+                        let mut items = vec![inner.clone(); min].concat();
+                        match max {
+                            None => items.push(self.star(&repeat.symbol, &inner)),
+                            Some(max) => {
+                                let optional = self.optional(&repeat.symbol, &inner);
+                                items.extend((min..max).map(|_| optional.clone()));
+                            }
+                        }
+                        items
+                    }
+                }
+            }
+            SymbolKind::Choose(ref symbol)
+            | SymbolKind::Name(_, ref symbol)
+            | SymbolKind::DocName(_, ref symbol) => self.symbol(symbol),
+            SymbolKind::Error => vec!["? error ?".to_string()],
+            SymbolKind::Lookahead
+            | SymbolKind::Lookbehind
+            | SymbolKind::PosLookahead(_)
+            | SymbolKind::NegLookahead(_)
+            | SymbolKind::Cut => vec![],
+            SymbolKind::Macro(_) | SymbolKind::AmbiguousId(_) => {
+                unreachable!("symbol `{}` should have been expanded away", symbol)
+            }
+        }
+    }
+
+    /// `symbol*`, where `inner` is the BNF for `symbol`.
+    fn star(&mut self, symbol: &Symbol, inner: &[String]) -> String {
+        let key = format!("{}*", symbol);
+        self.helper_for(key, symbol, "star", |_, this| {
+            vec![vec![], [vec![this], inner.to_vec()].concat()]
+        })
+    }
+
+    /// `symbol?`, where `inner` is the BNF for `symbol`.
+    fn optional(&mut self, symbol: &Symbol, inner: &[String]) -> String {
+        let key = format!("{}?", symbol);
+        self.helper_for(key, symbol, "opt", |_, _| vec![vec![], inner.to_vec()])
+    }
+
+    fn helper<F>(&mut self, symbol: &Symbol, kind: &str, alternatives: F) -> String
+    where
+        F: FnOnce(&mut BnfWriter, String) -> Vec<Vec<String>>,
+    {
+        self.helper_for(symbol.to_string(), symbol, kind, alternatives)
+    }
+
+    /// The nonterminal of the rule for the repeat, option or group
+    /// whose text is `key`, written the first time it is needed;
+    /// `alternatives` is given the nonterminal, for recursive rules.
+    fn helper_for<F>(&mut self, key: String, symbol: &Symbol, kind: &str, alternatives: F) -> String
+    where
+        F: FnOnce(&mut BnfWriter, String) -> Vec<Vec<String>>,
+    {
+        if let Some(nonterminal) = self.helpers.get(&key) {
+            return nonterminal.clone();
+        }
+        let words = symbol_words(symbol);
+        let base = if words.is_empty() {
+            kind.to_string()
+        } else {
+            format!("{}_{}", words, kind)
+        };
+        let mut ident = base.clone();
+        let mut suffix = 1;
+        while self.idents.values().any(|used| *used == ident)
+            || self.rules.iter().any(|(used, _)| *used == ident)
+        {
+            suffix += 1;
+            ident = format!("{}_{}", base, suffix);
+        }
+        let nonterminal = format!("<{}>", ident);
+        self.helpers.insert(key, nonterminal.clone());
+        // reserve the name before writing the alternatives, which may
+        // need rules of their own
+        let index = self.rules.len();
+        self.rules.push((ident, vec![]));
+        let alternatives = alternatives(self, nonterminal.clone());
+        self.rules[index].1 = alternatives;
+        nonterminal
+    }
+}
+
+/// The words of `symbol` a helper rule for it is named after, joined
+/// with underscores, like `Expr` for `(<Expr> ",")*`.
+fn symbol_words(symbol: &Symbol) -> String {
+    match symbol.kind {
+        SymbolKind::Repeat(ref repeat) => symbol_words(&repeat.symbol),
+        SymbolKind::Choose(ref symbol)
+        | SymbolKind::Name(_, ref symbol)
+        | SymbolKind::DocName(_, ref symbol) => symbol_words(symbol),
+        _ => {
+            let text = symbol.to_string();
+            let words: Vec<&str> = text
+                .split(|c: char| !c.is_alphanumeric() && c != '_')
+                .filter(|word| !word.is_empty())
+                .collect();
+            words.join("_")
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::normalize;
+    use crate::parser;
+
+    fn bnf(text: &str) -> String {
+        let grammar = parser::parse_grammar(text).unwrap();
+        let grammar = normalize::expand_macro_uses(grammar).unwrap();
+        grammar.to_bnf_string()
+    }
+
+    #[test]
+    fn repeats_and_macros() {
+        let text = r#"
+grammar;
+pub Exprs = Comma<Expr>;
+Comma<T>: Vec<T> = {
+    <mut v:(<T> ",")*> <e:T?> => v,
+};
+Expr: () = {
+    @L "(" Expr+ ")" @R => (),
+    r"[0-9]+" => (),
+    "x"{2,3} => (),
+    ("+" | "-") Expr => (),
+};
+"#;
+        assert_eq!(
+            bnf(text),
+            r#"<Exprs> ::= <Comma_Expr>
+
+<Expr> ::= "(" <Expr_plus> ")"
+         | ? [0-9]+ ?
+         | "x" "x" <x_opt>
+         | <choice> <Expr>
+
+<Comma_Expr> ::= <Expr_star> <Expr_opt>
+
+<Expr_plus> ::= <Expr>
+              | <Expr_plus> <Expr>
+
+<x_opt> ::= ""
+          | "x"
+
+<choice> ::= "+"
+           | "-"
+
+<Expr_star> ::= ""
+              | <Expr_star> <Expr> ","
+
+<Expr_opt> ::= ""
+             | <Expr>
+"#
+        );
+    }
+}
@@ -33,6 +33,7 @@
 use crate::grammar::repr as r;
 use crate::rust::RustWrite;
 use std::io::{self, Write};
+use unicode_xid::UnicodeXID;
 
 pub fn emit_action_code<W: Write>(grammar: &r::Grammar, rust: &mut RustWrite<W>) -> io::Result<()> {
     for (i, defn) in grammar.action_fn_defns.iter().enumerate() {
@@ -85,10 +86,18 @@ fn emit_user_action_code<W: Write>(
 ) -> io::Result<()> {
     let ret_type = ret_type_string(grammar, defn);
 
+    // `@L`, `@R` and `@span` in the code refer to the start and end of
+    // the alternative.
+    let start = format!("{}start", grammar.prefix);
+    let end = format!("{}end", grammar.prefix);
+    let (code, uses_locations) = replace_locations(grammar, &data.code, &start, &end);
+
     // For each symbol to be reduced, we will receive
     // a (L, T, L) triple where the Ls are locations and
-    // the T is the data. Ignore the locations and bind
-    // the data to the name the user gave.
+    // the T is the data. Ignore the locations (except the
+    // outer ones, if the code needs them) and bind the data
+    // to the name the user gave.
+    let last = data.arg_patterns.len().saturating_sub(1);
     let mut arguments: Vec<String> = data
         .arg_patterns
         .iter()
@@ -98,7 +107,23 @@ fn emit_user_action_code<W: Write>(
                 .cloned()
                 .map(|t| grammar.types.spanned_type(t)),
         )
-        .map(|(name, ty)| format!("(_, {}, _): {}", name, ty))
+        .enumerate()
+        .map(|(i, (name, ty))| {
+            if let Some(ref parse_tree) = data.parse_tree {
+                return parse_tree_argument(grammar, parse_tree, i, last, name, &ty);
+            }
+            let l = if uses_locations && i == 0 {
+                &start
+            } else {
+                "_"
+            };
+            let r = if uses_locations && i == last {
+                &end
+            } else {
+                "_"
+            };
+            format!("({}, {}, {}): {}", l, name, r, ty)
+        })
         .collect();
 
     // If this is a reduce of an empty production, we will
@@ -120,7 +145,24 @@ fn emit_user_action_code<W: Write>(
         ]);
     }
 
-    rust!(rust, "#[allow(clippy::too_many_arguments, clippy::needless_lifetimes, clippy::just_underscores_and_digits)]");
+    // `#[fallible]` grammars call a closure to give `?` a scope, and
+    // parse tree nodes clone the locations; the argument types spell out
+    // those of the symbols, which nest deeply for groups like
+    // `(("+" | "-") Num)*`
+    rust!(
+        rust,
+        "#[allow(clippy::too_many_arguments, clippy::needless_lifetimes, \
+         clippy::just_underscores_and_digits, clippy::redundant_closure_call, \
+         clippy::type_complexity{})]",
+        if data.parse_tree.is_some() {
+            ", clippy::clone_on_copy"
+        } else {
+            ""
+        }
+    );
+    for attribute in &data.attributes {
+        rust!(rust, "#[{}]", attribute);
+    }
     rust.fn_header(
         &r::Visibility::Priv,
         format!("{}action{}", grammar.prefix, index),
@@ -132,15 +174,149 @@ fn emit_user_action_code<W: Write>(
 
     rust!(rust, "{{");
 
+    if let Some(ref parse_tree) = data.parse_tree {
+        emit_parse_tree_node(grammar, rust, defn, parse_tree, &code)?;
+        rust!(rust, "}}");
+        return Ok(());
+    }
+
+    if uses_locations && data.arg_patterns.is_empty() {
+        rust!(rust, "let {} = *{}lookahead;", start, grammar.prefix);
+        rust!(rust, "let {} = *{}lookbehind;", end, grammar.prefix);
+    }
+
     // The user did not provide any code
-    if data.code != "()" {
-        rust!(rust, "{}", data.code);
+    if code != "()" {
+        rust!(rust, "{}", code);
     }
 
     rust!(rust, "}}");
     Ok(())
 }
 
+/// An argument of the action fn of an `#[emit_cst]` grammar. The
+/// locations are bound when the node needs them, and the node of a
+/// nonterminal is split from its value.
+fn parse_tree_argument(
+    grammar: &r::Grammar,
+    parse_tree: &r::ParseTreeAction,
+    i: usize,
+    last: usize,
+    name: &r::Name,
+    ty: &r::TypeRepr,
+) -> String {
+    let symbol = &parse_tree.symbols[i];
+    let terminal = matches!(symbol, r::ParseTreeSymbol::Terminal(_));
+    let l = if terminal || i == 0 {
+        format!("{}l{}", grammar.prefix, i)
+    } else {
+        "_".to_string()
+    };
+    let r = if terminal || i == last {
+        format!("{}r{}", grammar.prefix, i)
+    } else {
+        "_".to_string()
+    };
+    let value = match symbol {
+        r::ParseTreeSymbol::Nonterminal => format!("({}, {}node{})", name, grammar.prefix, i),
+        r::ParseTreeSymbol::Terminal(_) | r::ParseTreeSymbol::Hidden => name.to_string(),
+    };
+    format!("({}, {}, {}): {}", l, value, r, ty)
+}
+
+/// The body of the action fn of an `#[emit_cst]` grammar, which pairs
+/// the value of `code` with the node of the alternative.
+fn emit_parse_tree_node<W: Write>(
+    grammar: &r::Grammar,
+    rust: &mut RustWrite<W>,
+    defn: &r::ActionFnDefn,
+    parse_tree: &r::ParseTreeAction,
+    code: &str,
+) -> io::Result<()> {
+    let p = &grammar.prefix;
+    if parse_tree.symbols.is_empty() {
+        rust!(rust, "let {p}start = (*{p}lookahead).clone();", p = p);
+        rust!(rust, "let {p}end = (*{p}lookbehind).clone();", p = p);
+    } else {
+        let last = parse_tree.symbols.len() - 1;
+        rust!(rust, "let {p}start = {p}l0.clone();", p = p);
+        rust!(rust, "let {p}end = {p}r{}.clone();", last, p = p);
+    }
+
+    let children: Vec<String> = parse_tree
+        .symbols
+        .iter()
+        .enumerate()
+        .filter_map(|(i, symbol)| match symbol {
+            r::ParseTreeSymbol::Terminal(terminal) => Some(format!(
+                "{p}lalrpop_util::ParseTreeNode::Terminal {{ token: {:?}, \
+                 span: {p}lalrpop_util::Span::new({p}l{i}.clone(), {p}r{i}.clone()) }}",
+                terminal.to_string(),
+                p = p,
+                i = i
+            )),
+            r::ParseTreeSymbol::Nonterminal => Some(format!("{}node{}", p, i)),
+            r::ParseTreeSymbol::Hidden => None,
+        })
+        .collect();
+    rust!(
+        rust,
+        "let {p}node = {p}lalrpop_util::ParseTreeNode::Nonterminal {{ name: {:?}, \
+         children: alloc::vec![{}], \
+         span: {p}lalrpop_util::Span::new({p}start.clone(), {p}end.clone()) }};",
+        parse_tree.nonterminal.to_string(),
+        children.join(", "),
+        p = p
+    );
+
+    if defn.fallible {
+        rust!(
+            rust,
+            "({}).map(|{p}value| ({p}value, {p}node))",
+            code,
+            p = p
+        );
+    } else {
+        rust!(rust, "({}, {}node)", code, p);
+    }
+    Ok(())
+}
+
+/// Replaces `@L`, `@R` and `@span` in `code` with `start`, `end` and a
+/// `Span` of the two, and says whether there were any.
+fn replace_locations(grammar: &r::Grammar, code: &str, start: &str, end: &str) -> (String, bool) {
+    let mut result = String::with_capacity(code.len());
+    let mut uses_locations = false;
+    let mut rest = code;
+    while let Some(at) = rest.find('@') {
+        result.push_str(&rest[..at]);
+        let after = &rest[at + 1..];
+        let word_len = after
+            .find(|c: char| !UnicodeXID::is_xid_continue(c))
+            .unwrap_or(after.len());
+        match &after[..word_len] {
+            "L" => result.push_str(start),
+            "R" => result.push_str(end),
+            "span" => {
+                result.push_str(&format!(
+                    "{}lalrpop_util::Span::new({}, {})",
+                    grammar.prefix, start, end
+                ));
+            }
+            word => {
+                result.push('@');
+                result.push_str(word);
+                rest = &after[word_len..];
+                continue;
+            }
+        }
+        uses_locations = true;
+        rest = &after[word_len..];
+    }
+    result.push_str(rest);
+    (result, uses_locations)
+}
+
 fn emit_lookaround_action_code<W: Write>(
     grammar: &r::Grammar,
     rust: &mut RustWrite<W>,
@@ -237,7 +413,7 @@ fn emit_inline_action_code<W: Write>(
     rust!(
         rust,
         "#[allow(clippy::too_many_arguments, clippy::needless_lifetimes,
-    clippy::just_underscores_and_digits)]"
+    clippy::just_underscores_and_digits, clippy::type_complexity)]"
     );
     rust.fn_header(
         &r::Visibility::Priv,
@@ -0,0 +1,265 @@
+//! Resolution of `import "other.lalrpop";` items. This runs after the
+//! grammar has been parsed and before it is normalized: each imported
+//! file is read and parsed, and its nonterminals (and `use` lines) are
+//! spliced into the importing grammar, so the rest of the pipeline
+//! never sees an import. The macros of the prelude are spliced in the
+//! same way.
+
+use crate::collections::Set;
+use crate::file_text::FileText;
+use crate::grammar::parse_tree::{Grammar, GrammarItem, Import, Visibility};
+use crate::normalize::{NormError, NormResult};
+use crate::parser;
+use crate::tok::{self, Tok};
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+mod test;
+
+/// The macros that every grammar can use, unless it opts out with
+/// `#![no_prelude]`.
+const PRELUDE: &str = include_str!("prelude.lalrpop");
+
+const NO_PRELUDE: &str = "#![no_prelude]";
+
+/// Replaces the `import` items of `grammar`, which was parsed from
+/// `file_text`, with the nonterminals of the files they name. The
+/// imported files are added to `file_text` so that errors in them can
+/// be reported.
+pub fn resolve_imports(file_text: &mut FileText, grammar: Grammar) -> NormResult<Grammar> {
+    let root = file_text.path().to_path_buf();
+    let mut importer = Importer {
+        file_text,
+        imported: Set::new(),
+        stack: vec![],
+        texts: vec![],
+    };
+    let canonical = fs::canonicalize(&root).unwrap_or_else(|_| root.clone());
+    importer.imported.insert(canonical.clone());
+    importer.stack.push((canonical, root.display().to_string()));
+
+    let mut grammar = grammar;
+    let mut items = vec![];
+    let mut imported_items = vec![];
+    for item in grammar.items {
+        match item {
+            GrammarItem::Import(import) => {
+                let mut new_items = vec![];
+                importer.import(&root, &import, &mut new_items)?;
+                imported_items.push((import, new_items));
+            }
+            item => items.push(item),
+        }
+    }
+
+    let mut uses = vec![];
+    let mut defined: Set<_> = items
+        .iter()
+        .filter_map(GrammarItem::as_nonterminal)
+        .map(|nt| nt.name.clone())
+        .collect();
+    for (import, new_items) in imported_items {
+        for item in new_items {
+            match item {
+                GrammarItem::Nonterminal(ref data) => {
+                    if !defined.insert(data.name.clone()) {
+                        return Err(NormError {
+                            message: format!(
+                                "importing `{}` would redefine `{}`",
+                                import.path, data.name
+                            ),
+                            span: import.span,
+                        });
+                    }
+                    items.push(item);
+                }
+                GrammarItem::Use(ref u) => {
+                    let duplicate = items
+                        .iter()
+                        .chain(&uses)
+                        .any(|i| matches!(i, GrammarItem::Use(v) if v == u));
+                    if !duplicate {
+                        uses.push(item);
+                    }
+                }
+                // the imported nonterminals keep their declared types
+                GrammarItem::TypeDecl(..) => items.push(item),
+                _ => {}
+            }
+        }
+    }
+
+    // the prefix must not collide with anything in the imported files either
+    while importer.texts.iter().any(|t| t.contains(&grammar.prefix)) {
+        grammar.prefix.push('_');
+    }
+
+    grammar.items = uses.into_iter().chain(items).collect();
+    Ok(grammar)
+}
+
+/// Adds the macros of the prelude that `grammar` does not define itself,
+/// unless it has a `#![no_prelude]` attribute, which is removed. Like an
+/// imported file, the prelude is added to `file_text` so that errors in
+/// its expansions can be reported.
+pub fn add_prelude(file_text: &mut FileText, mut grammar: Grammar) -> Grammar {
+    let attributes = grammar.module_attributes.len();
+    grammar
+        .module_attributes
+        .retain(|attribute| attribute.split_whitespace().collect::<String>() != NO_PRELUDE);
+    if grammar.module_attributes.len() != attributes {
+        return grammar;
+    }
+
+    let defined: Set<_> = grammar
+        .items
+        .iter()
+        .filter_map(GrammarItem::as_nonterminal)
+        .map(|nt| nt.name.clone())
+        .collect();
+    let offset = file_text.add_import(FileText::new(
+        PathBuf::from("<prelude>"),
+        PRELUDE.to_string(),
+    ));
+    let prelude = parser::parse_grammar_at(PRELUDE, offset).expect("invalid prelude");
+    grammar
+        .items
+        .extend(prelude.items.into_iter().filter(|item| {
+            item.as_nonterminal()
+                .is_some_and(|nt| !defined.contains(&nt.name))
+        }));
+    grammar
+}
+
+/// Returns `lalrpop_file` followed by every file it imports, directly
+/// or indirectly. This only scans the tokens of each file, so that it
+/// is cheap enough to run before deciding whether to rebuild; files
+/// that cannot be read or tokenized are skipped, leaving the errors to
+/// be reported by `resolve_imports`.
+pub fn grammar_files(lalrpop_file: &Path) -> Vec<PathBuf> {
+    let canonical = |file: &Path| fs::canonicalize(file).unwrap_or_else(|_| file.to_path_buf());
+    let mut files = vec![lalrpop_file.to_path_buf()];
+    let mut seen: Set<PathBuf> = Some(canonical(lalrpop_file)).into_iter().collect();
+    let mut index = 0;
+    while index < files.len() {
+        let text = match fs::read_to_string(&files[index]) {
+            Ok(text) => text,
+            Err(_) => {
+                index += 1;
+                continue;
+            }
+        };
+        let base = files[index]
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .to_path_buf();
+        index += 1;
+
+        let mut tokens = tok::Tokenizer::new(&text, 0).map_while(Result::ok);
+        while let Some((_, token, _)) = tokens.next() {
+            if token != Tok::Import {
+                continue;
+            }
+            if let Some((lo, Tok::StringLiteral(s), _)) = tokens.next() {
+                if let Ok(path) = tok::apply_string_escapes(s, lo + 1) {
+                    let file = base.join(&*path);
+                    if seen.insert(canonical(&file)) {
+                        files.push(file);
+                    }
+                }
+            }
+        }
+    }
+    files
+}
+
+struct Importer<'a> {
+    /// the file being processed; every imported file is added to it
+    file_text: &'a mut FileText,
+
+    /// canonical paths of the files imported so far, so that a file
+    /// imported along several paths is only merged once
+    imported: Set<PathBuf>,
+
+    /// the chain of files currently being imported (canonical path
+    /// and a path for messages), used to detect cycles
+    stack: Vec<(PathBuf, String)>,
+
+    /// the text of every imported file
+    texts: Vec<String>,
+}
+
+impl<'a> Importer<'a> {
+    /// Resolves `import`, found in the file `importer`, appending the
+    /// items it brings in to `items`.
+    fn import(
+        &mut self,
+        importer: &Path,
+        import: &Import,
+        items: &mut Vec<GrammarItem>,
+    ) -> NormResult<()> {
+        let path = importer
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join(&import.path);
+        let error = |message: String| NormError {
+            message,
+            span: import.span,
+        };
+
+        let canonical = fs::canonicalize(&path)
+            .map_err(|e| error(format!("could not read `{}`: {}", path.display(), e)))?;
+
+        if let Some(i) = self.stack.iter().position(|(p, _)| *p == canonical) {
+            let cycle: Vec<_> = self.stack[i..]
+                .iter()
+                .map(|(_, name)| format!("`{}`", name))
+                .chain(Some(format!("`{}`", path.display())))
+                .collect();
+            return Err(error(format!("circular import: {}", cycle.join(" -> "))));
+        }
+
+        if !self.imported.insert(canonical.clone()) {
+            return Ok(());
+        }
+
+        let text = fs::read_to_string(&path)
+            .map_err(|e| error(format!("could not read `{}`: {}", path.display(), e)))?;
+        let offset = self
+            .file_text
+            .add_import(FileText::new(path.clone(), text.clone()));
+        let grammar = match parser::parse_grammar_at(&text, offset) {
+            Ok(grammar) => grammar,
+            Err(e) => {
+                let (span, message) = super::parse_error_message(&text, offset, e);
+                return Err(NormError { message, span });
+            }
+        };
+
+        self.stack.push((canonical, path.display().to_string()));
+        for item in grammar.items {
+            match item {
+                GrammarItem::Import(ref nested) => self.import(&path, nested, items)?,
+                GrammarItem::Nonterminal(mut data) => {
+                    // the importing grammar chooses its own entry points
+                    data.visibility = Visibility::Priv;
+                    items.push(GrammarItem::Nonterminal(data));
+                }
+                GrammarItem::Use(..) | GrammarItem::TypeDecl(..) => items.push(item),
+                GrammarItem::MatchToken(..)
+                | GrammarItem::ExternToken(..)
+                | GrammarItem::InternToken(..)
+                | GrammarItem::PrecedenceDecl(..)
+                | GrammarItem::LeftRecursiveDecl(..)
+                | GrammarItem::StartDecl(..)
+                | GrammarItem::Test(..) => {}
+            }
+        }
+        self.stack.pop();
+        self.texts.push(text);
+
+        Ok(())
+    }
+}
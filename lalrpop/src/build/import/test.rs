@@ -0,0 +1,237 @@
+use crate::file_text::FileText;
+use crate::grammar::parse_tree::{GrammarItem, NonterminalString, Visibility};
+use crate::parser;
+use std::fs;
+use std::path::PathBuf;
+use string_cache::DefaultAtom as Atom;
+
+/// Writes `files` into a fresh directory and returns the path of the
+/// first one.
+fn write_files(test_name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir()
+        .join(format!("lalrpop-import-{}", std::process::id()))
+        .join(test_name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    for (name, text) in files {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, text).unwrap();
+    }
+    dir.join(files[0].0)
+}
+
+fn resolve(path: PathBuf) -> (FileText, Result<Vec<GrammarItem>, String>) {
+    let mut file_text = FileText::from_path(path).unwrap();
+    let grammar = parser::parse_grammar(file_text.text()).unwrap();
+    let result = super::resolve_imports(&mut file_text, grammar)
+        .map(|grammar| grammar.items)
+        .map_err(|error| error.message);
+    (file_text, result)
+}
+
+fn nonterminals(items: &[GrammarItem]) -> Vec<(String, Visibility)> {
+    items
+        .iter()
+        .filter_map(GrammarItem::as_nonterminal)
+        .map(|nt| (nt.name.to_string(), nt.visibility.clone()))
+        .collect()
+}
+
+#[test]
+fn splices_nonterminals_and_uses() {
+    let main = write_files(
+        "splice",
+        &[
+            (
+                "main.lalrpop",
+                r#"grammar; import "sub/num.lalrpop"; pub Sum = Num "+" Num;"#,
+            ),
+            (
+                "sub/num.lalrpop",
+                r#"use std::str::FromStr; grammar; import "digits.lalrpop"; pub Num = Digits;"#,
+            ),
+            ("sub/digits.lalrpop", r#"grammar; pub Digits = r"[0-9]+";"#),
+        ],
+    );
+    let (_, items) = resolve(main);
+    let items = items.unwrap();
+    assert!(matches!(&items[0], GrammarItem::Use(u) if u == "std::str::FromStr"));
+    assert_eq!(
+        nonterminals(&items),
+        vec![
+            ("Sum".to_string(), Visibility::Pub(None)),
+            ("Digits".to_string(), Visibility::Priv),
+            ("Num".to_string(), Visibility::Priv),
+        ]
+    );
+}
+
+#[test]
+fn keeps_type_declarations() {
+    let main = write_files(
+        "type_decl",
+        &[
+            (
+                "main.lalrpop",
+                r#"grammar; import "nums.lalrpop"; pub S = Nums;"#,
+            ),
+            (
+                "nums.lalrpop",
+                r#"grammar; %type<Vec<u32>> Nums; pub Nums = Num => vec![<>]; Num: u32 = "0" => 0;"#,
+            ),
+        ],
+    );
+    let (_, items) = resolve(main);
+    let declared: Vec<_> = items
+        .unwrap()
+        .iter()
+        .filter_map(|item| match item {
+            GrammarItem::TypeDecl(data) => Some(data.nonterminals.clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(declared, vec![vec![NonterminalString(Atom::from("Nums"))]]);
+}
+
+#[test]
+fn diamond_imports_are_merged_once() {
+    let main = write_files(
+        "diamond",
+        &[
+            (
+                "main.lalrpop",
+                r#"grammar; import "a.lalrpop"; import "b.lalrpop"; pub S = A B;"#,
+            ),
+            ("a.lalrpop", r#"grammar; import "c.lalrpop"; pub A = C;"#),
+            ("b.lalrpop", r#"grammar; import "c.lalrpop"; pub B = C;"#),
+            ("c.lalrpop", r#"grammar; pub C = "c";"#),
+        ],
+    );
+    let (_, items) = resolve(main);
+    let names: Vec<_> = nonterminals(&items.unwrap())
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    assert_eq!(names, vec!["S", "C", "A", "B"]);
+}
+
+#[test]
+fn redefinition() {
+    let main = write_files(
+        "redefinition",
+        &[
+            (
+                "main.lalrpop",
+                r#"grammar; import "a.lalrpop"; pub A = "x";"#,
+            ),
+            ("a.lalrpop", r#"grammar; pub A = "a";"#),
+        ],
+    );
+    let (_, items) = resolve(main);
+    assert_eq!(
+        items.unwrap_err(),
+        "importing `a.lalrpop` would redefine `A`"
+    );
+}
+
+#[test]
+fn circular_import() {
+    let main = write_files(
+        "circular",
+        &[
+            ("main.lalrpop", r#"grammar; import "a.lalrpop"; pub S = A;"#),
+            ("a.lalrpop", r#"grammar; import "b.lalrpop"; pub A = B;"#),
+            ("b.lalrpop", r#"grammar; import "a.lalrpop"; pub B = "b";"#),
+        ],
+    );
+    let dir = main.parent().unwrap().to_path_buf();
+    let (_, items) = resolve(main.clone());
+    assert_eq!(
+        items.unwrap_err(),
+        format!(
+            "circular import: `{}` -> `{}` -> `{}`",
+            dir.join("a.lalrpop").display(),
+            dir.join("b.lalrpop").display(),
+            dir.join("a.lalrpop").display(),
+        )
+    );
+}
+
+#[test]
+fn missing_file() {
+    let main = write_files(
+        "missing",
+        &[(
+            "main.lalrpop",
+            r#"grammar; import "nope.lalrpop"; pub S = "s";"#,
+        )],
+    );
+    let (_, items) = resolve(main);
+    assert!(items.unwrap_err().starts_with("could not read `"));
+}
+
+#[test]
+fn errors_point_into_imported_file() {
+    let main = write_files(
+        "error_span",
+        &[
+            ("main.lalrpop", r#"grammar; import "a.lalrpop"; pub S = A;"#),
+            ("a.lalrpop", "grammar;\npub A = \"a\" );"),
+        ],
+    );
+    let mut file_text = FileText::from_path(main).unwrap();
+    let grammar = parser::parse_grammar(file_text.text()).unwrap();
+    let error = super::resolve_imports(&mut file_text, grammar).unwrap_err();
+    assert!(file_text
+        .span_str(error.span)
+        .ends_with("a.lalrpop:2:13: 2:13"));
+}
+
+#[test]
+fn grammar_files_follow_imports() {
+    let main = write_files(
+        "grammar_files",
+        &[
+            ("main.lalrpop", r#"grammar; import "a.lalrpop"; pub S = A;"#),
+            (
+                "a.lalrpop",
+                r#"grammar; import "main.lalrpop"; pub A = "a";"#,
+            ),
+        ],
+    );
+    let dir = main.parent().unwrap().to_path_buf();
+    assert_eq!(
+        super::grammar_files(&main),
+        vec![main.clone(), dir.join("a.lalrpop")]
+    );
+}
+
+#[test]
+fn prelude_adds_undefined_macros() {
+    let mut file_text = FileText::test();
+    let grammar = parser::parse_grammar(r#"grammar; pub S = Many1<"a">; Opt<T> = T;"#).unwrap();
+    let items = super::add_prelude(&mut file_text, grammar).items;
+    let names: Vec<_> = nonterminals(&items)
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            "S",
+            "Opt",
+            "Separated",
+            "SeparatedTrailing",
+            "Delimited",
+            "Many0",
+            "Many1"
+        ]
+    );
+
+    let grammar =
+        parser::parse_grammar(r#"#![no_prelude] #![allow(unused)] grammar; pub S = "a";"#).unwrap();
+    let grammar = super::add_prelude(&mut file_text, grammar);
+    assert_eq!(nonterminals(&grammar.items).len(), 1);
+    assert_eq!(grammar.module_attributes, vec!["#![allow(unused)]"]);
+}
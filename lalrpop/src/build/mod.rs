@@ -1,14 +1,20 @@
 //! Utilities for running in a build script.
 
+use crate::codegen;
+use crate::conflicts_json::ConflictsJson;
+use crate::ebnf;
 use crate::file_text::FileText;
 use crate::grammar::parse_tree as pt;
 use crate::grammar::repr as r;
+use crate::graphviz;
 use crate::lexer::intern_token;
+use crate::lint::Lint;
 use crate::lr1;
 use crate::message::builder::InlineBuilder;
 use crate::message::{Content, Message};
 use crate::normalize;
 use crate::parser;
+use crate::railroad;
 use crate::rust::RustWrite;
 use crate::session::{ColorConfig, Session};
 use crate::tls::Tls;
@@ -17,15 +23,22 @@ use crate::util::Sep;
 use itertools::Itertools;
 use lalrpop_util::ParseError;
 use tiny_keccak::{Hasher, Sha3};
+use unicode_xid::UnicodeXID;
 
+use std::env;
 use std::fs;
 use std::io::{self, BufRead, IsTerminal, Read, Write};
 use std::path::{Path, PathBuf};
-use std::process::exit;
+use std::process::{exit, Command, Stdio};
 use std::rc::Rc;
+use std::thread;
 
 mod action;
 mod fake_term;
+mod import;
+
+#[cfg(test)]
+mod test;
 
 use self::fake_term::FakeTerminal;
 
@@ -37,13 +50,15 @@ const LALRPOP_VERSION_HEADER: &str = concat!(
     "\""
 );
 
-fn hash_file(file: &Path) -> io::Result<String> {
-    let mut file = fs::File::open(file)?;
-    let mut file_bytes = Vec::new();
-    file.read_to_end(&mut file_bytes).unwrap();
-
+/// Hashes a grammar file together with the files it imports.
+fn hash_files(files: &[PathBuf]) -> io::Result<String> {
     let mut sha3 = Sha3::v256();
-    sha3.update(&file_bytes);
+    for file in files {
+        let mut file = fs::File::open(file)?;
+        let mut file_bytes = Vec::new();
+        file.read_to_end(&mut file_bytes).unwrap();
+        sha3.update(&file_bytes);
+    }
 
     let mut output = [0u8; 32];
     sha3.finalize(&mut output);
@@ -52,7 +67,29 @@ fn hash_file(file: &Path) -> io::Result<String> {
 }
 
 pub fn process_dir<P: AsRef<Path>>(session: Rc<Session>, root_dir: P) -> io::Result<()> {
-    let lalrpop_files = lalrpop_files(root_dir)?;
+    process_files(session, lalrpop_files(root_dir)?)
+}
+
+/// Processes the given files, in order.
+pub fn process_files<I, P>(session: Rc<Session>, lalrpop_files: I) -> io::Result<()>
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<Path>,
+{
+    let lalrpop_files: Vec<P> = lalrpop_files.into_iter().collect();
+    if let (Some(name), [_, _, ..]) = (&session.module_name, &lalrpop_files[..]) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "the module name `{}` cannot be given to several grammars: {}",
+                name,
+                lalrpop_files
+                    .iter()
+                    .map(|file| file.as_ref().display())
+                    .format(", ")
+            ),
+        ));
+    }
     for lalrpop_file in lalrpop_files {
         process_file(session.clone(), lalrpop_file)?;
     }
@@ -63,7 +100,18 @@ pub fn process_file<P: AsRef<Path>>(session: Rc<Session>, lalrpop_file: P) -> io
     let lalrpop_file = lalrpop_file.as_ref();
     let rs_file = resolve_rs_file(&session, lalrpop_file)?;
     let report_file = resolve_report_file(&session, lalrpop_file)?;
-    process_file_into(session, lalrpop_file, &rs_file, &report_file)
+    let dot_file = resolve_dot_file(&session, lalrpop_file)?;
+    let ast_file = resolve_ast_file(&session, lalrpop_file)?;
+    let conflicts_file = resolve_conflicts_file(&session, lalrpop_file)?;
+    process_file_into(
+        session,
+        lalrpop_file,
+        &rs_file,
+        &report_file,
+        &dot_file,
+        &ast_file,
+        &conflicts_file,
+    )
 }
 
 fn resolve_rs_file(session: &Session, lalrpop_file: &Path) -> io::Result<PathBuf> {
@@ -74,6 +122,30 @@ fn resolve_report_file(session: &Session, lalrpop_file: &Path) -> io::Result<Pat
     gen_resolve_file(session, lalrpop_file, "report")
 }
 
+fn resolve_dot_file(session: &Session, lalrpop_file: &Path) -> io::Result<PathBuf> {
+    gen_resolve_file(session, lalrpop_file, "dot")
+}
+
+fn resolve_ast_file(session: &Session, lalrpop_file: &Path) -> io::Result<PathBuf> {
+    gen_resolve_file(session, lalrpop_file, "ast.rs")
+}
+
+fn resolve_conflicts_file(session: &Session, lalrpop_file: &Path) -> io::Result<PathBuf> {
+    gen_resolve_file(session, lalrpop_file, "conflicts.json")
+}
+
+fn resolve_ebnf_file(session: &Session, lalrpop_file: &Path) -> io::Result<PathBuf> {
+    gen_resolve_file(session, lalrpop_file, "ebnf")
+}
+
+fn resolve_bnf_file(session: &Session, lalrpop_file: &Path) -> io::Result<PathBuf> {
+    gen_resolve_file(session, lalrpop_file, "bnf")
+}
+
+fn resolve_railroad_file(session: &Session, lalrpop_file: &Path) -> io::Result<PathBuf> {
+    gen_resolve_file(session, lalrpop_file, "railroad.html")
+}
+
 fn gen_resolve_file(session: &Session, lalrpop_file: &Path, ext: &str) -> io::Result<PathBuf> {
     let in_dir = if let Some(ref d) = session.in_dir {
         d.as_path()
@@ -119,9 +191,24 @@ fn gen_resolve_file(session: &Session, lalrpop_file: &Path, ext: &str) -> io::Re
     // If the lalrpop file is not in in_dir, the result is that the
     // .rs file is created in the same directory as the lalrpop file
     // for compatibility reasons
-    Ok(out_dir
-        .join(lalrpop_file.strip_prefix(in_dir).unwrap_or(lalrpop_file))
-        .with_extension(ext))
+    let out_file = out_dir.join(lalrpop_file.strip_prefix(in_dir).unwrap_or(lalrpop_file));
+    match session.module_name {
+        Some(ref name) => {
+            let mut chars = name.chars();
+            let is_identifier = chars
+                .next()
+                .is_some_and(|c| UnicodeXID::is_xid_start(c) || c == '_')
+                && chars.all(UnicodeXID::is_xid_continue);
+            if !is_identifier {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("the module name `{}` is not a Rust identifier", name),
+                ));
+            }
+            Ok(out_file.with_file_name(format!("{}.{}", name, ext)))
+        }
+        None => Ok(out_file.with_extension(ext)),
+    }
 }
 
 fn process_file_into(
@@ -129,9 +216,21 @@ fn process_file_into(
     lalrpop_file: &Path,
     rs_file: &Path,
     report_file: &Path,
+    dot_file: &Path,
+    ast_file: &Path,
+    conflicts_file: &Path,
 ) -> io::Result<()> {
-    session.emit_rerun_directive(lalrpop_file);
-    if session.force_build || needs_rebuild(lalrpop_file, rs_file)? {
+    if session.check_only {
+        check_file(session.clone(), lalrpop_file, conflicts_file)?;
+        print_timings(&session, lalrpop_file);
+        return Ok(());
+    }
+
+    let grammar_files = import::grammar_files(lalrpop_file);
+    for file in &grammar_files {
+        session.emit_rerun_directive(file);
+    }
+    if session.force_build || needs_rebuild(&grammar_files, rs_file)? {
         log!(
             session,
             Informative,
@@ -143,8 +242,12 @@ fn process_file_into(
         }
         remove_old_file(rs_file)?;
 
-        // Load the LALRPOP source text for this file:
-        let file_text = Rc::new(FileText::from_path(lalrpop_file.to_path_buf())?);
+        // Load the LALRPOP source text for this file, and parse it
+        // along with any files it imports (whose text is added to
+        // `file_text` for error reporting):
+        let mut file_text = FileText::from_path(lalrpop_file.to_path_buf())?;
+        let grammar = profile!(session, "Parsing", parse_grammar(&mut file_text));
+        let file_text = Rc::new(file_text);
 
         // Store the session and file-text in TLS -- this is not
         // intended to be used in this high-level code, but it gives
@@ -158,13 +261,172 @@ fn process_file_into(
         // generation fails at some point, we don't leave a partial
         // file behind.
         {
-            let grammar = parse_and_normalize_grammar(&session, &file_text)?;
-            let buffer = emit_recursive_ascent(&session, &grammar, report_file)?;
+            // the EBNF, BNF and railroad diagrams are written from the
+            // grammar before it is lowered, while `X*` and the like are
+            // still there
+            let ebnf_grammar = (session.emit_ebnf || session.emit_bnf || session.emit_railroad)
+                .then(|| grammar.clone());
+            let grammar = normalize_grammar(&session, &file_text, grammar)?;
+            if let Some(ebnf_grammar) = ebnf_grammar {
+                let ebnf_grammar = match normalize::expand_macro_uses(ebnf_grammar) {
+                    Ok(grammar) => grammar,
+                    Err(error) => report_error(&file_text, error.span, &error.message),
+                };
+                if session.emit_ebnf {
+                    let mut ebnf_file =
+                        fs::File::create(resolve_ebnf_file(&session, lalrpop_file)?)?;
+                    ebnf::write_ebnf(&ebnf_grammar, &mut ebnf_file)?;
+                }
+                if session.emit_bnf {
+                    fs::write(
+                        resolve_bnf_file(&session, lalrpop_file)?,
+                        ebnf_grammar.to_bnf_string(),
+                    )?;
+                }
+                if session.emit_railroad {
+                    let mut railroad_file =
+                        fs::File::create(resolve_railroad_file(&session, lalrpop_file)?)?;
+                    railroad::write_railroad(&ebnf_grammar, &mut railroad_file)?;
+                }
+            }
+            if session.emit_rust_types {
+                let mut rust = RustWrite::new(fs::File::create(ast_file)?);
+                codegen::rust_types::compile(&grammar, &mut rust)?;
+            }
+            let mut buffer =
+                emit_recursive_ascent(&session, &grammar, report_file, dot_file, conflicts_file)?;
+            if session.format_generated_code {
+                buffer = profile!(session, "Formatting", rustfmt(buffer));
+            }
             let mut output_file = fs::File::create(rs_file)?;
             writeln!(output_file, "{}", LALRPOP_VERSION_HEADER)?;
-            writeln!(output_file, "{}", hash_file(lalrpop_file)?)?;
+            writeln!(output_file, "{}", hash_files(&grammar_files)?)?;
+            if let Some(text) = &session.module_comment {
+                output_file.write_all(module_comment(text).as_bytes())?;
+            }
             output_file.write_all(&buffer)?;
         }
+        print_timings(&session, lalrpop_file);
+    }
+    Ok(())
+}
+
+/// `text` as `//` comment lines, for the top of the generated code.
+fn module_comment(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            if line.is_empty() {
+                "//\n".to_string()
+            } else {
+                format!("// {}\n", line)
+            }
+        })
+        .collect()
+}
+
+/// Prints how long each phase of processing `lalrpop_file` took, if
+/// the phases are being timed.
+fn print_timings(session: &Session, lalrpop_file: &Path) {
+    if let Some(timings) = &session.timings {
+        let timings = timings.borrow_mut().take();
+        eprintln!("phase timings for `{}`:", lalrpop_file.display());
+        eprint!("{}", timings);
+    }
+}
+
+/// Pipes the generated code through rustfmt, or through the program
+/// named by `RUSTFMT`, like bindgen does. If that fails, the code is
+/// returned as it is.
+fn rustfmt(buffer: Vec<u8>) -> Vec<u8> {
+    let program = env::var_os("RUSTFMT").unwrap_or_else(|| "rustfmt".into());
+    let mut child = match Command::new(&program)
+        .args(["--edition", "2021"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(error) => {
+            eprintln!(
+                "Warning: could not run {}: {}",
+                program.to_string_lossy(),
+                error
+            );
+            return buffer;
+        }
+    };
+
+    // write from another thread, so that rustfmt cannot be blocked on a
+    // full stdout while we are still writing its input
+    let mut stdin = child.stdin.take().unwrap();
+    let writer = thread::spawn(move || {
+        let _ = stdin.write_all(&buffer);
+        buffer
+    });
+    let output = child.wait_with_output();
+    let buffer = writer.join().unwrap();
+    match output {
+        Ok(output) if output.status.success() => output.stdout,
+        Ok(output) => {
+            eprintln!(
+                "Warning: could not format the generated code with {}: {}",
+                program.to_string_lossy(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            buffer
+        }
+        Err(error) => {
+            eprintln!(
+                "Warning: could not run {}: {}",
+                program.to_string_lossy(),
+                error
+            );
+            buffer
+        }
+    }
+}
+
+/// Runs everything up to and including the LR(1) construction, so that
+/// errors and conflicts are reported, but writes no files.
+fn check_file(session: Rc<Session>, lalrpop_file: &Path, conflicts_file: &Path) -> io::Result<()> {
+    log!(
+        session,
+        Verbose,
+        "checking file `{}`",
+        lalrpop_file.to_string_lossy()
+    );
+    let mut file_text = FileText::from_path(lalrpop_file.to_path_buf())?;
+    let grammar = profile!(session, "Parsing", parse_grammar(&mut file_text));
+    let file_text = Rc::new(file_text);
+    let _tls = Tls::install(session.clone(), file_text.clone());
+
+    let grammar = normalize_grammar(&session, &file_text, grammar)?;
+    if grammar.start_nonterminals.is_empty() {
+        println!("Error: no public symbols declared in grammar");
+        exit(1);
+    }
+    let mut conflicts_json = session.emit_conflicts_json.then(ConflictsJson::default);
+    let mut reduced = lr1::ReducedAlternatives::default();
+    for (user_nt, start_nt) in &grammar.start_nonterminals {
+        let _lr1_tls = lr1::Lr1Tls::install(grammar.terminals.clone());
+        match lr1::build_states(&grammar, start_nt.clone()) {
+            Ok(states) => reduced.add(&states),
+            Err(error) => {
+                if let Some(json) = &mut conflicts_json {
+                    json.add(user_nt, &error.states, &error.conflicts);
+                    json.save_to_path(conflicts_file)?;
+                }
+                let _ = lr1::report_error(&grammar, &error, report_message);
+                exit(1)
+            }
+        }
+    }
+    if let Some(json) = &conflicts_json {
+        json.save_to_path(conflicts_file)?;
+    }
+    if session.warn_unreachable {
+        report_unreduced(&session, &file_text, &grammar, &reduced);
     }
     Ok(())
 }
@@ -182,7 +444,7 @@ fn remove_old_file(rs_file: &Path) -> io::Result<()> {
     }
 }
 
-fn needs_rebuild(lalrpop_file: &Path, rs_file: &Path) -> io::Result<bool> {
+fn needs_rebuild(grammar_files: &[PathBuf], rs_file: &Path) -> io::Result<bool> {
     match fs::File::open(rs_file) {
         Ok(rs_file) => {
             let mut version_str = String::new();
@@ -193,7 +455,7 @@ fn needs_rebuild(lalrpop_file: &Path, rs_file: &Path) -> io::Result<bool> {
             f.read_line(&mut version_str)?;
             f.read_line(&mut hash_str)?;
 
-            Ok(hash_str.trim() != hash_file(lalrpop_file)?
+            Ok(hash_str.trim() != hash_files(grammar_files)?
                 || version_str.trim() != LALRPOP_VERSION_HEADER)
         }
         Err(e) => match e.kind() {
@@ -244,50 +506,61 @@ fn lalrpop_files<P: AsRef<Path>>(root_dir: P) -> io::Result<Vec<PathBuf>> {
     Ok(result)
 }
 
-fn parse_and_normalize_grammar(session: &Session, file_text: &FileText) -> io::Result<r::Grammar> {
-    let grammar = match parser::parse_grammar(file_text.text()) {
+fn parse_grammar(file_text: &mut FileText) -> pt::Grammar {
+    match try_parse_grammar(file_text) {
         Ok(grammar) => grammar,
+        Err((span, message)) => report_error(file_text, span, &message),
+    }
+}
 
-        Err(ParseError::InvalidToken { location }) => {
-            let ch = file_text.text()[location..].chars().next().unwrap();
-            report_error(
-                file_text,
-                pt::Span(location, location),
-                &format!("invalid character `{}`", ch),
-            );
-        }
+/// Parses the text of `file_text` along with the files it imports, or
+/// describes the first error.
+pub(crate) fn try_parse_grammar(
+    file_text: &mut FileText,
+) -> Result<pt::Grammar, (pt::Span, String)> {
+    let grammar = parser::parse_grammar(file_text.text())
+        .map_err(|error| parse_error_message(file_text.text(), 0, error))?;
+
+    match import::resolve_imports(file_text, grammar) {
+        Ok(grammar) => Ok(import::add_prelude(file_text, grammar)),
+        Err(error) => Err((error.span, error.message)),
+    }
+}
 
-        Err(ParseError::UnrecognizedEof { location, .. }) => {
-            report_error(
-                file_text,
+/// Describes an error from parsing `text`, whose spans start at `offset`.
+fn parse_error_message(text: &str, offset: usize, error: parser::ParseError) -> (pt::Span, String) {
+    match error {
+        ParseError::InvalidToken { location } => {
+            let ch = text[location - offset..].chars().next().unwrap();
+            (
                 pt::Span(location, location),
-                "unexpected end of file",
-            );
+                format!("invalid character `{}`", ch),
+            )
         }
 
-        Err(ParseError::UnrecognizedToken {
+        ParseError::UnrecognizedEof { location, .. } => (
+            pt::Span(location, location),
+            "unexpected end of file".to_string(),
+        ),
+
+        ParseError::UnrecognizedToken {
             token: (lo, _, hi),
             expected,
-        }) => {
+        } => {
             let _ = expected; // didn't implement this yet :)
-            let text = &file_text.text()[lo..hi];
-            report_error(
-                file_text,
-                pt::Span(lo, hi),
-                &format!("unexpected token: `{}`", text),
-            );
+            let text = &text[lo - offset..hi - offset];
+            (pt::Span(lo, hi), format!("unexpected token: `{}`", text))
         }
 
-        Err(ParseError::ExtraToken { token: (lo, _, hi) }) => {
-            let text = &file_text.text()[lo..hi];
-            report_error(
-                file_text,
+        ParseError::ExtraToken { token: (lo, _, hi) } => {
+            let text = &text[lo - offset..hi - offset];
+            (
                 pt::Span(lo, hi),
-                &format!("extra token at end of input: `{}`", text),
-            );
+                format!("extra token at end of input: `{}`", text),
+            )
         }
 
-        Err(ParseError::User { error }) => {
+        ParseError::User { error } => {
             let string = match error.code {
                 tok::ErrorCode::UnrecognizedToken => "unrecognized token",
                 tok::ErrorCode::UnterminatedEscape => "unterminated escape; missing '`'?",
@@ -307,18 +580,37 @@ fn parse_and_normalize_grammar(session: &Session, file_text: &FileText) -> io::R
                 }
             };
 
-            report_error(
-                file_text,
+            (
                 pt::Span(error.location, error.location + 1),
-                string,
+                string.to_string(),
             )
         }
-    };
 
-    match normalize::normalize(session, grammar) {
-        Ok(grammar) => Ok(grammar),
+        // the grammar of LALRPOP itself has no cuts, and its stack is
+        // not bounded
+        error => unreachable!("unexpected parse error: {:?}", error),
+    }
+}
+
+fn normalize_grammar(
+    session: &Session,
+    file_text: &FileText,
+    grammar: pt::Grammar,
+) -> io::Result<r::Grammar> {
+    let grammar = match profile!(
+        session,
+        "Normalization",
+        normalize::normalize(session, grammar)
+    ) {
+        Ok(grammar) => grammar,
         Err(error) => report_error(file_text, error.span, &error.message),
+    };
+
+    for lint in grammar.lint() {
+        report_lint(session, file_text, &lint);
     }
+
+    Ok(grammar)
 }
 
 fn report_error(file_text: &FileText, span: pt::Span, message: &str) -> ! {
@@ -331,6 +623,37 @@ fn report_error(file_text: &FileText, span: pt::Span, message: &str) -> ! {
     exit(1);
 }
 
+fn report_warning(session: &Session, file_text: &FileText, span: pt::Span, message: &str) {
+    let location = file_text.span_str(span);
+    println!("{} warning: {}", location, message);
+
+    // cargo only shows the output of a build script that fails, except
+    // for these directives
+    if session.emit_rerun_directives {
+        println!("cargo:warning={} warning: {}", location, message);
+    }
+
+    let out = io::stderr();
+    let mut out = out.lock();
+    file_text.highlight(span, &mut out).unwrap();
+}
+
+fn report_unreduced(
+    session: &Session,
+    file_text: &FileText,
+    grammar: &r::Grammar,
+    reduced: &lr1::ReducedAlternatives,
+) {
+    for lint in grammar.lint_unreduced(reduced) {
+        report_lint(session, file_text, &lint);
+    }
+}
+
+fn report_lint(session: &Session, file_text: &FileText, lint: &Lint) {
+    let span = pt::Span(lint.span.start, lint.span.end);
+    report_warning(session, file_text, span, &lint.message);
+}
+
 fn report_message(message: Message) -> term::Result<()> {
     let content = InlineBuilder::new().push(Box::new(message)).end();
     report_content(&*content)?;
@@ -370,10 +693,12 @@ fn emit_uses<W: Write>(grammar: &r::Grammar, rust: &mut RustWrite<W>) -> io::Res
     rust.write_uses("", grammar)
 }
 
-fn emit_recursive_ascent(
+pub(crate) fn emit_recursive_ascent(
     session: &Session,
     grammar: &r::Grammar,
     report_file: &Path,
+    dot_file: &Path,
+    conflicts_file: &Path,
 ) -> io::Result<Vec<u8>> {
     let mut rust = RustWrite::new(vec![]);
 
@@ -404,6 +729,19 @@ fn emit_recursive_ascent(
         exit(1);
     }
 
+    // One graph per public nonterminal, all in the same file.
+    let mut dot_file = if session.emit_dot {
+        Some(fs::File::create(dot_file)?)
+    } else {
+        None
+    };
+
+    // Written once all public nonterminals are built, or at the first
+    // one with conflicts.
+    let mut conflicts_json = session.emit_conflicts_json.then(ConflictsJson::default);
+
+    let mut reduced = lr1::ReducedAlternatives::default();
+
     // Find a better visibility for some generated items.
     // This will be the maximum of the visibility of all starting nonterminals.
     let mut max_start_nt_visibility = pt::Visibility::Priv;
@@ -434,56 +772,113 @@ fn emit_recursive_ascent(
         let lr1result = lr1::build_states(grammar, start_nt.clone());
         if session.emit_report {
             let mut output_report_file = fs::File::create(report_file)?;
-            lr1::generate_report(&mut output_report_file, &lr1result)?;
+            lr1::generate_report(&mut output_report_file, grammar, &lr1result)?;
+        }
+        if let Some(dot_file) = &mut dot_file {
+            let states = match &lr1result {
+                Ok(states) => states,
+                Err(error) => &error.states,
+            };
+            graphviz::write_dot(grammar, states, dot_file)?;
         }
 
         let states = match lr1result {
             Ok(states) => states,
             Err(error) => {
+                if let Some(json) = &mut conflicts_json {
+                    json.add(user_nt, &error.states, &error.conflicts);
+                    json.save_to_path(conflicts_file)?;
+                }
                 let _ = lr1::report_error(grammar, &error, report_message);
                 exit(1) // FIXME -- propagate up instead of calling `exit`
             }
         };
+        reduced.add(&states);
+
+        profile!(
+            session,
+            format!("Code generation for `{}`", user_nt),
+            match grammar.algorithm.codegen {
+                r::LrCodeGeneration::RecursiveAscent => lr1::codegen::ascent::compile(
+                    grammar,
+                    user_nt.clone(),
+                    start_nt.clone(),
+                    &states,
+                    "super",
+                    &mut rust,
+                )?,
+                r::LrCodeGeneration::TableDriven => lr1::codegen::parse_table::compile(
+                    grammar,
+                    user_nt.clone(),
+                    start_nt.clone(),
+                    &states,
+                    "super",
+                    &mut rust,
+                )?,
+
+                r::LrCodeGeneration::TestAll => lr1::codegen::test_all::compile(
+                    grammar,
+                    user_nt.clone(),
+                    start_nt.clone(),
+                    &states,
+                    &mut rust,
+                )?,
+            }
+        );
 
-        match grammar.algorithm.codegen {
-            r::LrCodeGeneration::RecursiveAscent => lr1::codegen::ascent::compile(
-                grammar,
-                user_nt.clone(),
-                start_nt.clone(),
-                &states,
-                "super",
-                &mut rust,
-            )?,
-            r::LrCodeGeneration::TableDriven => lr1::codegen::parse_table::compile(
-                grammar,
-                user_nt.clone(),
-                start_nt.clone(),
-                &states,
-                "super",
-                &mut rust,
-            )?,
-
-            r::LrCodeGeneration::TestAll => lr1::codegen::test_all::compile(
-                grammar,
-                user_nt.clone(),
-                start_nt.clone(),
-                &states,
-                &mut rust,
-            )?,
+        let mut parsers = vec!["Parser"];
+        if grammar.incremental {
+            parsers.push("IncrementalParser");
+        }
+        if grammar.async_parser {
+            parsers.push("AsyncParser");
         }
 
-        rust!(
-            rust,
-            "{}use self::{}parse{}::{}Parser;",
-            grammar.nonterminals[user_nt].visibility,
-            grammar.prefix,
-            start_nt,
-            user_nt
-        );
+        // the parsers of `#[deprecated]` nonterminals are deprecated too
+        let deprecated = grammar.nonterminals[user_nt]
+            .deprecated_attribute()
+            .is_some();
+        // a `pub(self)` parser is re-exported with a plain `use`, which
+        // nothing in the generated module uses
+        let private = grammar.nonterminals[user_nt].visibility.is_private();
+        let visibility = if private {
+            pt::Visibility::Priv
+        } else {
+            grammar.nonterminals[user_nt].visibility.clone()
+        };
+        for parser in parsers {
+            if deprecated {
+                rust!(rust, "#[allow(deprecated)]");
+            }
+            if private {
+                rust!(rust, "#[allow(unused_imports)]");
+            }
+            rust!(
+                rust,
+                "{}use self::{}parse{}::{}{};",
+                visibility,
+                grammar.prefix,
+                start_nt,
+                user_nt,
+                parser
+            );
+        }
+    }
+
+    if let Some(json) = &conflicts_json {
+        json.save_to_path(conflicts_file)?;
+    }
+
+    if session.warn_unreachable {
+        report_unreduced(session, &Tls::file_text(), grammar, &reduced);
     }
 
     if let Some(ref intern_token) = grammar.intern_token {
-        intern_token::compile(grammar, intern_token, &mut rust)?;
+        profile!(
+            session,
+            "Lexer generation",
+            intern_token::compile(grammar, intern_token, &mut rust)?
+        );
         rust!(
             rust,
             "pub(crate) use self::{}lalrpop_util::lexer::Token;",
@@ -493,9 +888,19 @@ fn emit_recursive_ascent(
 
     action::emit_action_code(grammar, &mut rust)?;
 
+    if grammar.generate_visitor {
+        codegen::visitor::compile(grammar, &max_start_nt_visibility, &mut rust)?;
+    }
+
+    if grammar.generate_folder {
+        codegen::folder::compile(grammar, &max_start_nt_visibility, &mut rust)?;
+    }
+
     rust!(rust, "#[allow(clippy::type_complexity)]");
     emit_to_triple_trait(grammar, max_start_nt_visibility, &mut rust)?;
 
+    codegen::grammar_tests::compile(grammar, &mut rust)?;
+
     Ok(rust.into_inner())
 }
 
@@ -609,6 +1014,66 @@ fn emit_to_triple_trait<W: Write>(
         rust!(rust, "}}"); // match
         rust!(rust, "}}");
         rust!(rust, "}}");
+
+        // with `extern token T: Token;`, the tokens give their own spans
+        if grammar.match_terminals_by_token_trait {
+            rust!(
+                rust,
+                "impl<{utp}> {p}ToTriple<{utp}> for {T}",
+                utp = user_type_parameters,
+                p = grammar.prefix,
+                T = T,
+            );
+            write_where_clause(where_clauses, &to_triple_where_clauses, rust)?;
+            rust!(rust, "{{");
+            rust!(
+                rust,
+                "fn to_triple(value: Self) -> Result<({L},{T},{L}), {parse_error}> {{",
+                L = L,
+                T = T,
+                parse_error = parse_error,
+            );
+            rust!(
+                rust,
+                "let (start, end) = {p}lalrpop_util::token::Token::span(&value);",
+                p = grammar.prefix
+            );
+            rust!(rust, "Ok((start, value, end))");
+            rust!(rust, "}}");
+            rust!(rust, "}}");
+
+            rust!(
+                rust,
+                "impl<{utp}> {p}ToTriple<{utp}> for Result<{T}, {E}>",
+                utp = user_type_parameters,
+                p = grammar.prefix,
+                T = T,
+                E = E,
+            );
+            write_where_clause(where_clauses, &to_triple_where_clauses, rust)?;
+            rust!(rust, "{{");
+            rust!(
+                rust,
+                "fn to_triple(value: Self) -> Result<({L},{T},{L}), {parse_error}> {{",
+                L = L,
+                T = T,
+                parse_error = parse_error,
+            );
+            rust!(rust, "match value {{");
+            rust!(
+                rust,
+                "Ok(v) => {p}ToTriple::to_triple(v),",
+                p = grammar.prefix
+            );
+            rust!(
+                rust,
+                "Err(error) => Err({p}lalrpop_util::ParseError::User {{ error }}),",
+                p = grammar.prefix
+            );
+            rust!(rust, "}}"); // match
+            rust!(rust, "}}"); // fn
+            rust!(rust, "}}"); // impl
+        }
     } else {
         rust!(
             rust,
@@ -0,0 +1,63 @@
+use super::{gen_resolve_file, module_comment, process_files};
+use crate::session::Session;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+fn session(module_name: Option<&str>) -> Session {
+    let mut session = Session::new();
+    session.in_dir = Some(PathBuf::from("src"));
+    session.out_dir = Some(PathBuf::from("out"));
+    session.module_name = module_name.map(String::from);
+    session
+}
+
+#[test]
+fn file_stem_names_module() {
+    let file = Path::new("src/lang/grammar.lalrpop");
+    assert_eq!(
+        gen_resolve_file(&session(None), file, "rs").unwrap(),
+        Path::new("out/lang/grammar.rs")
+    );
+}
+
+#[test]
+fn module_name() {
+    let session = session(Some("lang_grammar"));
+    let file = Path::new("src/lang/grammar.lalrpop");
+    assert_eq!(
+        gen_resolve_file(&session, file, "rs").unwrap(),
+        Path::new("out/lang/lang_grammar.rs")
+    );
+    assert_eq!(
+        gen_resolve_file(&session, file, "ast.rs").unwrap(),
+        Path::new("out/lang/lang_grammar.ast.rs")
+    );
+}
+
+#[test]
+fn module_name_not_identifier() {
+    let file = Path::new("src/grammar.lalrpop");
+    for name in ["", "lang-grammar", "1grammar", "lang.grammar"] {
+        let error = gen_resolve_file(&session(Some(name)), file, "rs").unwrap_err();
+        assert!(error.to_string().contains("is not a Rust identifier"));
+    }
+}
+
+#[test]
+fn module_name_several_files() {
+    let session = Rc::new(session(Some("lang_grammar")));
+    let error = process_files(session, ["src/a.lalrpop", "src/b.lalrpop"]).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "the module name `lang_grammar` cannot be given to several grammars: \
+         src/a.lalrpop, src/b.lalrpop"
+    );
+}
+
+#[test]
+fn module_comment_lines() {
+    assert_eq!(
+        module_comment("Generated by lalrpop.\n\nDo not edit."),
+        "// Generated by lalrpop.\n//\n// Do not edit.\n"
+    );
+}
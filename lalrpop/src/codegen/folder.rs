@@ -0,0 +1,191 @@
+//! Generates the `Folder` trait requested by `#[generate_folder]`.
+//!
+//! Where a visitor looks at the values produced by the parser, a folder
+//! consumes them and builds new ones. There is one `fold_*` method per
+//! nonterminal the user named, taking and returning the nonterminal's
+//! type, and a `noop_fold_*` free function that the method calls by
+//! default:
+//!
+//! ```ignore
+//! pub trait Folder<'input> {             // user-declared type parameters
+//!     fn fold_exprs(&mut self, node: Vec<Box<Expr<'input>>>) -> Vec<Box<Expr<'input>>> {
+//!         noop_fold_exprs(self, node)
+//!     }
+//!     ...
+//! }
+//!
+//! pub fn noop_fold_exprs<'input, F: Folder<'input> + ?Sized>(
+//!     folder: &mut F,
+//!     node: Vec<Box<Expr<'input>>>,
+//! ) -> Vec<Box<Expr<'input>>> {
+//!     node.into_iter().map(|__0| Box::new(folder.fold_expr(*__0))).collect::<Vec<_>>()
+//! }
+//! ```
+//!
+//! As for the visitor, the `noop_fold_*` functions only rebuild `Vec`,
+//! `Option`, `Box` and tuples, folding the values inside them whose type
+//! is the type of some nonterminal; everything else is returned as is.
+
+use super::visitor::{is_user_name, snake_case};
+use crate::grammar::repr::{Grammar, NonterminalString, TypeRepr, Visibility};
+use crate::rust::RustWrite;
+use crate::util::Sep;
+use std::io::{self, Write};
+
+pub fn compile<W: Write>(
+    grammar: &Grammar,
+    visibility: &Visibility,
+    rust: &mut RustWrite<W>,
+) -> io::Result<()> {
+    let mut nodes: Vec<_> = grammar
+        .nonterminals
+        .values()
+        .filter(|data| is_user_name(&data.name))
+        .collect();
+    nodes.sort_by_key(|data| data.span);
+    let nodes: Vec<_> = nodes
+        .into_iter()
+        .map(|data| {
+            (
+                data.name.clone(),
+                grammar.types.nonterminal_type(&data.name),
+            )
+        })
+        .collect();
+
+    let type_parameters = Sep(", ", &grammar.type_parameters).to_string();
+    let trait_ref = if type_parameters.is_empty() {
+        "Folder".to_string()
+    } else {
+        format!("Folder<{}>", type_parameters)
+    };
+    let where_clauses = &grammar.where_clauses;
+
+    rust!(rust, "");
+    rust!(
+        rust,
+        "#[allow(clippy::type_complexity, clippy::boxed_local)]"
+    );
+    rust!(rust, "{}trait {}", visibility, trait_ref);
+    if !where_clauses.is_empty() {
+        rust!(rust, "where {}", Sep(",", where_clauses));
+    }
+    rust!(rust, "{{");
+    for (name, ty) in &nodes {
+        let name = snake_case(name);
+        rust!(
+            rust,
+            "fn fold_{}(&mut self, node: {}) -> {} {{",
+            name,
+            ty,
+            ty
+        );
+        rust!(rust, "noop_fold_{}(self, node)", name);
+        rust!(rust, "}}");
+    }
+    rust!(rust, "}}");
+
+    for (name, ty) in &nodes {
+        rust!(rust, "");
+        rust!(
+            rust,
+            "#[allow(clippy::type_complexity, clippy::boxed_local)]"
+        );
+        let generics = if type_parameters.is_empty() {
+            format!("F: {} + ?Sized", trait_ref)
+        } else {
+            format!("{}, F: {} + ?Sized", type_parameters, trait_ref)
+        };
+        rust!(
+            rust,
+            "{}fn noop_fold_{}<{}>(folder: &mut F, node: {}) -> {}",
+            visibility,
+            snake_case(name),
+            generics,
+            ty,
+            ty
+        );
+        if !where_clauses.is_empty() {
+            rust!(rust, "where {}", Sep(",", where_clauses));
+        }
+        rust!(rust, "{{");
+        match fold_type(&nodes, ty, "node", 0) {
+            Some(body) => rust!(rust, "{}", body),
+            None => {
+                rust!(rust, "let _ = folder;");
+                rust!(rust, "node");
+            }
+        }
+        rust!(rust, "}}");
+    }
+
+    Ok(())
+}
+
+/// Returns an expression that folds the children of `expr` (a value of
+/// type `ty`), or `None` if there is nothing to fold and `expr` can be
+/// used as it is. At depth 0, `expr` is the node itself, which must not
+/// be folded again.
+fn fold_type(
+    nodes: &[(NonterminalString, &TypeRepr)],
+    ty: &TypeRepr,
+    expr: &str,
+    depth: usize,
+) -> Option<String> {
+    if depth > 0 {
+        if let Some((name, _)) = nodes.iter().find(|(_, node_ty)| *node_ty == ty) {
+            return Some(format!("folder.fold_{}({})", snake_case(name), expr));
+        }
+    }
+
+    match ty {
+        TypeRepr::Nominal(data) if data.types.len() == 1 => {
+            match data.path.ids.last().map(|id| &**id) {
+                Some("Vec") => {
+                    let var = format!("__{}", depth);
+                    let body = fold_type(nodes, &data.types[0], &var, depth + 1)?;
+                    Some(format!(
+                        "{}.into_iter().map(|{}| {}).collect::<Vec<_>>()",
+                        expr, var, body
+                    ))
+                }
+                Some("Option") => {
+                    let var = format!("__{}", depth);
+                    let body = fold_type(nodes, &data.types[0], &var, depth + 1)?;
+                    Some(format!("{}.map(|{}| {})", expr, var, body))
+                }
+                Some("Box") => {
+                    let inner = format!("*{}", expr);
+                    let body = fold_type(nodes, &data.types[0], &inner, depth + 1)?;
+                    Some(format!("Box::new({})", body))
+                }
+                _ => None,
+            }
+        }
+        TypeRepr::Tuple(types) => {
+            let vars: Vec<_> = (0..types.len())
+                .map(|i| format!("__{}_{}", depth, i))
+                .collect();
+            let bodies: Vec<_> = types
+                .iter()
+                .zip(&vars)
+                .map(|(ty, var)| fold_type(nodes, ty, var, depth + 1))
+                .collect();
+            if bodies.iter().all(Option::is_none) {
+                return None;
+            }
+            let fields: Vec<_> = bodies
+                .into_iter()
+                .zip(&vars)
+                .map(|(body, var)| body.unwrap_or_else(|| var.clone()))
+                .collect();
+            Some(format!(
+                "{{ let ({},) = {}; ({},) }}",
+                vars.join(", "),
+                expr,
+                fields.join(", ")
+            ))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,62 @@
+//! Generates the `#[test]` functions requested by the
+//! `#[test] parse "..." as X => pattern;` items of a grammar:
+//!
+//! ```ignore
+//! #[cfg(test)]
+//! mod __tests {
+//!     use super::*;
+//!
+//!     #[test]
+//!     fn expr_0() {
+//!         let __result = super::ExprParser::new().parse("1 + 2");
+//!         assert!(matches!(__result, Ok(Expr::Add(1, 2))), ...);
+//!     }
+//! }
+//! ```
+//!
+//! The parse result is only matched against the pattern, so neither
+//! the nonterminal type nor the error type has to implement `Debug`.
+
+use super::visitor::snake_case;
+use crate::grammar::repr::Grammar;
+use crate::rust::RustWrite;
+use std::io::{self, Write};
+
+pub fn compile<W: Write>(grammar: &Grammar, rust: &mut RustWrite<W>) -> io::Result<()> {
+    if grammar.tests.is_empty() {
+        return Ok(());
+    }
+
+    rust!(rust, "");
+    rust!(rust, "#[cfg(test)]");
+    rust!(rust, "mod {}tests {{", grammar.prefix);
+    rust!(rust, "#[allow(unused_imports)]");
+    rust!(rust, "use super::*;");
+    for (index, test) in grammar.tests.iter().enumerate() {
+        let message = format!(
+            "`{}` did not parse as `{}` matching `{}`",
+            test.input, test.nonterminal, test.pattern
+        );
+        rust!(rust, "");
+        rust!(rust, "#[test]");
+        rust!(rust, "fn {}_{}() {{", snake_case(&test.nonterminal), index);
+        rust!(
+            rust,
+            "let {}result = super::{}Parser::new().parse({:?});",
+            grammar.prefix,
+            test.nonterminal,
+            test.input
+        );
+        rust!(
+            rust,
+            "assert!(matches!({}result, Ok({})), \"{{}}\", {:?});",
+            grammar.prefix,
+            test.pattern,
+            message
+        );
+        rust!(rust, "}}");
+    }
+    rust!(rust, "}}");
+
+    Ok(())
+}
@@ -0,0 +1,7 @@
+//! Generators for optional items emitted alongside the parser, which
+//! do not depend on the LR(1) states.
+
+pub mod folder;
+pub mod grammar_tests;
+pub mod rust_types;
+pub mod visitor;
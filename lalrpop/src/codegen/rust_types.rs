@@ -0,0 +1,741 @@
+//! Generates the starting point for an AST requested by `--emit
+//! rust-types`: one enum per nonterminal, with one variant per
+//! alternative, whose fields are the symbols the alternative selects:
+//!
+//! ```ignore
+//! mod ast {
+//!     #[derive(Clone, Debug, PartialEq)]
+//!     pub enum Expr {
+//!         Add { l: Box<Expr>, r: Box<Expr> },   // <l:Expr> "+" <r:Factor> => Expr::Add(l, r)
+//!         Factor(Box<Expr>),                    // Factor
+//!     }
+//!     ...
+//! }
+//! ```
+//!
+//! A variant is named after the constructor its action code calls, or
+//! else after the only nonterminal it selects; the others are numbered.
+//! The field types are the types of the symbols, as declared (or
+//! inferred) in the grammar. Alternatives that were split up by
+//! inlining an `#[inline]` nonterminal are merged again, since they
+//! share their action code.
+//!
+//! Nonterminals annotated with `#[display]` also get a `Display` impl,
+//! which prints the text of the terminals of the alternative and the
+//! fields in between, unless the alternative gives its own format with
+//! `#[display = "..."]`.
+//!
+//! An `#[emit_cst]` grammar also gets a `CstNode` enum: the parse tree
+//! the parsers return, whose leaves can also be values of these types,
+//! with `From` and `TryFrom` conversions between each type and the node.
+
+use super::visitor::is_user_name;
+use crate::collections::Set;
+use crate::grammar::consts::DISPLAY;
+use crate::grammar::free_variables::FreeVariables;
+use crate::grammar::repr::{
+    ActionFn, ActionFnDefnKind, Grammar, InlinedSymbol, NonterminalData, ParseTreeSymbol,
+    Production, Symbol, TerminalLiteral, TerminalString, TypeParameter, TypeRepr,
+};
+use crate::rust::RustWrite;
+use crate::util::Sep;
+use std::io::{self, Write};
+
+struct Variant {
+    name: String,
+    /// `None` for the fields of a tuple variant
+    fields: Vec<(Option<String>, TypeRepr)>,
+    /// the format string of the `Display` impl, and the fields it
+    /// prints, in order; a `#[display = "..."]` format names the
+    /// fields itself
+    format: String,
+    format_args: Vec<String>,
+}
+
+impl Variant {
+    /// The names the fields are bound to in a pattern: their own names,
+    /// or `_0`, `_1`, ... in a tuple variant.
+    fn bindings(&self) -> Vec<String> {
+        if self.is_struct() {
+            self.fields
+                .iter()
+                .map(|(name, _)| name.clone().unwrap())
+                .collect()
+        } else {
+            (0..self.fields.len()).map(|i| format!("_{}", i)).collect()
+        }
+    }
+
+    fn is_struct(&self) -> bool {
+        !self.fields.is_empty() && self.fields.iter().all(|(name, _)| name.is_some())
+    }
+}
+
+pub fn compile<W: Write>(grammar: &Grammar, rust: &mut RustWrite<W>) -> io::Result<()> {
+    let mut nonterminals: Vec<_> = grammar
+        .nonterminals
+        .values()
+        .filter(|data| is_user_name(&data.name))
+        .collect();
+    nonterminals.sort_by_key(|data| data.span);
+
+    rust!(rust, "mod ast {{");
+    let mut enums = vec![];
+    for (index, data) in nonterminals.into_iter().enumerate() {
+        let variants = variants(grammar, data);
+
+        let free_variables: Vec<TypeParameter> = variants
+            .iter()
+            .flat_map(|v| &v.fields)
+            .flat_map(|(_, ty)| ty.free_variables(&grammar.type_parameters))
+            .collect();
+        let type_parameters: Vec<_> = grammar
+            .type_parameters
+            .iter()
+            .filter(|tp| free_variables.contains(tp))
+            .collect();
+
+        if index > 0 {
+            rust!(rust, "");
+        }
+        rust!(rust, "#[derive(Clone, Debug, PartialEq)]");
+        if type_parameters.is_empty() {
+            rust!(rust, "pub enum {} {{", data.name);
+        } else {
+            rust!(
+                rust,
+                "pub enum {}<{}> {{",
+                data.name,
+                Sep(", ", &type_parameters)
+            );
+        }
+        for variant in &variants {
+            if variant.fields.is_empty() {
+                rust!(rust, "{},", variant.name);
+            } else if variant.is_struct() {
+                let fields: Vec<_> = variant
+                    .fields
+                    .iter()
+                    .map(|(name, ty)| format!("{}: {}", name.as_ref().unwrap(), ty))
+                    .collect();
+                rust!(rust, "{} {{ {} }},", variant.name, Sep(", ", &fields));
+            } else {
+                let fields: Vec<_> = variant.fields.iter().map(|(_, ty)| ty).collect();
+                rust!(rust, "{}({}),", variant.name, Sep(", ", &fields));
+            }
+        }
+        rust!(rust, "}}");
+
+        if data.annotations.iter().any(|ann| &*ann.id == DISPLAY) {
+            rust!(rust, "");
+            emit_display(rust, data, &type_parameters, &variants)?;
+        }
+        enums.push((data.name.to_string(), type_parameters));
+    }
+    if grammar.emit_cst {
+        rust!(rust, "");
+        emit_cst_node(rust, grammar, &enums)?;
+    }
+    rust!(rust, "}}");
+
+    Ok(())
+}
+
+fn emit_display<W: Write>(
+    rust: &mut RustWrite<W>,
+    data: &NonterminalData,
+    type_parameters: &[&TypeParameter],
+    variants: &[Variant],
+) -> io::Result<()> {
+    if type_parameters.is_empty() {
+        rust!(rust, "impl ::std::fmt::Display for {} {{", data.name);
+    } else {
+        let bounds: Vec<_> = type_parameters
+            .iter()
+            .map(|tp| match tp {
+                TypeParameter::Lifetime(_) => tp.to_string(),
+                TypeParameter::Id(_) => format!("{}: ::std::fmt::Display", tp),
+            })
+            .collect();
+        rust!(
+            rust,
+            "impl<{}> ::std::fmt::Display for {}<{}> {{",
+            Sep(", ", &bounds),
+            data.name,
+            Sep(", ", &type_parameters.to_vec())
+        );
+    }
+    rust!(rust, "#[allow(unused_variables)]");
+    rust!(
+        rust,
+        "fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {{"
+    );
+    rust!(rust, "match self {{");
+    for variant in variants {
+        let bindings = variant.bindings();
+        let pattern = if variant.fields.is_empty() {
+            format!("{}::{}", data.name, variant.name)
+        } else if variant.is_struct() {
+            format!(
+                "{}::{} {{ {} }}",
+                data.name,
+                variant.name,
+                Sep(", ", &bindings)
+            )
+        } else {
+            format!("{}::{}({})", data.name, variant.name, Sep(", ", &bindings))
+        };
+        let args: String = variant
+            .format_args
+            .iter()
+            .map(|arg| format!(", {}", arg))
+            .collect();
+        rust!(
+            rust,
+            "{} => write!(f, {:?}{}),",
+            pattern,
+            variant.format,
+            args
+        );
+    }
+    rust!(rust, "}}");
+    rust!(rust, "}}");
+    rust!(rust, "}}");
+    Ok(())
+}
+
+/// Writes `CstNode`, with a variant for each of the `enums`, named
+/// after it, and the `Terminal` and `Nonterminal` variants of
+/// `lalrpop_util::ParseTreeNode`, which converts to it.
+fn emit_cst_node<W: Write>(
+    rust: &mut RustWrite<W>,
+    grammar: &Grammar,
+    enums: &[(String, Vec<&TypeParameter>)],
+) -> io::Result<()> {
+    let type_parameters: Vec<_> = grammar
+        .type_parameters
+        .iter()
+        .filter(|tp| enums.iter().any(|(_, tps)| tps.contains(tp)))
+        .collect();
+    let generics = |tps: &Vec<&TypeParameter>| {
+        if tps.is_empty() {
+            String::new()
+        } else {
+            format!("<{}>", Sep(", ", tps))
+        }
+    };
+    let impl_generics = generics(&type_parameters);
+    let node = format!("CstNode{}", impl_generics);
+    let location = grammar.types.terminal_loc_type();
+    let span = format!("::lalrpop_util::Span<{}>", location);
+    let tree = format!("::lalrpop_util::ParseTreeNode<{}>", location);
+
+    rust!(rust, "#[derive(Clone, Debug, PartialEq)]");
+    rust!(rust, "pub enum {} {{", node);
+    for (name, tps) in enums {
+        rust!(rust, "{}({}{}),", name, name, generics(tps));
+    }
+    rust!(rust, "Terminal {{ token: &'static str, span: {} }},", span);
+    rust!(
+        rust,
+        "Nonterminal {{ name: &'static str, children: Vec<{}>, span: {} }},",
+        node,
+        span
+    );
+    rust!(rust, "}}");
+
+    for (name, tps) in enums {
+        let ty = format!("{}{}", name, generics(tps));
+        rust!(rust, "");
+        rust!(rust, "impl{} From<{}> for {} {{", impl_generics, ty, node);
+        rust!(rust, "fn from(node: {}) -> Self {{", ty);
+        rust!(rust, "CstNode::{}(node)", name);
+        rust!(rust, "}}");
+        rust!(rust, "}}");
+        rust!(rust, "");
+        rust!(
+            rust,
+            "impl{} TryFrom<{}> for {} {{",
+            impl_generics,
+            node,
+            ty
+        );
+        rust!(rust, "type Error = {};", node);
+        rust!(rust, "");
+        rust!(
+            rust,
+            "fn try_from(node: {}) -> Result<Self, Self::Error> {{",
+            node
+        );
+        rust!(rust, "match node {{");
+        rust!(rust, "CstNode::{}(node) => Ok(node),", name);
+        rust!(rust, "node => Err(node),");
+        rust!(rust, "}}");
+        rust!(rust, "}}");
+        rust!(rust, "}}");
+    }
+
+    rust!(rust, "");
+    rust!(rust, "impl{} From<{}> for {} {{", impl_generics, tree, node);
+    rust!(rust, "fn from(node: {}) -> Self {{", tree);
+    rust!(rust, "match node {{");
+    rust!(
+        rust,
+        "::lalrpop_util::ParseTreeNode::Terminal {{ token, span }} => CstNode::Terminal {{ token, span }},"
+    );
+    rust!(
+        rust,
+        "::lalrpop_util::ParseTreeNode::Nonterminal {{ name, children, span }} => CstNode::Nonterminal {{"
+    );
+    rust!(rust, "name,");
+    rust!(
+        rust,
+        "children: children.into_iter().map(CstNode::from).collect(),"
+    );
+    rust!(rust, "span,");
+    rust!(rust, "}},");
+    rust!(rust, "}}");
+    rust!(rust, "}}");
+    rust!(rust, "}}");
+    Ok(())
+}
+
+fn variants(grammar: &Grammar, data: &NonterminalData) -> Vec<Variant> {
+    let mut seen = Set::new();
+    // `#[rename]`d variants keep their names, so the others avoid them
+    let mut names: Set<String> = data
+        .productions
+        .iter()
+        .filter_map(|production| {
+            let action = user_action(grammar, production.action);
+            grammar.variant_names.get(&action).cloned()
+        })
+        .collect();
+    let mut variants = vec![];
+    for production in &data.productions {
+        let action = user_action(grammar, production.action);
+        if !seen.insert(action) {
+            continue;
+        }
+
+        let defn = &grammar.action_fn_defns[action.index()];
+        // the text printed by `Display`: `Err` for the nth field, or
+        // `Ok` for the text of a terminal
+        let mut pieces: Vec<Result<String, usize>> = vec![];
+        let (fields, code) = match defn.kind {
+            ActionFnDefnKind::User(ref user) => {
+                let mut fields = vec![];
+                for (index, (pattern, ty)) in
+                    user.arg_patterns.iter().zip(&user.arg_types).enumerate()
+                {
+                    let symbol = original_symbol(grammar, production, action, index);
+                    if &*pattern.name == "_" {
+                        if let Some(Symbol::Terminal(TerminalString::Literal(
+                            TerminalLiteral::Quoted(text),
+                        ))) = symbol
+                        {
+                            pieces.push(Ok(text.replace('{', "{{").replace('}', "}}")));
+                        }
+                        continue;
+                    }
+                    let name = if pattern.name.starts_with("__") {
+                        None
+                    } else {
+                        Some(pattern.name.to_string())
+                    };
+                    // in `#[emit_cst]` grammars, the value of a
+                    // nonterminal is paired with its parse tree node
+                    let ty = match (&user.parse_tree, ty) {
+                        (Some(tree), TypeRepr::Tuple(types))
+                            if tree.symbols[index] == ParseTreeSymbol::Nonterminal =>
+                        {
+                            types[0].clone()
+                        }
+                        _ => ty.clone(),
+                    };
+                    pieces.push(Err(fields.len()));
+                    fields.push((symbol, name, ty));
+                }
+                (fields, Some(&user.code))
+            }
+            _ => {
+                pieces.push(Err(0));
+                (vec![(None, None, defn.ret_type.clone())], None)
+            }
+        };
+
+        let name = match grammar.variant_names.get(&action) {
+            Some(name) => name.clone(),
+            None => {
+                let name = code
+                    .and_then(|code| constructor_name(code))
+                    .or(match fields[..] {
+                        [(Some(Symbol::Nonterminal(ref nt)), _, _)] if is_user_name(nt) => {
+                            Some(nt.to_string())
+                        }
+                        _ => None,
+                    })
+                    .filter(|name| !names.contains(name))
+                    .unwrap_or_else(|| format!("Variant{}", variants.len()));
+                names.insert(name.clone());
+                name
+            }
+        };
+
+        let mut variant = Variant {
+            name,
+            fields: fields.into_iter().map(|(_, name, ty)| (name, ty)).collect(),
+            format: String::new(),
+            format_args: vec![],
+        };
+        match grammar.display_formats.get(&action) {
+            Some(format) => variant.format = format.clone(),
+            None => {
+                let bindings = variant.bindings();
+                let format: Vec<_> = pieces
+                    .into_iter()
+                    .map(|piece| match piece {
+                        Ok(text) => text,
+                        Err(field) => {
+                            variant.format_args.push(bindings[field].clone());
+                            "{}".to_string()
+                        }
+                    })
+                    .collect();
+                variant.format = format.join(" ");
+            }
+        }
+        variants.push(variant);
+    }
+    variants
+}
+
+/// The symbol of `production` that became the `index`th argument of
+/// `action`, unless it was an inlined nonterminal.
+fn original_symbol<'g>(
+    grammar: &'g Grammar,
+    production: &'g Production,
+    action: ActionFn,
+    index: usize,
+) -> Option<&'g Symbol> {
+    if production.action == action {
+        return production.symbols.get(index);
+    }
+    match grammar.action_fn_defns[production.action.index()].kind {
+        ActionFnDefnKind::Inline(ref inline) if inline.action == action => {
+            match inline.symbols.get(index)? {
+                InlinedSymbol::Original(ref symbol) => Some(symbol),
+                InlinedSymbol::Inlined(..) => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The action fn the user wrote, before `#[inline]` nonterminals were
+/// inlined into it.
+fn user_action(grammar: &Grammar, mut action: ActionFn) -> ActionFn {
+    while let ActionFnDefnKind::Inline(ref inline) = grammar.action_fn_defns[action.index()].kind {
+        action = inline.action;
+    }
+    action
+}
+
+/// `Expr::Add(l, r)`, `Box::new(Expr::Add(l, r))` and `Add { l, r }`
+/// are all named `Add`.
+fn constructor_name(code: &str) -> Option<String> {
+    let code = code.trim();
+    let code = code.strip_prefix("Box::new(").unwrap_or(code).trim_start();
+    let end = code
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+        .unwrap_or(code.len());
+    let name = code[..end].rsplit("::").next()?;
+    let rest = code[end..].trim_start();
+    let starts_upper = name.chars().next().is_some_and(char::is_uppercase);
+    let is_prelude = ["Some", "Ok", "Err"].contains(&name);
+    if starts_upper
+        && !is_prelude
+        && (rest.is_empty() || rest.starts_with('(') || rest.starts_with('{'))
+    {
+        Some(name.to_string())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::compile;
+    use crate::rust::RustWrite;
+    use crate::test_util::normalized_grammar;
+    use crate::tls::Tls;
+
+    #[test]
+    fn rust_types() {
+        let text = r#"
+            grammar;
+            pub Expr: Box<Expr> = {
+                <l:Expr> "+" <r:Factor> => Box::new(Expr::Add(l, r)),
+                Factor,
+            };
+            Factor: Box<Expr> = {
+                Num => Box::new(Expr::Num(<>)),
+                "(" <Op> <Expr> ")" => Box::new(Expr::Op(<>)),
+                "-" Factor => Box::new(Expr::Neg(<>)),
+            };
+            #[inline]
+            Op: char = { "!" => '!', "?" => '?' };
+            Num: i32 = r"[0-9]+" => <>.parse().unwrap();
+            Name: &'input str = r"[a-z]+";
+        "#;
+        let _tls = Tls::test_string(text);
+        let grammar = normalized_grammar(text);
+
+        let mut rust = RustWrite::new(vec![]);
+        compile(&grammar, &mut rust).unwrap();
+        let ast = String::from_utf8(rust.into_inner()).unwrap();
+
+        assert_eq!(
+            ast,
+            r#"mod ast {
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Expr {
+        Add { l: Box<Expr>, r: Box<Expr> },
+        Factor(Box<Expr>),
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Factor<'input> {
+        Num(i32),
+        Op(char, Box<Expr>),
+        Neg(&'input str, Box<Expr>),
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Op<'input> {
+        Variant0(&'input str),
+        Variant1(&'input str),
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Num<'input> {
+        Variant0(&'input str),
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Name<'input> {
+        Variant0(&'input str),
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn display() {
+        let text = r#"
+            grammar;
+            #[display]
+            pub Expr: Box<Expr> = {
+                <l:Expr> "+" <r:Factor> => Box::new(Expr::Add(l, r)),
+                #[display = "({_0})"]
+                "(" <Expr> ")" => Box::new(Expr::Paren(<>)),
+                Factor => Box::new(Expr::Factor(<>)),
+            };
+            #[display]
+            Factor: i32 = {
+                "{" <Num> "}" => -<>,
+                Num,
+            };
+            Num: i32 = r"[0-9]+" => <>.parse().unwrap();
+        "#;
+        let _tls = Tls::test_string(text);
+        let grammar = normalized_grammar(text);
+
+        let mut rust = RustWrite::new(vec![]);
+        compile(&grammar, &mut rust).unwrap();
+        let ast = String::from_utf8(rust.into_inner()).unwrap();
+
+        assert_eq!(
+            ast,
+            r#"mod ast {
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Expr {
+        Add { l: Box<Expr>, r: i32 },
+        Paren(Box<Expr>),
+        Factor(i32),
+    }
+
+    impl ::std::fmt::Display for Expr {
+        #[allow(unused_variables)]
+        fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+            match self {
+                Expr::Add { l, r } => write!(f, "{} + {}", l, r),
+                Expr::Paren(_0) => write!(f, "({_0})"),
+                Expr::Factor(_0) => write!(f, "{}", _0),
+            }
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Factor {
+        Num(i32),
+        Variant1(i32),
+    }
+
+    impl ::std::fmt::Display for Factor {
+        #[allow(unused_variables)]
+        fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+            match self {
+                Factor::Num(_0) => write!(f, "{{ {} }}", _0),
+                Factor::Variant1(_0) => write!(f, "{}", _0),
+            }
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Num<'input> {
+        Variant0(&'input str),
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn rename() {
+        let text = r#"
+            grammar;
+            pub Expr: Box<Expr> = {
+                #[rename = "Sum"]
+                <l:Expr> "+" <r:Factor> => Box::new(Expr::Add(l, r)),
+                #[rename = "Factor"]
+                "(" <Expr> ")" => <>,
+                Factor,
+            };
+            Factor: i32 = {
+                #[rename = "Bang"]
+                "!" => 0,
+                "?" => 1,
+            };
+        "#;
+        let _tls = Tls::test_string(text);
+        let grammar = normalized_grammar(text);
+
+        let mut rust = RustWrite::new(vec![]);
+        compile(&grammar, &mut rust).unwrap();
+        let ast = String::from_utf8(rust.into_inner()).unwrap();
+
+        // the unrenamed `Factor` alternative gives way to the renamed one
+        assert_eq!(
+            ast,
+            r#"mod ast {
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Expr {
+        Sum { l: Box<Expr>, r: i32 },
+        Factor(Box<Expr>),
+        Variant2(i32),
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Factor<'input> {
+        Bang(&'input str),
+        Variant1(&'input str),
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn cst_node() {
+        let text = r#"
+            #[emit_cst]
+            grammar;
+            pub Expr: Box<Expr> = {
+                <l:Expr> "+" <r:Num> => Box::new(Expr::Add(l, r)),
+                Num => Box::new(Expr::Num(<>)),
+            };
+            Num: i32 = r"[0-9]+" => <>.parse().unwrap();
+        "#;
+        let _tls = Tls::test_string(text);
+        let grammar = normalized_grammar(text);
+
+        let mut rust = RustWrite::new(vec![]);
+        compile(&grammar, &mut rust).unwrap();
+        let ast = String::from_utf8(rust.into_inner()).unwrap();
+
+        // the fields hold the values of the symbols, not their nodes
+        assert_eq!(
+            ast,
+            r#"mod ast {
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Expr {
+        Add { l: Box<Expr>, r: i32 },
+        Num(i32),
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Num<'input> {
+        Variant0(&'input str),
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum CstNode<'input> {
+        Expr(Expr),
+        Num(Num<'input>),
+        Terminal { token: &'static str, span: ::lalrpop_util::Span<usize> },
+        Nonterminal { name: &'static str, children: Vec<CstNode<'input>>, span: ::lalrpop_util::Span<usize> },
+    }
+
+    impl<'input> From<Expr> for CstNode<'input> {
+        fn from(node: Expr) -> Self {
+            CstNode::Expr(node)
+        }
+    }
+
+    impl<'input> TryFrom<CstNode<'input>> for Expr {
+        type Error = CstNode<'input>;
+
+        fn try_from(node: CstNode<'input>) -> Result<Self, Self::Error> {
+            match node {
+                CstNode::Expr(node) => Ok(node),
+                node => Err(node),
+            }
+        }
+    }
+
+    impl<'input> From<Num<'input>> for CstNode<'input> {
+        fn from(node: Num<'input>) -> Self {
+            CstNode::Num(node)
+        }
+    }
+
+    impl<'input> TryFrom<CstNode<'input>> for Num<'input> {
+        type Error = CstNode<'input>;
+
+        fn try_from(node: CstNode<'input>) -> Result<Self, Self::Error> {
+            match node {
+                CstNode::Num(node) => Ok(node),
+                node => Err(node),
+            }
+        }
+    }
+
+    impl<'input> From<::lalrpop_util::ParseTreeNode<usize>> for CstNode<'input> {
+        fn from(node: ::lalrpop_util::ParseTreeNode<usize>) -> Self {
+            match node {
+                ::lalrpop_util::ParseTreeNode::Terminal { token, span } => CstNode::Terminal { token, span },
+                ::lalrpop_util::ParseTreeNode::Nonterminal { name, children, span } => CstNode::Nonterminal {
+                    name,
+                    children: children.into_iter().map(CstNode::from).collect(),
+                    span,
+                },
+            }
+        }
+    }
+}
+"#,
+        );
+    }
+}
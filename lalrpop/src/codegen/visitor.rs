@@ -0,0 +1,205 @@
+//! Generates the `Visitor` trait requested by `#[generate_visitor]`.
+//!
+//! There is one `visit_*` method per nonterminal the user named, taking
+//! a reference to the nonterminal's type, and a `walk_*` free function
+//! that the method calls by default:
+//!
+//! ```ignore
+//! pub trait Visitor<'ast, 'input> {      // user-declared type parameters
+//!     fn visit_exprs(&mut self, node: &'ast Vec<Box<Expr<'input>>>) {
+//!         walk_exprs(self, node)
+//!     }
+//!     ...
+//! }
+//!
+//! pub fn walk_exprs<'ast, 'input, V: Visitor<'ast, 'input> + ?Sized>(
+//!     visitor: &mut V,
+//!     node: &'ast Vec<Box<Expr<'input>>>,
+//! ) {
+//!     for __0 in node.iter() {
+//!         visitor.visit_expr(__0);
+//!     }
+//! }
+//! ```
+//!
+//! We cannot see inside the user's own types, so the walk functions
+//! only descend through `Vec`, `Option`, `Box` and tuples. Whenever
+//! that reaches a value whose type is the type of some nonterminal, it
+//! calls the `visit_*` method of the first such nonterminal.
+
+use crate::grammar::repr::{Grammar, NonterminalString, TypeRepr, Visibility};
+use crate::rust::RustWrite;
+use crate::util::Sep;
+use std::io::{self, Write};
+use unicode_xid::UnicodeXID;
+
+pub fn compile<W: Write>(
+    grammar: &Grammar,
+    visibility: &Visibility,
+    rust: &mut RustWrite<W>,
+) -> io::Result<()> {
+    let mut nodes: Vec<_> = grammar
+        .nonterminals
+        .values()
+        .filter(|data| is_user_name(&data.name))
+        .collect();
+    nodes.sort_by_key(|data| data.span);
+    let nodes: Vec<_> = nodes
+        .into_iter()
+        .map(|data| {
+            (
+                data.name.clone(),
+                grammar.types.nonterminal_type(&data.name),
+            )
+        })
+        .collect();
+
+    let mut type_parameters = String::from("'ast");
+    for type_parameter in &grammar.type_parameters {
+        type_parameters.push_str(&format!(", {}", type_parameter));
+    }
+    let where_clauses = &grammar.where_clauses;
+
+    rust!(rust, "");
+    rust!(
+        rust,
+        "#[allow(clippy::type_complexity, clippy::borrowed_box)]"
+    );
+    rust!(rust, "{}trait Visitor<{}>", visibility, type_parameters);
+    if !where_clauses.is_empty() {
+        rust!(rust, "where {}", Sep(",", where_clauses));
+    }
+    rust!(rust, "{{");
+    for (name, ty) in &nodes {
+        let name = snake_case(name);
+        rust!(rust, "fn visit_{}(&mut self, node: &'ast {}) {{", name, ty);
+        rust!(rust, "walk_{}(self, node)", name);
+        rust!(rust, "}}");
+    }
+    rust!(rust, "}}");
+
+    for (name, ty) in &nodes {
+        let mut body = vec![];
+        walk_type(&nodes, ty, "node", 0, &mut body);
+
+        rust!(rust, "");
+        rust!(
+            rust,
+            "#[allow(clippy::type_complexity, clippy::borrowed_box)]"
+        );
+        rust!(
+            rust,
+            "{}fn walk_{}<{}, V: Visitor<{}> + ?Sized>(visitor: &mut V, node: &'ast {})",
+            visibility,
+            snake_case(name),
+            type_parameters,
+            type_parameters,
+            ty
+        );
+        if !where_clauses.is_empty() {
+            rust!(rust, "where {}", Sep(",", where_clauses));
+        }
+        rust!(rust, "{{");
+        if body.is_empty() {
+            rust!(rust, "let _ = (visitor, node);");
+        }
+        for line in body {
+            rust!(rust, "{}", line);
+        }
+        rust!(rust, "}}");
+    }
+
+    Ok(())
+}
+
+/// Pushes the lines that visit the children of `expr` (an expression of
+/// type `&'ast ty`) onto `out`. At depth 0, `expr` is the node itself,
+/// which must not be visited again.
+fn walk_type(
+    nodes: &[(NonterminalString, &TypeRepr)],
+    ty: &TypeRepr,
+    expr: &str,
+    depth: usize,
+    out: &mut Vec<String>,
+) {
+    if depth > 0 {
+        if let Some((name, _)) = nodes.iter().find(|(_, node_ty)| *node_ty == ty) {
+            out.push(format!("visitor.visit_{}({});", snake_case(name), expr));
+            return;
+        }
+    }
+
+    match ty {
+        TypeRepr::Nominal(data) if data.types.len() == 1 => {
+            match data.path.ids.last().map(|id| &**id) {
+                Some("Vec") | Some("Option") => {
+                    let var = format!("__{}", depth);
+                    let mut body = vec![];
+                    walk_type(nodes, &data.types[0], &var, depth + 1, &mut body);
+                    if !body.is_empty() {
+                        out.push(format!("for {} in {}.iter() {{", var, expr));
+                        out.extend(body);
+                        out.push("}".to_string());
+                    }
+                }
+                Some("Box") => {
+                    // auto-deref sees through the box, except for patterns
+                    let expr = match data.types[0] {
+                        TypeRepr::Tuple(_) => format!("&**{}", expr),
+                        _ => expr.to_string(),
+                    };
+                    walk_type(nodes, &data.types[0], &expr, depth + 1, out);
+                }
+                _ => {}
+            }
+        }
+        TypeRepr::Tuple(types) => {
+            let mut vars = vec![];
+            let mut bodies = vec![];
+            for (i, ty) in types.iter().enumerate() {
+                let var = format!("__{}_{}", depth, i);
+                let mut body = vec![];
+                walk_type(nodes, ty, &var, depth + 1, &mut body);
+                vars.push(if body.is_empty() {
+                    "_".to_string()
+                } else {
+                    var
+                });
+                bodies.extend(body);
+            }
+            if !bodies.is_empty() {
+                out.push(format!("let ({},) = {};", vars.join(", "), expr));
+                out.extend(bodies);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Nonterminals introduced by macro expansion (`Expr?`, `Comma<Expr>`,
+/// ...) or by LALRPOP itself (`__Expr`) don't get a method of their own.
+pub(crate) fn is_user_name(name: &NonterminalString) -> bool {
+    let name = &name.0;
+    !name.starts_with("__") && name.chars().all(UnicodeXID::is_xid_continue)
+}
+
+pub(super) fn snake_case(name: &NonterminalString) -> String {
+    let chars: Vec<char> = name.0.chars().collect();
+    let mut result = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() {
+            // `BinOp` -> `bin_op`, `XMLNode` -> `xml_node`
+            let after_lower = i > 0 && !chars[i - 1].is_uppercase() && chars[i - 1] != '_';
+            let ends_acronym = i > 0
+                && chars[i - 1].is_uppercase()
+                && chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if after_lower || ends_acronym {
+                result.push('_');
+            }
+            result.extend(c.to_lowercase());
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
@@ -0,0 +1,183 @@
+//! Writes the conflicts of a grammar as JSON, for editors and other
+//! tools that want to point at the conflicting rules. The file has the
+//! shape
+//!
+//! ```json
+//! {"conflicts": [
+//!   {"start": "Expr", "state": 5, "lookahead": ["\"+\""],
+//!    "reduce": RULE,
+//!    "action": {"shift": "\"+\"", "rules": [RULE, ...]}}
+//! ]}
+//! ```
+//!
+//! where `action` is `{"reduce": RULE}` for a reduce/reduce conflict, and
+//! each `RULE` is
+//!
+//! ```json
+//! {"rule": "Expr = Expr \"+\" Expr", "file": "calc.lalrpop",
+//!  "span": [40, 56], "start": [3, 5], "end": [3, 20]}
+//! ```
+//!
+//! giving the byte offsets of the alternative in its file and the line
+//! and column of its start and end, counted from 1. States are numbered
+//! separately for each public nonterminal, named by `start`.
+
+use crate::grammar::repr::{NonterminalString, Production, Symbol};
+use crate::lr1::{Action, Lr1Conflict, Lr1State};
+use crate::tls::Tls;
+use crate::util::Sep;
+use std::fmt::Display;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+#[derive(Default)]
+pub struct ConflictsJson {
+    conflicts: Vec<String>,
+}
+
+impl ConflictsJson {
+    /// Records the conflicts found while building the states of the
+    /// public nonterminal `start`.
+    pub fn add(
+        &mut self,
+        start: &NonterminalString,
+        states: &[Lr1State<'_>],
+        conflicts: &[Lr1Conflict<'_>],
+    ) {
+        for conflict in conflicts {
+            let lookahead: Vec<_> = conflict.lookahead.iter().map(string).collect();
+            let action = match &conflict.action {
+                Action::Shift(terminal, _) => {
+                    let rules: Vec<_> = states[conflict.state.0]
+                        .items
+                        .vec
+                        .iter()
+                        .filter(|item| match item.shift_symbol() {
+                            Some((Symbol::Terminal(t), _)) => t == terminal,
+                            _ => false,
+                        })
+                        .map(|item| rule(item.production))
+                        .collect();
+                    format!(
+                        "{{\"shift\": {}, \"rules\": [{}]}}",
+                        string(terminal),
+                        Sep(", ", &rules)
+                    )
+                }
+                Action::Reduce(production) => format!("{{\"reduce\": {}}}", rule(production)),
+            };
+            self.conflicts.push(format!(
+                "{{\"start\": {}, \"state\": {}, \"lookahead\": [{}], \"reduce\": {}, \"action\": {}}}",
+                string(start),
+                conflict.state.0,
+                Sep(", ", &lookahead),
+                rule(conflict.production),
+                action
+            ));
+        }
+    }
+
+    pub fn write_to(&self, out: &mut dyn Write) -> io::Result<()> {
+        write!(out, "{{\"conflicts\": [")?;
+        for (i, conflict) in self.conflicts.iter().enumerate() {
+            let sep = if i == 0 { "" } else { "," };
+            write!(out, "{}\n  {}", sep, conflict)?;
+        }
+        writeln!(out, "\n]}}")
+    }
+
+    pub fn save_to_path(&self, path: &Path) -> io::Result<()> {
+        self.write_to(&mut fs::File::create(path)?)
+    }
+}
+
+fn rule(production: &Production) -> String {
+    let file_text = Tls::file_text();
+    let location = file_text.location(production.span);
+    let text = if production.symbols.is_empty() {
+        format!("{} =", production.nonterminal)
+    } else {
+        format!(
+            "{} = {}",
+            production.nonterminal,
+            Sep(" ", &production.symbols)
+        )
+    };
+    format!(
+        "{{\"rule\": {}, \"file\": {}, \"span\": [{}, {}], \"start\": [{}, {}], \"end\": [{}, {}]}}",
+        string(text),
+        string(location.path.display()),
+        location.span.0,
+        location.span.1,
+        location.start.0,
+        location.start.1,
+        location.end.0,
+        location.end.1
+    )
+}
+
+fn string<T: Display>(value: T) -> String {
+    let mut out = String::from("\"");
+    for c in value.to_string().chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::ConflictsJson;
+    use crate::grammar::repr::NonterminalString;
+    use crate::lr1::{self, Lr1Tls};
+    use crate::test_util::normalized_grammar;
+    use crate::tls::Tls;
+    use string_cache::DefaultAtom as Atom;
+
+    #[test]
+    fn shift_reduce() {
+        let text = r#"grammar; pub E: () = { E "+" E => (), "x" => () };"#;
+        let _tls = Tls::test_string(text);
+        let grammar = normalized_grammar(text);
+        let _lr1_tls = Lr1Tls::install(grammar.terminals.clone());
+        let user_nt = NonterminalString(Atom::from("E"));
+        let start = grammar.start_nonterminals[&user_nt].clone();
+        let error = lr1::build_states(&grammar, start).unwrap_err();
+
+        let mut json = ConflictsJson::default();
+        json.add(&user_nt, &error.states, &error.conflicts);
+        let mut out = vec![];
+        json.write_to(&mut out).unwrap();
+        let json = String::from_utf8(out).unwrap();
+
+        let rule = r#"{"rule": "E = E \"+\" E", "file": "tmp.txt", "span": [23, 36], "start": [1, 24], "end": [1, 36]}"#;
+        let conflict = |state: usize| {
+            format!(
+                r#"{{"start": "E", "state": {state}, "lookahead": ["\"+\""], "reduce": {rule}, "action": {{"shift": "\"+\"", "rules": [{rule}]}}}}"#
+            )
+        };
+        assert_eq!(
+            json,
+            format!(
+                "{{\"conflicts\": [\n  {},\n  {}\n]}}\n",
+                conflict(4),
+                conflict(5)
+            )
+        );
+    }
+
+    #[test]
+    fn no_conflicts() {
+        let mut out = vec![];
+        ConflictsJson::default().write_to(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "{\"conflicts\": [\n]}\n");
+    }
+}
@@ -0,0 +1,220 @@
+//! Writes a grammar in the EBNF notation of ISO 14977, to share it with
+//! people and tools that do not know LALRPOP's syntax. The uses of
+//! macros are expected to have been expanded (see
+//! `normalize::expand_macro_uses`); repeats are written with EBNF's
+//! own brackets, so `X*` becomes `{ X }`, `X+` becomes `X , { X }` and
+//! `X?` becomes `[ X ]`.
+
+use crate::collections::{map, Map};
+use crate::grammar::parse_tree::{
+    Grammar, GrammarItem, NonterminalData, NonterminalString, RepeatOp, Symbol, SymbolKind,
+    TerminalLiteral, TerminalString,
+};
+use std::io::{self, Write};
+
+pub fn write_ebnf(grammar: &Grammar, out: &mut dyn Write) -> io::Result<()> {
+    let nonterminals: Vec<_> = grammar
+        .items
+        .iter()
+        .filter_map(GrammarItem::as_nonterminal)
+        .collect();
+
+    let writer = EbnfWriter {
+        idents: nonterminal_idents(&nonterminals),
+    };
+
+    for (index, data) in nonterminals.iter().enumerate() {
+        if index > 0 {
+            writeln!(out)?;
+        }
+        let ident = writer.nonterminal(&data.name);
+        write!(out, "{} = ", ident)?;
+        for (index, alternative) in data.alternatives.iter().enumerate() {
+            if index > 0 {
+                write!(out, "\n{:width$}| ", "", width = ident.len() + 1)?;
+            }
+            write!(out, "{}", writer.sequence(&alternative.expr.symbols))?;
+        }
+        writeln!(out, " ;")?;
+    }
+    Ok(())
+}
+
+/// The identifier each nonterminal is written with. EBNF identifiers
+/// are letters, digits and (here) underscores, so names like
+/// `Comma<Expr>` are rewritten, keeping them distinct.
+pub fn nonterminal_idents(nonterminals: &[&NonterminalData]) -> Map<NonterminalString, String> {
+    let mut idents: Map<NonterminalString, String> = map();
+    for data in nonterminals {
+        let mut ident = sanitize(&data.name.0);
+        let base = ident.clone();
+        let mut suffix = 1;
+        while idents.values().any(|used| *used == ident) {
+            suffix += 1;
+            ident = format!("{}_{}", base, suffix);
+        }
+        idents.insert(data.name.clone(), ident);
+    }
+    idents
+}
+
+struct EbnfWriter {
+    idents: Map<NonterminalString, String>,
+}
+
+impl EbnfWriter {
+    fn nonterminal(&self, name: &NonterminalString) -> String {
+        self.idents
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| sanitize(&name.0))
+    }
+
+    fn sequence(&self, symbols: &[Symbol]) -> String {
+        let symbols: Vec<String> = symbols
+            .iter()
+            .filter_map(|symbol| self.symbol(symbol))
+            .filter(|symbol| !symbol.is_empty())
+            .collect();
+        symbols.join(" , ")
+    }
+
+    /// The EBNF for `symbol`, or `None` if it matches no input, like
+    /// `@L` or a lookahead assertion.
+    fn symbol(&self, symbol: &Symbol) -> Option<String> {
+        match symbol.kind {
+            SymbolKind::Expr(ref expr) => Some(format!("( {} )", self.sequence(&expr.symbols))),
+            SymbolKind::Choice(ref exprs) => Some(format!(
+                "( {} )",
+                exprs
+                    .iter()
+                    .map(|expr| self.sequence(&expr.symbols))
+                    .collect::<Vec<_>>()
+                    .join(" | ")
+            )),
+            SymbolKind::Terminal(ref terminal) => Some(terminal_string(terminal)),
+            SymbolKind::Nonterminal(ref name) => Some(self.nonterminal(name)),
+            SymbolKind::Repeat(ref repeat) => {
+                let inner = self.symbol(&repeat.symbol)?;
+                let factor = if is_atomic(&repeat.symbol) {
+                    inner.clone()
+                } else {
+                    format!("( {} )", inner)
+                };
+                Some(match repeat.op {
+                    RepeatOp::Star => format!("{{ {} }}", inner),
+                    RepeatOp::Plus => format!("{} , {{ {} }}", factor, inner),
+                    RepeatOp::Question => format!("[ {} ]", inner),
+                    RepeatOp::Count { min, max } => {
+                        let mut parts = vec![];
+                        if min > 0 {
+                            parts.push(format!("{} * {}", min, factor));
+                        }
+                        match max {
+                            None => parts.push(format!("{{ {} }}", inner)),
+                            Some(max) if max > min => {
+                                parts.push(format!("{} * [ {} ]", max - min, inner))
+                            }
+                            Some(_) => {}
+                        }
+                        parts.join(" , ")
+                    }
+                })
+            }
+            SymbolKind::Choose(ref symbol)
+            | SymbolKind::Name(_, ref symbol)
+            | SymbolKind::DocName(_, ref symbol) => self.symbol(symbol),
+            SymbolKind::Error => Some("? error ?".to_string()),
+            SymbolKind::Lookahead
+            | SymbolKind::Lookbehind
+            | SymbolKind::PosLookahead(_)
+            | SymbolKind::NegLookahead(_)
+            | SymbolKind::Cut => None,
+            SymbolKind::Macro(_) | SymbolKind::AmbiguousId(_) => {
+                unreachable!("symbol `{}` should have been expanded away", symbol)
+            }
+        }
+    }
+}
+
+/// Whether `symbol` can be repeated with `n * ...` without parentheses.
+fn is_atomic(symbol: &Symbol) -> bool {
+    match symbol.kind {
+        SymbolKind::Choose(ref symbol)
+        | SymbolKind::Name(_, ref symbol)
+        | SymbolKind::DocName(_, ref symbol) => is_atomic(symbol),
+        SymbolKind::Repeat(ref repeat) => {
+            matches!(repeat.op, RepeatOp::Star | RepeatOp::Question)
+        }
+        _ => true,
+    }
+}
+
+pub fn terminal_string(terminal: &TerminalString) -> String {
+    match terminal {
+        TerminalString::Literal(TerminalLiteral::Quoted(s)) if !s.contains('"') => {
+            format!("\"{}\"", s)
+        }
+        TerminalString::Literal(TerminalLiteral::Quoted(s)) if !s.contains('\'') => {
+            format!("'{}'", s)
+        }
+        TerminalString::Literal(TerminalLiteral::Quoted(s))
+        | TerminalString::Literal(TerminalLiteral::Regex(s)) => format!("? {} ?", s),
+        TerminalString::Bare(s) => sanitize(s),
+        TerminalString::Error => "? error ?".to_string(),
+    }
+}
+
+pub fn sanitize(name: &str) -> String {
+    let words: Vec<&str> = name
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| !word.is_empty())
+        .collect();
+    if words.is_empty() {
+        "nonterminal".to_string()
+    } else {
+        words.join("_")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::write_ebnf;
+    use crate::normalize;
+    use crate::parser;
+
+    fn ebnf(text: &str) -> String {
+        let grammar = parser::parse_grammar(text).unwrap();
+        let grammar = normalize::expand_macro_uses(grammar).unwrap();
+        let mut out = vec![];
+        write_ebnf(&grammar, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn repeats_and_macros() {
+        let text = r#"
+grammar;
+pub Exprs = Comma<Expr>;
+Comma<T>: Vec<T> = {
+    <mut v:(<T> ",")*> <e:T?> => v,
+};
+Expr: () = {
+    @L "(" Expr+ ")" @R => (),
+    r"[0-9]+" => (),
+    "x"{2,3} => (),
+};
+"#;
+        assert_eq!(
+            ebnf(text),
+            r#"Exprs = Comma_Expr ;
+
+Expr = "(" , Expr , { Expr } , ")"
+     | ? [0-9]+ ?
+     | 2 * "x" , 1 * [ "x" ] ;
+
+Comma_Expr = { ( Expr , "," ) } , [ Expr ] ;
+"#
+        );
+    }
+}
@@ -2,12 +2,22 @@ use crate::grammar::parse_tree as pt;
 use std::fmt::{Display, Error, Formatter};
 use std::fs::File;
 use std::io::{self, Read, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub struct FileText {
     path: PathBuf,
     input_str: String,
     newlines: Vec<usize>,
+    /// Files imported by this one, each with the offset its spans
+    /// were shifted by (see `add_import`).
+    imports: Vec<(usize, FileText)>,
+}
+
+pub struct Location<'text> {
+    pub path: &'text Path,
+    pub span: pt::Span,
+    pub start: (usize, usize),
+    pub end: (usize, usize),
 }
 
 impl FileText {
@@ -33,6 +43,34 @@ impl FileText {
             path,
             input_str,
             newlines: newline_indices,
+            imports: vec![],
+        }
+    }
+
+    /// Records an imported file, returning the offset to apply to its
+    /// spans. The offsets place each import after this file and any
+    /// earlier imports, so spans from different files never overlap
+    /// and `span_str` and `highlight` can report against the right one.
+    pub fn add_import(&mut self, import: FileText) -> usize {
+        let offset = match self.imports.last() {
+            Some((offset, last)) => offset + last.input_str.len() + 1,
+            None => self.input_str.len() + 1,
+        };
+        self.imports.push((offset, import));
+        offset
+    }
+
+    /// Finds the file that `span` points into, and the span relative
+    /// to that file.
+    fn locate(&self, span: pt::Span) -> (&FileText, pt::Span) {
+        match self
+            .imports
+            .iter()
+            .rev()
+            .find(|(offset, _)| *offset <= span.0)
+        {
+            Some((offset, import)) => (import, pt::Span(span.0 - offset, span.1 - offset)),
+            None => (self, span),
         }
     }
 
@@ -41,11 +79,35 @@ impl FileText {
         Self::new(PathBuf::from("test.lalrpop"), String::from(""))
     }
 
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
     pub fn text(&self) -> &String {
         &self.input_str
     }
 
     pub fn span_str(&self, span: pt::Span) -> String {
+        let (file, span) = self.locate(span);
+        file.local_span_str(span)
+    }
+
+    /// Where `span` points: the file, the span relative to that file,
+    /// and the line and column of its start and end, numbered as in
+    /// `span_str`.
+    pub fn location(&self, span: pt::Span) -> Location<'_> {
+        let (file, span) = self.locate(span);
+        let (start_line, start_col) = file.line_col(span.0);
+        let (end_line, end_col) = file.line_col(span.1);
+        Location {
+            path: &file.path,
+            span,
+            start: (start_line + 1, start_col + 1),
+            end: (end_line + 1, end_col),
+        }
+    }
+
+    fn local_span_str(&self, span: pt::Span) -> String {
         let (start_line, start_col) = self.line_col(span.0);
         let (end_line, end_col) = self.line_col(span.1);
         format!(
@@ -87,6 +149,11 @@ impl FileText {
     }
 
     pub fn highlight(&self, span: pt::Span, out: &mut dyn Write) -> io::Result<()> {
+        let (file, span) = self.locate(span);
+        file.local_highlight(span, out)
+    }
+
+    fn local_highlight(&self, span: pt::Span, out: &mut dyn Write) -> io::Result<()> {
         let (start_line, start_col) = self.line_col(span.0);
         let (end_line, end_col) = self.line_col(span.1);
 
@@ -13,12 +13,21 @@ pub const INPUT_PARAMETER: &str = "input";
 /// The annotation to request inlining.
 pub const INLINE: &str = "inline";
 
+/// The annotation to mark an alternative as unlikely to be parsed.
+pub const COLD: &str = "cold";
+
 /// The annotation to request conditional compilation.
 pub const CFG: &str = "cfg";
 
 /// Annotation to request LALR.
 pub const LALR: &str = "LALR";
 
+/// Annotation to request canonical LR(1).
+pub const LR1: &str = "LR1";
+
+/// Annotation to request a GLR parser for an ambiguous grammar.
+pub const AMBIGUOUS: &str = "ambiguous";
+
 /// Annotation to request recursive-ascent-style code generation.
 pub const TABLE_DRIVEN: &str = "table_driven";
 
@@ -27,3 +36,57 @@ pub const RECURSIVE_ASCENT: &str = "recursive_ascent";
 
 /// Annotation to request test-all-style code generation.
 pub const TEST_ALL: &str = "test_all";
+
+/// Annotation to request a `Visitor` trait for the nonterminal types.
+pub const GENERATE_VISITOR: &str = "generate_visitor";
+
+/// Annotation to request a `Folder` trait for the nonterminal types.
+pub const GENERATE_FOLDER: &str = "generate_folder";
+
+/// Annotation to request a parser whose stack has a fixed maximum depth.
+pub const NO_STD: &str = "no_std";
+
+/// Annotation to request an `IncrementalParser` next to each `Parser`.
+pub const INCREMENTAL: &str = "incremental";
+
+/// Annotation to request an `AsyncParser` next to each `Parser`.
+pub const ASYNC: &str = "async";
+
+/// Annotation to take the tokens from somewhere other than a lexer of
+/// the grammar's own, `#[token_source = "proc_macro2"]`.
+pub const TOKEN_SOURCE: &str = "token_source";
+
+/// The `token_source` of the tokens of a `proc_macro2::TokenStream`, as
+/// the `lalrpop_procmacro` crate hands them out.
+pub const PROC_MACRO2: &str = "proc_macro2";
+
+/// Annotation to make the action code return a `Result` whose error is
+/// reported as `ParseError::User`.
+pub const FALLIBLE: &str = "fallible";
+
+/// Annotation to make the parsers also return a concrete syntax tree.
+pub const EMIT_CST: &str = "emit_cst";
+
+/// The annotation marking a `parse "..." as Nonterminal => pattern;` item.
+pub const TEST: &str = "test";
+
+/// Annotation to turn off a lint, `#[allow(name)]`, on the grammar or
+/// on a nonterminal.
+pub const ALLOW: &str = "allow";
+
+/// Annotation marking a public nonterminal as deprecated, like
+/// `#[deprecated(since = "1.2.0", note = "use NewExpr instead")]`. It is
+/// copied onto the generated parser.
+pub const DEPRECATED: &str = "deprecated";
+
+/// Annotation giving action code that the values of all alternatives
+/// of a nonterminal pass through, `#[shared_action => code]`.
+pub const SHARED_ACTION: &str = "shared_action";
+
+/// Annotation to request a `Display` impl for the `--emit rust-types`
+/// type of a nonterminal, and to give the format of an alternative.
+pub const DISPLAY: &str = "display";
+
+/// Annotation naming the variant of an alternative in the `--emit
+/// rust-types` type of its nonterminal, `#[rename = "Add"]`.
+pub const RENAME: &str = "rename";
@@ -1,7 +1,10 @@
 //! The "parse-tree" is what is produced by the parser. We use it do
 //! some pre-expansion and so forth before creating the proper AST.
 
-use crate::grammar::consts::{INPUT_LIFETIME, LALR, RECURSIVE_ASCENT, TABLE_DRIVEN, TEST_ALL};
+use crate::grammar::consts::{
+    ALLOW, AMBIGUOUS, ASYNC, EMIT_CST, FALLIBLE, GENERATE_FOLDER, GENERATE_VISITOR, INCREMENTAL,
+    INPUT_LIFETIME, LALR, LR1, NO_STD, RECURSIVE_ASCENT, TABLE_DRIVEN, TEST_ALL, TOKEN_SOURCE,
+};
 use crate::grammar::pattern::Pattern;
 use crate::grammar::repr::{self as r, NominalTypeRepr, TypeRepr};
 use crate::lexer::dfa::Dfa;
@@ -18,6 +21,8 @@ pub struct Grammar {
     pub prefix: String,
     pub span: Span,
     pub type_parameters: Vec<TypeParameter>,
+    // the `E` of `grammar<E = MyError>`, the error type of the parser
+    pub error_type: Option<TypeRef>,
     pub parameters: Vec<Parameter>,
     pub where_clauses: Vec<WhereClause<TypeRef>>,
     pub items: Vec<GrammarItem>,
@@ -28,6 +33,18 @@ pub struct Grammar {
 #[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Span(pub usize, pub usize);
 
+impl Span {
+    /// The smallest span that encloses both `self` and `other`.
+    pub fn merge(self, other: Span) -> Span {
+        Span(self.0.min(other.0), self.1.max(other.1))
+    }
+
+    /// Whether `other` lies within `self`.
+    pub fn contains(&self, other: Span) -> bool {
+        self.0 <= other.0 && other.1 <= self.1
+    }
+}
+
 impl From<Span> for Box<dyn Content> {
     fn from(val: Span) -> Self {
         let file_text = Tls::file_text();
@@ -50,6 +67,85 @@ pub enum GrammarItem {
     InternToken(InternToken),
     Nonterminal(NonterminalData),
     Use(String),
+    Import(Import),
+    PrecedenceDecl(PrecedenceData),
+    TypeDecl(TypeDeclData),
+    LeftRecursiveDecl(LeftRecursiveData),
+    StartDecl(StartData),
+    Test(TestData),
+}
+
+/// `import "path/to/other.lalrpop";` -- splices the nonterminals of
+/// another grammar file into this one. Imports are resolved by the
+/// build process before normalization (see `build::import`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Import {
+    pub span: Span,
+    /// path of the imported file, relative to the importing file
+    pub path: String,
+}
+
+/// `%left "+" "-";` -- declares the precedence and associativity of
+/// some terminals, which is used to resolve shift/reduce conflicts.
+/// Each declaration binds more tightly than the ones before it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrecedenceData {
+    pub span: Span,
+    pub assoc: Associativity,
+    pub terminals: Vec<TerminalString>,
+}
+
+/// `%type<Expr> Sum Product;` -- declares the type of some
+/// nonterminals up front, as with yacc's `%type`, for nonterminals whose
+/// type cannot be inferred. It is the same as writing `Sum: Expr`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeDeclData {
+    pub span: Span,
+    pub type_ref: TypeRef,
+    pub nonterminals: Vec<NonterminalString>,
+}
+
+/// `%left_recursive Expr;` -- states that the binary operators of some
+/// nonterminals group to the left, which is used to resolve the
+/// shift/reduce conflicts of alternatives like `Expr "+" Expr` that no
+/// `%left` (etc) declaration settles.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LeftRecursiveData {
+    pub span: Span,
+    pub nonterminals: Vec<NonterminalString>,
+}
+
+/// `%start Expr Statement;` -- declares some nonterminals as start
+/// symbols, each of which gets its own parser. It is the same as
+/// writing `pub Expr` and `pub Statement`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StartData {
+    pub span: Span,
+    pub nonterminals: Vec<NonterminalString>,
+}
+
+/// `#[test] parse "1 + 2" as Expr => Expr::Add(1, 2);` -- a unit test
+/// that is emitted into the generated file, asserting that the input
+/// parses as the given public nonterminal and that the result matches
+/// the pattern.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TestData {
+    pub span: Span,
+    pub annotations: Vec<Annotation>,
+    pub nonterminal: NonterminalString,
+    pub input: String,
+    /// the Rust pattern after the `=>`, exactly as written
+    pub pattern: String,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Associativity {
+    /// `%left`: `a + b + c` is `(a + b) + c`
+    Left,
+    /// `%right`: `a ^ b ^ c` is `a ^ (b ^ c)`
+    Right,
+    /// `%nonassoc`: `a == b == c` is an error
+    NonAssoc,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -135,6 +231,21 @@ pub struct InternToken {
     /// grammar. Sorted by order of increasing precedence.
     pub match_entries: Vec<MatchEntry>,
     pub dfa: Dfa,
+
+    /// Pairs of literals that the tokenizer had to choose between.
+    pub ambiguities: Vec<TerminalAmbiguity>,
+}
+
+/// Two terminal literals with the same precedence (e.g., `r"\w+"` and
+/// `r"[0-9]+"`) that can match the same input. The tokenizer picks
+/// `preferred`, which comes first in the grammar.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TerminalAmbiguity {
+    pub preferred: TerminalLiteral,
+    pub other: TerminalLiteral,
+
+    // where `other` appears
+    pub span: Span,
 }
 
 /// In `token_check`, as we prepare to generate a tokenizer, we
@@ -195,6 +306,14 @@ pub struct EnumToken {
     pub type_name: TypeRef,
     pub type_span: Span,
     pub conversions: Vec<Conversion>,
+    /// From `extern token T;`: terminals are quoted strings, matched
+    /// by comparing them with the `AsRef<str>` of the token. The
+    /// `conversions` are filled in by `normalize::token_check`.
+    pub match_by_str: bool,
+    /// From `extern token T: Token;`: as with `match_by_str`, but the
+    /// tokens are matched by their `lalrpop_util::token::Token`
+    /// implementation, which also gives their locations.
+    pub token_trait: bool,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -373,6 +492,48 @@ impl Visibility {
             Visibility::Priv => false,
         }
     }
+
+    /// Whether the item is only visible in the module it is declared
+    /// in, as with `pub(self)`.
+    pub fn is_private(&self) -> bool {
+        match *self {
+            Visibility::Pub(Some(ref path)) | Visibility::PubIn(ref path) => {
+                !path.absolute && path.ids.len() == 1 && &*path.ids[0] == "self"
+            }
+            Visibility::Pub(None) => false,
+            Visibility::Priv => true,
+        }
+    }
+
+    /// The same visibility, written in a module nested in the one the
+    /// item is declared in, like the `__parse__X` module of the parser
+    /// of `X`: `pub(super)` becomes `pub(in super::super)`.
+    pub fn nested(&self) -> Visibility {
+        let nested = |path: &Path| {
+            let mut ids = path.ids.clone();
+            match ids.first().map(|id| &**id) {
+                Some("self") if !path.absolute => ids[0] = Atom::from("super"),
+                Some("super") if !path.absolute => ids.insert(0, Atom::from("super")),
+                _ => {}
+            }
+            Path {
+                absolute: path.absolute,
+                ids,
+            }
+        };
+        match *self {
+            Visibility::Pub(Some(ref path)) => {
+                let path = nested(path);
+                if path.ids.len() == 1 {
+                    Visibility::Pub(Some(path))
+                } else {
+                    Visibility::PubIn(path)
+                }
+            }
+            Visibility::PubIn(ref path) => Visibility::PubIn(nested(path)),
+            Visibility::Pub(None) | Visibility::Priv => self.clone(),
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -381,8 +542,16 @@ pub struct NonterminalData {
     pub name: NonterminalString,
     pub annotations: Vec<Annotation>,
     pub span: Span,
-    pub args: Vec<NonterminalString>, // macro arguments
+    pub args: Vec<(NonterminalString, Option<TypeRef>)>, // macro arguments
     pub type_decl: Option<TypeRef>,
+    // from a `%type` declaration, filled in by `normalize::resolve`
+    pub declared_type: Option<TypeRef>,
+    // from `E: Type` macro arguments, filled in by `normalize::macro_expand`
+    // with the symbol given for `E` and the type it must produce
+    pub arg_bounds: Vec<(Symbol, TypeRef)>,
+    // the code of `#[shared_action => code]`, applied to the value of
+    // every alternative by `normalize::shared_action`, which clears it
+    pub shared_action: Option<String>,
     pub alternatives: Vec<Alternative>,
 }
 
@@ -390,6 +559,8 @@ pub struct NonterminalData {
 pub struct Annotation {
     pub id_span: Span,
     pub id: Atom,
+    // `#[id(name = "value")]`, `#[id(name)]` with an empty value, or
+    // `#[id = "value"]` with an empty name
     pub arg: Option<(Atom, String)>,
 }
 
@@ -399,7 +570,7 @@ pub struct Alternative {
 
     pub expr: ExprSymbol,
 
-    // if C, only legal in macros
+    // if C, or #[if { expr }]
     pub condition: Option<Condition>,
 
     // => { code }
@@ -412,18 +583,43 @@ pub struct Alternative {
 pub enum ActionKind {
     User(String),
     Fallible(String),
+
+    // `=> fn path` and `=>? fn path`, which call the function with the
+    // selected symbols as arguments
+    ExternalFn(Path),
+    FallibleExternalFn(Path),
+
     Lookahead,
     Lookbehind,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct Condition {
+pub enum Condition {
+    // if X == "Foo", only legal in macros
+    Macro(MacroCondition),
+
+    // #[if { expr }], a guard checked when the alternative is reduced;
+    // the expression can use the names bound by the alternative
+    Arbitrary(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MacroCondition {
     pub span: Span,
     pub lhs: NonterminalString, // X
-    pub rhs: Atom,              // "Foo"
+    pub rhs: ConditionRhs,      // "Foo"
     pub op: ConditionOp,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConditionRhs {
+    // X == "Foo"; with `~~` and `!~`, the string is a regexp
+    Literal(Atom),
+
+    // X ~~ r"Foo", a regexp without escapes, only with `~~` and `!~`
+    Regex(Atom),
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ConditionOp {
     // X == "Foo", equality
@@ -450,6 +646,9 @@ pub enum SymbolKind {
     // (X Y)
     Expr(ExprSymbol),
 
+    // (X Y | Z), one of several sequences
+    Choice(Vec<ExprSymbol>),
+
     // foo, before name resolution
     AmbiguousId(Atom),
 
@@ -471,13 +670,25 @@ pub enum SymbolKind {
     // <x:X> or <mut x:X>
     Name(Name, Box<Symbol>),
 
+    // @x:X, which names X in messages but not in the action code
+    DocName(Atom, Box<Symbol>),
+
     // @L
     Lookahead,
 
     // @R
     Lookbehind,
 
+    // (?=X), asserts that the next token is the terminal X
+    PosLookahead(Box<Symbol>),
+
+    // (?!X), asserts that the next token is not the terminal X
+    NegLookahead(Box<Symbol>),
+
     Error,
+
+    // ^, commits to the alternative for error reporting
+    Cut,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -542,6 +753,16 @@ impl NonterminalString {
     pub fn len(&self) -> usize {
         self.0.len()
     }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for NonterminalString {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
 }
 
 impl From<NonterminalString> for Box<dyn Content> {
@@ -585,6 +806,8 @@ pub enum RepeatOp {
     Star,
     Plus,
     Question,
+    // `X{n}`, `X{n,}` or `X{n,m}`
+    Count { min: usize, max: Option<usize> },
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -601,7 +824,17 @@ pub struct ExprSymbol {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct MacroSymbol {
     pub name: NonterminalString,
-    pub args: Vec<Symbol>,
+    pub args: Vec<MacroArg>,
+}
+
+/// An argument in a use of a macro, like `Expr` in `Comma<Expr>`, or
+/// `Vec<u32>` in `Parse<Vec<u32>>`. The parser only produces symbols;
+/// `normalize::resolve` turns those naming no terminal, nonterminal or
+/// macro into types.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MacroArg {
+    Symbol(Symbol),
+    Type(TypeRef),
 }
 
 impl TerminalString {
@@ -667,9 +900,15 @@ impl GrammarItem {
         match *self {
             GrammarItem::Nonterminal(ref d) => Some(d),
             GrammarItem::Use(..) => None,
+            GrammarItem::Import(..) => None,
             GrammarItem::MatchToken(..) => None,
             GrammarItem::ExternToken(..) => None,
             GrammarItem::InternToken(..) => None,
+            GrammarItem::PrecedenceDecl(..) => None,
+            GrammarItem::TypeDecl(..) => None,
+            GrammarItem::LeftRecursiveDecl(..) => None,
+            GrammarItem::StartDecl(..) => None,
+            GrammarItem::Test(..) => None,
         }
     }
 
@@ -677,9 +916,15 @@ impl GrammarItem {
         match *self {
             GrammarItem::Nonterminal(..) => None,
             GrammarItem::Use(..) => None,
+            GrammarItem::Import(..) => None,
             GrammarItem::MatchToken(ref d) => Some(d),
             GrammarItem::ExternToken(..) => None,
             GrammarItem::InternToken(..) => None,
+            GrammarItem::PrecedenceDecl(..) => None,
+            GrammarItem::TypeDecl(..) => None,
+            GrammarItem::LeftRecursiveDecl(..) => None,
+            GrammarItem::StartDecl(..) => None,
+            GrammarItem::Test(..) => None,
         }
     }
 
@@ -687,9 +932,15 @@ impl GrammarItem {
         match *self {
             GrammarItem::Nonterminal(..) => None,
             GrammarItem::Use(..) => None,
+            GrammarItem::Import(..) => None,
             GrammarItem::MatchToken(..) => None,
             GrammarItem::ExternToken(ref d) => Some(d),
             GrammarItem::InternToken(..) => None,
+            GrammarItem::PrecedenceDecl(..) => None,
+            GrammarItem::TypeDecl(..) => None,
+            GrammarItem::LeftRecursiveDecl(..) => None,
+            GrammarItem::StartDecl(..) => None,
+            GrammarItem::Test(..) => None,
         }
     }
 
@@ -697,9 +948,15 @@ impl GrammarItem {
         match *self {
             GrammarItem::Nonterminal(..) => None,
             GrammarItem::Use(..) => None,
+            GrammarItem::Import(..) => None,
             GrammarItem::MatchToken(..) => None,
             GrammarItem::ExternToken(..) => None,
             GrammarItem::InternToken(ref d) => Some(d),
+            GrammarItem::PrecedenceDecl(..) => None,
+            GrammarItem::TypeDecl(..) => None,
+            GrammarItem::LeftRecursiveDecl(..) => None,
+            GrammarItem::StartDecl(..) => None,
+            GrammarItem::Test(..) => None,
         }
     }
 }
@@ -710,6 +967,14 @@ impl NonterminalData {
     }
 }
 
+impl ConditionRhs {
+    pub fn text(&self) -> &Atom {
+        match self {
+            ConditionRhs::Literal(text) | ConditionRhs::Regex(text) => text,
+        }
+    }
+}
+
 impl Symbol {
     pub fn new(span: Span, kind: SymbolKind) -> Symbol {
         Symbol { span, kind }
@@ -718,6 +983,10 @@ impl Symbol {
     pub fn canonical_form(&self) -> String {
         format!("{}", self)
     }
+
+    pub fn is_cut(&self) -> bool {
+        matches!(self.kind, SymbolKind::Cut)
+    }
 }
 
 impl Name {
@@ -942,6 +1211,16 @@ impl Display for SymbolKind {
     fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
         match *self {
             SymbolKind::Expr(ref expr) => write!(fmt, "{}", expr),
+            SymbolKind::Choice(ref exprs) => {
+                write!(fmt, "(")?;
+                for (i, expr) in exprs.iter().enumerate() {
+                    if i > 0 {
+                        write!(fmt, " | ")?;
+                    }
+                    write!(fmt, "{}", Sep(" ", &expr.symbols))?;
+                }
+                write!(fmt, ")")
+            }
             SymbolKind::Terminal(ref s) => write!(fmt, "{}", s),
             SymbolKind::Nonterminal(ref s) => write!(fmt, "{}", s),
             SymbolKind::AmbiguousId(ref s) => write!(fmt, "{}", s),
@@ -949,9 +1228,13 @@ impl Display for SymbolKind {
             SymbolKind::Repeat(ref r) => write!(fmt, "{}", r),
             SymbolKind::Choose(ref s) => write!(fmt, "<{}>", s),
             SymbolKind::Name(ref n, ref s) => write!(fmt, "{}:{}", n, s),
+            SymbolKind::DocName(ref n, ref s) => write!(fmt, "@{}:{}", n, s),
             SymbolKind::Lookahead => write!(fmt, "@L"),
             SymbolKind::Lookbehind => write!(fmt, "@R"),
+            SymbolKind::PosLookahead(ref s) => write!(fmt, "(?={})", s),
+            SymbolKind::NegLookahead(ref s) => write!(fmt, "(?!{})", s),
             SymbolKind::Error => write!(fmt, "error"),
+            SymbolKind::Cut => write!(fmt, "^"),
         }
     }
 }
@@ -978,6 +1261,15 @@ impl Display for RepeatOp {
             RepeatOp::Plus => write!(fmt, "+"),
             RepeatOp::Star => write!(fmt, "*"),
             RepeatOp::Question => write!(fmt, "?"),
+            RepeatOp::Count {
+                min,
+                max: Some(max),
+            } if min == max => write!(fmt, "{{{}}}", min),
+            RepeatOp::Count {
+                min,
+                max: Some(max),
+            } => write!(fmt, "{{{},{}}}", min, max),
+            RepeatOp::Count { min, max: None } => write!(fmt, "{{{},}}", min),
         }
     }
 }
@@ -998,6 +1290,11 @@ impl ExprSymbol {
     pub fn canonical_form(&self) -> String {
         format!("{}", self)
     }
+
+    /// True for the epsilon production, which matches no symbols.
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
 }
 
 impl MacroSymbol {
@@ -1018,6 +1315,15 @@ impl Display for MacroSymbol {
     }
 }
 
+impl Display for MacroArg {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        match *self {
+            MacroArg::Symbol(ref symbol) => write!(fmt, "{}", symbol),
+            MacroArg::Type(ref type_ref) => write!(fmt, "{}", type_ref),
+        }
+    }
+}
+
 impl Display for TypeParameter {
     fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
         match *self {
@@ -1143,6 +1449,128 @@ impl TypeRef {
             },
         }
     }
+
+    // Replaces each `OfSymbol` (as left by macro expansion) with the type
+    // of the symbol: the declared type of a nonterminal, or the type of a
+    // terminal (`&'input str` unless an `extern` block says otherwise).
+    // Returns `None` if some type is not known without inference, e.g.
+    // that of a nonterminal without a type annotation.
+    #[allow(dead_code)] // for diagnostics; tyinfer does its own resolution
+    pub fn resolve(&self, grammar: &Grammar) -> Option<TypeRef> {
+        self.resolve_in(grammar, &mut vec![])
+    }
+
+    fn resolve_in(&self, grammar: &Grammar, stack: &mut Vec<NonterminalString>) -> Option<TypeRef> {
+        let resolve_all = |types: &[TypeRef], stack: &mut Vec<NonterminalString>| {
+            types
+                .iter()
+                .map(|t| t.resolve_in(grammar, stack))
+                .collect::<Option<Vec<_>>>()
+        };
+        Some(match *self {
+            TypeRef::Tuple(ref types) => TypeRef::Tuple(resolve_all(types, stack)?),
+            TypeRef::Slice(ref ty) => TypeRef::Slice(Box::new(ty.resolve_in(grammar, stack)?)),
+            TypeRef::Nominal {
+                ref path,
+                ref types,
+            } => TypeRef::Nominal {
+                path: path.clone(),
+                types: resolve_all(types, stack)?,
+            },
+            TypeRef::Ref {
+                ref lifetime,
+                mutable,
+                ref referent,
+            } => TypeRef::Ref {
+                lifetime: lifetime.clone(),
+                mutable,
+                referent: Box::new(referent.resolve_in(grammar, stack)?),
+            },
+            TypeRef::TraitObject {
+                ref path,
+                ref types,
+            } => TypeRef::TraitObject {
+                path: path.clone(),
+                types: resolve_all(types, stack)?,
+            },
+            TypeRef::Lifetime(_) | TypeRef::Id(_) => self.clone(),
+            TypeRef::OfSymbol(ref symbol) => return symbol_type_ref(grammar, symbol, stack),
+            TypeRef::Fn {
+                ref forall,
+                ref path,
+                ref parameters,
+                ref ret,
+            } => TypeRef::Fn {
+                forall: forall.clone(),
+                path: path.clone(),
+                parameters: resolve_all(parameters, stack)?,
+                ret: match ret {
+                    Some(ret) => Some(Box::new(ret.resolve_in(grammar, stack)?)),
+                    None => None,
+                },
+            },
+        })
+    }
+}
+
+#[allow(dead_code)]
+fn symbol_type_ref(
+    grammar: &Grammar,
+    symbol: &SymbolKind,
+    stack: &mut Vec<NonterminalString>,
+) -> Option<TypeRef> {
+    match *symbol {
+        SymbolKind::Nonterminal(ref id) => {
+            // a type that mentions itself cannot be resolved
+            if stack.contains(id) {
+                return None;
+            }
+            let type_decl = grammar
+                .items
+                .iter()
+                .filter_map(GrammarItem::as_nonterminal)
+                .find(|data| data.name == *id && !data.is_macro_def())?
+                .type_decl
+                .as_ref()?;
+            stack.push(id.clone());
+            let ty = type_decl.resolve_in(grammar, stack);
+            stack.pop();
+            ty
+        }
+        SymbolKind::Terminal(ref id) => match grammar.enum_token() {
+            None => Some(TypeRef::Ref {
+                lifetime: Some(Lifetime::input()),
+                mutable: false,
+                referent: Box::new(TypeRef::Id(Atom::from("str"))),
+            }),
+            Some(enum_token) => {
+                let mut types = vec![];
+                if let Some(conversion) = enum_token.conversions.iter().find(|c| c.from == *id) {
+                    conversion
+                        .to
+                        .for_each_binding(&mut |ty| types.push(ty.clone()));
+                }
+                match types.len() {
+                    0 => Some(enum_token.type_name.clone()),
+                    1 => types.pop(),
+                    _ => Some(TypeRef::Tuple(types)),
+                }
+            }
+        },
+        SymbolKind::Choose(ref s) | SymbolKind::Name(_, ref s) | SymbolKind::DocName(_, ref s) => {
+            symbol_type_ref(grammar, &s.kind, stack)
+        }
+        SymbolKind::PosLookahead(_) | SymbolKind::NegLookahead(_) => Some(TypeRef::Tuple(vec![])),
+        SymbolKind::Expr(..)
+        | SymbolKind::Choice(..)
+        | SymbolKind::Macro(..)
+        | SymbolKind::Repeat(..)
+        | SymbolKind::AmbiguousId(..)
+        | SymbolKind::Lookahead
+        | SymbolKind::Lookbehind
+        | SymbolKind::Error
+        | SymbolKind::Cut => None,
+    }
 }
 
 impl Path {
@@ -1197,13 +1625,28 @@ impl Path {
 pub fn read_algorithm(annotations: &[Annotation], algorithm: &mut r::Algorithm) {
     for annotation in annotations {
         if annotation.id == *LALR {
-            algorithm.lalr = true;
+            algorithm.construction = r::LrConstruction::Lalr;
+        } else if annotation.id == *LR1 {
+            algorithm.construction = r::LrConstruction::CanonicalLr1;
+        } else if annotation.id == *AMBIGUOUS {
+            algorithm.construction = r::LrConstruction::Glr;
         } else if annotation.id == *TABLE_DRIVEN {
             algorithm.codegen = r::LrCodeGeneration::TableDriven;
         } else if annotation.id == *RECURSIVE_ASCENT {
             algorithm.codegen = r::LrCodeGeneration::RecursiveAscent;
         } else if annotation.id == *TEST_ALL {
             algorithm.codegen = r::LrCodeGeneration::TestAll;
+        } else if annotation.id == *GENERATE_VISITOR
+            || annotation.id == *GENERATE_FOLDER
+            || annotation.id == *NO_STD
+            || annotation.id == *INCREMENTAL
+            || annotation.id == *ASYNC
+            || annotation.id == *TOKEN_SOURCE
+            || annotation.id == *FALLIBLE
+            || annotation.id == *EMIT_CST
+            || annotation.id == *ALLOW
+        {
+            // not about the algorithm; read when lowering
         } else {
             panic!(
                 "validation permitted unknown annotation: {:?}",
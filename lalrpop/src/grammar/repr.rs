@@ -2,18 +2,24 @@
 //! version of `parse_tree`. The normalization passes produce this
 //! representation incrementally.
 
-use crate::collections::{map, Map};
+use crate::collections::{map, set, Map, Set};
+use crate::grammar::consts::DEPRECATED;
 use crate::grammar::free_variables::FreeVariables;
 use crate::grammar::pattern::Pattern;
+use crate::lint::LintKind;
 use crate::message::Content;
 use crate::util::Sep;
+use petgraph::algo::tarjan_scc;
+use petgraph::graph::{Graph, NodeIndex};
+use std::cell::OnceCell;
+use std::collections::VecDeque;
 use std::fmt::{Debug, Display, Error, Formatter};
 use string_cache::DefaultAtom as Atom;
 
 // These concepts we re-use wholesale
 pub use crate::grammar::parse_tree::{
-    Annotation, InternToken, Lifetime, Name, NonterminalString, Path, Span, TerminalLiteral,
-    TerminalString, TypeBound, TypeParameter, Visibility,
+    Annotation, Associativity, InternToken, Lifetime, Name, NonterminalString, Path, Span,
+    TerminalLiteral, TerminalString, TestData, TypeBound, TypeParameter, Visibility,
 };
 
 #[derive(Clone, Debug)]
@@ -28,6 +34,54 @@ pub struct Grammar {
     // true if the grammar mentions the `!` terminal anywhere
     pub uses_error_recovery: bool,
 
+    // true if the grammar is annotated with `#[generate_visitor]`
+    pub generate_visitor: bool,
+
+    // true if the grammar is annotated with `#[generate_folder]`
+    pub generate_folder: bool,
+
+    // the most states the parser stack may hold, from
+    // `#[no_std(stack_depth = "N")]`
+    pub stack_depth: Option<usize>,
+
+    // true if the grammar is annotated with `#[incremental]`
+    pub incremental: bool,
+
+    // true if the grammar is annotated with `#[async]`
+    pub async_parser: bool,
+
+    // true if the grammar is annotated with `#[emit_cst]`
+    pub emit_cst: bool,
+
+    // the empty nonterminals standing in for `(?=X)` and `(?!X)`
+    pub lookahead_assertions: Map<NonterminalString, LookaheadAssertion>,
+
+    // the terminals given a precedence by `%left`, `%right` or `%nonassoc`
+    pub precedences: Map<TerminalString, PrecedenceEntry>,
+
+    // the nonterminals named by `%left_recursive`
+    pub left_recursive: Set<NonterminalString>,
+
+    // the `#[test] parse "..." as X => pattern;` items declared in the grammar
+    pub tests: Vec<TestData>,
+
+    // the `#[display = "..."]` formats of alternatives, by their action fn
+    pub display_formats: Map<ActionFn, String>,
+
+    // the `#[rename = "..."]` variant names of alternatives, by their action fn
+    pub variant_names: Map<ActionFn, String>,
+
+    // the empty alternatives without action code of nonterminals whose
+    // type is not `()`, which the build warns about
+    pub epsilon_alternatives: Vec<(NonterminalString, Span)>,
+
+    // the lints turned off by `#[allow(name)]` on the grammar
+    pub allowed_lints: Vec<LintKind>,
+
+    // the nonterminals that can derive the empty string, computed on
+    // first use by `Grammar::nullable_nonterminals`
+    pub nullable_nonterminals: OnceCell<Set<NonterminalString>>,
+
     // these are the nonterminals that were declared to be public; the
     // key is the user's name for the symbol, the value is the
     // artificial symbol we introduce, which will always have a single
@@ -50,6 +104,18 @@ pub struct Grammar {
     // an extern token declaration
     pub intern_token: Option<InternToken>,
 
+    // with `extern token T;`, tokens are matched against terminals by
+    // comparing their `AsRef<str>` with the text of the terminal
+    pub match_terminals_by_str: bool,
+
+    // with `extern token T: Token;`, the comparison is with the
+    // `lalrpop_util::token::Token::terminal_name` of the token instead
+    pub match_terminals_by_token_trait: bool,
+
+    // with `#[token_source = "proc_macro2"]`, which also sets the two
+    // above, tokens are compared with `lalrpop_procmacro::Token::matches`
+    pub match_terminals_by_proc_macro2: bool,
+
     // the grammar proper:
     pub action_fn_defns: Vec<ActionFnDefn>,
     pub terminals: TerminalSet,
@@ -93,12 +159,50 @@ pub struct NonterminalData {
     pub productions: Vec<Production>,
 }
 
+/// A zero-width `(?=X)` (or, if `negated`, `(?!X)`) assertion. It is
+/// lowered to an empty nonterminal that may only be reduced when the
+/// next token is (or is not) `terminal`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LookaheadAssertion {
+    pub terminal: TerminalString,
+    pub negated: bool,
+}
+
+/// The precedence of a terminal, from a `%left`, `%right` or
+/// `%nonassoc` declaration. Later declarations get higher levels and
+/// bind more tightly.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PrecedenceEntry {
+    pub level: u32,
+    pub assoc: Associativity,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Algorithm {
-    pub lalr: bool,
+    pub construction: LrConstruction,
     pub codegen: LrCodeGeneration,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LrConstruction {
+    /// Lane table construction (the default); has the power of LR(1)
+    /// but produces tables about as small as LALR(1).
+    LaneTable,
+
+    /// Canonical LR(1) construction, requested with `#[LR1]`. States
+    /// are never merged, so the tables can get large.
+    CanonicalLr1,
+
+    /// LALR(1) construction, requested with `#[LALR]`.
+    Lalr,
+
+    /// Canonical LR(1) construction that keeps conflicting actions
+    /// rather than reporting them, requested with `#[ambiguous]`. The
+    /// generated parser explores every action (GLR) and returns all
+    /// successful parses.
+    Glr,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum LrCodeGeneration {
     TableDriven,
@@ -120,6 +224,12 @@ pub struct Production {
     pub symbols: Vec<Symbol>,
     pub action: ActionFn,
     pub span: Span,
+    // the number of symbols before a cut (`^`), if the alternative had one;
+    // once the parser is past them, errors name them as the context
+    pub cut: Option<usize>,
+    // the index and name of each symbol written `@x:X`; the parser adds
+    // the name to the tokens it expects at the start of that symbol
+    pub doc_names: Vec<(usize, Atom)>,
 }
 
 #[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
@@ -148,6 +258,34 @@ pub struct UserActionFnDefn {
     pub arg_patterns: Vec<Name>,
     pub arg_types: Vec<TypeRepr>,
     pub code: String,
+
+    /// Attributes from the alternative to put on the action fn, like
+    /// `inline(always)` or `cold`.
+    pub attributes: Vec<String>,
+
+    /// In `#[emit_cst]` grammars, the parse tree node that the action
+    /// fn returns next to the value of `code`.
+    pub parse_tree: Option<ParseTreeAction>,
+}
+
+/// The parse tree node of an alternative of `nonterminal`. Each
+/// argument of the action fn gives a child.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseTreeAction {
+    pub nonterminal: NonterminalString,
+    pub symbols: Vec<ParseTreeSymbol>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseTreeSymbol {
+    /// A leaf of the tree
+    Terminal(TerminalString),
+
+    /// A nonterminal whose value is paired with its node
+    Nonterminal,
+
+    /// A symbol that matches no input, like `@L`, so has no node
+    Hidden,
 }
 
 /// An action fn generated by the inlining pass.  If we were
@@ -423,6 +561,10 @@ impl Types {
         assert!(self.nonterminal_types.insert(nt_id, ty).is_none());
     }
 
+    pub fn replace_type(&mut self, nt_id: NonterminalString, ty: TypeRepr) {
+        assert!(self.nonterminal_types.insert(nt_id, ty).is_some());
+    }
+
     pub fn add_term_type(&mut self, term: TerminalString, ty: TypeRepr) {
         assert!(self.terminal_types.insert(term, ty).is_none());
     }
@@ -612,6 +754,15 @@ impl Symbol {
             Symbol::Nonterminal(ref id) => t.nonterminal_type(id),
         }
     }
+
+    /// The level of the `%left`, `%right` or `%nonassoc` declaration of
+    /// this symbol; `None` for nonterminals and undeclared terminals.
+    pub fn precedence_level(&self, grammar: &Grammar) -> Option<u32> {
+        match self {
+            Symbol::Terminal(terminal) => grammar.precedences.get(terminal).map(|e| e.level),
+            Symbol::Nonterminal(_) => None,
+        }
+    }
 }
 
 impl Display for Symbol {
@@ -675,8 +826,15 @@ impl UserActionFnDefn {
             .map(|(name, ty)| format!("{}: {}", name, ty))
             .collect();
 
+        let attributes: String = self
+            .attributes
+            .iter()
+            .map(|attribute| format!("#[{}] ", attribute))
+            .collect();
+
         format!(
-            "fn {}({}) -> {} {{ {} }}",
+            "{}fn {}({}) -> {} {{ {} }}",
+            attributes,
             name,
             Sep(", ", &arg_strings),
             defn.ret_type,
@@ -705,7 +863,225 @@ impl InlineActionFnDefn {
     }
 }
 
+impl NonterminalData {
+    /// True if this nonterminal can derive the empty string.
+    pub fn is_nullable(&self, grammar: &Grammar) -> bool {
+        grammar.nullable_nonterminals().contains(&self.name)
+    }
+
+    /// The terminals that can appear in what this nonterminal derives.
+    #[allow(dead_code)] // for tests and tooling; nothing needs it to build parsers
+    pub fn referenced_terminals(&self, grammar: &Grammar) -> Set<TerminalString> {
+        grammar.referenced_terminals(&self.name)
+    }
+
+    /// The `#[deprecated(...)]` attribute for the parser of this
+    /// nonterminal, if it is marked `#[deprecated]`.
+    pub fn deprecated_attribute(&self) -> Option<String> {
+        let annotations: Vec<_> = self
+            .annotations
+            .iter()
+            .filter(|annotation| annotation.id == *DEPRECATED)
+            .collect();
+        if annotations.is_empty() {
+            return None;
+        }
+        let args: Vec<_> = annotations
+            .iter()
+            .filter_map(|annotation| annotation.arg.as_ref())
+            .map(|(name, value)| {
+                // `#[deprecated = "..."]` gives the note
+                let name = if name.is_empty() { "note" } else { name };
+                format!("{} = \"{}\"", name, value)
+            })
+            .collect();
+        if args.is_empty() {
+            Some("#[deprecated]".to_string())
+        } else {
+            Some(format!("#[deprecated({})]", Sep(", ", &args)))
+        }
+    }
+}
+
+impl Production {
+    fn is_nullable_given(&self, nullable: &Set<NonterminalString>) -> bool {
+        self.symbols.iter().all(|symbol| match symbol {
+            Symbol::Nonterminal(nt) => nullable.contains(nt),
+            Symbol::Terminal(_) => false,
+        })
+    }
+}
+
 impl Grammar {
+    /// The nonterminals that can derive the empty string: those with a
+    /// production whose symbols are all nullable nonterminals. This is
+    /// computed once, by iterating to a fixed point.
+    pub fn nullable_nonterminals(&self) -> &Set<NonterminalString> {
+        self.nullable_nonterminals.get_or_init(|| {
+            let mut nullable = set();
+            let mut changed = true;
+            while changed {
+                changed = false;
+                for (name, data) in &self.nonterminals {
+                    if !nullable.contains(name)
+                        && data
+                            .productions
+                            .iter()
+                            .any(|production| production.is_nullable_given(&nullable))
+                    {
+                        nullable.insert(name.clone());
+                        changed = true;
+                    }
+                }
+            }
+            nullable
+        })
+    }
+
+    /// The terminals that can appear in what `nonterminal` derives,
+    /// found by following the nonterminals of its productions (and of
+    /// theirs, and so on). Macros are expanded by then, so this also
+    /// covers the terminals of macro arguments.
+    pub fn referenced_terminals(&self, nonterminal: &NonterminalString) -> Set<TerminalString> {
+        let mut terminals = set();
+        let mut visited = set();
+        let mut stack = vec![nonterminal];
+        while let Some(nonterminal) = stack.pop() {
+            if !visited.insert(nonterminal) {
+                continue;
+            }
+            for production in self.productions_for(nonterminal) {
+                for symbol in &production.symbols {
+                    match symbol {
+                        Symbol::Terminal(terminal) => {
+                            terminals.insert(terminal.clone());
+                        }
+                        Symbol::Nonterminal(nonterminal) => stack.push(nonterminal),
+                    }
+                }
+            }
+        }
+        terminals
+    }
+
+    /// The nonterminals that `start` can derive, including `start`
+    /// itself, found by following the nonterminals of its productions
+    /// (and of theirs, and so on).
+    pub fn reachable_from(&self, start: &NonterminalString) -> Set<NonterminalString> {
+        let mut reachable = set();
+        let mut stack = vec![start];
+        while let Some(nonterminal) = stack.pop() {
+            if !reachable.insert(nonterminal.clone()) {
+                continue;
+            }
+            for production in self.productions_for(nonterminal) {
+                for symbol in &production.symbols {
+                    if let Symbol::Nonterminal(nonterminal) = symbol {
+                        stack.push(nonterminal);
+                    }
+                }
+            }
+        }
+        reachable
+    }
+
+    /// The cycles of nonterminals that none of them can ever get out
+    /// of, like `A = A "x";` or `A = B; B = A;`, so that they derive no
+    /// input. Each cycle is given as a chain from its first
+    /// nonterminal (in the order of their names) back to itself, and
+    /// the cycles are in the order of their first nonterminals.
+    pub fn unproductive_cycles(&self) -> Vec<Vec<NonterminalString>> {
+        let mut graph = Graph::<&NonterminalString, ()>::new();
+        let nodes: Map<_, _> = self
+            .nonterminals
+            .keys()
+            .map(|nonterminal| (nonterminal, graph.add_node(nonterminal)))
+            .collect();
+        for (nonterminal, data) in &self.nonterminals {
+            for production in &data.productions {
+                for symbol in &production.symbols {
+                    if let Symbol::Nonterminal(target) = symbol {
+                        graph.add_edge(nodes[nonterminal], nodes[target], ());
+                    }
+                }
+            }
+        }
+
+        let productive = self.productive_nonterminals();
+        let mut cycles = vec![];
+        for component in tarjan_scc(&graph) {
+            let members: Set<_> = component.iter().map(|&node| graph[node]).collect();
+            let start = members.iter().next().cloned().unwrap();
+            if members.len() == 1 && !graph.contains_edge(nodes[start], nodes[start]) {
+                continue;
+            }
+            // the nonterminals of a cycle either all derive some input,
+            // or none of them does
+            if !productive.contains(start) {
+                cycles.push(self.shortest_cycle(&graph, &nodes, &members, start));
+            }
+        }
+        cycles.sort();
+        cycles
+    }
+
+    /// The nonterminals that derive some input, found by adding those
+    /// with an alternative whose nonterminals are all known to, until
+    /// there are no more.
+    fn productive_nonterminals(&self) -> Set<&NonterminalString> {
+        let mut productive = set();
+        loop {
+            let before = productive.len();
+            for (nonterminal, data) in &self.nonterminals {
+                if !productive.contains(nonterminal)
+                    && data.productions.iter().any(|production| {
+                        production.symbols.iter().all(|symbol| match symbol {
+                            Symbol::Nonterminal(target) => productive.contains(target),
+                            Symbol::Terminal(_) => true,
+                        })
+                    })
+                {
+                    productive.insert(nonterminal);
+                }
+            }
+            if productive.len() == before {
+                return productive;
+            }
+        }
+    }
+
+    /// The shortest chain from `start` back to itself that stays among
+    /// `members`, found breadth first.
+    fn shortest_cycle(
+        &self,
+        graph: &Graph<&NonterminalString, ()>,
+        nodes: &Map<&NonterminalString, NodeIndex>,
+        members: &Set<&NonterminalString>,
+        start: &NonterminalString,
+    ) -> Vec<NonterminalString> {
+        let mut previous: Map<&NonterminalString, &NonterminalString> = map();
+        let mut queue = VecDeque::from([start]);
+        while let Some(nonterminal) = queue.pop_front() {
+            for target in graph.neighbors(nodes[nonterminal]).map(|node| graph[node]) {
+                if target == start {
+                    let mut chain = vec![start.clone(), nonterminal.clone()];
+                    let mut current = nonterminal;
+                    while current != start {
+                        current = previous[current];
+                        chain.push(current.clone());
+                    }
+                    chain.reverse();
+                    return chain;
+                }
+                if members.contains(target) && !previous.contains_key(target) {
+                    previous.insert(target, nonterminal);
+                    queue.push_back(target);
+                }
+            }
+        }
+        unreachable!("`{}` is part of a cycle", start)
+    }
+
     pub fn pattern(&self, t: &TerminalString) -> &Pattern<TypeRepr> {
         &self.conversions[t]
     }
@@ -743,8 +1119,88 @@ impl Grammar {
 impl Default for Algorithm {
     fn default() -> Self {
         Algorithm {
-            lalr: false,
+            construction: LrConstruction::LaneTable,
             codegen: LrCodeGeneration::TableDriven,
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::NonterminalString;
+    use crate::test_util::normalized_grammar;
+    use string_cache::DefaultAtom as Atom;
+
+    #[test]
+    fn referenced_terminals() {
+        let grammar = normalized_grammar(
+            r#"
+    grammar;
+    pub A = B "a" Comma<C>;
+    B = { "b", "(" A ")" };
+    C = "c";
+    Comma<T> = (T ",")*;
+    D = "d";
+"#,
+        );
+        let terminals = |name: &str| -> Vec<String> {
+            let name = NonterminalString(Atom::from(name));
+            let data = &grammar.nonterminals[&name];
+            let terminals = data.referenced_terminals(&grammar);
+            assert_eq!(terminals, grammar.referenced_terminals(&name));
+            terminals.iter().map(ToString::to_string).collect()
+        };
+        assert_eq!(
+            terminals("A"),
+            [r#""(""#, r#"")""#, r#"",""#, r#""a""#, r#""b""#, r#""c""#]
+        );
+        assert_eq!(terminals("C"), [r#""c""#]);
+        assert_eq!(terminals("D"), [r#""d""#]);
+    }
+
+    #[test]
+    fn reachable_from() {
+        let grammar = normalized_grammar(
+            r#"
+    grammar;
+    pub A = B "a" Comma<C>;
+    B = { "b", "(" A ")" };
+    C = "c";
+    Comma<T> = (T ",")+;
+    D = "d" C;
+"#,
+        );
+        let reachable = |name: &str| -> Vec<String> {
+            let name = NonterminalString(Atom::from(name));
+            let reachable = grammar.reachable_from(&name);
+            reachable.iter().map(ToString::to_string).collect()
+        };
+        assert_eq!(reachable("A"), [r#"(C ",")+"#, "A", "B", "C", "Comma<C>"]);
+        assert_eq!(reachable("B"), [r#"(C ",")+"#, "A", "B", "C", "Comma<C>"]);
+        assert_eq!(reachable("D"), ["C", "D"]);
+    }
+
+    #[test]
+    fn unproductive_cycles() {
+        let grammar = normalized_grammar(
+            r#"
+    grammar;
+    pub A: () = { B, "a" };
+    B: () = C "b";
+    C: () = { B, D };
+    D: () = D "d";
+    E: () = { E "e", "e" };
+    F: () = { G, "f" };
+    G: () = F;
+"#,
+        );
+        let cycles: Vec<Vec<String>> = grammar
+            .unproductive_cycles()
+            .iter()
+            .map(|cycle| cycle.iter().map(ToString::to_string).collect())
+            .collect();
+        // `E` and the cycle of `F` and `G` have alternatives that lead
+        // out of them, but the way out of `B` and `C` leads into `D`
+        assert_eq!(cycles, [vec!["B", "C", "B"], vec!["D", "D"]]);
+    }
+}
@@ -0,0 +1,132 @@
+//! Writes the LR automaton of a grammar as a Graphviz DOT graph, for
+//! debugging conflicts. Each node is a state, labeled with its kernel
+//! items; shifts and gotos are edges labeled with the symbol.
+
+use crate::grammar::repr::{Grammar, Production};
+use crate::lr1::Lr1State;
+use crate::tls::Tls;
+use std::fmt::Display;
+use std::io::{self, Write};
+
+pub fn write_dot(
+    grammar: &Grammar,
+    states: &[Lr1State<'_>],
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    // the start state's kernel consists of the items for the start symbol
+    let kernel_of_start = |production: &Production| {
+        grammar
+            .start_nonterminals
+            .values()
+            .any(|nt| *nt == production.nonterminal)
+    };
+    let start = states.first().and_then(|state| {
+        state
+            .items
+            .vec
+            .iter()
+            .find(|item| kernel_of_start(item.production))
+            .map(|item| item.production.nonterminal.clone())
+    });
+
+    match start {
+        Some(nt) => writeln!(out, "digraph {} {{", quote(nt))?,
+        None => writeln!(out, "digraph {{")?,
+    }
+    writeln!(out, "    node [shape=box, fontname=monospace];")?;
+
+    for state in states {
+        let kernel: Vec<_> = state
+            .items
+            .vec
+            .iter()
+            .filter(|item| item.index > 0 || kernel_of_start(item.production))
+            .collect();
+
+        let mut label = format!("State {}\\l", state.index.0);
+        for item in &kernel {
+            label.push_str(&escape(format!("{:?}", item.to_lr0())));
+            label.push_str("\\l");
+        }
+
+        // point at the alternatives the kernel items come from
+        let file_text = Tls::file_text();
+        let mut tooltip = String::new();
+        for item in &kernel {
+            tooltip.push_str(&escape(file_text.span_str(item.production.span)));
+            tooltip.push_str("\\n");
+        }
+
+        writeln!(
+            out,
+            "    s{} [label=\"{}\", tooltip=\"{}\"];",
+            state.index.0, label, tooltip
+        )?;
+    }
+
+    for state in states {
+        for (terminal, target) in &state.shifts {
+            writeln!(
+                out,
+                "    s{} -> s{} [label={}];",
+                state.index.0,
+                target.0,
+                quote(terminal)
+            )?;
+        }
+        for (nonterminal, target) in &state.gotos {
+            writeln!(
+                out,
+                "    s{} -> s{} [label={}, style=dashed];",
+                state.index.0,
+                target.0,
+                quote(nonterminal)
+            )?;
+        }
+    }
+
+    writeln!(out, "}}")
+}
+
+fn quote<T: Display>(value: T) -> String {
+    format!("\"{}\"", escape(value.to_string()))
+}
+
+fn escape(s: String) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod test {
+    use super::write_dot;
+    use crate::grammar::repr::NonterminalString;
+    use crate::lr1::{self, Lr1Tls};
+    use crate::test_util::normalized_grammar;
+    use crate::tls::Tls;
+    use string_cache::DefaultAtom as Atom;
+
+    #[test]
+    fn dot() {
+        let text = r#"grammar; pub S: () = { "(" S ")" => (), "x" => () };"#;
+        let _tls = Tls::test_string(text);
+        let grammar = normalized_grammar(text);
+        let _lr1_tls = Lr1Tls::install(grammar.terminals.clone());
+        let start = grammar.start_nonterminals[&NonterminalString(Atom::from("S"))].clone();
+        let states = lr1::build_states(&grammar, start).unwrap();
+
+        let mut out = vec![];
+        write_dot(&grammar, &states, &mut out).unwrap();
+        let dot = String::from_utf8(out).unwrap();
+
+        assert!(dot.starts_with("digraph \"__S\" {\n"));
+        assert!(
+            dot.contains(r#"s0 [label="State 0\l__S = (*) S\l", tooltip="tmp.txt:1:14: 1:14\n"];"#)
+        );
+        assert!(dot.contains(
+            r#"s1 [label="State 1\lS = \"(\" (*) S \")\"\l", tooltip="tmp.txt:1:24: 1:38\n"];"#
+        ));
+        assert!(dot.contains(r#"s0 -> s1 [label="\"(\""];"#));
+        assert!(dot.contains(r#"s0 -> s2 [label="S", style=dashed];"#));
+        assert!(dot.ends_with("}\n"));
+    }
+}
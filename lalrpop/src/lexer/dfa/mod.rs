@@ -1,10 +1,12 @@
 //! Constructs a Dfa which picks the longest matching regular
-//! expression from the input.
+//! expression from the input. If several match, the one with the
+//! highest precedence wins, and among those, the one that comes first.
 
 use crate::collections::Set;
 use crate::kernel_set::{Kernel, KernelSet};
 use crate::lexer::nfa::{self, Nfa, NfaConstructionError, NfaStateIndex, Test};
 use crate::lexer::re;
+use std::cmp::Reverse;
 use std::fmt::{Debug, Display, Error, Formatter};
 use std::rc::Rc;
 
@@ -19,6 +21,10 @@ mod overlap;
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Dfa {
     pub states: Vec<State>,
+
+    /// The regexs that can match the same input with equal precedence,
+    /// sorted and without duplicates.
+    pub ambiguities: Vec<Ambiguity>,
 }
 
 #[allow(clippy::upper_case_acronyms)]
@@ -28,16 +34,20 @@ pub type DFA = Dfa;
 #[derive(Copy, Clone, Debug, PartialOrd, Ord, PartialEq, Eq)]
 pub struct Precedence(pub usize);
 
+/// Either of the two regexs listed could match, and they have equal
+/// precedence. The Dfa accepts `preferred`, which comes first.
+#[derive(Copy, Clone, Debug, PartialOrd, Ord, PartialEq, Eq)]
+pub struct Ambiguity {
+    pub preferred: NfaIndex,
+    pub other: NfaIndex,
+}
+
 #[derive(Debug)]
 pub enum DfaConstructionError {
     NfaConstructionError {
         index: NfaIndex,
         error: NfaConstructionError,
     },
-
-    /// Either of the two regexs listed could match, and they have equal
-    /// priority.
-    Ambiguity { match0: NfaIndex, match1: NfaIndex },
 }
 
 #[deprecated(since = "1.0.0", note = "use `DfaConstructionError` instead")]
@@ -64,8 +74,7 @@ pub fn build_dfa(
         nfas: &nfas,
         precedences: precedences.to_vec(),
     };
-    let dfa = builder.build()?;
-    Ok(dfa)
+    Ok(builder.build())
 }
 
 struct DfaBuilder<'nfa> {
@@ -119,9 +128,10 @@ struct Item {
 const START: DfaStateIndex = DfaStateIndex(0);
 
 impl<'nfa> DfaBuilder<'nfa> {
-    fn build(&self) -> Result<Dfa, DfaConstructionError> {
+    fn build(&self) -> Dfa {
         let mut kernel_set = KernelSet::new();
         let mut states = vec![];
+        let mut ambiguities = Set::new();
 
         let start_state_index = self.start_state(&mut kernel_set);
         assert_eq!(start_state_index, START);
@@ -165,15 +175,19 @@ impl<'nfa> DfaBuilder<'nfa> {
                 // accepts just one Nfa, easy case
                 Kind::Accepts(all_accepts[0].1)
             } else {
-                all_accepts.sort(); // sort regex with higher precedence, well, higher
-                let (best_priority, best_nfa) = all_accepts[all_accepts.len() - 1];
-                let (next_priority, next_nfa) = all_accepts[all_accepts.len() - 2];
-                if best_priority == next_priority {
-                    return Err(DfaConstructionError::Ambiguity {
-                        match0: best_nfa,
-                        match1: next_nfa,
-                    });
-                }
+                // prefer the highest precedence, then the lowest index
+                all_accepts
+                    .sort_by_key(|&(precedence, nfa_index)| (Reverse(precedence), nfa_index));
+                let (best_precedence, best_nfa) = all_accepts[0];
+                ambiguities.extend(
+                    all_accepts[1..]
+                        .iter()
+                        .take_while(|&&(precedence, _)| precedence == best_precedence)
+                        .map(|&(_, other)| Ambiguity {
+                            preferred: best_nfa,
+                            other,
+                        }),
+                );
                 Kind::Accepts(best_nfa)
             };
 
@@ -223,7 +237,10 @@ impl<'nfa> DfaBuilder<'nfa> {
             states.push(state);
         }
 
-        Ok(Dfa { states })
+        Dfa {
+            states,
+            ambiguities: ambiguities.into_iter().collect(),
+        }
     }
 
     fn start_state(&self, kernel_set: &mut DfaKernelSet) -> DfaStateIndex {
@@ -1,5 +1,5 @@
 use crate::lexer::dfa::interpret::interpret;
-use crate::lexer::dfa::{self, Dfa, DfaConstructionError, NfaIndex, Precedence};
+use crate::lexer::dfa::{self, Ambiguity, Dfa, DfaConstructionError, NfaIndex, Precedence};
 use crate::lexer::re;
 
 pub fn dfa(inputs: &[(&str, Precedence)]) -> Result<Dfa, DfaConstructionError> {
@@ -35,11 +35,41 @@ fn tokenizer() {
     assert_eq!(interpret(&dfa, ">>"), Some((NfaIndex(4), ">>")));
 }
 
+#[test]
+#[cfg(feature = "unicode")]
+fn unicode_classes() {
+    let dfa = dfa(&[
+        (r#"\p{Letter}+"#, P0), // 0
+        (r#"\p{Number}+"#, P0), // 1
+        (r#"←"#, P0),           // 2
+    ])
+    .unwrap();
+
+    assert_eq!(interpret(&dfa, "λx←1"), Some((NfaIndex(0), "λx")));
+    assert_eq!(interpret(&dfa, "٤2 λ"), Some((NfaIndex(1), "٤2")));
+    assert_eq!(interpret(&dfa, "←λ"), Some((NfaIndex(2), "←")));
+}
+
 #[test]
 fn ambiguous_regex() {
     // here the keyword and the regex have same precedence, so we have
-    // an ambiguity
-    assert!(dfa(&[(r#"class"#, P0), (r#"[a-zA-Z_][a-zA-Z0-9_]*"#, P0)]).is_err());
+    // an ambiguity, which goes to the first one
+    let dfa = dfa(&[(r#"class"#, P0), (r#"[a-zA-Z_][a-zA-Z0-9_]*"#, P0)]).unwrap();
+    assert_eq!(
+        dfa.ambiguities,
+        vec![Ambiguity {
+            preferred: NfaIndex(0),
+            other: NfaIndex(1),
+        }]
+    );
+    assert_eq!(interpret(&dfa, "class"), Some((NfaIndex(0), "class")));
+    assert_eq!(interpret(&dfa, "classy"), Some((NfaIndex(1), "classy")));
+}
+
+#[test]
+fn unambiguous_regex() {
+    let dfa = dfa(&[(r#"class"#, P1), (r#"[a-zA-Z_][a-zA-Z0-9_]*"#, P0)]).unwrap();
+    assert!(dfa.ambiguities.is_empty());
 }
 
 #[test]
@@ -49,7 +79,8 @@ fn issue_32() {
 
 #[test]
 fn issue_35() {
-    assert!(dfa(&[(r#".*"#, P0), (r"[-+]?[0-9]*\.?[0-9]+", P0)]).is_err());
+    let dfa = dfa(&[(r#".*"#, P0), (r"[-+]?[0-9]*\.?[0-9]+", P0)]).unwrap();
+    assert!(!dfa.ambiguities.is_empty());
 }
 
 #[test]
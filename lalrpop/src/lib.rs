@@ -15,16 +15,23 @@ mod rust;
 mod log;
 
 mod api;
+mod bnf;
 mod build;
+mod codegen;
 mod collections;
+mod conflicts_json;
+mod ebnf;
 mod file_text;
 mod grammar;
+mod graphviz;
 mod kernel_set;
 mod lexer;
+mod lint;
 mod lr1;
 mod message;
 mod normalize;
 mod parser;
+mod railroad;
 mod session;
 mod tls;
 mod tok;
@@ -39,4 +46,7 @@ pub use crate::api::process_root;
 #[allow(deprecated)]
 pub use crate::api::process_root_unconditionally;
 pub use crate::api::Configuration;
+pub use crate::api::{
+    CodegenError, CycleError, Grammar, ParseError, ValidatedGrammar, ValidationError,
+};
 use ascii_canvas::style;
@@ -0,0 +1,277 @@
+//! Lints are warnings about a grammar: the parser can be built, but
+//! part of the grammar is likely not doing what its author meant. Each
+//! kind of lint has a name, and `#[allow(name)]` on the grammar or on
+//! the nonterminal concerned turns it off there.
+
+use crate::codegen::visitor::is_user_name;
+use crate::collections::Set;
+use crate::grammar::consts::{ALLOW, INLINE};
+use crate::grammar::parse_tree as pt;
+use crate::grammar::repr::{Grammar, NonterminalString};
+use crate::lr1::ReducedAlternatives;
+use string_cache::DefaultAtom as Atom;
+
+/// A warning about a grammar.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lint {
+    pub kind: LintKind,
+    /// The byte offsets of the grammar text concerned.
+    pub span: lalrpop_util::Span,
+    pub message: String,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum LintKind {
+    /// Two terminals of the generated lexer can match the same input.
+    AmbiguousTerminals,
+    /// An empty alternative of a nonterminal whose type is not `()` has
+    /// no action code, so it produces `()`.
+    MissingEmptyAction,
+    /// The generated parser never reduces an alternative. This is only
+    /// found once the LR states are built.
+    UnreachableAlternatives,
+    /// A nonterminal cannot be reached from any public nonterminal.
+    UnusedNonterminals,
+}
+
+impl LintKind {
+    pub const ALL: [LintKind; 4] = [
+        LintKind::AmbiguousTerminals,
+        LintKind::MissingEmptyAction,
+        LintKind::UnreachableAlternatives,
+        LintKind::UnusedNonterminals,
+    ];
+
+    /// The name of the lint in `#[allow(name)]`.
+    pub fn name(self) -> &'static str {
+        match self {
+            LintKind::AmbiguousTerminals => "ambiguous_terminals",
+            LintKind::MissingEmptyAction => "missing_empty_action",
+            LintKind::UnreachableAlternatives => "unreachable_alternatives",
+            LintKind::UnusedNonterminals => "unused_nonterminals",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<LintKind> {
+        LintKind::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+/// The lints named by the `#[allow(name)]` annotations among
+/// `annotations`.
+pub(crate) fn allowed_lints(annotations: &[pt::Annotation]) -> Vec<LintKind> {
+    annotations
+        .iter()
+        .filter(|annotation| annotation.id == *ALLOW)
+        .filter_map(|annotation| annotation.arg.as_ref())
+        .filter_map(|(name, _)| LintKind::from_name(name))
+        .collect()
+}
+
+impl Lint {
+    fn new(kind: LintKind, span: pt::Span, message: String) -> Lint {
+        Lint {
+            kind,
+            span: lalrpop_util::Span::new(span.0, span.1),
+            message,
+        }
+    }
+}
+
+impl Grammar {
+    /// The lints found while normalizing the grammar, in the order they
+    /// should be reported, leaving out those that are allowed.
+    pub fn lint(&self) -> Vec<Lint> {
+        let mut lints = vec![];
+        if let Some(intern_token) = &self.intern_token {
+            for ambiguity in &intern_token.ambiguities {
+                lints.push(Lint::new(
+                    LintKind::AmbiguousTerminals,
+                    ambiguity.span,
+                    format!(
+                        "the terminals `{}` and `{}` can match the same input; \
+                         `{}` is preferred because it comes first, use a `match` \
+                         block to choose explicitly",
+                        ambiguity.preferred, ambiguity.other, ambiguity.preferred
+                    ),
+                ));
+            }
+        }
+        // `normalize::epsilon_check` already left out the nonterminals
+        // that allow these, since `#[inline]` ones are gone by now
+        for (nonterminal, span) in &self.epsilon_alternatives {
+            lints.push(Lint::new(
+                LintKind::MissingEmptyAction,
+                *span,
+                format!(
+                    "this empty alternative of `{}` has no action code, so it \
+                     produces `()` instead of a `{}`",
+                    nonterminal,
+                    self.types.nonterminal_type(nonterminal)
+                ),
+            ));
+        }
+        let kind = LintKind::UnusedNonterminals;
+        for nonterminal in self.unused_nonterminals() {
+            if !self.nonterminal_allows(&nonterminal, kind) {
+                lints.push(Lint::new(
+                    kind,
+                    self.nonterminals[&nonterminal].span,
+                    format!(
+                        "the nonterminal `{}` is not used by any public nonterminal",
+                        nonterminal
+                    ),
+                ));
+            }
+        }
+        lints.retain(|lint| !self.allowed_lints.contains(&lint.kind));
+        lints
+    }
+
+    /// The private nonterminals the user wrote that cannot be reached
+    /// from any public nonterminal, in the order of their names. Public
+    /// ones are left out even if `#[cfg]` disabled their parser, and so
+    /// are those of macro expansions, since a macro is only expanded
+    /// where it is used, and `#[inline]` ones, which are no longer
+    /// referenced once they are inlined.
+    fn unused_nonterminals(&self) -> Vec<NonterminalString> {
+        let reachable: Set<_> = self
+            .start_nonterminals
+            .values()
+            .flat_map(|start| self.reachable_from(start))
+            .collect();
+        let inline = Atom::from(INLINE);
+        self.nonterminals
+            .values()
+            .filter(|data| is_user_name(&data.name) && !data.visibility.is_pub())
+            .filter(|data| !reachable.contains(&data.name))
+            .filter(|data| data.annotations.iter().all(|a| a.id != inline))
+            .map(|data| data.name.clone())
+            .collect()
+    }
+
+    /// The lints for the alternatives that no state reduces, given the
+    /// alternatives reduced in the states of all public nonterminals.
+    pub fn lint_unreduced(&self, reduced: &ReducedAlternatives) -> Vec<Lint> {
+        let kind = LintKind::UnreachableAlternatives;
+        if self.allowed_lints.contains(&kind) {
+            return vec![];
+        }
+        // the alternatives of unused nonterminals are never reduced, but
+        // the nonterminals are reported as a whole instead
+        let unused: Set<_> = self.unused_nonterminals().into_iter().collect();
+        reduced
+            .unreduced(self)
+            .into_iter()
+            .filter(|production| !unused.contains(&production.nonterminal))
+            .filter(|production| !self.nonterminal_allows(&production.nonterminal, kind))
+            .map(|production| {
+                Lint::new(
+                    kind,
+                    production.span,
+                    format!(
+                        "this alternative of `{}` is never reduced",
+                        production.nonterminal
+                    ),
+                )
+            })
+            .collect()
+    }
+
+    fn nonterminal_allows(&self, nonterminal: &NonterminalString, kind: LintKind) -> bool {
+        self.nonterminals
+            .get(nonterminal)
+            .is_some_and(|data| allowed_lints(&data.annotations).contains(&kind))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LintKind;
+    use crate::lr1::{self, Lr1Tls, ReducedAlternatives};
+    use crate::test_util::normalized_grammar;
+    use crate::tls::Tls;
+
+    fn lints(text: &str) -> Vec<(LintKind, String)> {
+        let _tls = Tls::test_string(text);
+        let grammar = normalized_grammar(text);
+        let _lr1_tls = Lr1Tls::install(grammar.terminals.clone());
+        let mut reduced = ReducedAlternatives::default();
+        for start in grammar.start_nonterminals.values() {
+            reduced.add(&lr1::build_states(&grammar, start.clone()).unwrap());
+        }
+        grammar
+            .lint()
+            .into_iter()
+            .chain(grammar.lint_unreduced(&reduced))
+            .map(|lint| (lint.kind, lint.message))
+            .collect()
+    }
+
+    #[test]
+    fn names() {
+        for kind in LintKind::ALL {
+            assert_eq!(LintKind::from_name(kind.name()), Some(kind));
+        }
+        assert_eq!(LintKind::from_name("unused"), None);
+    }
+
+    const GRAMMAR: &str = r#"
+    pub A: Vec<u32> = {
+        => ,
+        "x" <C>,
+    };
+    #[allow(unused_nonterminals)]
+    B: Vec<u32> = r"[0-5]" => vec![];
+    #[allow(missing_empty_action)]
+    C: Vec<u32> = {
+        => ,
+        "c" => vec![],
+    };
+    D: () = r"[0-9]" => ();
+"#;
+
+    #[test]
+    fn lints_and_allows() {
+        assert_eq!(
+            lints(&format!("grammar; {}", GRAMMAR)),
+            [
+                (
+                    LintKind::AmbiguousTerminals,
+                    "the terminals `r#\"[0-5]\"#` and `r#\"[0-9]\"#` can match the same \
+                     input; `r#\"[0-5]\"#` is preferred because it comes first, use a \
+                     `match` block to choose explicitly"
+                        .to_string()
+                ),
+                (
+                    LintKind::MissingEmptyAction,
+                    "this empty alternative of `A` has no action code, so it produces `()` \
+                     instead of a `Vec<u32>`"
+                        .to_string()
+                ),
+                // the alternative of `D` is never reduced either, but
+                // `D` is only reported as a whole
+                (
+                    LintKind::UnusedNonterminals,
+                    "the nonterminal `D` is not used by any public nonterminal".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn allow_on_grammar() {
+        let grammar = format!(
+            "#[allow(ambiguous_terminals)] #[allow(unreachable_alternatives)] \
+             #[allow(unused_nonterminals)] grammar; {}",
+            GRAMMAR
+        );
+        assert_eq!(
+            lints(&grammar)
+                .into_iter()
+                .map(|(kind, _)| kind)
+                .collect::<Vec<_>>(),
+            [LintKind::MissingEmptyAction]
+        );
+    }
+}
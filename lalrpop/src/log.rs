@@ -1,3 +1,6 @@
+use std::fmt::{self, Display};
+use std::time::Duration;
+
 #[derive(Clone)]
 pub struct Log {
     level: Level,
@@ -37,6 +40,74 @@ impl Log {
     }
 }
 
+/// How long each phase of processing a grammar took, for `--time`.
+#[derive(Clone, Default)]
+pub struct Timings {
+    // the number of phases begun but not yet completed
+    depth: usize,
+    phases: Vec<PhaseTime>,
+}
+
+#[derive(Clone)]
+struct PhaseTime {
+    depth: usize,
+    name: String,
+    elapsed: Duration,
+}
+
+impl Timings {
+    /// Starts a phase, which is nested in any phase that has not yet
+    /// completed, returning its index.
+    pub fn begin(&mut self, name: String) -> usize {
+        self.phases.push(PhaseTime {
+            depth: self.depth,
+            name,
+            elapsed: Duration::ZERO,
+        });
+        self.depth += 1;
+        self.phases.len() - 1
+    }
+
+    pub fn end(&mut self, index: usize, elapsed: Duration) {
+        self.phases[index].elapsed = elapsed;
+        self.depth = self.phases[index].depth;
+    }
+
+    /// Removes the phases timed so far, returning them as a table in
+    /// the order the phases began.
+    pub fn take(&mut self) -> Timings {
+        self.depth = 0;
+        Timings {
+            depth: 0,
+            phases: std::mem::take(&mut self.phases),
+        }
+    }
+}
+
+impl Display for Timings {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .phases
+            .iter()
+            .map(|phase| 2 * phase.depth + phase.name.len())
+            .max()
+            .unwrap_or(0);
+        for phase in &self.phases {
+            let indent = 2 * phase.depth;
+            writeln!(
+                fmt,
+                "  {:indent$}{:<name_width$}  {:>10.3} ms",
+                "",
+                phase.name,
+                phase.elapsed.as_secs_f64() * 1000.0,
+                indent = indent,
+                name_width = width - indent,
+            )?;
+        }
+        Ok(())
+    }
+}
+
 macro_rules! log {
     ($session:expr, $level:ident, $($args:expr),*) => {
         $session.log(crate::log::Level::$level, || ::std::fmt::format(format_args!($($args),*)))
@@ -52,9 +123,11 @@ macro_rules! debug {
 macro_rules! profile {
     ($session:expr, $phase_name:expr, $action:expr) => {{
         log!($session, Verbose, "Phase `{}` begun", $phase_name);
+        let phase = $session.begin_phase(&$phase_name);
         let time_stamp = ::std::time::Instant::now();
         let result = $action;
         let elapsed = time_stamp.elapsed();
+        $session.end_phase(phase, elapsed);
         log!(
             $session,
             Verbose,
@@ -65,3 +138,28 @@ macro_rules! profile {
         result
     }};
 }
+
+#[cfg(test)]
+mod test {
+    use super::Timings;
+    use std::time::Duration;
+
+    #[test]
+    fn nested_phases() {
+        let mut timings = Timings::default();
+        let outer = timings.begin("Normalization".to_string());
+        let inner = timings.begin("Macro expansion".to_string());
+        timings.end(inner, Duration::from_micros(1500));
+        timings.end(outer, Duration::from_millis(4));
+        let last = timings.begin("Parsing".to_string());
+        timings.end(last, Duration::from_millis(12));
+
+        assert_eq!(
+            timings.take().to_string(),
+            "  Normalization           4.000 ms\n\
+             \x20   Macro expansion       1.500 ms\n\
+             \x20 Parsing                12.000 ms\n"
+        );
+        assert_eq!(timings.take().to_string(), "");
+    }
+}
@@ -8,6 +8,7 @@ use crate::lr1::first;
 use crate::lr1::lane_table::*;
 use crate::lr1::lookahead::*;
 use crate::tls::Tls;
+use std::cmp::Ordering;
 use std::env;
 
 #[cfg(test)]
@@ -49,6 +50,37 @@ pub fn build_lr1_states(grammar: &Grammar, start: NonterminalString) -> Lr1Resul
     }
 }
 
+/// Builds the canonical LR(1) states, bypassing the lane table
+/// algorithm regardless of `LALRPOP_LANE_TABLE`. Requested with the
+/// `#[LR1]` annotation.
+pub fn build_canonical_lr1_states(grammar: &Grammar, start: NonterminalString) -> Lr1Result<'_> {
+    profile! {
+        &Tls::session(),
+        "LR(1) state construction (canonical)",
+        {
+            build_lr1_states_legacy(grammar, start)
+        }
+    }
+}
+
+/// Builds the canonical LR(1) states for an `#[ambiguous]` grammar.
+/// Conflicts are not an error here: every state is constructed, and
+/// the conflicting actions are left for the GLR runtime to explore.
+pub fn build_glr_states(grammar: &Grammar, start: NonterminalString) -> Lr1Result<'_> {
+    profile! {
+        &Tls::session(),
+        "LR(1) state construction (GLR)",
+        {
+            let eof = TokenSet::eof();
+            let lr1: Lr<'_, TokenSet> = Lr::new(grammar, start, eof);
+            match lr1.build_states() {
+                Ok(states) => Ok(states),
+                Err(TableConstructionError { states, .. }) => Ok(states),
+            }
+        }
+    }
+}
+
 pub fn build_lr0_states(
     grammar: &Grammar,
     start: NonterminalString,
@@ -162,7 +194,10 @@ impl<'grammar, L: LookaheadBuild> Lr<'grammar, L> {
                     .push((item.lookahead.clone(), item.production));
             }
 
-            // check for shift-reduce conflicts (reduce-reduce detected above)
+            // settle what we can with `%left` (etc) and `%left_recursive`,
+            // then check for shift-reduce conflicts (reduce-reduce
+            // detected above)
+            L::resolve_precedence(self.grammar, &mut this_state);
             conflicts.extend(L::conflicts(&this_state));
 
             // extract a new state
@@ -325,6 +360,11 @@ pub trait LookaheadBuild: Lookahead {
         remainder: &[Symbol],
         lookahead: &Self,
     ) -> Vec<Item<'grammar, Self>>;
+
+    // Removes the actions of `state` that lose a shift/reduce conflict
+    // according to the precedence and `%left_recursive` declarations of
+    // `grammar`.
+    fn resolve_precedence<'grammar>(grammar: &'grammar Grammar, state: &mut State<'grammar, Self>);
 }
 
 impl LookaheadBuild for Nil {
@@ -336,6 +376,10 @@ impl LookaheadBuild for Nil {
     ) -> Vec<Lr0Item<'grammar>> {
         lr.items(nt, 0, lookahead)
     }
+
+    fn resolve_precedence<'grammar>(_grammar: &'grammar Grammar, _state: &mut Lr0State<'grammar>) {
+        // without lookahead, there is nothing to resolve
+    }
 }
 
 impl LookaheadBuild for TokenSet {
@@ -345,7 +389,137 @@ impl LookaheadBuild for TokenSet {
         remainder: &[Symbol],
         lookahead: &Self,
     ) -> Vec<Lr1Item<'grammar>> {
-        let first_set = lr.first_sets.first1(remainder, lookahead);
+        let mut first_set = lr.first_sets.first1(remainder, lookahead);
+
+        // `(?=X)` can only be reduced when the next token is `X`, and
+        // `(?!X)` only when it is not
+        if let Some(assertion) = lr.grammar.lookahead_assertions.get(nt) {
+            let token = Token::Terminal(assertion.terminal.clone());
+            if assertion.negated {
+                first_set.remove(&token);
+            } else if first_set.contains(&token) {
+                first_set = TokenSet::new();
+                first_set.insert(token);
+            } else {
+                first_set = TokenSet::new();
+            }
+        }
+
         lr.items(nt, 0, &first_set)
     }
+    fn resolve_precedence<'grammar>(grammar: &'grammar Grammar, state: &mut Lr1State<'grammar>) {
+        resolve_declared_precedence(grammar, state);
+        resolve_left_recursion(grammar, state);
+    }
+}
+
+fn resolve_declared_precedence<'grammar>(
+    grammar: &'grammar Grammar,
+    state: &mut Lr1State<'grammar>,
+) {
+    if grammar.precedences.is_empty() {
+        return;
+    }
+
+    let terminals: Vec<TerminalString> = state.shifts.keys().cloned().collect();
+    for terminal in terminals {
+        let shift = match grammar.precedences.get(&terminal) {
+            Some(&entry) => entry,
+            None => continue,
+        };
+        let token = Token::Terminal(terminal.clone());
+
+        // a conflict is only settled if every reduction involved
+        // has a precedence too
+        let resolutions: Option<Vec<(usize, Resolution)>> = state
+            .reductions
+            .iter()
+            .enumerate()
+            .filter(|(_, (tokens, _))| tokens.contains(&token))
+            .map(|(index, &(_, production))| {
+                let reduce = production_precedence(grammar, production)?;
+                let resolution = match reduce.level.cmp(&shift.level) {
+                    Ordering::Greater => Resolution::Reduce,
+                    Ordering::Less => Resolution::Shift,
+                    Ordering::Equal => match shift.assoc {
+                        Associativity::Left => Resolution::Reduce,
+                        Associativity::Right => Resolution::Shift,
+                        Associativity::NonAssoc => Resolution::Error,
+                    },
+                };
+                Some((index, resolution))
+            })
+            .collect();
+
+        for (index, resolution) in resolutions.unwrap_or_default() {
+            if resolution != Resolution::Reduce {
+                state.reductions[index].0.remove(&token);
+            }
+            if resolution != Resolution::Shift {
+                state.shifts.remove(&terminal);
+            }
+        }
+    }
+}
+
+/// For a nonterminal `E` named by `%left_recursive`, settles the
+/// conflicts between reducing an alternative like `E = E "-" E` and
+/// shifting a terminal that continues an `E = E . "+" ...` of the same
+/// state in favor of the reduction, so that `a - b + c` is `(a - b) + c`.
+/// Conflicts that involve any other reduction are left alone.
+fn resolve_left_recursion<'grammar>(grammar: &'grammar Grammar, state: &mut Lr1State<'grammar>) {
+    if grammar.left_recursive.is_empty() {
+        return;
+    }
+
+    let terminals: Vec<TerminalString> = state.shifts.keys().cloned().collect();
+    for terminal in terminals {
+        let token = Token::Terminal(terminal.clone());
+        let mut conflicting = state
+            .reductions
+            .iter()
+            .filter(|(tokens, _)| tokens.contains(&token))
+            .map(|&(_, production)| production)
+            .peekable();
+        if conflicting.peek().is_none() {
+            continue;
+        }
+
+        let resolved = conflicting.all(|production| {
+            let nt = Symbol::Nonterminal(production.nonterminal.clone());
+            grammar.left_recursive.contains(&production.nonterminal)
+                && production.symbols.len() > 1
+                && production.symbols.first() == Some(&nt)
+                && production.symbols.last() == Some(&nt)
+                && state.items.vec.iter().any(|item| {
+                    item.production.nonterminal == production.nonterminal
+                        && item.index == 1
+                        && item.production.symbols[0] == nt
+                        && item.production.symbols[1] == Symbol::Terminal(terminal.clone())
+                })
+        });
+        if resolved {
+            state.shifts.remove(&terminal);
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Resolution {
+    Shift,
+    Reduce,
+    Error,
+}
+
+/// As in yacc, a production takes the precedence of the last terminal
+/// in it that has one.
+fn production_precedence(grammar: &Grammar, production: &Production) -> Option<PrecedenceEntry> {
+    production
+        .symbols
+        .iter()
+        .rev()
+        .find_map(|symbol| match symbol {
+            Symbol::Terminal(terminal) => grammar.precedences.get(terminal).copied(),
+            Symbol::Nonterminal(_) => None,
+        })
 }
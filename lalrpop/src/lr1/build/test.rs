@@ -354,3 +354,183 @@ pub Query = SELECT r"[a-z]+";
     let states = build_lr0_states(&grammar, nt("Query")).expect("build states");
     println!("states: {:?}", states);
 }
+
+#[test]
+fn precedence_declarations() {
+    let _tls = Tls::test();
+
+    let grammar = normalized_grammar(
+        r#"
+grammar;
+extern { enum Tok { "N" => .., "-" => .., "*" => .., "^" => .., "==" => .. } }
+
+%nonassoc "==";
+%left "-";
+%left "*";
+%right "^";
+
+S: () = E;
+
+E: () = {
+    E "==" E,
+    E "-" E,
+    E "*" E,
+    E "^" E,
+    "N",
+};
+"#,
+    );
+    let _lr1_tls = Lr1Tls::install(grammar.terminals.clone());
+
+    let level = |name: &str| {
+        Symbol::Terminal(TerminalString::quoted(Atom::from(name))).precedence_level(&grammar)
+    };
+    assert_eq!(level("=="), Some(1));
+    assert_eq!(level("*"), Some(3));
+    assert_eq!(level("^"), Some(4));
+    assert_eq!(level("N"), None);
+    assert_eq!(
+        Symbol::Nonterminal(nt("E")).precedence_level(&grammar),
+        None
+    );
+
+    let states = super::build_lr1_states_legacy(&grammar, nt("S")).unwrap();
+
+    let tree = interpret(&states, tokens!["N", "-", "N", "*", "N", "-", "N"]).unwrap();
+    assert_eq!(
+        &format!("{}", tree)[..],
+        r#"[S: [E: [E: [E: "N"], "-", [E: [E: "N"], "*", [E: "N"]]], "-", [E: "N"]]]"#
+    );
+
+    let tree = interpret(&states, tokens!["N", "^", "N", "^", "N", "*", "N"]).unwrap();
+    assert_eq!(
+        &format!("{}", tree)[..],
+        r#"[S: [E: [E: [E: "N"], "^", [E: [E: "N"], "^", [E: "N"]]], "*", [E: "N"]]]"#
+    );
+
+    assert!(interpret(&states, tokens!["N", "==", "N"]).is_ok());
+    assert!(interpret(&states, tokens!["N", "==", "N", "==", "N"]).is_err());
+}
+
+#[test]
+fn precedence_declarations_leave_other_conflicts() {
+    let _tls = Tls::test();
+
+    // `"-"` has no declared precedence, so `E "-" E` is still ambiguous
+    let grammar = normalized_grammar(
+        r#"
+grammar;
+extern { enum Tok { "N" => .., "-" => .., "*" => .. } }
+
+%left "*";
+
+S: () = E;
+
+E: () = {
+    E "-" E,
+    E "*" E,
+    "N",
+};
+"#,
+    );
+    let _lr1_tls = Lr1Tls::install(grammar.terminals.clone());
+
+    assert!(super::build_lr1_states_legacy(&grammar, nt("S")).is_err());
+}
+
+#[test]
+fn left_recursive_declaration() {
+    let _tls = Tls::test();
+
+    let grammar = normalized_grammar(
+        r#"
+grammar;
+extern { enum Tok { "N" => .., "-" => .., "*" => .., "(" => .., ")" => .. } }
+
+%left_recursive E;
+
+S: () = E;
+
+E: () = {
+    E "-" E,
+    E "*" E,
+    "(" E ")",
+    "N",
+};
+"#,
+    );
+    let _lr1_tls = Lr1Tls::install(grammar.terminals.clone());
+
+    let states = super::build_lr1_states_legacy(&grammar, nt("S")).unwrap();
+
+    // every operator groups to the left, with the same precedence
+    let tree = interpret(&states, tokens!["N", "-", "N", "*", "N", "-", "N"]).unwrap();
+    assert_eq!(
+        &format!("{}", tree)[..],
+        r#"[S: [E: [E: [E: [E: "N"], "-", [E: "N"]], "*", [E: "N"]], "-", [E: "N"]]]"#
+    );
+}
+
+#[test]
+fn left_recursive_declaration_leaves_other_conflicts() {
+    let _tls = Tls::test();
+
+    // `"-" E` is not left-recursive, so `- N - N` is still ambiguous
+    let grammar = normalized_grammar(
+        r#"
+grammar;
+extern { enum Tok { "N" => .., "-" => .. } }
+
+%left_recursive E;
+
+S: () = E;
+
+E: () = {
+    E "-" E,
+    "-" E,
+    "N",
+};
+"#,
+    );
+    let _lr1_tls = Lr1Tls::install(grammar.terminals.clone());
+
+    assert!(super::build_lr1_states_legacy(&grammar, nt("S")).is_err());
+}
+
+#[test]
+fn canonical_lr1_annotation() {
+    let _tls = Tls::test();
+
+    // LR(1) but not LALR(1); the lane table cannot build it either
+    let grammar = normalized_grammar(
+        r#"
+#[LR1]
+grammar;
+extern { enum Tok { "a" => .., "b" => .., "c" => .., "d" => .., "e" => .. } }
+
+pub S: () = {
+    "a" E "c",
+    "a" F "d",
+    "b" F "c",
+    "b" E "d",
+};
+
+E: () = "e";
+
+F: () = "e";
+"#,
+    );
+    let _lr1_tls = Lr1Tls::install(grammar.terminals.clone());
+    let states = crate::lr1::build_states(&grammar, nt("__S")).unwrap();
+    random_test(&grammar, &states, nt("__S"));
+
+    // `#[LALR]` only collapses the states when the lane table is
+    // disabled; collapsing them here merges the states after `"a" "e"`
+    // and `"b" "e"`, making `E` and `F` reducible on the same lookahead
+    let error = crate::lr1::build_lalr::collapse_to_lalr_states(&states).unwrap_err();
+    assert!(!error.conflicts.is_empty());
+    assert!(error
+        .conflicts
+        .iter()
+        .all(|conflict| matches!(conflict.action, Action::Reduce(_))));
+}
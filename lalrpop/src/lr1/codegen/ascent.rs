@@ -2,7 +2,7 @@
 //!
 //! [recursive ascent]: https://en.wikipedia.org/wiki/Recursive_ascent_parser
 
-use crate::collections::Multimap;
+use crate::collections::{Map, Multimap};
 use crate::grammar::repr::{
     Grammar, NonterminalString, Production, Symbol, TerminalString, TypeParameter, TypeRepr,
     Visibility, WhereClause,
@@ -14,6 +14,7 @@ use crate::rust::RustWrite;
 use crate::tls::Tls;
 use crate::util::{Escape, Sep};
 use std::io::{self, Write};
+use string_cache::DefaultAtom as Atom;
 
 use super::base::CodeGenerator;
 
@@ -49,6 +50,10 @@ struct RecursiveAscent<'ascent, 'grammar> {
     nonterminal_type_params: Vec<TypeParameter>,
 
     nonterminal_where_clauses: Vec<WhereClause>,
+
+    /// the names of `@x:X` symbols, keyed by the state and terminal
+    /// that start them
+    doc_names: Map<(usize, TerminalString), Atom>,
 }
 
 /// Tracks the suffix of the stack (that is, top-most elements) that any
@@ -135,7 +140,7 @@ impl<'ascent, 'grammar, W: Write>
             .map(|state| Self::state_input_for(state))
             .collect();
 
-        CodeGenerator::new(
+        let mut ascent = CodeGenerator::new(
             grammar,
             user_start_symbol,
             start_symbol,
@@ -148,8 +153,11 @@ impl<'ascent, 'grammar, W: Write>
                 state_inputs,
                 nonterminal_type_params,
                 nonterminal_where_clauses,
+                doc_names: Map::new(),
             },
-        )
+        );
+        ascent.custom.doc_names = ascent.doc_names();
+        ascent
     }
 
     /// Compute the stack suffix that the state expects on entry.
@@ -378,7 +386,15 @@ impl<'ascent, 'grammar, W: Write>
 
         rust!(self.out, "let {}expected = alloc::vec![", self.prefix);
         for terminal in successful_terminals {
-            rust!(self.out, "r###\"{}\"###.to_string(),", terminal);
+            match self.custom.doc_names.get(&(this_index.0, terminal.clone())) {
+                Some(name) => rust!(
+                    self.out,
+                    "r###\"{} (called {})\"###.to_string(),",
+                    terminal,
+                    name
+                ),
+                None => rust!(self.out, "r###\"{}\"###.to_string(),", terminal),
+            }
         }
         rust!(self.out, "];");
 
@@ -888,13 +904,26 @@ impl<'ascent, 'grammar, W: Write>
             args.push(format!("&{}end", self.prefix));
         }
 
-        // invoke the action code
+        // invoke the action code; a `()` value is not bound to a name
+        let is_unit = self
+            .grammar
+            .types
+            .nonterminal_type(&production.nonterminal)
+            .is_unit();
+        let (binding, value) = if is_unit {
+            (String::new(), "()".to_string())
+        } else {
+            (
+                format!("let {}nt = ", self.prefix),
+                format!("{}nt", self.prefix),
+            )
+        };
         let is_fallible = self.grammar.action_is_fallible(production.action);
         if is_fallible {
             rust!(
                 self.out,
-                "let {}nt = {}::{}action{}::<{}>({}{})?;",
-                self.prefix,
+                "{}{}::{}action{}::<{}>({}{})?;",
+                binding,
                 self.action_module,
                 self.prefix,
                 production.action.index(),
@@ -905,8 +934,8 @@ impl<'ascent, 'grammar, W: Write>
         } else {
             rust!(
                 self.out,
-                "let {}nt = {}::{}action{}::<{}>({}{});",
-                self.prefix,
+                "{}{}::{}action{}::<{}>({}{});",
+                binding,
                 self.action_module,
                 self.prefix,
                 production.action.index(),
@@ -925,7 +954,7 @@ impl<'ascent, 'grammar, W: Write>
             Escape(&production.nonterminal)
         );
         rust!(self.out, "{}start,", self.prefix);
-        rust!(self.out, "{}nt,", self.prefix);
+        rust!(self.out, "{},", value);
         rust!(self.out, "{}end,", self.prefix);
         rust!(self.out, "));");
 
@@ -1,12 +1,13 @@
 //! Base helper routines for a code generator.
 
-use crate::collections::Set;
+use crate::collections::{Map, Set};
 use crate::grammar::free_variables::FreeVariables;
 use crate::grammar::repr::*;
 use crate::lr1::core::*;
 use crate::rust::RustWrite;
 use crate::util::Sep;
 use std::io::{self, Write};
+use string_cache::DefaultAtom as Atom;
 
 /// Base struct for various kinds of code generator. The flavor of
 /// code generator is customized by supplying distinct types for `C`
@@ -69,6 +70,48 @@ impl<'codegen, 'grammar, W: Write, C> CodeGenerator<'codegen, 'grammar, W, C> {
         }
     }
 
+    /// Whether any symbol of the grammar is named with `@x:X`.
+    pub fn uses_doc_names(&self) -> bool {
+        self.grammar
+            .nonterminals
+            .values()
+            .flat_map(|data| &data.productions)
+            .any(|production| !production.doc_names.is_empty())
+    }
+
+    /// The name of the symbol each terminal starts in each state, for
+    /// the terminals that start a symbol named with `@x:X`. The parser
+    /// adds it to the terminal in the tokens it expects.
+    pub fn doc_names(&self) -> Map<(usize, TerminalString), Atom> {
+        let first_sets = self.grammar.first_sets();
+        let mut doc_names = Map::new();
+        for (index, state) in self.states.iter().enumerate() {
+            for item in &state.items.vec {
+                let Some((_, name)) = item
+                    .production
+                    .doc_names
+                    .iter()
+                    .find(|&&(symbol, _)| symbol == item.index)
+                else {
+                    continue;
+                };
+                let terminals: Vec<&TerminalString> = match item.production.symbols[item.index] {
+                    Symbol::Terminal(ref terminal) => vec![terminal],
+                    Symbol::Nonterminal(ref nonterminal) => {
+                        first_sets.get(nonterminal).into_iter().flatten().collect()
+                    }
+                };
+                for terminal in terminals {
+                    // the first name wins, if several symbols start with the terminal
+                    doc_names
+                        .entry((index, terminal.clone()))
+                        .or_insert_with(|| name.clone());
+                }
+            }
+        }
+        doc_names
+    }
+
     /// We often create meta types that pull together a bunch of
     /// user-given types -- basically describing (e.g.) the full set
     /// of return values from any nonterminal (and, in some cases,
@@ -159,6 +202,10 @@ impl<'codegen, 'grammar, W: Write, C> CodeGenerator<'codegen, 'grammar, W, C> {
     {
         rust!(self.out, "");
         rust!(self.out, "#[rustfmt::skip]");
+        if self.deprecated_attribute().is_some() {
+            // the parser is used in the module that defines it
+            rust!(self.out, "#[allow(deprecated)]");
+        }
         rust!(
             self.out,
             "#[allow(non_snake_case, non_camel_case_types, unused_mut, unused_variables, \
@@ -175,6 +222,25 @@ impl<'codegen, 'grammar, W: Write, C> CodeGenerator<'codegen, 'grammar, W, C> {
         Ok(())
     }
 
+    /// The `#[deprecated(...)]` attribute of the public nonterminal this
+    /// parser is for, if any.
+    pub fn deprecated_attribute(&self) -> Option<String> {
+        self.grammar.nonterminals[&self.user_start_symbol].deprecated_attribute()
+    }
+
+    /// The visibility of the parser, for the items in its
+    /// `__parse__X` module rather than next to the nonterminal: one
+    /// module deeper for each `super` of the path back to the grammar's
+    /// module, the `action_module`.
+    pub fn parser_visibility(&self) -> Visibility {
+        self.action_module.split("::").fold(
+            self.grammar.nonterminals[&self.start_symbol]
+                .visibility
+                .clone(),
+            |visibility, _| visibility.nested(),
+        )
+    }
+
     pub fn write_uses(&mut self) -> io::Result<()> {
         self.out
             .write_uses(&format!("{}::", self.action_module), self.grammar)?;
@@ -234,10 +300,13 @@ impl<'codegen, 'grammar, W: Write, C> CodeGenerator<'codegen, 'grammar, W, C> {
             }
         }
 
+        if let Some(attribute) = self.deprecated_attribute() {
+            rust!(self.out, "{}", attribute);
+        }
         rust!(
             self.out,
             "{}struct {}Parser {{",
-            self.grammar.nonterminals[&self.start_symbol].visibility,
+            self.parser_visibility(),
             self.user_start_symbol
         );
         if intern_token {
@@ -263,7 +332,7 @@ impl<'codegen, 'grammar, W: Write, C> CodeGenerator<'codegen, 'grammar, W, C> {
         rust!(
             self.out,
             "{}fn new() -> {}Parser {{",
-            self.grammar.nonterminals[&self.start_symbol].visibility,
+            self.parser_visibility(),
             self.user_start_symbol
         );
         if intern_token {
@@ -285,19 +354,27 @@ impl<'codegen, 'grammar, W: Write, C> CodeGenerator<'codegen, 'grammar, W, C> {
 
         rust!(self.out, "#[allow(dead_code)]");
         self.out
-            .fn_header(
-                &self.grammar.nonterminals[&self.start_symbol].visibility,
-                "parse".to_owned(),
-            )
+            .fn_header(&self.parser_visibility(), "parse".to_owned())
             .with_parameters(Some("&self".to_owned()))
             .with_grammar(self.grammar)
             .with_type_parameters(type_parameters)
             .with_parameters(parameters)
-            .with_return_type(format!(
-                "Result<{}, {}>",
-                self.types.nonterminal_type(&self.start_symbol),
-                parse_error_type
-            ))
+            .with_return_type(
+                if self.grammar.algorithm.construction == LrConstruction::Glr {
+                    // an ambiguous grammar yields every successful parse
+                    format!(
+                        "Result<alloc::vec::Vec<{}>, {}>",
+                        self.types.nonterminal_type(&self.start_symbol),
+                        parse_error_type
+                    )
+                } else {
+                    format!(
+                        "Result<{}, {}>",
+                        self.types.nonterminal_type(&self.start_symbol),
+                        parse_error_type
+                    )
+                },
+            )
             .with_where_clauses(where_clauses)
             .emit()?;
         rust!(self.out, "{{");
@@ -11,6 +11,7 @@ use itertools::Itertools;
 use std::fmt;
 use std::io::{self, Write};
 use std::rc::Rc;
+use string_cache::DefaultAtom as Atom;
 
 use super::base::CodeGenerator;
 
@@ -149,6 +150,18 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
             this.write_token_to_symbol_fn()?;
             this.write_simulate_reduce_fn()?;
             this.write_parser_fn()?;
+            if this.grammar.algorithm.construction != LrConstruction::Glr {
+                this.write_parse_partial_fn()?;
+            }
+            if this.grammar.incremental {
+                this.write_incremental_parser_fn()?;
+            }
+            if this.grammar.async_parser {
+                this.write_async_parser_fn()?;
+            }
+            if Tls::session().serialize_tables {
+                this.write_tables_fn()?;
+            }
             this.write_accepts_fn()?;
             this.emit_reduce_actions()?;
             this.emit_downcast_fns()?;
@@ -278,6 +291,22 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
         rust!(self.out, "{p}EOF_ACTION[state as usize]", p = self.prefix,);
         rust!(self.out, "}}");
 
+        if self.grammar.algorithm.construction == LrConstruction::Glr {
+            rust!(self.out, "");
+            rust!(self.out, "#[inline]");
+            rust!(
+                self.out,
+                "fn conflicting_actions(&self, state: {state_type}, integer: Option<usize>) -> &[{state_type}] {{",
+                state_type = state_type,
+            );
+            rust!(
+                self.out,
+                "{p}conflicting_actions(state, integer)",
+                p = self.prefix
+            );
+            rust!(self.out, "}}");
+        }
+
         rust!(self.out, "");
         rust!(self.out, "#[inline]");
         rust!(
@@ -324,6 +353,18 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
         );
         rust!(self.out, "}}");
 
+        if !self.cut_contexts().is_empty() {
+            rust!(self.out, "");
+            rust!(self.out, "#[inline]");
+            rust!(
+                self.out,
+                "fn cut_context(&self, state: {state_type}) -> Option<&'static str> {{",
+                state_type = state_type,
+            );
+            rust!(self.out, "{p}cut_context(state)", p = self.prefix);
+            rust!(self.out, "}}");
+        }
+
         rust!(self.out, "");
         rust!(self.out, "#[inline]");
         rust!(self.out, "fn uses_error_recovery(&self) -> bool {{");
@@ -364,12 +405,13 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
         rust!(self.out, "start_location: Option<&Self::Location>,");
         rust!(
             self.out,
-            "states: &mut alloc::vec::Vec<{state_type}>,",
+            "states: &mut impl {p}state_machine::Stack<{state_type}>,",
+            p = self.prefix,
             state_type = state_type
         );
         rust!(
             self.out,
-            "symbols: &mut alloc::vec::Vec<{p}state_machine::SymbolTriple<Self>>,",
+            "symbols: &mut impl {p}state_machine::Stack<{p}state_machine::SymbolTriple<Self>>,",
             p = self.prefix,
         );
         rust!(
@@ -413,6 +455,10 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
         // sometimes some of the variants are not used, particularly
         // if we are generating multiple parsers from the same file:
         rust!(self.out, "#[allow(dead_code)]");
+        if self.grammar.algorithm.construction == LrConstruction::Glr {
+            // the GLR parser copies its stacks when it forks
+            rust!(self.out, "#[derive(Clone)]");
+        }
         rust!(
             self.out,
             "pub(crate) enum {}Symbol<{}>",
@@ -475,6 +521,8 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
     fn write_parse_table(&mut self) -> io::Result<()> {
         let state_type = self.custom.state_type;
 
+        self.write_table_sizes()?;
+
         // The table is a two-dimensional matrix indexed first by state
         // and then by the terminal index. The value is described above.
         rust!(
@@ -545,6 +593,10 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
         }
         rust!(self.out, "];");
 
+        if self.grammar.algorithm.construction == LrConstruction::Glr {
+            self.write_conflicting_actions_fn()?;
+        }
+
         rust!(
             self.out,
             "fn {}goto(state: {state_type}, nt: usize) -> {state_type} {{",
@@ -572,6 +624,8 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
 
         rust!(self.out, "}}");
 
+        self.write_cut_context_fn()?;
+        self.write_doc_name_fn()?;
         self.emit_terminal_repr_list()?;
         self.emit_expected_tokens_fn()?;
         self.emit_expected_tokens_from_states_fn()?;
@@ -579,6 +633,220 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
         Ok(())
     }
 
+    /// Emits the sizes of the tables below as constants, so that they
+    /// can be tracked (e.g. by grepping the generated file in CI). The
+    /// action tables are the `ACTION` and `EOF_ACTION` arrays; the goto
+    /// table is compiled into a `match`, so its size is that of the
+    /// targets of its non-error entries.
+    fn write_table_sizes(&mut self) -> io::Result<()> {
+        let entry_bytes = match self.custom.state_type {
+            "i8" => 1,
+            "i16" => 2,
+            _ => 4,
+        };
+        let states = self.states.len();
+        let terminals = self.grammar.terminals.all.len();
+        let gotos: usize = self.states.iter().map(|state| state.gotos.len()).sum();
+
+        for (name, value) in [
+            ("TABLE_STATES", states),
+            ("TABLE_TERMINALS", terminals),
+            (
+                "ACTION_TABLE_BYTES",
+                (states * terminals + states) * entry_bytes,
+            ),
+            ("GOTO_TABLE_BYTES", gotos * entry_bytes),
+        ] {
+            rust!(self.out, "#[allow(dead_code)]");
+            rust!(
+                self.out,
+                "const {}{}: usize = {};",
+                self.prefix,
+                name,
+                value
+            );
+        }
+        Ok(())
+    }
+
+    /// For ambiguous grammars, the `ACTION` and `EOF_ACTION` tables
+    /// hold only the first of the possible actions; the others are
+    /// looked up with `__conflicting_actions`. Shifts come before
+    /// reductions, matching the choice made in the tables.
+    fn write_conflicting_actions_fn(&mut self) -> io::Result<()> {
+        let state_type = self.custom.state_type;
+
+        rust!(
+            self.out,
+            "fn {p}conflicting_actions(state: {state_type}, integer: Option<usize>) -> &'static [{state_type}] {{",
+            p = self.prefix,
+            state_type = state_type,
+        );
+        rust!(self.out, "match (state, integer) {{");
+        for (index, state) in self.states.iter().enumerate() {
+            let terminals = self
+                .grammar
+                .terminals
+                .all
+                .iter()
+                .enumerate()
+                .map(|(i, t)| (Some(i), Token::Terminal(t.clone())))
+                .chain(Some((None, Token::Eof)));
+            for (integer, token) in terminals {
+                let shift = match token {
+                    Token::Terminal(ref t) => state.shifts.get(t).map(|s| s.0 as i32 + 1),
+                    _ => None,
+                };
+                let actions: Vec<i32> = shift
+                    .into_iter()
+                    .chain(
+                        state
+                            .reductions
+                            .iter()
+                            .filter(|&(t, _)| t.contains(&token))
+                            .map(|&(_, p)| -(self.custom.reduce_indices[p] as i32 + 1)),
+                    )
+                    .skip(1)
+                    .collect();
+                if actions.is_empty() {
+                    continue;
+                }
+                let integer = match integer {
+                    Some(i) => format!("Some({})", i),
+                    None => "None".to_string(),
+                };
+                rust!(
+                    self.out,
+                    "({}, {}) => &[{}],",
+                    index,
+                    integer,
+                    Sep(", ", &actions)
+                );
+            }
+        }
+        rust!(self.out, "_ => &[],");
+        rust!(self.out, "}}");
+        rust!(self.out, "}}");
+
+        Ok(())
+    }
+
+    /// The states that are past the cut (`^`) of one of their items,
+    /// grouped by the symbols before the cut. When an item of a state has
+    /// a cut, it is a kernel item (cuts are never first), so the parser is
+    /// certainly in its alternative.
+    fn cut_contexts(&self) -> Vec<(String, Vec<usize>)> {
+        let mut contexts: Map<String, Vec<usize>> = Map::new();
+        for (index, state) in self.states.iter().enumerate() {
+            let context = state
+                .items
+                .vec
+                .iter()
+                .find_map(|item| match item.production.cut {
+                    Some(cut) if item.index >= cut => {
+                        Some(item.production.symbols[..cut].iter().join(" "))
+                    }
+                    _ => None,
+                });
+            if let Some(context) = context {
+                contexts.entry(context).or_default().push(index);
+            }
+        }
+        contexts.into_iter().collect()
+    }
+
+    /// For grammars with cuts, `__cut_context` gives the symbols before
+    /// the cut of the states that are past one, for error messages.
+    fn write_cut_context_fn(&mut self) -> io::Result<()> {
+        let contexts = self.cut_contexts();
+        if contexts.is_empty() {
+            return Ok(());
+        }
+
+        rust!(
+            self.out,
+            "fn {p}cut_context(state: {state_type}) -> Option<&'static str> {{",
+            p = self.prefix,
+            state_type = self.custom.state_type,
+        );
+        rust!(self.out, "match state {{");
+        for (context, states) in contexts {
+            rust!(
+                self.out,
+                "{} => Some(r###\"{}\"###),",
+                Sep(" | ", &states),
+                context
+            );
+        }
+        rust!(self.out, "_ => None,");
+        rust!(self.out, "}}");
+        rust!(self.out, "}}");
+
+        Ok(())
+    }
+
+    /// For grammars with `@x:X`, `__doc_name` gives the name of the
+    /// symbol a terminal starts in a state, if it starts a named one, for
+    /// the expected tokens of error messages.
+    fn write_doc_name_fn(&mut self) -> io::Result<()> {
+        if !self.uses_doc_names() {
+            return Ok(());
+        }
+
+        rust!(
+            self.out,
+            "fn {p}doc_name(state: {state_type}, terminal: usize) -> Option<&'static str> {{",
+            p = self.prefix,
+            state_type = self.custom.state_type,
+        );
+        // the terminals are given as their index in `__TERMINAL`
+        let mut patterns: Map<Atom, Vec<String>> = Map::new();
+        for ((state, terminal), name) in self.doc_names() {
+            if let Some(terminal) = self
+                .grammar
+                .terminals
+                .all
+                .iter()
+                .position(|t| *t == terminal)
+            {
+                patterns
+                    .entry(name)
+                    .or_default()
+                    .push(format!("({}, {})", state, terminal));
+            }
+        }
+        rust!(self.out, "match (state, terminal) {{");
+        for (name, patterns) in patterns {
+            rust!(
+                self.out,
+                "{} => Some(r###\"{}\"###),",
+                Sep(" | ", &patterns),
+                name
+            );
+        }
+        rust!(self.out, "_ => None,");
+        rust!(self.out, "}}");
+        rust!(self.out, "}}");
+
+        Ok(())
+    }
+
+    /// The expression for the expected token `terminal`, with the name
+    /// of the symbol it starts in `state`, if any.
+    fn expected_token_expr(&self, state: &str) -> String {
+        if self.uses_doc_names() {
+            format!(
+                "match {p}doc_name({state}, index) {{ \
+                 Some(name) => alloc::format!(\"{{}} (called {{}})\", terminal, name), \
+                 None => alloc::string::ToString::to_string(terminal), }}",
+                p = self.prefix,
+                state = state,
+            )
+        } else {
+            "alloc::string::ToString::to_string(terminal)".to_string()
+        }
+    }
+
     fn emit_goto_match<'a, 'k, K: 'k, K2: 'k, T>(
         out: &mut RustWrite<W>,
         k_name: &str,
@@ -710,11 +978,31 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
 
         self.define_tokens()?;
 
+        let (driver, drive) = if self.grammar.algorithm.construction == LrConstruction::Glr {
+            ("GlrParser", "drive".to_string())
+        } else if let Some(stack_depth) = self.grammar.stack_depth {
+            (
+                "Parser",
+                format!("drive_with_stack_depth::<{}>", stack_depth),
+            )
+        } else {
+            ("Parser", "drive".to_string())
+        };
         rust!(
             self.out,
-            "{p}state_machine::Parser::drive(",
+            "{p}state_machine::{driver}::{drive}(",
             p = self.prefix,
+            driver = driver,
+            drive = drive,
         );
+        self.write_state_machine_expr(&phantom_data_expr)?;
+        rust!(self.out, "{p}tokens,", p = self.prefix);
+        rust!(self.out, ")");
+
+        self.end_parser_fn()
+    }
+
+    fn write_state_machine_expr(&mut self, phantom_data_expr: &str) -> io::Result<()> {
         rust!(self.out, "{p}StateMachine {{", p = self.prefix);
         for Parameter { name, .. } in &self.grammar.parameters {
             rust!(self.out, "{},", name);
@@ -726,10 +1014,463 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
             phantom = phantom_data_expr,
         );
         rust!(self.out, "}},");
+        Ok(())
+    }
+
+    /// Writes `parse_partial`, which parses as much of the input as it
+    /// can, and also returns the rest of the input: for a generated
+    /// tokenizer, the text from the token it stopped at, otherwise the
+    /// token it stopped at and the iterator of the tokens after it.
+    fn write_parse_partial_fn(&mut self) -> io::Result<()> {
+        let phantom_data_expr = self.phantom_data_expr();
+        let visibility = &self.parser_visibility();
+        let start_type = self.types.nonterminal_type(&self.start_symbol);
+        let parse_error_type = self.types.parse_error_type();
+        let intern_token = self.grammar.intern_token.is_some();
+
+        let (type_parameters, parameters, rest_type);
+        if intern_token {
+            type_parameters = vec![];
+            parameters = vec![];
+            rest_type = "&'input str".to_string();
+        } else {
+            let mut user_type_parameters = String::new();
+            for type_parameter in &self.grammar.type_parameters {
+                user_type_parameters.push_str(&format!("{}, ", type_parameter));
+            }
+            type_parameters = vec![
+                format!(
+                    "{}TOKEN: {}ToTriple<{}>",
+                    self.prefix, self.prefix, user_type_parameters,
+                ),
+                format!(
+                    "{}TOKENS: IntoIterator<Item={}TOKEN>",
+                    self.prefix, self.prefix
+                ),
+            ];
+            parameters = vec![format!("{}tokens0: {}TOKENS", self.prefix, self.prefix)];
+            rest_type = format!(
+                "Option<{}>, <{}TOKENS as IntoIterator>::IntoIter",
+                self.types.triple_type(),
+                self.prefix
+            );
+        }
+
+        rust!(self.out, "#[allow(dead_code)]");
+        rust!(self.out, "impl {}Parser {{", self.user_start_symbol);
+        self.out
+            .fn_header(visibility, "parse_partial".to_owned())
+            .with_parameters(Some("&self".to_owned()))
+            .with_grammar(self.grammar)
+            .with_type_parameters(type_parameters)
+            .with_parameters(parameters)
+            .with_return_type(format!(
+                "Result<({}, {}), {}>",
+                start_type, rest_type, parse_error_type
+            ))
+            .emit()?;
+        rust!(self.out, "{{");
+
+        if intern_token {
+            rust!(
+                self.out,
+                "let mut {}tokens = self.builder.matcher(input);",
+                self.prefix
+            );
+        } else {
+            rust!(
+                self.out,
+                "let mut {p}tokens0 = {p}tokens0.into_iter();",
+                p = self.prefix
+            );
+            rust!(
+                self.out,
+                "let mut {p}tokens = {p}tokens0.by_ref().map(|t| {p}ToTriple::to_triple(t));",
+                p = self.prefix
+            );
+        }
+        let drive = match self.grammar.stack_depth {
+            Some(stack_depth) => format!("drive_partial_with_stack_depth::<{}>", stack_depth),
+            None => "drive_partial".to_string(),
+        };
+        rust!(
+            self.out,
+            "let ({p}result, {p}stopped_at) = {p}state_machine::Parser::{drive}(",
+            p = self.prefix,
+            drive = drive,
+        );
+        self.write_state_machine_expr(&phantom_data_expr)?;
         rust!(self.out, "{p}tokens,", p = self.prefix);
+        rust!(self.out, ")?;");
+        if intern_token {
+            rust!(
+                self.out,
+                "let {p}rest = match {p}stopped_at {{",
+                p = self.prefix
+            );
+            rust!(
+                self.out,
+                "Some(({p}start, _, _)) => &input[{p}start..],",
+                p = self.prefix
+            );
+            rust!(self.out, "None => &input[input.len()..],");
+            rust!(self.out, "}};");
+            rust!(self.out, "Ok(({p}result, {p}rest))", p = self.prefix);
+        } else {
+            rust!(
+                self.out,
+                "Ok(({p}result, {p}stopped_at, {p}tokens0))",
+                p = self.prefix
+            );
+        }
+        rust!(self.out, "}}"); // fn
+        rust!(self.out, "}}"); // impl
+        rust!(self.out, "");
+        Ok(())
+    }
+
+    /// Writes the `IncrementalParser` of an `#[incremental]` grammar,
+    /// which drives the same state machine as the `Parser`, but builds a
+    /// `ParseTree` instead of running the actions.
+    fn write_incremental_parser_fn(&mut self) -> io::Result<()> {
+        let visibility = &self.parser_visibility();
+        let tree_type = format!("{}lalrpop_util::incremental::ParseTree", self.prefix);
+        let return_type = format!("Result<{}, {}>", tree_type, self.types.parse_error_type());
+
+        let nonterminals: Vec<_> = self
+            .custom
+            .all_nonterminals
+            .iter()
+            .map(|nt| format!("{:?}", nt.to_string()))
+            .collect();
+        rust!(
+            self.out,
+            "const {p}NONTERMINALS: &[&str] = &[{}];",
+            Sep(", ", &nonterminals),
+            p = self.prefix,
+        );
+        rust!(self.out, "");
+
+        if let Some(attribute) = self.deprecated_attribute() {
+            rust!(self.out, "{}", attribute);
+        }
+        rust!(
+            self.out,
+            "{}struct {}IncrementalParser {{",
+            visibility,
+            self.user_start_symbol
+        );
+        rust!(
+            self.out,
+            "builder: {}lalrpop_util::lexer::MatcherBuilder,",
+            self.prefix,
+        );
+        rust!(self.out, "_priv: (),");
+        rust!(self.out, "}}");
+        rust!(self.out, "");
+
+        rust!(
+            self.out,
+            "impl Default for {}IncrementalParser {{ fn default() -> Self {{ Self::new() }} }}",
+            self.user_start_symbol
+        );
+
+        rust!(
+            self.out,
+            "impl {}IncrementalParser {{",
+            self.user_start_symbol
+        );
+        rust!(
+            self.out,
+            "{}fn new() -> {}IncrementalParser {{",
+            visibility,
+            self.user_start_symbol
+        );
+        rust!(
+            self.out,
+            "let {0}builder = {1}::{0}intern_token::new_builder();",
+            self.prefix,
+            self.action_module
+        );
+        rust!(self.out, "{}IncrementalParser {{", self.user_start_symbol);
+        rust!(self.out, "builder: {}builder,", self.prefix);
+        rust!(self.out, "_priv: (),");
+        rust!(self.out, "}}"); // IncrementalParser
+        rust!(self.out, "}}"); // new()
+
+        for reparse in [false, true] {
+            let (name, parameters, old) = if reparse {
+                (
+                    "reparse",
+                    vec![
+                        format!("{p}old: &{tree}", p = self.prefix, tree = tree_type),
+                        format!(
+                            "{p}edit: &{p}lalrpop_util::incremental::Edit<'_>",
+                            p = self.prefix
+                        ),
+                    ],
+                    format!("Some(({p}old, {p}edit))", p = self.prefix),
+                )
+            } else {
+                ("parse", vec![], "None".to_string())
+            };
+
+            rust!(self.out, "");
+            rust!(self.out, "#[allow(dead_code)]");
+            self.out
+                .fn_header(visibility, name.to_owned())
+                .with_parameters(Some("&self".to_owned()))
+                .with_grammar(self.grammar)
+                .with_parameters(parameters)
+                .with_return_type(&return_type)
+                .emit()?;
+            rust!(self.out, "{{");
+            self.define_tokens()?;
+            rust!(
+                self.out,
+                "{p}lalrpop_util::incremental::parse(",
+                p = self.prefix
+            );
+            rust!(self.out, "{p}StateMachine {{", p = self.prefix);
+            for Parameter { name, .. } in &self.grammar.parameters {
+                rust!(self.out, "{},", name);
+            }
+            rust!(
+                self.out,
+                "{p}phantom: {phantom},",
+                p = self.prefix,
+                phantom = self.phantom_data_expr(),
+            );
+            rust!(self.out, "}},");
+            rust!(self.out, "{p}TERMINAL,", p = self.prefix);
+            rust!(self.out, "{p}NONTERMINALS,", p = self.prefix);
+            rust!(self.out, "{p}tokens,", p = self.prefix);
+            rust!(self.out, "{},", old);
+            rust!(self.out, ")");
+            rust!(self.out, "}}"); // fn
+        }
+
+        rust!(self.out, "}}"); // impl
+        Ok(())
+    }
+
+    /// Writes the `AsyncParser` of an `#[async]` grammar, which drives
+    /// the same state machine as the `Parser`, with the tokens of a
+    /// `TokenStream`. Since the parse can stop whenever the next token
+    /// has not arrived yet, the state machine is kept in the
+    /// `AsyncParser`, which is created with the grammar's parameters.
+    fn write_async_parser_fn(&mut self) -> io::Result<()> {
+        let visibility = &self.parser_visibility();
+        let phantom_data_expr = self.phantom_data_expr();
+        let machine = self.custom.machine.clone();
+        let type_parameters = Sep(", ", &machine.type_parameters);
+        let where_clauses = Sep(", ", &machine.where_clauses);
+        let name = format!("{}AsyncParser", self.user_start_symbol);
+
+        if let Some(attribute) = self.deprecated_attribute() {
+            rust!(self.out, "{}", attribute);
+        }
+        rust!(
+            self.out,
+            "{}struct {}<{}>",
+            visibility,
+            name,
+            type_parameters
+        );
+        rust!(self.out, "where {}", where_clauses);
+        rust!(self.out, "{{");
+        match self.grammar.stack_depth {
+            Some(stack_depth) => rust!(
+                self.out,
+                "parser: {p}state_machine::AsyncParser<{p}StateMachine<{tp}>, \
+                 {p}state_machine::ArrayStack<{}, {n}>, \
+                 {p}state_machine::ArrayStack<{p}state_machine::SymbolTriple<{p}StateMachine<{tp}>>, {n}>>,",
+                self.custom.state_type,
+                tp = type_parameters,
+                n = stack_depth,
+                p = self.prefix,
+            ),
+            None => rust!(
+                self.out,
+                "parser: {p}state_machine::AsyncParser<{p}StateMachine<{}>>,",
+                type_parameters,
+                p = self.prefix,
+            ),
+        }
+        rust!(self.out, "}}");
+        rust!(self.out, "");
+
+        rust!(self.out, "impl<{0}> {1}<{0}>", type_parameters, name);
+        rust!(self.out, "where {}", where_clauses);
+        rust!(self.out, "{{");
+        let parameters: Vec<_> = machine
+            .fields
+            .iter()
+            .map(|Parameter { name, ty }| format!("{}: {}", name, ty))
+            .collect();
+        rust!(
+            self.out,
+            "{}fn new({}) -> Self {{",
+            visibility,
+            Sep(", ", &parameters)
+        );
+        rust!(self.out, "{} {{", name);
+        rust!(
+            self.out,
+            "parser: {p}state_machine::AsyncParser::new(",
+            p = self.prefix
+        );
+        self.write_state_machine_expr(&phantom_data_expr)?;
+        rust!(self.out, "),");
+        rust!(self.out, "}}"); // AsyncParser
+        rust!(self.out, "}}"); // new()
+        rust!(self.out, "");
+
+        let mut user_type_parameters = String::new();
+        for type_parameter in &self.grammar.type_parameters {
+            user_type_parameters.push_str(&format!("{}, ", type_parameter));
+        }
+        rust!(self.out, "#[allow(dead_code)]");
+        rust!(
+            self.out,
+            "{}fn poll_parse<{p}TOKEN, {p}TOKENS>(",
+            visibility,
+            p = self.prefix
+        );
+        rust!(self.out, "&mut self,");
+        rust!(
+            self.out,
+            "{p}cx: &mut core::task::Context<'_>,",
+            p = self.prefix
+        );
+        rust!(self.out, "{p}tokens: &mut {p}TOKENS,", p = self.prefix);
+        rust!(
+            self.out,
+            ") -> core::task::Poll<Result<{}, {}>>",
+            self.types.nonterminal_type(&self.start_symbol),
+            self.types.parse_error_type()
+        );
+        rust!(self.out, "where");
+        rust!(
+            self.out,
+            "{p}TOKEN: {p}ToTriple<{}>,",
+            user_type_parameters,
+            p = self.prefix
+        );
+        rust!(
+            self.out,
+            "{p}TOKENS: {p}state_machine::TokenStream<Item = {p}TOKEN> + Unpin,",
+            p = self.prefix
+        );
+        rust!(self.out, "{{");
+        rust!(self.out, "self.parser.poll_parse(|| {{");
+        rust!(
+            self.out,
+            "{p}state_machine::TokenStream::poll_next(core::pin::Pin::new(&mut *{p}tokens), {p}cx)",
+            p = self.prefix
+        );
+        rust!(
+            self.out,
+            ".map(|t| t.map({p}ToTriple::to_triple))",
+            p = self.prefix
+        );
+        rust!(self.out, "}})");
+        rust!(self.out, "}}"); // fn
+        rust!(self.out, "}}"); // impl
+        rust!(self.out, "");
+        Ok(())
+    }
+
+    /// Emits `XParser::tables()` and `XParser::load_tables_from_path`,
+    /// along with a `GOTO` table in the layout expected by
+    /// `lalrpop_util::tables::ParseTables` (the parser itself uses the
+    /// `goto` function instead).
+    fn write_tables_fn(&mut self) -> io::Result<()> {
+        let visibility = &self.parser_visibility();
+        let tables_type = format!("{}lalrpop_util::tables::ParseTables", self.prefix);
+
+        rust!(
+            self.out,
+            "const {}GOTO: &[{}] = &[",
+            self.prefix,
+            self.custom.state_type
+        );
+        for (index, state) in self.states.iter().enumerate() {
+            rust!(self.out, "// State {}", index);
+            let row = self.custom.all_nonterminals.iter().map(|nonterminal| {
+                match state.gotos.get(nonterminal) {
+                    Some(new_state) => {
+                        (new_state.0 as i32, Comment::Goto(nonterminal, new_state.0))
+                    }
+                    None => (-1, Comment::Error(nonterminal)),
+                }
+            });
+            self.out.write_table_row(row)?;
+        }
+        rust!(self.out, "];");
+        rust!(self.out, "");
+
+        let terminals: Vec<_> = self
+            .grammar
+            .terminals
+            .all
+            .iter()
+            .map(|t| format!("{:?}", t.to_string()))
+            .collect();
+        let nonterminals: Vec<_> = self
+            .custom
+            .all_nonterminals
+            .iter()
+            .map(|nt| format!("{:?}", nt.to_string()))
+            .collect();
+
+        rust!(self.out, "#[allow(dead_code)]");
+        rust!(self.out, "impl {}Parser {{", self.user_start_symbol);
+        rust!(self.out, "{}fn tables() -> {} {{", visibility, tables_type);
+        rust!(self.out, "{}::new(", tables_type);
+        rust!(self.out, "&[{}],", Sep(", ", &terminals));
+        rust!(self.out, "&[{}],", Sep(", ", &nonterminals));
+        rust!(
+            self.out,
+            "{p}ACTION, {p}EOF_ACTION, {p}GOTO,",
+            p = self.prefix
+        );
         rust!(self.out, ")");
+        rust!(self.out, "}}"); // tables()
+        rust!(self.out, "");
 
-        self.end_parser_fn()
+        // Tables written for another grammar are stale
+        rust!(
+            self.out,
+            "{}fn load_tables_from_path<P: AsRef<::std::path::Path>>(path: P) -> ::std::io::Result<{}> {{",
+            visibility,
+            tables_type
+        );
+        rust!(
+            self.out,
+            "let {p}tables = {}::load_from_path(path)?;",
+            tables_type,
+            p = self.prefix
+        );
+        rust!(
+            self.out,
+            "let {p}expected = Self::tables();",
+            p = self.prefix
+        );
+        rust!(
+            self.out,
+            "if {p}tables.terminals != {p}expected.terminals || {p}tables.nonterminals != {p}expected.nonterminals {{",
+            p = self.prefix
+        );
+        rust!(
+            self.out,
+            "return Err(::std::io::Error::new(::std::io::ErrorKind::InvalidData, \"the tables were written for a different grammar\"));"
+        );
+        rust!(self.out, "}}");
+        rust!(self.out, "Ok({}tables)", self.prefix);
+        rust!(self.out, "}}"); // load_tables_from_path()
+        rust!(self.out, "}}"); // impl
+        Ok(())
     }
 
     fn write_token_to_integer_fn(&mut self) -> io::Result<()> {
@@ -758,10 +1499,50 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
 
         rust!(self.out, "match *{p}token {{", p = self.prefix);
 
-        for (terminal, index) in self.grammar.terminals.all.iter().zip(0..) {
+        let mut terminals: Vec<_> = self.grammar.terminals.all.iter().zip(0..).collect();
+        if self.grammar.match_terminals_by_proc_macro2 {
+            // any identifier matches `"Ident"`, so it comes after the
+            // terminals spelled like identifiers
+            terminals.sort_by_key(|&(terminal, _)| {
+                matches!(terminal, TerminalString::Literal(TerminalLiteral::Quoted(text)) if &**text == "Ident")
+            });
+        }
+        for (terminal, index) in terminals {
             if *terminal == TerminalString::Error {
                 continue;
             }
+            if self.grammar.match_terminals_by_str {
+                let text = match terminal {
+                    TerminalString::Literal(TerminalLiteral::Quoted(text)) => text,
+                    _ => panic!("`extern token` with a terminal that is not a string: {terminal}"),
+                };
+                if self.grammar.match_terminals_by_proc_macro2 {
+                    rust!(
+                        self.out,
+                        "ref {p}t if ::lalrpop_procmacro::Token::matches({p}t, {text:?}) => Some({index}),",
+                        p = self.prefix,
+                        text = &**text,
+                        index = index
+                    );
+                } else if self.grammar.match_terminals_by_token_trait {
+                    rust!(
+                        self.out,
+                        "ref {p}t if {p}lalrpop_util::token::Token::terminal_name({p}t) == {text:?} => Some({index}),",
+                        p = self.prefix,
+                        text = &**text,
+                        index = index
+                    );
+                } else {
+                    rust!(
+                        self.out,
+                        "ref {p}t if ::core::convert::AsRef::<str>::as_ref({p}t) == {text:?} => Some({index}),",
+                        p = self.prefix,
+                        text = &**text,
+                        index = index
+                    );
+                }
+                continue;
+            }
             let pattern = self.grammar.pattern(terminal).map(&mut |_| "_");
             rust!(
                 self.out,
@@ -907,12 +1688,14 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
             format!("{}action: {}", self.prefix, self.custom.state_type),
             format!("{}lookahead_start: Option<&{}>", self.prefix, loc_type),
             format!(
-                "{}states: &mut alloc::vec::Vec<{}>",
-                self.prefix, self.custom.state_type,
+                "{p}states: &mut impl {p}state_machine::Stack<{}>",
+                self.custom.state_type,
+                p = self.prefix,
             ),
             format!(
-                "{}symbols: &mut alloc::vec::Vec<{}>",
-                self.prefix, spanned_symbol_type,
+                "{p}symbols: &mut impl {p}state_machine::Stack<{}>",
+                spanned_symbol_type,
+                p = self.prefix,
             ),
             format!("_: {}", self.phantom_data_type()),
         ];
@@ -1036,8 +1819,9 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
         let parameters = vec![
             format!("{}lookahead_start: Option<&{}>", self.prefix, loc_type),
             format!(
-                "{}symbols: &mut alloc::vec::Vec<{}>",
-                self.prefix, spanned_symbol_type,
+                "{p}symbols: &mut impl {p}state_machine::Stack<{}>",
+                spanned_symbol_type,
+                p = self.prefix,
             ),
             format!("_: {}", self.phantom_data_type()),
         ];
@@ -1111,13 +1895,26 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
             args.push(format!("&{}end", self.prefix));
         }
 
-        // invoke the action code
+        // invoke the action code; a `()` value is not bound to a name
+        let is_unit = self
+            .grammar
+            .types
+            .nonterminal_type(&production.nonterminal)
+            .is_unit();
+        let (binding, value) = if is_unit {
+            (String::new(), "()".to_string())
+        } else {
+            (
+                format!("let {}nt = ", self.prefix),
+                format!("{}nt", self.prefix),
+            )
+        };
         let is_fallible = self.grammar.action_is_fallible(production.action);
         if is_fallible {
             rust!(
                 self.out,
-                "let {}nt = match {}::{}action{}::<{}>({}{}) {{",
-                self.prefix,
+                "{}match {}::{}action{}::<{}>({}{}) {{",
+                binding,
                 self.action_module,
                 self.prefix,
                 production.action.index(),
@@ -1131,8 +1928,8 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
         } else {
             rust!(
                 self.out,
-                "let {}nt = {}::{}action{}::<{}>({}{});",
-                self.prefix,
+                "{}{}::{}action{}::<{}>({}{});",
+                binding,
                 self.action_module,
                 self.prefix,
                 production.action.index(),
@@ -1144,7 +1941,7 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
 
         // if this is the final state, return it
         if production.nonterminal == self.start_symbol {
-            rust!(self.out, "return Some(Ok({}nt));", self.prefix);
+            rust!(self.out, "return Some(Ok({}));", value);
             return Ok(());
         }
 
@@ -1153,8 +1950,9 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
             self.variant_name_for_symbol(&Symbol::Nonterminal(production.nonterminal.clone()));
         rust!(
             self.out,
-            "{p}symbols.push(({p}start, {p}Symbol::{}({p}nt), {p}end));",
+            "{p}symbols.push(({p}start, {p}Symbol::{}({}), {p}end));",
             name,
+            value,
             p = self.prefix
         );
 
@@ -1203,9 +2001,9 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
         rust!(self.out, ">(");
         rust!(
             self.out,
-            "{}symbols: &mut alloc::vec::Vec<{}>",
-            self.prefix,
+            "{p}symbols: &mut impl {p}state_machine::Stack<{}>",
             spanned_symbol_type,
+            p = self.prefix,
         );
         rust!(self.out, ") -> {}", self.types.spanned_type(variant_ty));
 
@@ -1575,10 +2373,8 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
         rust!(self.out, "if next_state == 0 {{");
         rust!(self.out, "None");
         rust!(self.out, "}} else {{");
-        rust!(
-            self.out,
-            "Some(alloc::string::ToString::to_string(terminal))"
-        );
+        let expected_token = self.expected_token_expr(&format!("{}state", self.prefix));
+        rust!(self.out, "Some({})", expected_token);
         rust!(self.out, "}}");
         rust!(self.out, "}}).collect()");
         rust!(self.out, "}}");
@@ -1622,10 +2418,9 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
             p = self.prefix,
             pde = self.phantom_data_expr(),
         );
-        rust!(
-            self.out,
-            "Some(alloc::string::ToString::to_string(terminal))"
-        );
+        let expected_token =
+            self.expected_token_expr(&format!("*{}states.last().unwrap()", self.prefix));
+        rust!(self.out, "Some({})", expected_token);
         rust!(self.out, "}} else {{");
         rust!(self.out, "None");
         rust!(self.out, "}}");
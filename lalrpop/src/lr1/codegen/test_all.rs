@@ -58,9 +58,11 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TestAll> {
                 "super::super::super",
                 this.out,
             )?;
+            // the delegates are in modules of their own, in the module
+            // of this parser
             let pub_use = format!(
                 "{}use self::{}parse{}::{}Parser;",
-                this.grammar.nonterminals[&this.user_start_symbol].visibility,
+                this.parser_visibility().nested(),
                 this.prefix,
                 this.start_symbol,
                 this.user_start_symbol
@@ -6,6 +6,7 @@ use crate::lr1::core::*;
 use crate::lr1::example::{Example, ExampleStyles, ExampleSymbol};
 use crate::lr1::first::FirstSets;
 use crate::lr1::lookahead::{Token, TokenSet};
+use crate::lr1::shortest_input::shortest_input;
 use crate::lr1::trace::Tracer;
 use crate::message::builder::{BodyCharacter, Builder, Character, MessageBuilder};
 use crate::message::Message;
@@ -135,7 +136,7 @@ impl<'cx, 'grammar> ErrorReportingCx<'cx, 'grammar> {
         reduce: Example,
     ) -> Builder<BodyCharacter> {
         let styles = ExampleStyles::ambig();
-        MessageBuilder::new(conflict.production.span)
+        let builder = MessageBuilder::new(conflict.production.span)
             .heading()
             .text("Ambiguous grammar detected")
             .end()
@@ -151,7 +152,9 @@ impl<'cx, 'grammar> ErrorReportingCx<'cx, 'grammar> {
             .begin_lines()
             .wrap_text("Alternatively, they could be reduced like so:")
             .push(shift.into_picture(styles))
-            .end()
+            .end();
+        let builder = self.describe_input(builder, conflict);
+        self.describe_state(builder, conflict)
     }
 
     fn report_error_ambiguity(
@@ -235,14 +238,71 @@ impl<'cx, 'grammar> ErrorReportingCx<'cx, 'grammar> {
 
         let builder = self.describe_reduce(builder, styles, conflict.production, reduce, "First");
 
-        match conflict.action {
+        let builder = match conflict.action {
             Action::Shift(ref lookahead, _) => {
                 self.describe_shift(builder, styles, lookahead.clone(), action, "Alternatively")
             }
             Action::Reduce(production) => {
                 self.describe_reduce(builder, styles, production, action, "Alternatively")
             }
+        };
+
+        let builder = self.describe_input(builder, conflict);
+        self.describe_state(builder, conflict)
+    }
+
+    /// Describes a shortest input that leads to the conflict, made of
+    /// terminals only, unlike the examples above.
+    fn describe_input<C: Character>(
+        &self,
+        builder: Builder<C>,
+        conflict: &TokenConflict<'grammar>,
+    ) -> Builder<C> {
+        let Some(input) = shortest_input(self.grammar, self.states, conflict.state) else {
+            return builder;
+        };
+
+        let builder = builder.begin_wrap();
+        let builder = if input.is_empty() {
+            builder.text("The conflict arises at the start of the input,")
+        } else {
+            builder
+                .text("A shortest input that leads to the conflict is")
+                .text(input.iter().join(" "))
+                .verbatimed()
+                .punctuated(",")
+        };
+        let builder = match conflict.lookahead {
+            Token::Terminal(ref term) => builder
+                .text("followed by")
+                .push(term.clone())
+                .verbatimed()
+                .styled(Tls::session().cursor_symbol),
+            Token::Error => builder.text("followed by an error"),
+            Token::Eof => builder.text("followed by the end of the input"),
+        };
+        builder.punctuated(".").end()
+    }
+
+    /// Lists the items of the state where the conflict arises, with
+    /// their lookaheads, if the session asks for verbose conflicts.
+    fn describe_state<C: Character>(
+        &self,
+        builder: Builder<C>,
+        conflict: &TokenConflict<'grammar>,
+    ) -> Builder<C> {
+        if !Tls::session().verbose_conflicts {
+            return builder;
         }
+
+        let mut builder = builder.begin_lines().wrap_text(format!(
+            "The conflict arises in state {}, whose items are:",
+            conflict.state.0
+        ));
+        for item in self.states[conflict.state.0].items.vec.iter() {
+            builder = builder.text(format!("{:?}", item)).indented();
+        }
+        builder.end()
     }
 
     fn describe_shift<C: Character>(
@@ -291,7 +351,7 @@ impl<'cx, 'grammar> ErrorReportingCx<'cx, 'grammar> {
         example: Example,
         intro_word: &str,
     ) -> Builder<C> {
-        builder
+        let builder = builder
             .begin_lines()
             .begin_wrap()
             .text(intro_word)
@@ -305,7 +365,19 @@ impl<'cx, 'grammar> ErrorReportingCx<'cx, 'grammar> {
             .text("and produce a")
             .push(production.nonterminal.clone())
             .verbatimed()
-            .punctuated(".")
+            .punctuated(".");
+        let nonterminal = &self.grammar.nonterminals[&production.nonterminal];
+        let builder = if nonterminal.is_nullable(self.grammar) {
+            builder
+                .text("Since")
+                .push(production.nonterminal.clone())
+                .verbatimed()
+                .text("can match empty input, the parser may have to make this")
+                .text("choice before seeing any of its tokens.")
+        } else {
+            builder
+        };
+        builder
             .text("This might then yield a parse tree like")
             .end()
             .push(example.into_picture(styles))
@@ -1,8 +1,12 @@
+use crate::file_text::FileText;
 use crate::grammar::repr::*;
 use crate::lr1::build_states;
 use crate::lr1::tls::Lr1Tls;
+use crate::message::builder::InlineBuilder;
+use crate::session::Session;
 use crate::test_util::normalized_grammar;
 use crate::tls::Tls;
+use std::rc::Rc;
 use string_cache::DefaultAtom as Atom;
 
 use super::{ConflictClassification, ErrorReportingCx};
@@ -185,3 +189,43 @@ VarDecl = "let";
         cx.classify(conflict);
     }
 }
+
+#[test]
+fn verbose_conflict() {
+    let mut session = Session::test();
+    session.verbose_conflicts = true;
+    let _tls = Tls::install(Rc::new(session), Rc::new(FileText::test()));
+    let grammar = normalized_grammar(
+        r#"
+grammar;
+pub Ty: () = {
+    "int" => (),
+    <t1:Ty> "->" <t2:Ty> => (),
+};
+"#,
+    );
+    let _lr1_tls = Lr1Tls::install(grammar.terminals.clone());
+    let err = build_states(&grammar, nt("Ty")).unwrap_err();
+    let mut cx = ErrorReportingCx::new(&grammar, &err.states, &err.conflicts);
+    let conflicts = super::token_conflicts(&err.conflicts);
+    let message = cx.report_error(&conflicts[0]);
+    let lines: Vec<String> = InlineBuilder::new()
+        .push(Box::new(message))
+        .end()
+        .emit_to_canvas(80)
+        .to_strings()
+        .iter()
+        .map(|line| line.to_string().trim_end().to_string())
+        .collect();
+    let start = lines
+        .iter()
+        .position(|line| line == "  The conflict arises in state 4, whose items are:")
+        .unwrap();
+    assert_eq!(
+        lines[start + 1..start + 3],
+        [
+            r#"    Ty = Ty (*) "->" Ty ["->", "int", Eof]"#,
+            r#"    Ty = Ty "->" Ty (*) ["->", "int", Eof]"#,
+        ]
+    );
+}
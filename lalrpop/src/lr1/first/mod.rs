@@ -1,6 +1,6 @@
 //! First set construction and computation.
 
-use crate::collections::{map, Map};
+use crate::collections::{map, Map, Set};
 use crate::grammar::repr::*;
 use crate::lr1::lookahead::{Token, TokenSet};
 
@@ -103,3 +103,78 @@ impl FirstSets {
         set
     }
 }
+
+impl Grammar {
+    /// FIRST of each nonterminal: the terminals that can begin a string
+    /// it derives, with `!` as `TerminalString::Error`. Whether the
+    /// nonterminal can also derive the empty string is given by
+    /// `nullable_nonterminals`.
+    pub fn first_sets(&self) -> Map<NonterminalString, Set<TerminalString>> {
+        let _lr1_tls = Lr1Tls::install(self.terminals.clone());
+        let first_sets = FirstSets::new(self);
+        self.nonterminals
+            .keys()
+            .map(|nt| {
+                let terminals = first_sets
+                    .map
+                    .get(nt)
+                    .into_iter()
+                    .flat_map(|set| set.iter())
+                    .filter_map(|token| match token {
+                        Token::Terminal(t) => Some(t),
+                        Token::Error => Some(TerminalString::Error),
+                        Token::Eof => None,
+                    })
+                    .collect();
+                (nt.clone(), terminals)
+            })
+            .collect()
+    }
+
+    /// FOLLOW of each nonterminal: the terminals that can come right
+    /// after it, with `None` for the end of the input (which follows
+    /// the public nonterminals).
+    pub fn follow_sets(&self) -> Map<NonterminalString, Set<Option<TerminalString>>> {
+        let _lr1_tls = Lr1Tls::install(self.terminals.clone());
+        let first_sets = FirstSets::new(self);
+        let mut follow: Map<NonterminalString, TokenSet> = self
+            .nonterminals
+            .keys()
+            .map(|nt| (nt.clone(), TokenSet::new()))
+            .collect();
+        for start in self.start_nonterminals.values() {
+            follow.insert(start.clone(), TokenSet::eof());
+        }
+
+        // for each `A = ... B rest`, FOLLOW(B) includes FIRST(rest),
+        // and FOLLOW(A) as well if `rest` can be empty
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for production in self.nonterminals.values().flat_map(|p| &p.productions) {
+                for (index, symbol) in production.symbols.iter().enumerate() {
+                    if let Symbol::Nonterminal(nt) = symbol {
+                        let rest = &production.symbols[index + 1..];
+                        let set = first_sets.first1(rest, &follow[&production.nonterminal]);
+                        changed |= follow.get_mut(nt).unwrap().union_with(&set);
+                    }
+                }
+            }
+        }
+
+        follow
+            .into_iter()
+            .map(|(nt, set)| {
+                let terminals = set
+                    .iter()
+                    .map(|token| match token {
+                        Token::Terminal(t) => Some(t),
+                        Token::Error => Some(TerminalString::Error),
+                        Token::Eof => None,
+                    })
+                    .collect();
+                (nt, terminals)
+            })
+            .collect()
+    }
+}
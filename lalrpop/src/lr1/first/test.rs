@@ -96,3 +96,88 @@ fn basic_first0() {
 
     assert_eq!(first0(&first_sets, &[nt("X")]), vec![la("E")]);
 }
+
+#[test]
+fn nullable() {
+    let grammar = normalized_grammar(
+        r#"
+    grammar;
+    A = B C;
+    B: Option<u32> = {
+        "D" => Some(1),
+        => None
+    };
+    C: u32 = {
+        => 0,
+        C "D" => 1,
+    };
+    E = A "E";
+"#,
+    );
+    let nullable: Vec<_> = grammar
+        .nonterminals
+        .values()
+        .filter(|data| data.is_nullable(&grammar))
+        .map(|data| data.name.to_string())
+        .collect();
+    assert_eq!(nullable, ["A", "B", "C"]);
+}
+
+#[test]
+fn first_sets() {
+    let grammar = normalized_grammar(
+        r#"
+    grammar;
+    A = B C;
+    B: Option<u32> = {
+        "D" => Some(1),
+        => None
+    };
+    C: u32 = {
+        "E" => 0,
+        ! => 1,
+    };
+"#,
+    );
+    let first_sets = grammar.first_sets();
+    let first = |name: &str| -> Vec<String> {
+        first_sets[&NonterminalString(Atom::from(name))]
+            .iter()
+            .map(|t| t.to_string())
+            .collect()
+    };
+    assert_eq!(first("A"), [r#""D""#, r#""E""#, "error"]);
+    assert_eq!(first("B"), [r#""D""#]);
+    assert_eq!(first("C"), [r#""E""#, "error"]);
+}
+
+#[test]
+fn follow_sets() {
+    let grammar = normalized_grammar(
+        r#"
+    grammar;
+    pub A = B C;
+    B: Option<u32> = {
+        "D" => Some(1),
+        => None
+    };
+    C: u32 = {
+        "E" => 0,
+        "(" <A> ")" => 1,
+    };
+"#,
+    );
+    let follow_sets = grammar.follow_sets();
+    let follow = |name: &str| -> Vec<Option<String>> {
+        follow_sets[&NonterminalString(Atom::from(name))]
+            .iter()
+            .map(|t| t.as_ref().map(|t| t.to_string()))
+            .collect()
+    };
+    assert_eq!(follow("A"), [None, Some(r#"")""#.to_string())]);
+    assert_eq!(
+        follow("B"),
+        [Some(r#""(""#.to_string()), Some(r#""E""#.to_string())]
+    );
+    assert_eq!(follow("C"), [None, Some(r#"")""#.to_string())]);
+}
@@ -10,6 +10,7 @@ use crate::lr1::lane_table::table::context_set::OverlappingLookahead;
 use crate::lr1::lane_table::table::{ConflictIndex, LaneTable};
 use crate::lr1::lookahead::{Lookahead, TokenSet};
 use crate::lr1::state_graph::StateGraph;
+use crate::tls::Tls;
 use ena::unify::InPlaceUnificationTable;
 
 mod merge;
@@ -35,7 +36,8 @@ impl<'grammar> LaneTableConstruct<'grammar> {
     }
 
     pub fn construct(self) -> Result<Vec<Lr1State<'grammar>>, Lr1TableConstructionError<'grammar>> {
-        let states = {
+        let session = Tls::session();
+        let states = profile!(session, "LR(0) state construction", {
             match build::build_lr0_states(self.grammar, self.start_nt.clone()) {
                 Ok(states) => {
                     // In this case, the grammar is actually
@@ -60,13 +62,20 @@ impl<'grammar> LaneTableConstruct<'grammar> {
                 }
                 Err(TableConstructionError { states, .. }) => states,
             }
-        };
+        });
 
         // Convert the LR(0) states into LR(0-1) states.
-        let mut states = self.promote_lr0_states(states);
+        let states = self.promote_lr0_states(states);
 
         // For each inconsistent state, apply the lane-table algorithm to
         // resolve it.
+        profile!(session, "Conflict resolution", self.resolve_all(states))
+    }
+
+    fn resolve_all(
+        &self,
+        mut states: Vec<Lr1State<'grammar>>,
+    ) -> Result<Vec<Lr1State<'grammar>>, Lr1TableConstructionError<'grammar>> {
         for i in 0.. {
             if i >= states.len() {
                 break;
@@ -230,6 +230,11 @@ impl TokenSet {
         TokenSet { bit_set }
     }
 
+    pub fn remove(&mut self, token: &Token) -> bool {
+        let bit = self.bit(token);
+        self.bit_set.remove(bit)
+    }
+
     pub fn contains(&self, token: &Token) -> bool {
         self.bit_set.contains(self.bit(token))
     }
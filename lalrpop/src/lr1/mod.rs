@@ -12,23 +12,27 @@ mod first;
 mod lane_table;
 mod lookahead;
 mod report;
+mod shortest_input;
 mod state_graph;
 mod tls;
 mod trace;
+mod unreduced;
 use std::io::{self, Write};
 
 #[cfg(test)]
 mod interpret;
 
-pub use self::core::{Lr1Result, Lr1TableConstructionError};
+pub use self::core::{Action, Lr1Conflict, Lr1Result, Lr1State, Lr1TableConstructionError};
 pub use self::error::report_error;
 pub use self::tls::Lr1Tls;
+pub use self::unreduced::ReducedAlternatives;
 
 pub fn build_states(grammar: &Grammar, start: NonterminalString) -> Lr1Result<'_> {
-    let mut lr1_states = if !grammar.algorithm.lalr {
-        build::build_lr1_states(grammar, start)?
-    } else {
-        build_lalr::build_lalr_states(grammar, start)?
+    let mut lr1_states = match grammar.algorithm.construction {
+        LrConstruction::LaneTable => build::build_lr1_states(grammar, start)?,
+        LrConstruction::CanonicalLr1 => build::build_canonical_lr1_states(grammar, start)?,
+        LrConstruction::Lalr => build_lalr::build_lalr_states(grammar, start)?,
+        LrConstruction::Glr => build::build_glr_states(grammar, start)?,
     };
 
     rewrite_state_indices(grammar, &mut lr1_states);
@@ -38,9 +42,10 @@ pub fn build_states(grammar: &Grammar, start: NonterminalString) -> Lr1Result<'_
 
 pub fn generate_report<'grammar, W: Write + 'grammar>(
     out: &'grammar mut W,
+    grammar: &'grammar Grammar,
     lr1result: &Lr1Result<'grammar>,
 ) -> io::Result<()> {
-    report::generate_report(out, lr1result)
+    report::generate_report(out, grammar, lr1result)
 }
 
 /// By packing all states which start a reduction we can generate a smaller goto table as any
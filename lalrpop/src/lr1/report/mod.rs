@@ -8,10 +8,13 @@ use super::lookahead::*;
 
 pub fn generate_report<'grammar, W: Write + 'grammar>(
     out: &'grammar mut W,
+    grammar: &'grammar Grammar,
     lr1result: &Lr1Result<'grammar>,
 ) -> io::Result<()> {
     let mut generator = ReportGenerator::new(out);
-    generator.report_lr_table_construction(lr1result)
+    generator.report_lr_table_construction(lr1result)?;
+    generator.report_first_sets(grammar)?;
+    generator.report_follow_sets(grammar)
 }
 
 static INDENT_STRING: &str = "    ";
@@ -79,6 +82,35 @@ where
         Ok(())
     }
 
+    fn report_first_sets(&mut self, grammar: &Grammar) -> io::Result<()> {
+        self.write_section_header("First Sets")?;
+        writeln!(self.out)?;
+        for (nonterminal, terminals) in grammar.first_sets() {
+            write!(self.out, "{}:", nonterminal)?;
+            for terminal in terminals {
+                write!(self.out, " {}", terminal)?;
+            }
+            writeln!(self.out)?;
+        }
+        Ok(())
+    }
+
+    fn report_follow_sets(&mut self, grammar: &Grammar) -> io::Result<()> {
+        self.write_section_header("Follow Sets")?;
+        writeln!(self.out)?;
+        for (nonterminal, terminals) in grammar.follow_sets() {
+            write!(self.out, "{}:", nonterminal)?;
+            for terminal in terminals {
+                match terminal {
+                    Some(terminal) => write!(self.out, " {}", terminal)?,
+                    None => write!(self.out, " EOF")?,
+                }
+            }
+            writeln!(self.out)?;
+        }
+        Ok(())
+    }
+
     fn process_conflicts<'grammar, L>(
         &mut self,
         conflicts: &'report [Conflict<'grammar, L>],
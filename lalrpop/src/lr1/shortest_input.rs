@@ -0,0 +1,167 @@
+//! Finds a shortest input that leads the parser to a given state, so
+//! that conflicts can be explained with a concrete example rather than
+//! only with the items of the state.
+//!
+//! The states form a graph whose edges are the shifts and gotos. A
+//! shift consumes one terminal; a goto on a nonterminal consumes the
+//! shortest string of terminals the nonterminal derives. A shortest
+//! path from the start state (state 0) is then found with Dijkstra's
+//! algorithm.
+
+use crate::collections::{map, Map};
+use crate::grammar::repr::{Grammar, NonterminalString, Symbol, TerminalString};
+use crate::lr1::core::{Lr1State, StateIndex};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// The terminals of a shortest input that leads from the start state
+/// to `target`, or `None` if no input does.
+pub fn shortest_input(
+    grammar: &Grammar,
+    states: &[Lr1State<'_>],
+    target: StateIndex,
+) -> Option<Vec<TerminalString>> {
+    let yields = shortest_yields(grammar);
+
+    // for each state reached, its distance and the edge it was reached by
+    let mut distances: Map<StateIndex, usize> = map();
+    let mut edges: Map<StateIndex, (StateIndex, Symbol)> = map();
+    let mut queue = BinaryHeap::new();
+    distances.insert(StateIndex(0), 0);
+    queue.push(Reverse((0, StateIndex(0))));
+
+    while let Some(Reverse((distance, index))) = queue.pop() {
+        if index == target {
+            break;
+        }
+        if distances[&index] < distance {
+            continue; // already reached through a shorter path
+        }
+        // construction may have stopped at the first conflicts, before
+        // building all the states
+        let Some(state) = states.get(index.0) else {
+            continue;
+        };
+        let shifts = state
+            .shifts
+            .iter()
+            .map(|(terminal, &next)| (Symbol::Terminal(terminal.clone()), 1, next));
+        let gotos = state.gotos.iter().filter_map(|(nonterminal, &next)| {
+            let length = yields.get(nonterminal)?.len();
+            Some((Symbol::Nonterminal(nonterminal.clone()), length, next))
+        });
+        for (symbol, length, next) in shifts.chain(gotos) {
+            let distance = distance + length;
+            if distances.get(&next).map_or(true, |&d| distance < d) {
+                distances.insert(next, distance);
+                edges.insert(next, (index, symbol));
+                queue.push(Reverse((distance, next)));
+            }
+        }
+    }
+
+    if !distances.contains_key(&target) {
+        return None;
+    }
+
+    let mut symbols = vec![];
+    let mut index = target;
+    while let Some((previous, symbol)) = edges.get(&index) {
+        symbols.push(symbol);
+        index = *previous;
+    }
+    Some(
+        symbols
+            .into_iter()
+            .rev()
+            .flat_map(|symbol| match symbol {
+                Symbol::Terminal(terminal) => vec![terminal.clone()],
+                Symbol::Nonterminal(nonterminal) => yields[nonterminal].clone(),
+            })
+            .collect(),
+    )
+}
+
+/// The shortest string of terminals that each nonterminal derives,
+/// computed by iterating to a fixed point. Nonterminals that derive no
+/// finite string are left out.
+fn shortest_yields(grammar: &Grammar) -> Map<NonterminalString, Vec<TerminalString>> {
+    let mut yields: Map<NonterminalString, Vec<TerminalString>> = map();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for production in grammar.nonterminals.values().flat_map(|d| &d.productions) {
+            let candidate: Option<Vec<TerminalString>> = production
+                .symbols
+                .iter()
+                .map(|symbol| match symbol {
+                    Symbol::Terminal(terminal) => Some(vec![terminal.clone()]),
+                    Symbol::Nonterminal(nonterminal) => yields.get(nonterminal).cloned(),
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(|parts| parts.concat());
+            let Some(candidate) = candidate else {
+                continue;
+            };
+            let shorter = yields
+                .get(&production.nonterminal)
+                .map_or(true, |current| candidate.len() < current.len());
+            if shorter {
+                yields.insert(production.nonterminal.clone(), candidate);
+                changed = true;
+            }
+        }
+    }
+    yields
+}
+
+#[cfg(test)]
+mod test {
+    use super::shortest_input;
+    use crate::lr1::{self, Lr1Tls};
+    use crate::test_util::normalized_grammar;
+    use crate::tls::Tls;
+
+    /// The shortest input to the state of each conflict that some
+    /// input reaches.
+    fn conflict_inputs(text: &str) -> Vec<String> {
+        let _tls = Tls::test_string(text);
+        let grammar = normalized_grammar(text);
+        let _lr1_tls = Lr1Tls::install(grammar.terminals.clone());
+        let start = grammar.start_nonterminals.values().next().unwrap();
+        let error = lr1::build_states(&grammar, start.clone()).unwrap_err();
+        error
+            .conflicts
+            .iter()
+            .filter_map(|conflict| shortest_input(&grammar, &error.states, conflict.state))
+            .map(|input| {
+                input
+                    .iter()
+                    .map(|terminal| terminal.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn binary_operator() {
+        let text = r#"
+grammar;
+pub E: () = { E "+" E => (), "(" E ")" => (), "1" => () };
+"#;
+        assert_eq!(conflict_inputs(text), [r#""1" "+" "1""#]);
+    }
+
+    #[test]
+    fn through_nonterminals() {
+        let text = r#"
+grammar;
+pub S: () = { "x" A "y" => (), "x" B "y" "z" => () };
+A: () = { "a" "a" "a" => (), C => () };
+B: () = C => ();
+C: () = "c" => ();
+"#;
+        assert_eq!(conflict_inputs(text), [r#""x" "c""#]);
+    }
+}
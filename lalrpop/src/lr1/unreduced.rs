@@ -0,0 +1,108 @@
+//! Finds the alternatives that no state of the LR tables ever reduces,
+//! for `--warn-unreachable`. Such an alternative is dead code: either its
+//! nonterminal cannot be reached from any public nonterminal, or it is
+//! always shadowed by another alternative (for example through a
+//! precedence annotation).
+//!
+//! Alternatives are identified by their span, so that an alternative of a
+//! nonterminal split up by `#[precedence]` is only dead if none of the
+//! productions it gave rise to is ever reduced. The productions of macro
+//! expansions (`Comma<Expr>`, `Expr?`, ...) all carry the span of the use
+//! of the macro rather than that of an alternative, so they are left out:
+//! an expansion that is never used is reported through the alternative
+//! that uses it.
+
+use crate::codegen::visitor::is_user_name;
+use crate::collections::{set, Set};
+use crate::grammar::parse_tree::Span;
+use crate::grammar::repr::{Grammar, Production};
+use crate::lr1::core::Lr1State;
+
+#[derive(Default)]
+pub struct ReducedAlternatives {
+    spans: Set<Span>,
+}
+
+impl ReducedAlternatives {
+    /// Records the productions reduced in the states of one public
+    /// nonterminal.
+    pub fn add(&mut self, states: &[Lr1State<'_>]) {
+        for state in states {
+            for &(_, production) in &state.reductions {
+                self.spans.insert(production.span);
+            }
+        }
+    }
+
+    /// Returns one production for each alternative that was never
+    /// reduced, in the order they appear in the grammar.
+    pub fn unreduced<'grammar>(&self, grammar: &'grammar Grammar) -> Vec<&'grammar Production> {
+        let mut seen = set();
+        let mut productions: Vec<_> = grammar
+            .nonterminals
+            .values()
+            .filter(|data| is_user_name(&data.name))
+            .flat_map(|data| &data.productions)
+            .filter(|production| !self.spans.contains(&production.span))
+            .filter(|production| seen.insert(production.span))
+            .collect();
+        productions.sort_by_key(|production| production.span);
+        productions
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ReducedAlternatives;
+    use crate::lr1::{self, Lr1Tls};
+    use crate::test_util::normalized_grammar;
+    use crate::tls::Tls;
+
+    fn unreduced(text: &str) -> Vec<String> {
+        let _tls = Tls::test_string(text);
+        let grammar = normalized_grammar(text);
+        let _lr1_tls = Lr1Tls::install(grammar.terminals.clone());
+        let mut reduced = ReducedAlternatives::default();
+        for start in grammar.start_nonterminals.values() {
+            reduced.add(&lr1::build_states(&grammar, start.clone()).unwrap());
+        }
+        reduced
+            .unreduced(&grammar)
+            .into_iter()
+            .map(|production| {
+                text[production.span.0..production.span.1]
+                    .trim()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn all_reduced() {
+        let text = r#"grammar; pub A: () = { "a" B => (), B => () }; B: () = "b";"#;
+        assert!(unreduced(text).is_empty());
+    }
+
+    #[test]
+    fn unused_nonterminal() {
+        let text = r#"grammar; pub A: () = "a" => (); B: () = { "b" => (), "c" => () };"#;
+        assert_eq!(unreduced(text), [r#""b" => ()"#, r#""c" => ()"#]);
+    }
+
+    #[test]
+    fn used_by_other_start() {
+        let text = r#"grammar; pub A: () = "a" B => (); pub C: () = "c" => (); B: () = "b";"#;
+        assert!(unreduced(text).is_empty());
+    }
+
+    #[test]
+    fn macro_alternative_used_once() {
+        let text = r#"
+grammar;
+pub A: () = "a" M<"b"> => ();
+B: () = "c" M<"d"> => ();
+M<T>: () = { T => (), "e" T => () };
+"#;
+        assert_eq!(unreduced(text), [r#""c" M<"d"> => ()"#]);
+    }
+}
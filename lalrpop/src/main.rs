@@ -26,7 +26,16 @@ Options:
     -c, --color          Force colorful output, even if this is not a TTY.
     --no-whitespace      Removes redundant whitespace from the generated file. (Default: false)
     --comments           Enable comments in the generated code.
-    --report             Generate report files.\
+    --rustfmt            Format the generated file with rustfmt, if it can be run.
+    --report             Generate report files.
+    --emit KIND          Emit an additional file next to the .rs file. May be repeated.
+                         Valid values: dot, rust-types, conflicts-json, ebnf, bnf,
+                         railroad.
+    --serialize-tables   Give table-driven parsers functions to save and load their tables.
+    --check              Only check the grammars for errors and conflicts; write no files.
+    --warn-unreachable   Warn about alternatives that the parser never reduces.
+    --verbose-conflicts  List all the items of the state of each conflict.
+    --time               Print how long each phase took to stderr.\
 ";
 
 #[derive(Debug)]
@@ -40,7 +49,14 @@ struct Args {
     flag_color: bool,
     flag_comments: bool,
     flag_no_whitespace: bool,
+    flag_rustfmt: bool,
     flag_report: bool,
+    flag_emit: Vec<EmitFlag>,
+    flag_serialize_tables: bool,
+    flag_check: bool,
+    flag_warn_unreachable: bool,
+    flag_verbose_conflicts: bool,
+    flag_time: bool,
     flag_version: bool,
 }
 
@@ -67,6 +83,32 @@ impl FromStr for LevelFlag {
     }
 }
 
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum EmitFlag {
+    Dot,
+    RustTypes,
+    ConflictsJson,
+    Ebnf,
+    Bnf,
+    Railroad,
+}
+
+impl FromStr for EmitFlag {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dot" => Ok(EmitFlag::Dot),
+            "rust-types" => Ok(EmitFlag::RustTypes),
+            "conflicts-json" => Ok(EmitFlag::ConflictsJson),
+            "ebnf" => Ok(EmitFlag::Ebnf),
+            "bnf" => Ok(EmitFlag::Bnf),
+            "railroad" => Ok(EmitFlag::Railroad),
+            x => Err(format!("Unknown emit kind: {x}")),
+        }
+    }
+}
+
 fn parse_args(mut args: Arguments) -> Result<Args, pico_args::Error> {
     Ok(Args {
         flag_out_dir: args.opt_value_from_fn(["-o", "--out-dir"], PathBuf::from_str)?,
@@ -77,7 +119,14 @@ fn parse_args(mut args: Arguments) -> Result<Args, pico_args::Error> {
         flag_color: args.contains(["-c", "--color"]),
         flag_comments: args.contains("--comments"),
         flag_no_whitespace: args.contains("--no-whitespace"),
+        flag_rustfmt: args.contains("--rustfmt"),
         flag_report: args.contains("--report"),
+        flag_emit: args.values_from_fn("--emit", EmitFlag::from_str)?,
+        flag_serialize_tables: args.contains("--serialize-tables"),
+        flag_check: args.contains("--check"),
+        flag_warn_unreachable: args.contains("--warn-unreachable"),
+        flag_verbose_conflicts: args.contains("--verbose-conflicts"),
+        flag_time: args.contains("--time"),
         flag_version: args.contains(["-V", "--version"]),
         arg_inputs: args.finish(),
     })
@@ -124,10 +173,45 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         config.emit_whitespace(false);
     }
 
+    if args.flag_rustfmt {
+        config.format_generated_code(true);
+    }
+
     if args.flag_report {
         config.emit_report(true);
     }
 
+    for emit in args.flag_emit {
+        match emit {
+            EmitFlag::Dot => config.emit_dot(true),
+            EmitFlag::RustTypes => config.emit_rust_types(true),
+            EmitFlag::ConflictsJson => config.emit_conflicts_json(true),
+            EmitFlag::Ebnf => config.emit_ebnf(true),
+            EmitFlag::Bnf => config.emit_bnf(true),
+            EmitFlag::Railroad => config.emit_railroad(true),
+        };
+    }
+
+    if args.flag_serialize_tables {
+        config.serialize_tables(true);
+    }
+
+    if args.flag_check {
+        config.check_only(true);
+    }
+
+    if args.flag_warn_unreachable {
+        config.warn_unreachable(true);
+    }
+
+    if args.flag_verbose_conflicts {
+        config.verbose_conflicts(true);
+    }
+
+    if args.flag_time {
+        config.time_phases(true);
+    }
+
     if args.arg_inputs.is_empty() {
         return Err("Error: no input files specified! Try --help for help.".into());
     }
@@ -217,6 +301,64 @@ mod test {
         assert_eq!(args.arg_inputs, ["file.lalrpop"]);
     }
 
+    #[test]
+    fn test_usage_rustfmt() {
+        let args = parse_args_slice(&["--rustfmt", "file.lalrpop"]);
+        assert!(args.flag_rustfmt);
+        assert_eq!(args.arg_inputs, ["file.lalrpop"]);
+    }
+
+    #[test]
+    fn test_usage_emit() {
+        let args = parse_args_slice(&["--emit", "dot", "file.lalrpop"]);
+        assert_eq!(args.flag_emit, [EmitFlag::Dot]);
+        assert_eq!(args.arg_inputs, ["file.lalrpop"]);
+
+        let args = parse_args_slice(&["--emit", "dot", "--emit", "rust-types", "file.lalrpop"]);
+        assert_eq!(args.flag_emit, [EmitFlag::Dot, EmitFlag::RustTypes]);
+
+        let args = parse_args_slice(&["--emit", "conflicts-json", "file.lalrpop"]);
+        assert_eq!(args.flag_emit, [EmitFlag::ConflictsJson]);
+
+        let args = parse_args_slice(&["--emit", "railroad", "file.lalrpop"]);
+        assert_eq!(args.flag_emit, [EmitFlag::Railroad]);
+    }
+
+    #[test]
+    fn test_usage_serialize_tables() {
+        let args = parse_args_slice(&["--serialize-tables", "file.lalrpop"]);
+        assert!(args.flag_serialize_tables);
+        assert_eq!(args.arg_inputs, ["file.lalrpop"]);
+    }
+
+    #[test]
+    fn test_usage_check() {
+        let args = parse_args_slice(&["--check", "file.lalrpop"]);
+        assert!(args.flag_check);
+        assert_eq!(args.arg_inputs, ["file.lalrpop"]);
+    }
+
+    #[test]
+    fn test_usage_warn_unreachable() {
+        let args = parse_args_slice(&["--warn-unreachable", "file.lalrpop"]);
+        assert!(args.flag_warn_unreachable);
+        assert_eq!(args.arg_inputs, ["file.lalrpop"]);
+    }
+
+    #[test]
+    fn test_usage_verbose_conflicts() {
+        let args = parse_args_slice(&["--verbose-conflicts", "file.lalrpop"]);
+        assert!(args.flag_verbose_conflicts);
+        assert_eq!(args.arg_inputs, ["file.lalrpop"]);
+    }
+
+    #[test]
+    fn test_usage_time() {
+        let args = parse_args_slice(&["--time", "file.lalrpop"]);
+        assert!(args.flag_time);
+        assert_eq!(args.arg_inputs, ["file.lalrpop"]);
+    }
+
     #[test]
     fn test_usage_level() {
         assert_eq!(
@@ -42,6 +42,10 @@ impl Message {
             body,
         }
     }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
 }
 
 impl Content for Message {
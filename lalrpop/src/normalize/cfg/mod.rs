@@ -0,0 +1,44 @@
+//! Removes the alternatives whose `#[cfg(...)]` annotations do not hold
+//! in the session, so that the rest of the passes never see them. Like
+//! the `#[cfg(feature = "...")]` of public nonterminals, the conditions
+//! are decided when the parser is generated, from the features and
+//! target configuration that cargo gives the build script.
+
+use crate::grammar::consts::CFG;
+use crate::grammar::parse_tree::*;
+use crate::session::Session;
+
+#[cfg(test)]
+mod test;
+
+pub fn apply(session: &Session, mut grammar: Grammar) -> Grammar {
+    for item in &mut grammar.items {
+        if let GrammarItem::Nonterminal(ref mut data) = *item {
+            data.alternatives
+                .retain(|alternative| cfg_active(session, &alternative.annotations));
+        }
+    }
+    grammar
+}
+
+/// Whether all the `#[cfg(...)]` among `annotations` hold: `feature =
+/// "x"` if the feature is enabled, `true` but not `false`, and any other
+/// `key = "value"` or `key` if the target configuration has it.
+pub fn cfg_active(session: &Session, annotations: &[Annotation]) -> bool {
+    annotations
+        .iter()
+        .filter(|ann| ann.id == *CFG)
+        .all(|ann| match ann.arg {
+            Some((ref key, ref feature)) if key == "feature" => session
+                .features
+                .as_ref()
+                .is_some_and(|features| features.contains(feature)),
+            Some((ref key, ref value)) if value.is_empty() && key == "true" => true,
+            Some((ref key, ref value)) if value.is_empty() && key == "false" => false,
+            Some((ref key, ref value)) => session
+                .cfg
+                .as_ref()
+                .is_some_and(|cfg| cfg.contains(&(key.to_string(), value.clone()))),
+            None => false,
+        })
+}
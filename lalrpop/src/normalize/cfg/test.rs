@@ -0,0 +1,76 @@
+use crate::grammar::parse_tree::{Grammar, GrammarItem};
+use crate::parser;
+use crate::session::Session;
+
+/// The number of alternatives of each nonterminal once the `#[cfg]`s
+/// have been decided.
+fn alternative_counts(session: &Session, text: &str) -> Vec<usize> {
+    let grammar: Grammar = super::apply(session, parser::parse_grammar(text).unwrap());
+    grammar
+        .items
+        .iter()
+        .filter_map(GrammarItem::as_nonterminal)
+        .map(|data| data.alternatives.len())
+        .collect()
+}
+
+const GRAMMAR: &str = r#"
+grammar;
+Sep = {
+    #[cfg(target_os = "windows")]
+    "\\",
+    #[cfg(unix)]
+    "/",
+    #[cfg(feature = "pipes")]
+    "|",
+    #[cfg(false)]
+    "!",
+    #[cfg(true)]
+    ":",
+};
+"#;
+
+#[test]
+fn nothing_enabled() {
+    assert_eq!(alternative_counts(&Session::test(), GRAMMAR), vec![1]);
+}
+
+#[test]
+fn target_cfg() {
+    let mut session = Session::test();
+    session.cfg = Some(
+        [("target_os", "windows"), ("target_family", "windows")]
+            .iter()
+            .map(|&(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+    );
+    assert_eq!(alternative_counts(&session, GRAMMAR), vec![2]);
+
+    session.cfg = Some(Some(("unix".to_string(), String::new())).into_iter().collect());
+    assert_eq!(alternative_counts(&session, GRAMMAR), vec![2]);
+}
+
+#[test]
+fn features() {
+    let mut session = Session::test();
+    session.features = Some(Some("pipes".to_string()).into_iter().collect());
+    assert_eq!(alternative_counts(&session, GRAMMAR), vec![2]);
+}
+
+#[test]
+fn all_must_hold() {
+    let text = r#"
+grammar;
+Sep = {
+    #[cfg(unix)]
+    #[cfg(feature = "pipes")]
+    "|",
+};
+"#;
+    let mut session = Session::test();
+    session.cfg = Some(Some(("unix".to_string(), String::new())).into_iter().collect());
+    assert_eq!(alternative_counts(&session, text), vec![0]);
+
+    session.features = Some(Some("pipes".to_string()).into_iter().collect());
+    assert_eq!(alternative_counts(&session, text), vec![1]);
+}
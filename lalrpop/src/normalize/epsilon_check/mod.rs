@@ -0,0 +1,40 @@
+//! Finds the empty alternatives without action code (like `=> ,`) of
+//! nonterminals whose type is not `()`. Such an alternative produces
+//! `()`, so it is almost always missing an action like
+//! `=> Vec::new()`. These are only warnings, which the build reports,
+//! unless the nonterminal has `#[allow(missing_empty_action)]`.
+
+use crate::grammar::parse_tree::{
+    ActionKind, Alternative, Grammar, GrammarItem, NonterminalString, Span,
+};
+use crate::grammar::repr::Types;
+use crate::lint::{self, LintKind};
+
+#[cfg(test)]
+mod test;
+
+pub fn validate(grammar: &Grammar, types: &Types) -> Vec<(NonterminalString, Span)> {
+    grammar
+        .items
+        .iter()
+        .filter_map(GrammarItem::as_nonterminal)
+        .filter(|data| !types.nonterminal_type(&data.name).is_unit())
+        .filter(|data| {
+            !lint::allowed_lints(&data.annotations).contains(&LintKind::MissingEmptyAction)
+        })
+        .flat_map(|data| {
+            data.alternatives
+                .iter()
+                .filter(|alt| alt.expr.is_empty() && !has_action_code(alt))
+                .map(move |alt| (data.name.clone(), alt.span))
+        })
+        .collect()
+}
+
+fn has_action_code(alt: &Alternative) -> bool {
+    match alt.action {
+        None => false,
+        Some(ActionKind::User(ref code)) => !code.trim().is_empty(),
+        Some(_) => true,
+    }
+}
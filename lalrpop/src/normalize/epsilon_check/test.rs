@@ -0,0 +1,50 @@
+use crate::normalize::epsilon_check::validate;
+use crate::normalize::macro_expand::expand_macros;
+use crate::normalize::token_check;
+use crate::normalize::tyinfer::infer_types;
+use crate::parser;
+
+fn epsilon_alternatives(text: &str) -> Vec<String> {
+    let grammar = parser::parse_grammar(text).unwrap();
+    let grammar = expand_macros(grammar).unwrap();
+    let grammar = token_check::validate(grammar).unwrap();
+    let types = infer_types(&grammar).unwrap();
+    validate(&grammar, &types)
+        .into_iter()
+        .map(|(nonterminal, _)| nonterminal.to_string())
+        .collect()
+}
+
+#[test]
+fn typed_without_action() {
+    assert_eq!(
+        epsilon_alternatives(
+            r#"
+grammar;
+    Items: Vec<u32> = {
+        => ,
+        <v:Items> <n:Num> => { let mut v = v; v.push(n); v },
+    };
+    Num: u32 = r"[0-9]+" => <>.parse().unwrap();
+"#
+        ),
+        vec!["Items"]
+    );
+}
+
+#[test]
+fn with_action_or_unit() {
+    assert!(epsilon_alternatives(
+        r#"
+grammar;
+    Items: Vec<u32> = {
+        => vec![],
+        <v:Items> <n:Num> => { let mut v = v; v.push(n); v },
+    };
+    Num: u32 = r"[0-9]+" => <>.parse().unwrap();
+    Nothing: () = { => , "a" => () };
+    Start: Option<u32> = <Num?> <Num*> <@L> => <>.0;
+"#
+    )
+    .is_empty());
+}
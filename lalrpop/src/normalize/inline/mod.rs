@@ -110,11 +110,28 @@ impl<'a> Inliner<'a> {
                     InlinedSymbol::Inlined(_, ref s) => s.clone(),
                 })
                 .collect();
+            let len = |sym: &InlinedSymbol| match *sym {
+                InlinedSymbol::Original(_) => 1,
+                InlinedSymbol::Inlined(_, ref s) => s.len(),
+            };
+            // the index in the new production of the symbol at `index`
+            let new_index = |index: usize| self.new_symbols[..index].iter().map(len).sum();
+            let cut = self.into_production.cut.map(new_index);
+            // an inlined symbol's name goes to the first of its symbols
+            let doc_names = self
+                .into_production
+                .doc_names
+                .iter()
+                .filter(|&&(index, _)| len(&self.new_symbols[index]) > 0)
+                .map(|(index, name)| (new_index(*index), name.clone()))
+                .collect();
             self.new_productions.push(Production {
                 nonterminal: self.into_production.nonterminal.clone(),
                 span: self.into_production.span,
                 symbols: prod_symbols,
                 action: action_fn,
+                cut,
+                doc_names,
             });
         } else {
             let next_symbol = &into_symbols[0];
@@ -1,8 +1,11 @@
 //! Lower
 //!
 
-use crate::collections::{map, Map};
-use crate::grammar::consts::CFG;
+use crate::collections::{map, set, Map, Set};
+use crate::grammar::consts::{
+    ASYNC, COLD, DISPLAY, EMIT_CST, FALLIBLE, GENERATE_FOLDER, GENERATE_VISITOR, INCREMENTAL,
+    INLINE, NO_STD, RENAME, TOKEN_SOURCE,
+};
 use crate::grammar::parse_tree as pt;
 use crate::grammar::parse_tree::{
     read_algorithm, GrammarItem, InternToken, Lifetime, MatchMapping, Name, NonterminalString,
@@ -10,6 +13,8 @@ use crate::grammar::parse_tree::{
 };
 use crate::grammar::pattern::{Pattern, PatternKind};
 use crate::grammar::repr as r;
+use crate::lint;
+use crate::normalize::cfg;
 use crate::normalize::norm_util::{self, Symbols};
 use crate::normalize::NormResult;
 use crate::session::Session;
@@ -27,8 +32,23 @@ struct LowerState<'s> {
     nonterminals: Map<NonterminalString, r::NonterminalData>,
     conversions: Vec<(TerminalString, Pattern<r::TypeRepr>)>,
     intern_token: Option<InternToken>,
+    match_terminals_by_str: bool,
+    match_terminals_by_token_trait: bool,
     types: r::Types,
     uses_error_recovery: bool,
+    uses_cut: bool,
+    // `#[fallible]`: action code returns a `Result`
+    fallible_actions: bool,
+    // `#[emit_cst]`: action fns also return a parse tree node, except
+    // for the nonterminals in `hidden_nonterminals`, which match no input
+    emit_cst: bool,
+    hidden_nonterminals: Set<NonterminalString>,
+    lookahead_assertions: Map<NonterminalString, r::LookaheadAssertion>,
+    precedences: Map<TerminalString, r::PrecedenceEntry>,
+    left_recursive: Set<NonterminalString>,
+    tests: Vec<r::TestData>,
+    display_formats: Map<r::ActionFn, String>,
+    variant_names: Map<r::ActionFn, String>,
 }
 
 impl<'s> LowerState<'s> {
@@ -41,15 +61,38 @@ impl<'s> LowerState<'s> {
             conversions: vec![],
             types,
             intern_token: None,
+            match_terminals_by_str: false,
+            match_terminals_by_token_trait: false,
             uses_error_recovery: false,
+            uses_cut: false,
+            fallible_actions: grammar
+                .annotations
+                .iter()
+                .any(|annotation| annotation.id == *FALLIBLE),
+            emit_cst: grammar
+                .annotations
+                .iter()
+                .any(|annotation| annotation.id == *EMIT_CST),
+            hidden_nonterminals: set(),
+            lookahead_assertions: map(),
+            precedences: map(),
+            left_recursive: set(),
+            tests: vec![],
+            display_formats: map(),
+            variant_names: map(),
         }
     }
 
     fn lower(mut self, grammar: pt::Grammar) -> NormResult<r::Grammar> {
+        if self.emit_cst {
+            self.add_parse_tree_types(&grammar);
+        }
+
         let start_symbols = self.synthesize_start_symbols(&grammar);
 
         let mut uses = vec![];
         let mut token_span = None;
+        let mut precedence_level = 0;
         let internal_token_path = Path {
             absolute: false,
             ids: vec![Atom::from("Token")],
@@ -61,6 +104,41 @@ impl<'s> LowerState<'s> {
                     uses.push(data);
                 }
 
+                pt::GrammarItem::Import(_) => {
+                    // Imports are resolved before normalization.
+                }
+
+                pt::GrammarItem::TypeDecl(_) => {
+                    // Moved onto the nonterminals by `resolve`.
+                }
+
+                pt::GrammarItem::PrecedenceDecl(data) => {
+                    precedence_level += 1;
+                    let entry = r::PrecedenceEntry {
+                        level: precedence_level,
+                        assoc: data.assoc,
+                    };
+                    for terminal in data.terminals {
+                        self.precedences.insert(terminal, entry);
+                    }
+                }
+
+                pt::GrammarItem::LeftRecursiveDecl(data) => {
+                    self.left_recursive.extend(data.nonterminals);
+                }
+
+                pt::GrammarItem::StartDecl(_) => {
+                    // Applied to the nonterminals by `start`.
+                }
+
+                pt::GrammarItem::Test(data) => {
+                    // a test of a nonterminal disabled by `#[cfg]` has
+                    // no parser to call
+                    if start_symbols.contains_key(&data.nonterminal) {
+                        self.tests.push(data);
+                    }
+                }
+
                 pt::GrammarItem::MatchToken(_) => {
                     // The declarations in the match token are handled
                     // fully by the `token_check` when it constructs the
@@ -110,6 +188,8 @@ impl<'s> LowerState<'s> {
                 pt::GrammarItem::ExternToken(data) => {
                     if let Some(enum_token) = data.enum_token {
                         token_span = Some(enum_token.type_span);
+                        self.match_terminals_by_str = enum_token.match_by_str;
+                        self.match_terminals_by_token_trait = enum_token.token_trait;
                         self.conversions
                             .extend(enum_token.conversions.iter().map(|conversion| {
                                 (
@@ -125,15 +205,44 @@ impl<'s> LowerState<'s> {
                     let productions: Vec<_> = nt
                         .alternatives
                         .into_iter()
-                        .map(|alt| {
+                        .map(|mut alt| {
                             let nt_type = self.types.nonterminal_type(nt_name).clone();
+                            let cut = strip_cut(&mut alt.expr);
+                            self.uses_cut |= cut.is_some();
+                            let doc_names = doc_names(&alt.expr);
                             let symbols = self.symbols(&alt.expr.symbols);
-                            let action = self.action_kind(nt_type, &alt.expr, &symbols, alt.action);
+                            let attributes = alternative_attributes(&alt.annotations);
+                            let action = self
+                                .action_kind(nt_type, &alt.expr, &symbols, alt.action, attributes);
+                            if self.emit_cst {
+                                self.parse_tree_action_fn(action, nt_name, &symbols);
+                            }
+                            if let Some(pt::Condition::Arbitrary(guard)) = alt.condition {
+                                self.guard_action_fn(action, &guard);
+                            }
+                            if let Some((_, format)) = alt
+                                .annotations
+                                .iter()
+                                .find(|ann| &*ann.id == DISPLAY)
+                                .and_then(|ann| ann.arg.clone())
+                            {
+                                self.display_formats.insert(action, format);
+                            }
+                            if let Some((_, name)) = alt
+                                .annotations
+                                .iter()
+                                .find(|ann| &*ann.id == RENAME)
+                                .and_then(|ann| ann.arg.clone())
+                            {
+                                self.variant_names.insert(action, name);
+                            }
                             r::Production {
                                 nonterminal: nt_name.clone(),
                                 span: alt.span,
                                 symbols,
                                 action,
+                                cut,
+                                doc_names,
                             }
                         })
                         .collect();
@@ -176,6 +285,72 @@ impl<'s> LowerState<'s> {
 
         read_algorithm(&grammar.annotations, &mut algorithm);
 
+        // Likewise, the GLR runtime is only implemented for parse tables
+        // (any other explicit choice was rejected in prevalidate)
+        if algorithm.construction == r::LrConstruction::Glr {
+            algorithm.codegen = r::LrCodeGeneration::TableDriven;
+        }
+
+        // Only the table-driven runtime can bound its stack (any other
+        // explicit choice was rejected in prevalidate)
+        let stack_depth = grammar
+            .annotations
+            .iter()
+            .find(|annotation| annotation.id == *NO_STD)
+            .and_then(|annotation| annotation.arg.as_ref())
+            .map(|(_, depth)| depth.parse().unwrap());
+        if stack_depth.is_some() {
+            algorithm.codegen = r::LrCodeGeneration::TableDriven;
+        }
+
+        // The same goes for the incremental runtime
+        let incremental = grammar
+            .annotations
+            .iter()
+            .any(|annotation| annotation.id == *INCREMENTAL);
+        if incremental {
+            algorithm.codegen = r::LrCodeGeneration::TableDriven;
+        }
+
+        // And for the async one
+        let async_parser = grammar
+            .annotations
+            .iter()
+            .any(|annotation| annotation.id == *ASYNC);
+        if async_parser {
+            algorithm.codegen = r::LrCodeGeneration::TableDriven;
+        }
+
+        // `#[token_source = "proc_macro2"]` added an `extern token` that
+        // matches terminals by string, which the code below handles
+        let match_terminals_by_proc_macro2 = grammar
+            .annotations
+            .iter()
+            .any(|annotation| annotation.id == *TOKEN_SOURCE);
+
+        // And for matching terminals by string, and for cuts
+        if self.match_terminals_by_str || self.uses_cut {
+            algorithm.codegen = r::LrCodeGeneration::TableDriven;
+        }
+
+        // Lookahead assertions filter the lookahead of LR(1) items, which
+        // the lane table algorithm does not compute for every state
+        if !self.lookahead_assertions.is_empty()
+            && algorithm.construction == r::LrConstruction::LaneTable
+        {
+            algorithm.construction = r::LrConstruction::CanonicalLr1;
+        }
+
+        // Precedence declarations (and `%left_recursive`) are applied to
+        // the shift/reduce conflicts of exact LR(1) states; the lane table
+        // algorithm would try to split them instead (and prevalidate
+        // rejects them under `#[LALR]`)
+        if !(self.precedences.is_empty() && self.left_recursive.is_empty())
+            && algorithm.construction == r::LrConstruction::LaneTable
+        {
+            algorithm.construction = r::LrConstruction::CanonicalLr1;
+        }
+
         let mut all_terminals: Vec<_> = self
             .conversions
             .iter()
@@ -190,8 +365,32 @@ impl<'s> LowerState<'s> {
 
         let terminal_bits: Map<_, _> = all_terminals.iter().cloned().zip(0..).collect();
 
+        let generate_visitor = grammar
+            .annotations
+            .iter()
+            .any(|annotation| annotation.id == *GENERATE_VISITOR);
+        let generate_folder = grammar
+            .annotations
+            .iter()
+            .any(|annotation| annotation.id == *GENERATE_FOLDER);
+
         Ok(r::Grammar {
             uses_error_recovery: self.uses_error_recovery,
+            generate_visitor,
+            generate_folder,
+            stack_depth,
+            incremental,
+            async_parser,
+            emit_cst: self.emit_cst,
+            lookahead_assertions: self.lookahead_assertions,
+            precedences: self.precedences,
+            left_recursive: self.left_recursive,
+            tests: self.tests,
+            display_formats: self.display_formats,
+            variant_names: self.variant_names,
+            epsilon_alternatives: vec![],
+            allowed_lints: lint::allowed_lints(&grammar.annotations),
+            nullable_nonterminals: Default::default(),
             prefix: self.prefix,
             start_nonterminals: start_symbols,
             uses,
@@ -205,6 +404,9 @@ impl<'s> LowerState<'s> {
             where_clauses,
             algorithm,
             intern_token: self.intern_token,
+            match_terminals_by_str: self.match_terminals_by_str,
+            match_terminals_by_token_trait: self.match_terminals_by_token_trait,
+            match_terminals_by_proc_macro2,
             terminals: r::TerminalSet {
                 all: all_terminals,
                 bits: terminal_bits,
@@ -223,7 +425,7 @@ impl<'s> LowerState<'s> {
             .iter()
             .filter_map(GrammarItem::as_nonterminal)
             .filter(|nt| nt.visibility.is_pub())
-            .filter(|nt| cfg_active(session, nt))
+            .filter(|nt| cfg::cfg_active(session, &nt.annotations))
             .map(|nt| {
                 // create a synthetic symbol `__Foo` for each public symbol `Foo`
                 // with a rule like:
@@ -240,12 +442,14 @@ impl<'s> LowerState<'s> {
                     )],
                 };
                 let symbols = vec![r::Symbol::Nonterminal(nt.name.clone())];
-                let action_fn = self.action_fn(nt_type, false, &expr, &symbols, None);
+                let action_fn = self.action_fn(nt_type, false, &expr, &symbols, None, vec![]);
                 let production = r::Production {
                     nonterminal: fake_name.clone(),
                     symbols,
                     action: action_fn,
                     span: nt.span,
+                    cut: None,
+                    doc_names: vec![],
                 };
                 self.nonterminals.insert(
                     fake_name.clone(),
@@ -262,6 +466,39 @@ impl<'s> LowerState<'s> {
             .collect()
     }
 
+    /// In an `#[emit_cst]` grammar, pairs the type of each nonterminal
+    /// with the type of its parse tree node. `@L` and `@R` match no
+    /// input, so keep their type and have no node.
+    fn add_parse_tree_types(&mut self, grammar: &pt::Grammar) {
+        let node_type = r::TypeRepr::Nominal(r::NominalTypeRepr {
+            path: Path {
+                absolute: false,
+                ids: vec![
+                    Atom::from(format!("{}lalrpop_util", self.prefix)),
+                    Atom::from("ParseTreeNode"),
+                ],
+            },
+            types: vec![self.types.terminal_loc_type()],
+        });
+        for nt in grammar.items.iter().filter_map(GrammarItem::as_nonterminal) {
+            let lookaround = nt.alternatives.iter().all(|alt| {
+                matches!(
+                    alt.action,
+                    Some(pt::ActionKind::Lookahead | pt::ActionKind::Lookbehind)
+                )
+            });
+            if lookaround {
+                self.hidden_nonterminals.insert(nt.name.clone());
+            } else {
+                let nt_type = self.types.nonterminal_type(&nt.name).clone();
+                self.types.replace_type(
+                    nt.name.clone(),
+                    r::TypeRepr::Tuple(vec![nt_type, node_type.clone()]),
+                );
+            }
+        }
+    }
+
     /// When we lower where clauses into `repr::WhereClause`, they get
     /// flattened; so we may go from `T: Foo + Bar` into `[T: Foo, T:
     /// Bar]`. We also convert to `TypeRepr` and so forth.
@@ -301,17 +538,31 @@ impl<'s> LowerState<'s> {
         expr: &pt::ExprSymbol,
         symbols: &[r::Symbol],
         action: Option<pt::ActionKind>,
+        attributes: Vec<String>,
     ) -> r::ActionFn {
         match action {
             Some(pt::ActionKind::Lookahead) => self.lookahead_action_fn(),
             Some(pt::ActionKind::Lookbehind) => self.lookbehind_action_fn(),
             Some(pt::ActionKind::User(string)) => {
-                self.action_fn(nt_type, false, expr, symbols, Some(string))
+                let action =
+                    self.action_fn(nt_type, false, expr, symbols, Some(string), attributes);
+                self.fallible_user_action_fn(action);
+                action
             }
             Some(pt::ActionKind::Fallible(string)) => {
-                self.action_fn(nt_type, true, expr, symbols, Some(string))
+                self.action_fn(nt_type, true, expr, symbols, Some(string), attributes)
+            }
+            Some(pt::ActionKind::ExternalFn(path)) => {
+                let call = format!("{}(<>)", path);
+                let action = self.action_fn(nt_type, false, expr, symbols, Some(call), attributes);
+                self.fallible_user_action_fn(action);
+                action
+            }
+            Some(pt::ActionKind::FallibleExternalFn(path)) => {
+                let call = format!("{}(<>)", path);
+                self.action_fn(nt_type, true, expr, symbols, Some(call), attributes)
             }
-            None => self.action_fn(nt_type, false, expr, symbols, None),
+            None => self.action_fn(nt_type, false, expr, symbols, None, attributes),
         }
     }
 
@@ -342,6 +593,7 @@ impl<'s> LowerState<'s> {
         expr: &pt::ExprSymbol,
         symbols: &[r::Symbol],
         action: Option<String>,
+        attributes: Vec<String>,
     ) -> r::ActionFn {
         let normalized_symbols = norm_util::analyze_expr(expr);
 
@@ -416,6 +668,8 @@ impl<'s> LowerState<'s> {
                         arg_patterns,
                         arg_types,
                         code: action,
+                        attributes,
+                        parse_tree: None,
                     }),
                 }
             }
@@ -438,6 +692,8 @@ impl<'s> LowerState<'s> {
                         arg_patterns,
                         arg_types,
                         code: action,
+                        attributes,
+                        parse_tree: None,
                     }),
                 }
             }
@@ -446,6 +702,76 @@ impl<'s> LowerState<'s> {
         self.add_action_fn(action_fn_defn)
     }
 
+    /// In a `#[fallible]` grammar, makes a user action fallible: its
+    /// code returns a `Result` with the grammar's error type, so that it
+    /// can use `?`, and an error is reported as `ParseError::User`.
+    fn fallible_user_action_fn(&mut self, action: r::ActionFn) {
+        if !self.fallible_actions {
+            return;
+        }
+        let error_type = self.types.error_type();
+        let defn = &mut self.action_fn_defns[action.index()];
+        if let r::ActionFnDefnKind::User(ref mut data) = defn.kind {
+            data.code = format!(
+                "(|| -> Result<_, {e}> {{ let {p}value = {c}; Ok({p}value) }})()\
+                 .map_err(|error| {p}lalrpop_util::ParseError::User {{ error }})",
+                e = error_type,
+                c = data.code,
+                p = self.prefix
+            );
+            defn.fallible = true;
+        }
+    }
+
+    /// In an `#[emit_cst]` grammar, makes the action fn of an
+    /// alternative of `nonterminal` build its parse tree node.
+    fn parse_tree_action_fn(
+        &mut self,
+        action: r::ActionFn,
+        nonterminal: &NonterminalString,
+        symbols: &[r::Symbol],
+    ) {
+        let symbols = symbols
+            .iter()
+            .map(|symbol| match symbol {
+                r::Symbol::Terminal(terminal) => r::ParseTreeSymbol::Terminal(terminal.clone()),
+                r::Symbol::Nonterminal(nt)
+                    if self.hidden_nonterminals.contains(nt)
+                        || self.lookahead_assertions.contains_key(nt) =>
+                {
+                    r::ParseTreeSymbol::Hidden
+                }
+                r::Symbol::Nonterminal(_) => r::ParseTreeSymbol::Nonterminal,
+            })
+            .collect();
+        let defn = &mut self.action_fn_defns[action.index()];
+        if let r::ActionFnDefnKind::User(ref mut data) = defn.kind {
+            data.parse_tree = Some(r::ParseTreeAction {
+                nonterminal: nonterminal.clone(),
+                symbols,
+            });
+        }
+    }
+
+    /// Makes a `#[if { guard }]` action fallible: unless the guard
+    /// holds, it fails with an `InvalidToken` error at the start of the
+    /// alternative.
+    fn guard_action_fn(&mut self, action: r::ActionFn, guard: &str) {
+        let defn = &mut self.action_fn_defns[action.index()];
+        if let r::ActionFnDefnKind::User(ref mut data) = defn.kind {
+            let ok = if defn.fallible {
+                data.code.clone()
+            } else {
+                format!("Ok({})", data.code)
+            };
+            data.code = format!(
+                "if {} {{ {} }} else {{ Err({}lalrpop_util::ParseError::InvalidToken {{ location: @L }}) }}",
+                guard, ok, self.prefix
+            );
+            defn.fallible = true;
+        }
+    }
+
     fn add_action_fn(&mut self, action_fn_defn: r::ActionFnDefn) -> r::ActionFn {
         let index = r::ActionFn::new(self.action_fn_defns.len());
         self.action_fn_defns.push(action_fn_defn);
@@ -460,24 +786,77 @@ impl<'s> LowerState<'s> {
         match symbol.kind {
             pt::SymbolKind::Terminal(ref id) => r::Symbol::Terminal(id.clone()),
             pt::SymbolKind::Nonterminal(ref id) => r::Symbol::Nonterminal(id.clone()),
-            pt::SymbolKind::Choose(ref s) | pt::SymbolKind::Name(_, ref s) => self.symbol(s),
+            pt::SymbolKind::Choose(ref s)
+            | pt::SymbolKind::Name(_, ref s)
+            | pt::SymbolKind::DocName(_, ref s) => self.symbol(s),
             pt::SymbolKind::Error => {
                 self.uses_error_recovery = true;
                 r::Symbol::Terminal(TerminalString::Error)
             }
+            pt::SymbolKind::PosLookahead(ref s) => self.lookahead_assertion(symbol, s, false),
+            pt::SymbolKind::NegLookahead(ref s) => self.lookahead_assertion(symbol, s, true),
 
             pt::SymbolKind::Macro(..)
             | pt::SymbolKind::Repeat(..)
             | pt::SymbolKind::Expr(..)
+            | pt::SymbolKind::Choice(..)
             | pt::SymbolKind::AmbiguousId(_)
             | pt::SymbolKind::Lookahead
-            | pt::SymbolKind::Lookbehind => unreachable!(
+            | pt::SymbolKind::Lookbehind
+            | pt::SymbolKind::Cut => unreachable!(
                 "symbol `{}` should have been normalized away by now",
                 symbol
             ),
         }
     }
 
+    /// Creates (once per distinct assertion) an empty nonterminal like
+    ///
+    ///     (?="x") = => ();
+    ///
+    /// which the LR(1) construction only reduces if the next token is
+    /// (or, for `(?!"x")`, is not) `"x"`.
+    fn lookahead_assertion(
+        &mut self,
+        symbol: &pt::Symbol,
+        terminal: &pt::Symbol,
+        negated: bool,
+    ) -> r::Symbol {
+        let terminal = match terminal.kind {
+            pt::SymbolKind::Terminal(ref id) => id.clone(),
+            _ => unreachable!("`{}` should have been rejected by token_check", terminal),
+        };
+
+        let name = NonterminalString(Atom::from(symbol.canonical_form()));
+        if !self.nonterminals.contains_key(&name) {
+            let unit = r::TypeRepr::Tuple(vec![]);
+            self.types.add_type(name.clone(), unit.clone());
+            let expr = pt::ExprSymbol { symbols: vec![] };
+            let action = self.action_fn(unit, false, &expr, &[], Some("()".to_string()), vec![]);
+            self.nonterminals.insert(
+                name.clone(),
+                r::NonterminalData {
+                    name: name.clone(),
+                    visibility: pt::Visibility::Priv,
+                    annotations: vec![],
+                    span: symbol.span,
+                    productions: vec![r::Production {
+                        nonterminal: name.clone(),
+                        symbols: vec![],
+                        action,
+                        span: symbol.span,
+                        cut: None,
+                        doc_names: vec![],
+                    }],
+                },
+            );
+            self.lookahead_assertions
+                .insert(name.clone(), r::LookaheadAssertion { terminal, negated });
+        }
+
+        r::Symbol::Nonterminal(name)
+    }
+
     fn fresh_name(&self, i: usize) -> Atom {
         Atom::from(format!("{}{}", self.prefix, i))
     }
@@ -506,17 +885,38 @@ where
     result
 }
 
-fn cfg_active(session: &Session, nt: &pt::NonterminalData) -> bool {
-    let cfg_atom = Atom::from(CFG);
-    nt.annotations
+/// The Rust attributes requested by the annotations of an alternative.
+fn alternative_attributes(annotations: &[pt::Annotation]) -> Vec<String> {
+    annotations
         .iter()
-        .filter(|ann| ann.id == cfg_atom)
-        .all(|ann| {
-            ann.arg.as_ref().map_or(false, |(_, feature)| {
-                session
-                    .features
-                    .as_ref()
-                    .map_or(false, |features| features.contains(feature))
-            })
+        .filter(|ann| &*ann.id == INLINE || &*ann.id == COLD)
+        .map(|ann| match ann.arg {
+            Some((ref name, _)) => format!("{}({})", ann.id, name),
+            None => ann.id.to_string(),
         })
+        .collect()
+}
+
+/// Removes the cut (`^`) from an alternative, returning the number of
+/// symbols before it. prevalidate has checked there is at most one.
+fn strip_cut(expr: &mut pt::ExprSymbol) -> Option<usize> {
+    let index = expr.symbols.iter().position(pt::Symbol::is_cut)?;
+    expr.symbols.remove(index);
+    Some(index)
+}
+
+/// The index and name of each symbol of an alternative written `@x:X`.
+fn doc_names(expr: &pt::ExprSymbol) -> Vec<(usize, Atom)> {
+    fn doc_name(symbol: &pt::Symbol) -> Option<Atom> {
+        match symbol.kind {
+            pt::SymbolKind::DocName(ref name, _) => Some(name.clone()),
+            pt::SymbolKind::Choose(ref s) | pt::SymbolKind::Name(_, ref s) => doc_name(s),
+            _ => None,
+        }
+    }
+    expr.symbols
+        .iter()
+        .enumerate()
+        .filter_map(|(index, symbol)| Some((index, doc_name(symbol)?)))
+        .collect()
 }
@@ -1,8 +1,8 @@
 use crate::grammar::consts::INLINE;
 use crate::grammar::parse_tree::{
-    ActionKind, Alternative, Annotation, Condition, ConditionOp, ExprSymbol, Grammar, GrammarItem,
-    MacroSymbol, Name, NonterminalData, NonterminalString, Path, RepeatOp, RepeatSymbol, Span,
-    Symbol, SymbolKind, TerminalLiteral, TerminalString, TypeRef, Visibility,
+    ActionKind, Alternative, Annotation, Condition, ConditionOp, ConditionRhs, ExprSymbol, Grammar,
+    GrammarItem, MacroArg, MacroSymbol, Name, NonterminalData, NonterminalString, Path, RepeatOp,
+    RepeatSymbol, Span, Symbol, SymbolKind, TerminalLiteral, TerminalString, TypeRef, Visibility,
 };
 use crate::normalize::norm_util::{self, Symbols};
 use crate::normalize::resolve;
@@ -16,6 +16,16 @@ use string_cache::DefaultAtom as Atom;
 mod test;
 
 pub fn expand_macros(input: Grammar) -> NormResult<Grammar> {
+    expand(input, false)
+}
+
+/// Like `expand_macros`, but only expands the uses of macros: repeats,
+/// parenthesized symbols and `@L`/`@R` are left in place.
+pub fn expand_macro_uses(input: Grammar) -> NormResult<Grammar> {
+    expand(input, true)
+}
+
+fn expand(input: Grammar, only_macros: bool) -> NormResult<Grammar> {
     let input = resolve::resolve(input)?;
 
     let items = input.items;
@@ -31,7 +41,7 @@ pub fn expand_macros(input: Grammar) -> NormResult<Grammar> {
         })
         .collect();
 
-    let mut expander = MacroExpander::new(macro_defs);
+    let mut expander = MacroExpander::new(macro_defs, only_macros)?;
     expander.expand(&mut items)?;
 
     Ok(Grammar { items, ..input })
@@ -39,17 +49,55 @@ pub fn expand_macros(input: Grammar) -> NormResult<Grammar> {
 
 struct MacroExpander {
     macro_defs: HashMap<NonterminalString, NonterminalData>,
+    // Canonical names of the symbols expanded so far; every use of
+    // `Comma<Expr>` refers to the same nonterminal, which is generated once.
     expansion_set: HashSet<NonterminalString>,
     expansion_stack: Vec<Symbol>,
+    // Whether to leave everything but macro uses unexpanded.
+    only_macros: bool,
+    // The regexps of the `~~` and `!~` conditions, compiled once.
+    regexes: HashMap<Atom, Regex>,
 }
 
 impl MacroExpander {
-    fn new(macro_defs: HashMap<NonterminalString, NonterminalData>) -> MacroExpander {
-        MacroExpander {
+    fn new(
+        macro_defs: HashMap<NonterminalString, NonterminalData>,
+        only_macros: bool,
+    ) -> NormResult<MacroExpander> {
+        let mut regexes = HashMap::new();
+        let conditions = macro_defs
+            .values()
+            .flat_map(|mdef| &mdef.alternatives)
+            .filter_map(|alternative| match alternative.condition {
+                Some(Condition::Macro(ref c)) => Some(c),
+                _ => None,
+            });
+        for c in conditions {
+            let regex = match (&c.op, &c.rhs) {
+                (ConditionOp::Match | ConditionOp::NotMatch, rhs) => rhs.text(),
+                (_, ConditionRhs::Literal(_)) => continue,
+                (_, ConditionRhs::Regex(_)) => return_err!(
+                    c.span,
+                    "a regular expression can only be compared with `~~` or `!~`"
+                ),
+            };
+            if !regexes.contains_key(regex) {
+                match Regex::new(regex) {
+                    Ok(re) => regexes.insert(regex.clone(), re),
+                    Err(err) => {
+                        return_err!(c.span, "invalid regular expression `{}`: {}", regex, err)
+                    }
+                };
+            }
+        }
+
+        Ok(MacroExpander {
             macro_defs,
             expansion_stack: Vec::new(),
             expansion_set: HashSet::new(),
-        }
+            only_macros,
+            regexes,
+        })
     }
 
     fn expand(&mut self, items: &mut Vec<GrammarItem>) -> NormResult<()> {
@@ -74,6 +122,9 @@ impl MacroExpander {
                         items.push(self.expand_macro_symbol(sym.span, msym)?)
                     }
                     SymbolKind::Expr(expr) => items.push(self.expand_expr_symbol(sym.span, expr)?),
+                    SymbolKind::Choice(exprs) => {
+                        items.push(self.expand_choice_symbol(sym.span, exprs)?)
+                    }
                     SymbolKind::Repeat(repeat) => {
                         items.push(self.expand_repeat_symbol(sym.span, *repeat)?)
                     }
@@ -99,6 +150,12 @@ impl MacroExpander {
             GrammarItem::ExternToken(..) => {}
             GrammarItem::InternToken(..) => {}
             GrammarItem::Use(..) => {}
+            GrammarItem::Import(..) => {}
+            GrammarItem::PrecedenceDecl(..) => {}
+            GrammarItem::TypeDecl(..) => {}
+            GrammarItem::LeftRecursiveDecl(..) => {}
+            GrammarItem::StartDecl(..) => {}
+            GrammarItem::Test(..) => {}
             GrammarItem::Nonterminal(ref mut data) => {
                 // Should not encounter macro definitions here,
                 // they've already been siphoned off.
@@ -123,24 +180,54 @@ impl MacroExpander {
                 panic!("ambiguous id `{}` encountered after name resolution", id)
             }
             SymbolKind::Macro(ref mut m) => {
-                for sym in &mut m.args {
-                    self.replace_symbol(sym);
+                for arg in &mut m.args {
+                    if let MacroArg::Symbol(ref mut sym) = *arg {
+                        self.replace_symbol(sym);
+                    }
                 }
             }
             SymbolKind::Expr(ref mut expr) => {
                 self.replace_symbols(&mut expr.symbols);
+                if self.only_macros {
+                    return;
+                }
+            }
+            SymbolKind::Choice(ref mut exprs) => {
+                for expr in exprs {
+                    self.replace_symbols(&mut expr.symbols);
+                }
+                if self.only_macros {
+                    return;
+                }
             }
             SymbolKind::Repeat(ref mut repeat) => {
                 self.replace_symbol(&mut repeat.symbol);
+                if self.only_macros {
+                    return;
+                }
             }
-            SymbolKind::Terminal(_) | SymbolKind::Nonterminal(_) | SymbolKind::Error => {
+            SymbolKind::Terminal(_)
+            | SymbolKind::Nonterminal(_)
+            | SymbolKind::Error
+            | SymbolKind::Cut => {
                 return;
             }
-            SymbolKind::Choose(ref mut sym) | SymbolKind::Name(_, ref mut sym) => {
+            SymbolKind::Choose(ref mut sym)
+            | SymbolKind::Name(_, ref mut sym)
+            | SymbolKind::DocName(_, ref mut sym) => {
                 self.replace_symbol(sym);
                 return;
             }
-            SymbolKind::Lookahead | SymbolKind::Lookbehind => {}
+            SymbolKind::Lookahead | SymbolKind::Lookbehind => {
+                if self.only_macros {
+                    return;
+                }
+            }
+            SymbolKind::PosLookahead(_) | SymbolKind::NegLookahead(_) => {
+                // lowered into an empty nonterminal by `lower`, once
+                // `token_check` has seen the terminal
+                return;
+            }
         }
 
         // only symbols we intend to expand fallthrough to here
@@ -177,11 +264,43 @@ impl MacroExpander {
             );
         }
 
-        let args: HashMap<NonterminalString, SymbolKind> = mdef
+        // a type can only be given for an argument that only appears in
+        // types, like `T` in `Parse<T>: T = ...`
+        for ((param, _), arg) in mdef.args.iter().zip(&msym.args) {
+            if let MacroArg::Type(ref type_ref) = *arg {
+                let mut symbols = mdef.alternatives.iter().flat_map(|a| &a.expr.symbols);
+                if symbols.any(|symbol| uses_symbol(symbol, param)) {
+                    return_err!(
+                        span,
+                        "no definition found for `{}`, and `{}` uses its argument `{}` \
+                         as a symbol, so it cannot be a type",
+                        type_ref,
+                        msym.name,
+                        param
+                    );
+                }
+            }
+        }
+
+        let args: HashMap<NonterminalString, MacroArg> = mdef
+            .args
+            .iter()
+            .map(|(arg, _)| arg.clone())
+            .zip(msym.args.iter().cloned())
+            .collect();
+
+        // bounds constrain the types of symbols, so those of the
+        // arguments given a type are left out
+        let arg_bounds = mdef
             .args
             .iter()
-            .cloned()
-            .zip(msym.args.into_iter().map(|s| s.kind))
+            .zip(msym.args)
+            .filter_map(|((_, bound), arg)| match (bound, arg) {
+                (Some(tr), MacroArg::Symbol(sym)) => {
+                    Some((sym, self.macro_expand_type_ref(&args, tr)))
+                }
+                _ => None,
+            })
             .collect();
 
         let type_decl = mdef
@@ -196,10 +315,15 @@ impl MacroExpander {
             if !self.evaluate_cond(&args, &alternative.condition)? {
                 continue;
             }
+            // guards are kept, to be checked by the expanded alternative
+            let condition = match alternative.condition {
+                Some(Condition::Arbitrary(_)) => alternative.condition.clone(),
+                _ => None,
+            };
             alternatives.push(Alternative {
                 span,
                 expr: self.macro_expand_expr_symbol(&args, &alternative.expr),
-                condition: None,
+                condition,
                 action: alternative.action.clone(),
                 annotations: alternative.annotations.clone(),
             });
@@ -212,13 +336,16 @@ impl MacroExpander {
             annotations: mdef.annotations.clone(),
             args: vec![],
             type_decl,
+            declared_type: None,
+            arg_bounds,
+            shared_action: mdef.shared_action.clone(),
             alternatives,
         }))
     }
 
     fn macro_expand_type_refs(
         &self,
-        args: &HashMap<NonterminalString, SymbolKind>,
+        args: &HashMap<NonterminalString, MacroArg>,
         type_refs: &[TypeRef],
     ) -> Vec<TypeRef> {
         type_refs
@@ -229,7 +356,7 @@ impl MacroExpander {
 
     fn macro_expand_type_ref(
         &self,
-        args: &HashMap<NonterminalString, SymbolKind>,
+        args: &HashMap<NonterminalString, MacroArg>,
         type_ref: &TypeRef,
     ) -> TypeRef {
         match *type_ref {
@@ -256,7 +383,8 @@ impl MacroExpander {
                 referent: Box::new(self.macro_expand_type_ref(args, referent)),
             },
             TypeRef::Id(ref id) => match args.get(&NonterminalString(id.clone())) {
-                Some(sym) => TypeRef::OfSymbol(sym.clone()),
+                Some(MacroArg::Symbol(sym)) => TypeRef::OfSymbol(sym.kind.clone()),
+                Some(MacroArg::Type(type_ref)) => type_ref.clone(),
                 None => TypeRef::Nominal {
                     path: Path::from_id(id.clone()),
                     types: vec![],
@@ -287,17 +415,22 @@ impl MacroExpander {
 
     fn evaluate_cond(
         &self,
-        args: &HashMap<NonterminalString, SymbolKind>,
+        args: &HashMap<NonterminalString, MacroArg>,
         opt_cond: &Option<Condition>,
     ) -> NormResult<bool> {
-        if let Some(ref c) = *opt_cond {
+        if let Some(Condition::Macro(ref c)) = *opt_cond {
             match args[&c.lhs] {
-                SymbolKind::Terminal(TerminalString::Literal(TerminalLiteral::Quoted(ref lhs))) => {
+                MacroArg::Symbol(Symbol {
+                    kind:
+                        SymbolKind::Terminal(TerminalString::Literal(TerminalLiteral::Quoted(ref lhs))),
+                    ..
+                }) => {
+                    let rhs = c.rhs.text();
                     match c.op {
-                        ConditionOp::Equals => Ok(lhs == &c.rhs),
-                        ConditionOp::NotEquals => Ok(lhs != &c.rhs),
-                        ConditionOp::Match => self.re_match(c.span, lhs, &c.rhs),
-                        ConditionOp::NotMatch => Ok(!self.re_match(c.span, lhs, &c.rhs)?),
+                        ConditionOp::Equals => Ok(lhs == rhs),
+                        ConditionOp::NotEquals => Ok(lhs != rhs),
+                        ConditionOp::Match => Ok(self.regexes[rhs].is_match(lhs)),
+                        ConditionOp::NotMatch => Ok(!self.regexes[rhs].is_match(lhs)),
                     }
                 }
                 ref lhs => {
@@ -314,17 +447,9 @@ impl MacroExpander {
         }
     }
 
-    fn re_match(&self, span: Span, lhs: &Atom, regex: &Atom) -> NormResult<bool> {
-        let re = match Regex::new(regex) {
-            Ok(re) => re,
-            Err(err) => return_err!(span, "invalid regular expression `{}`: {}", regex, err),
-        };
-        Ok(re.is_match(lhs))
-    }
-
     fn macro_expand_symbols(
         &self,
-        args: &HashMap<NonterminalString, SymbolKind>,
+        args: &HashMap<NonterminalString, MacroArg>,
         expr: &[Symbol],
     ) -> Vec<Symbol> {
         expr.iter()
@@ -334,7 +459,7 @@ impl MacroExpander {
 
     fn macro_expand_expr_symbol(
         &self,
-        args: &HashMap<NonterminalString, SymbolKind>,
+        args: &HashMap<NonterminalString, MacroArg>,
         expr: &ExprSymbol,
     ) -> ExprSymbol {
         ExprSymbol {
@@ -342,23 +467,53 @@ impl MacroExpander {
         }
     }
 
+    fn macro_expand_arg(
+        &self,
+        args: &HashMap<NonterminalString, MacroArg>,
+        arg: &MacroArg,
+    ) -> MacroArg {
+        match *arg {
+            MacroArg::Symbol(ref sym) => match sym.kind {
+                // passes a type given to this macro on to the one used
+                SymbolKind::Nonterminal(ref id)
+                    if matches!(args.get(id), Some(MacroArg::Type(_))) =>
+                {
+                    args[id].clone()
+                }
+                _ => MacroArg::Symbol(self.macro_expand_symbol(args, sym)),
+            },
+            MacroArg::Type(ref tr) => MacroArg::Type(self.macro_expand_type_ref(args, tr)),
+        }
+    }
+
     fn macro_expand_symbol(
         &self,
-        args: &HashMap<NonterminalString, SymbolKind>,
+        args: &HashMap<NonterminalString, MacroArg>,
         symbol: &Symbol,
     ) -> Symbol {
         let kind = match symbol.kind {
             SymbolKind::Expr(ref expr) => {
                 SymbolKind::Expr(self.macro_expand_expr_symbol(args, expr))
             }
+            SymbolKind::Choice(ref exprs) => SymbolKind::Choice(
+                exprs
+                    .iter()
+                    .map(|expr| self.macro_expand_expr_symbol(args, expr))
+                    .collect(),
+            ),
             SymbolKind::Terminal(ref id) => SymbolKind::Terminal(id.clone()),
             SymbolKind::Nonterminal(ref id) => match args.get(id) {
-                Some(sym) => sym.clone(),
+                Some(MacroArg::Symbol(sym)) => sym.kind.clone(),
+                Some(MacroArg::Type(_)) => unreachable!("type given for symbol `{}`", id),
                 None => SymbolKind::Nonterminal(id.clone()),
             },
             SymbolKind::Macro(ref msym) => SymbolKind::Macro(MacroSymbol {
                 name: msym.name.clone(),
-                args: self.macro_expand_symbols(args, &msym.args),
+                args: msym
+                    .args
+                    .iter()
+                    .map(|arg| self.macro_expand_arg(args, arg))
+                    .collect(),
             }),
             SymbolKind::Repeat(ref r) => SymbolKind::Repeat(Box::new(RepeatSymbol {
                 op: r.op,
@@ -371,9 +526,19 @@ impl MacroExpander {
                 Name::new(id.mutable, id.name.clone()),
                 Box::new(self.macro_expand_symbol(args, sym)),
             ),
+            SymbolKind::DocName(ref name, ref sym) => {
+                SymbolKind::DocName(name.clone(), Box::new(self.macro_expand_symbol(args, sym)))
+            }
             SymbolKind::Lookahead => SymbolKind::Lookahead,
             SymbolKind::Lookbehind => SymbolKind::Lookbehind,
+            SymbolKind::PosLookahead(ref sym) => {
+                SymbolKind::PosLookahead(Box::new(self.macro_expand_symbol(args, sym)))
+            }
+            SymbolKind::NegLookahead(ref sym) => {
+                SymbolKind::NegLookahead(Box::new(self.macro_expand_symbol(args, sym)))
+            }
             SymbolKind::Error => SymbolKind::Error,
+            SymbolKind::Cut => SymbolKind::Cut,
             SymbolKind::AmbiguousId(ref id) => {
                 panic!("ambiguous id `{}` encountered after name resolution", id)
             }
@@ -390,29 +555,7 @@ impl MacroExpander {
 
     fn expand_expr_symbol(&mut self, span: Span, expr: ExprSymbol) -> NormResult<GrammarItem> {
         let name = NonterminalString(Atom::from(expr.canonical_form()));
-
-        let (action, ty_ref) =
-            match norm_util::analyze_expr(&expr) {
-                Symbols::Named(names) => {
-                    let (_, ref ex_id, ex_sym) = names[0];
-                    return_err!(
-                    span,
-                    "named symbols like `{}:{}` are only allowed at the top-level of a nonterminal",
-                    ex_id, ex_sym)
-                }
-                Symbols::Anon(syms) => (
-                    if syms.len() == 1 {
-                        action("<>")
-                    } else {
-                        action("(<>)")
-                    },
-                    maybe_tuple(
-                        syms.into_iter()
-                            .map(|(_, s)| TypeRef::OfSymbol(s.kind.clone()))
-                            .collect(),
-                    ),
-                ),
-            };
+        let (action, ty_ref) = expr_action(span, &expr)?;
 
         Ok(GrammarItem::Nonterminal(NonterminalData {
             visibility: Visibility::Priv,
@@ -421,6 +564,9 @@ impl MacroExpander {
             annotations: inline(span),
             args: vec![],
             type_decl: Some(ty_ref),
+            declared_type: None,
+            arg_bounds: vec![],
+            shared_action: None,
             alternatives: vec![Alternative {
                 span,
                 expr,
@@ -431,6 +577,47 @@ impl MacroExpander {
         }))
     }
 
+    ///////////////////////////////////////////////////////////////////////////
+    // Choice expansion
+
+    /// `("x" | "y" Z)` becomes an inline nonterminal with one alternative
+    /// per sequence. Its type is that of the first sequence; the others
+    /// must produce the same type.
+    fn expand_choice_symbol(
+        &mut self,
+        span: Span,
+        exprs: Vec<ExprSymbol>,
+    ) -> NormResult<GrammarItem> {
+        let name = NonterminalString(Atom::from(SymbolKind::Choice(exprs.clone()).to_string()));
+
+        let mut type_decl = None;
+        let mut alternatives = vec![];
+        for expr in exprs {
+            let (action, ty_ref) = expr_action(span, &expr)?;
+            type_decl.get_or_insert(ty_ref);
+            alternatives.push(Alternative {
+                span,
+                expr,
+                condition: None,
+                action,
+                annotations: Vec::new(),
+            });
+        }
+
+        Ok(GrammarItem::Nonterminal(NonterminalData {
+            visibility: Visibility::Priv,
+            span,
+            name,
+            annotations: inline(span),
+            args: vec![],
+            type_decl,
+            declared_type: None,
+            arg_bounds: vec![],
+            shared_action: None,
+            alternatives,
+        }))
+    }
+
     ///////////////////////////////////////////////////////////////////////////
     // Expr expansion
 
@@ -465,6 +652,9 @@ impl MacroExpander {
                     annotations: inline(span),
                     args: vec![],
                     type_decl: Some(ty_ref),
+                    declared_type: None,
+                    arg_bounds: vec![],
+                    shared_action: None,
                     alternatives: vec![
                         // X* =
                         Alternative {
@@ -511,6 +701,9 @@ impl MacroExpander {
                     annotations: vec![],
                     args: vec![],
                     type_decl: Some(ty_ref),
+                    declared_type: None,
+                    arg_bounds: vec![],
+                    shared_action: None,
                     alternatives: vec![
                         // X+ = X
                         Alternative {
@@ -565,6 +758,9 @@ impl MacroExpander {
                     annotations: inline(span),
                     args: vec![],
                     type_decl: Some(ty_ref),
+                    declared_type: None,
+                    arg_bounds: vec![],
+                    shared_action: None,
                     alternatives: vec![
                         // X? = X => Some(<>)
                         Alternative {
@@ -587,6 +783,86 @@ impl MacroExpander {
                     ],
                 }))
             }
+
+            RepeatOp::Count { min, max } => {
+                let path = Path::vec();
+                let ty_ref = TypeRef::Nominal {
+                    path,
+                    types: vec![base_symbol_ty],
+                };
+
+                // `X{n}` is `<e0:X> ... <en-1:X>`
+                let counted = |count: usize| -> (Vec<Symbol>, Vec<String>) {
+                    (0..count)
+                        .map(|i| {
+                            let name = format!("e{}", i);
+                            let symbol = Symbol::new(
+                                span,
+                                SymbolKind::Name(
+                                    Name::immut(Atom::from(&name[..])),
+                                    Box::new(repeat.symbol.clone()),
+                                ),
+                            );
+                            (symbol, name)
+                        })
+                        .unzip()
+                };
+
+                let alternatives = match max {
+                    // X{n,m} = X{n} | X{n+1} | ... | X{m}
+                    Some(max) => (min..=max)
+                        .map(|count| {
+                            let (symbols, names) = counted(count);
+                            Alternative {
+                                span,
+                                expr: ExprSymbol { symbols },
+                                condition: None,
+                                action: action(&format!("alloc::vec![{}]", names.join(", "))),
+                                annotations: vec![],
+                            }
+                        })
+                        .collect(),
+
+                    // X{n,} = X{n} <v:X*>
+                    None => {
+                        let (mut symbols, names) = counted(min);
+                        let star_repeat = Box::new(RepeatSymbol {
+                            op: RepeatOp::Star,
+                            symbol: repeat.symbol,
+                        });
+                        symbols.push(Symbol::new(
+                            span,
+                            SymbolKind::Name(
+                                Name::immut(v),
+                                Box::new(Symbol::new(span, SymbolKind::Repeat(star_repeat))),
+                            ),
+                        ));
+                        vec![Alternative {
+                            span,
+                            expr: ExprSymbol { symbols },
+                            condition: None,
+                            action: action(&format!(
+                                "{{ let mut e = alloc::vec![{}]; e.extend(v); e }}",
+                                names.join(", ")
+                            )),
+                            annotations: vec![],
+                        }]
+                    }
+                };
+
+                Ok(GrammarItem::Nonterminal(NonterminalData {
+                    visibility: Visibility::Priv,
+                    span,
+                    name,
+                    annotations: vec![],
+                    args: vec![],
+                    type_decl: Some(ty_ref),
+                    declared_type: None,
+                    arg_bounds: vec![],
+                    shared_action: None,
+                    alternatives,
+                }))
+            }
         }
     }
 
@@ -604,6 +880,9 @@ impl MacroExpander {
             annotations: inline(span),
             args: vec![],
             type_decl: None,
+            declared_type: None,
+            arg_bounds: vec![],
+            shared_action: None,
             alternatives: vec![Alternative {
                 span,
                 expr: ExprSymbol { symbols: vec![] },
@@ -615,6 +894,39 @@ impl MacroExpander {
     }
 }
 
+/// Whether `symbol` uses the nonterminal (or macro argument) `name`,
+/// other than by passing it on to another macro.
+fn uses_symbol(symbol: &Symbol, name: &NonterminalString) -> bool {
+    match symbol.kind {
+        SymbolKind::Nonterminal(ref id) => id == name,
+        SymbolKind::Expr(ref expr) => expr.symbols.iter().any(|s| uses_symbol(s, name)),
+        SymbolKind::Choice(ref exprs) => exprs
+            .iter()
+            .flat_map(|expr| &expr.symbols)
+            .any(|s| uses_symbol(s, name)),
+        SymbolKind::Macro(ref msym) => msym.args.iter().any(|arg| match *arg {
+            MacroArg::Symbol(Symbol {
+                kind: SymbolKind::Nonterminal(_),
+                ..
+            }) => false,
+            MacroArg::Symbol(ref s) => uses_symbol(s, name),
+            MacroArg::Type(_) => false,
+        }),
+        SymbolKind::Repeat(ref repeat) => uses_symbol(&repeat.symbol, name),
+        SymbolKind::Choose(ref s)
+        | SymbolKind::Name(_, ref s)
+        | SymbolKind::DocName(_, ref s)
+        | SymbolKind::PosLookahead(ref s)
+        | SymbolKind::NegLookahead(ref s) => uses_symbol(s, name),
+        SymbolKind::Terminal(_)
+        | SymbolKind::AmbiguousId(_)
+        | SymbolKind::Lookahead
+        | SymbolKind::Lookbehind
+        | SymbolKind::Error
+        | SymbolKind::Cut => false,
+    }
+}
+
 fn maybe_tuple(v: Vec<TypeRef>) -> TypeRef {
     if v.len() == 1 {
         v.into_iter().next().unwrap()
@@ -623,6 +935,34 @@ fn maybe_tuple(v: Vec<TypeRef>) -> TypeRef {
     }
 }
 
+/// The action and type of a parenthesized sequence: the value of its
+/// one symbol, or a tuple of the values of all of them.
+fn expr_action(span: Span, expr: &ExprSymbol) -> NormResult<(Option<ActionKind>, TypeRef)> {
+    match norm_util::analyze_expr(expr) {
+        Symbols::Named(names) => {
+            let (_, ref ex_id, ex_sym) = names[0];
+            return_err!(
+                span,
+                "named symbols like `{}:{}` are only allowed at the top-level of a nonterminal",
+                ex_id,
+                ex_sym
+            )
+        }
+        Symbols::Anon(syms) => Ok((
+            if syms.len() == 1 {
+                action("<>")
+            } else {
+                action("(<>)")
+            },
+            maybe_tuple(
+                syms.into_iter()
+                    .map(|(_, s)| TypeRef::OfSymbol(s.kind.clone()))
+                    .collect(),
+            ),
+        )),
+    }
+}
+
 fn action(s: &str) -> Option<ActionKind> {
     Some(ActionKind::User(s.to_string()))
 }
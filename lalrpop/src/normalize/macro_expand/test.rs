@@ -1,3 +1,4 @@
+use crate::grammar::parse_tree::GrammarItem;
 use crate::parser;
 use crate::test_util::compare;
 
@@ -55,6 +56,37 @@ grammar;
     compare(actual, expected);
 }
 
+#[test]
+fn test_shared_expansion() {
+    let grammar = parser::parse_grammar(
+        r#"
+grammar;
+    Twice<E>: (E, E) = E E;
+
+    A = Twice<"Id">;
+    B = Twice<"Id"> ";";
+    C = Twice< "Id" >;
+"#,
+    )
+    .unwrap();
+
+    let actual = expand_macros(grammar).unwrap();
+
+    let expected = parser::parse_grammar(
+        r##"
+grammar;
+    A = `Twice<"Id">`;
+    B = `Twice<"Id">` ";";
+    C = `Twice<"Id">`;
+
+    `Twice<"Id">`: (#"Id"#, #"Id"#) = "Id" "Id";
+"##,
+    )
+    .unwrap();
+
+    compare(actual, expected);
+}
+
 #[test]
 fn test_if_match() {
     let grammar = parser::parse_grammar(
@@ -93,6 +125,56 @@ grammar;
     compare(actual, expected);
 }
 
+#[test]
+fn test_if_match_regex() {
+    let grammar = parser::parse_grammar(
+        r#"
+grammar;
+    Expr<E> = {
+       "A" if E ~~ r"^\w+$",
+       "B" if E !~ r"^\w+$"
+    };
+
+    Expr1 = Expr<"AAC">;
+    Expr2 = Expr<"A*C">;
+"#,
+    )
+    .unwrap();
+
+    let actual = expand_macros(grammar).unwrap();
+
+    let expected = parser::parse_grammar(
+        r#"
+grammar;
+    Expr1 = `Expr<"AAC">`;
+    Expr2 = `Expr<"A*C">`;
+
+    `Expr<"A*C">` = "B";
+    `Expr<"AAC">` = "A";
+"#,
+    )
+    .unwrap();
+
+    compare(actual, expected);
+}
+
+#[test]
+fn test_if_regex_equals() {
+    let grammar = parser::parse_grammar(
+        r#"
+grammar;
+    Expr<E> = "A" if E == r"^\w+$";
+"#,
+    )
+    .unwrap();
+
+    let error = expand_macros(grammar).unwrap_err();
+    assert_eq!(
+        error.message,
+        "a regular expression can only be compared with `~~` or `!~`"
+    );
+}
+
 #[test]
 fn test_lookahead() {
     let grammar = parser::parse_grammar(
@@ -116,3 +198,153 @@ fn test_lookahead() {
 
     compare(actual, expected);
 }
+
+#[test]
+fn test_choice() {
+    let grammar = parser::parse_grammar(
+        r#"
+        grammar;
+        Num = ("+" | "-") "1";
+"#,
+    )
+    .unwrap();
+
+    let actual = expand_macros(grammar).unwrap();
+
+    let expected = parser::parse_grammar(
+        r##"
+        grammar;
+        Num = `("+" | "-")` "1";
+        #[inline] `("+" | "-")`: #"+"# = { "+" => <>, "-" => <> };
+"##,
+    )
+    .unwrap();
+
+    compare(actual, expected);
+}
+
+#[test]
+fn test_resolve_of_symbol() {
+    let grammar = parser::parse_grammar(
+        r#"
+grammar;
+    Comma<E>: Vec<E> =
+       <v:(<E> ",")*> <e:E?> =>
+           v.into_iter().chain(e.into_iter()).collect();
+
+    Num: i32 = "Num" => 0;
+    Nums = Comma<Num>;
+    Ids = Comma<"Id">;
+    Untyped = Comma<Ids>;
+"#,
+    )
+    .unwrap();
+
+    let grammar = expand_macros(grammar).unwrap();
+    let type_of = |name: &str| {
+        grammar
+            .items
+            .iter()
+            .filter_map(GrammarItem::as_nonterminal)
+            .find(|data| data.name.0 == *name)
+            .and_then(|data| data.type_decl.as_ref())
+            .map(|ty| ty.resolve(&grammar).map(|ty| ty.to_string()))
+    };
+
+    assert_eq!(type_of("Comma<Num>"), Some(Some("Vec<i32>".to_string())));
+    assert_eq!(
+        type_of("Comma<\"Id\">"),
+        Some(Some("Vec<&'input str>".to_string()))
+    );
+    assert_eq!(type_of("Comma<Ids>"), Some(None));
+}
+
+#[test]
+fn test_type_arg() {
+    let grammar = parser::parse_grammar(
+        r#"
+grammar;
+    Parse<T>: T = "Num" => <>.parse().unwrap();
+    List<T>: Vec<T> = Parse<T>+;
+
+    Byte = Parse<u8>;
+    Lists = List<Vec<u32>>;
+"#,
+    )
+    .unwrap();
+
+    let actual = expand_macros(grammar).unwrap();
+
+    let expected = parser::parse_grammar(
+        r#"
+grammar;
+    Byte = `Parse<u8>`;
+    Lists = `List<Vec<u32>>`;
+
+    `List<Vec<u32>>`: Vec<Vec<u32>> = `Parse<Vec<u32>>+`;
+
+    `Parse<u8>`: u8 = "Num" => <>.parse().unwrap();
+
+    `Parse<Vec<u32>>+`: alloc::vec::Vec<#`Parse<Vec<u32>>`#> = {
+        `Parse<Vec<u32>>` => alloc::vec![<>],
+        <v:`Parse<Vec<u32>>+`> <e:`Parse<Vec<u32>>`> => { let mut v = v; v.push(e); v },
+    };
+
+    `Parse<Vec<u32>>`: Vec<u32> = "Num" => <>.parse().unwrap();
+"#,
+    )
+    .unwrap();
+
+    compare(actual, expected);
+}
+
+#[test]
+fn test_type_arg_used_as_symbol() {
+    let grammar = parser::parse_grammar(
+        r#"
+grammar;
+    Comma<T>: Vec<T> = (<T> ",")*;
+    Nums = Comma<Num>;
+"#,
+    )
+    .unwrap();
+
+    let error = expand_macros(grammar).unwrap_err();
+    assert_eq!(
+        error.message,
+        "no definition found for `Num`, and `Comma` uses its argument `T` as a symbol, \
+         so it cannot be a type"
+    );
+}
+
+#[test]
+fn test_nested_inline_repeats() {
+    // repeats of parenthesized groups nest without a named macro, each
+    // group getting an anonymous nonterminal of its own
+    let grammar = parser::parse_grammar(
+        r#"
+grammar;
+    Args = "(" (<"Id"> ("," <"Id">)*)? ")";
+"#,
+    )
+    .unwrap();
+
+    let actual = expand_macros(grammar).unwrap();
+    let names: Vec<_> = actual
+        .items
+        .iter()
+        .filter_map(GrammarItem::as_nonterminal)
+        .map(|nt| nt.name.to_string())
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            "Args",
+            r#"(<"Id"> ("," <"Id">)*)?"#,
+            r#"(<"Id"> ("," <"Id">)*)"#,
+            r#"("," <"Id">)*"#,
+            r#"("," <"Id">)"#,
+            r#"("," <"Id">)+"#,
+        ]
+    );
+}
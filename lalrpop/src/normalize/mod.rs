@@ -33,6 +33,15 @@ pub fn normalize_without_validating(grammar: pt::Grammar) -> NormResult<r::Gramm
     normalize_helper(&Session::new(), grammar, false)
 }
 
+/// Expands the uses of macros and the levels of `#[precedence]`
+/// nonterminals, but keeps repeats like `X*` and parenthesized symbols,
+/// for printing the grammar in another notation.
+pub fn expand_macro_uses(grammar: pt::Grammar) -> NormResult<pt::Grammar> {
+    let grammar = resolve::resolve(grammar)?;
+    let grammar = precedence::expand_precedence(grammar)?;
+    macro_expand::expand_macro_uses(grammar)
+}
+
 fn normalize_helper(
     session: &Session,
     grammar: pt::Grammar,
@@ -44,6 +53,7 @@ fn normalize_helper(
 }
 
 fn lower_helper(session: &Session, grammar: pt::Grammar, validate: bool) -> NormResult<r::Grammar> {
+    let grammar = profile!(session, "Start symbols", start::apply(grammar)?);
     profile!(
         session,
         "Grammar validation",
@@ -51,7 +61,14 @@ fn lower_helper(session: &Session, grammar: pt::Grammar, validate: bool) -> Norm
             prevalidate::validate(&grammar)?;
         }
     );
+    let grammar = profile!(
+        session,
+        "Conditional alternatives",
+        cfg::apply(session, grammar)
+    );
+    let grammar = profile!(session, "Token source", token_source::apply(grammar));
     let grammar = profile!(session, "Grammar resolution", resolve::resolve(grammar)?);
+    let grammar = profile!(session, "Shared actions", shared_action::apply(grammar));
     let grammar = profile!(
         session,
         "Precedence expansion",
@@ -64,18 +81,45 @@ fn lower_helper(session: &Session, grammar: pt::Grammar, validate: bool) -> Norm
     );
     let grammar = profile!(session, "Token check", token_check::validate(grammar)?);
     let types = profile!(session, "Infer types", tyinfer::infer_types(&grammar)?);
-    let grammar = profile!(session, "Lowering", lower::lower(session, grammar, types)?);
+    let epsilon_alternatives = profile!(
+        session,
+        "Epsilon check",
+        epsilon_check::validate(&grammar, &types)
+    );
+    let mut grammar = profile!(session, "Lowering", lower::lower(session, grammar, types)?);
+    grammar.epsilon_alternatives = epsilon_alternatives;
     Ok(grammar)
 }
 
 // These are executed *IN ORDER*:
 
+// Makes the nonterminals named by `%start` public.
+//
+// AFTER THIS POINT: The start symbols are the `pub` nonterminals.
+mod start;
+
 // Check most safety conditions.
 mod prevalidate;
 
+// Removes the alternatives disabled by `#[cfg(...)]`.
+//
+// AFTER THIS POINT: All alternatives are part of the grammar.
+mod cfg;
+
+// Adds the `extern` block of `#[token_source = "..."]`.
+//
+// AFTER THIS POINT: The tokens of the grammar come from an `extern`
+// block, a `match` block, or the terminals of the grammar.
+mod token_source;
+
 // Resolve identifiers into terminals/nonterminals etc.
 mod resolve;
 
+// Applies `#[shared_action => code]` to the actions of the alternatives.
+//
+// AFTER THIS POINT: No more shared actions.
+mod shared_action;
+
 mod precedence;
 
 // Expands macros and expressions
@@ -104,6 +148,10 @@ mod token_check;
 // providing all nonterminals with an explicit type.
 mod tyinfer;
 
+// Finds empty alternatives that produce `()` for a nonterminal of
+// another type.
+mod epsilon_check;
+
 // Lowers the parse tree to the repr notation.
 mod lower;
 
@@ -50,8 +50,15 @@ pub fn analyze_expr(expr: &ExprSymbol) -> Symbols<'_> {
         return Symbols::Anon(chosen_symbol_types);
     }
 
-    // If they didn't choose anything with `<>`, make a tuple of everything.
-    Symbols::Anon(expr.symbols.iter().enumerate().collect())
+    // If they didn't choose anything with `<>`, make a tuple of everything
+    // (except a cut, which has no value).
+    Symbols::Anon(
+        expr.symbols
+            .iter()
+            .enumerate()
+            .filter(|(_, sym)| !sym.is_cut())
+            .collect(),
+    )
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
@@ -10,8 +10,8 @@
 use super::resolve;
 use super::NormResult;
 use crate::grammar::parse_tree::{
-    Alternative, ExprSymbol, Grammar, GrammarItem, NonterminalData, NonterminalString, Symbol,
-    SymbolKind,
+    Alternative, ExprSymbol, Grammar, GrammarItem, MacroArg, NonterminalData, NonterminalString,
+    Symbol, SymbolKind, Visibility,
 };
 use std::fmt;
 use std::str::FromStr;
@@ -282,13 +282,23 @@ fn expand_nonterm(mut nonterm: NonterminalData) -> NormResult<Vec<GrammarItem>>
                 });
             }
 
+            // Only the last level is reachable from outside.
+            let visibility = if *lvl == lvl_max {
+                nonterm.visibility.clone()
+            } else {
+                Visibility::Priv
+            };
+
             GrammarItem::Nonterminal(NonterminalData {
-                visibility: nonterm.visibility.clone(),
+                visibility,
                 name,
                 annotations: nonterm.annotations.clone(),
                 span: nonterm.span,
                 args: nonterm.args.clone(), // macro arguments
                 type_decl: nonterm.type_decl.clone(),
+                declared_type: nonterm.declared_type.clone(),
+                arg_bounds: nonterm.arg_bounds.clone(),
+                shared_action: nonterm.shared_action.clone(),
                 alternatives,
             })
         });
@@ -347,28 +357,40 @@ fn replace_symbol<'a>(
             }
         },
         SymbolKind::Macro(ref mut m) => {
+            let symbols = m.args.iter_mut().filter_map(|arg| match *arg {
+                MacroArg::Symbol(ref mut sym) => Some(sym),
+                MacroArg::Type(_) => None,
+            });
             if dir == Direction::Forward {
-                m.args
-                    .iter_mut()
-                    .fold(subst, |subst, sym| replace_symbol(sym, target, subst, dir))
+                symbols.fold(subst, |subst, sym| replace_symbol(sym, target, subst, dir))
             } else {
-                m.args
-                    .iter_mut()
+                symbols
                     .rev()
                     .fold(subst, |subst, sym| replace_symbol(sym, target, subst, dir))
             }
         }
         SymbolKind::Expr(ref mut expr) => replace_symbols(&mut expr.symbols, target, subst, dir),
+        // each sequence starts from the same plan; if any of them used
+        // up the first occurrence, so does the choice
+        SymbolKind::Choice(ref mut exprs) => exprs.iter_mut().fold(subst, |after, expr| {
+            match replace_symbols(&mut expr.symbols, target, subst, dir) {
+                Substitution::Every(snd) => Substitution::Every(snd),
+                Substitution::OneThen(..) => after,
+            }
+        }),
         SymbolKind::Repeat(ref mut repeat) => {
             replace_symbol(&mut repeat.symbol, target, subst, dir)
         }
-        SymbolKind::Choose(ref mut sym) | SymbolKind::Name(_, ref mut sym) => {
-            replace_symbol(sym, target, subst, dir)
-        }
+        SymbolKind::Choose(ref mut sym)
+        | SymbolKind::Name(_, ref mut sym)
+        | SymbolKind::DocName(_, ref mut sym) => replace_symbol(sym, target, subst, dir),
         SymbolKind::Terminal(_)
         | SymbolKind::Nonterminal(_)
         | SymbolKind::Error
+        | SymbolKind::Cut
         | SymbolKind::Lookahead
-        | SymbolKind::Lookbehind => subst,
+        | SymbolKind::Lookbehind
+        | SymbolKind::PosLookahead(_)
+        | SymbolKind::NegLookahead(_) => subst,
     }
 }
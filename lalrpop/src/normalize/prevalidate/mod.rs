@@ -4,10 +4,11 @@ use super::norm_util::{self, Symbols};
 use super::precedence;
 use super::{NormError, NormResult};
 
-use crate::collections::{set, Multimap};
+use crate::collections::{set, Multimap, Set};
 use crate::grammar::consts::*;
 use crate::grammar::parse_tree::*;
 use crate::grammar::repr as r;
+use crate::lint::LintKind;
 use crate::util::Sep;
 use string_cache::DefaultAtom as Atom;
 
@@ -46,10 +47,23 @@ impl<'grammar> Validator<'grammar> {
     fn validate(&self) -> NormResult<()> {
         let allowed_names = vec![
             Atom::from(LALR),
+            Atom::from(LR1),
+            Atom::from(AMBIGUOUS),
             Atom::from(TABLE_DRIVEN),
             Atom::from(RECURSIVE_ASCENT),
             Atom::from(TEST_ALL),
+            Atom::from(GENERATE_VISITOR),
+            Atom::from(GENERATE_FOLDER),
+            Atom::from(NO_STD),
+            Atom::from(INCREMENTAL),
+            Atom::from(ASYNC),
+            Atom::from(TOKEN_SOURCE),
+            Atom::from(FALLIBLE),
+            Atom::from(EMIT_CST),
+            Atom::from(ALLOW),
         ];
+        let construction_names = [Atom::from(LALR), Atom::from(LR1), Atom::from(AMBIGUOUS)];
+        let mut construction: Option<&Annotation> = None;
         for annotation in &self.grammar.annotations {
             if !allowed_names.contains(&annotation.id) {
                 return_err!(
@@ -58,12 +72,186 @@ impl<'grammar> Validator<'grammar> {
                     annotation.id
                 );
             }
+            if annotation.id == *ALLOW {
+                validate_allow(annotation)?;
+            }
+            if construction_names.contains(&annotation.id) {
+                if let Some(previous) = construction {
+                    return_err!(
+                        annotation.id_span,
+                        "`#[{}]` conflicts with the earlier `#[{}]` annotation",
+                        annotation.id,
+                        previous.id
+                    );
+                }
+                construction = Some(annotation);
+            }
+        }
+
+        if let Some(ambiguous) = construction.filter(|a| a.id == *AMBIGUOUS) {
+            let mut algorithm = r::Algorithm::default();
+            read_algorithm(&self.grammar.annotations, &mut algorithm);
+            if algorithm.codegen != r::LrCodeGeneration::TableDriven {
+                return_err!(
+                    ambiguous.id_span,
+                    "ambiguous grammars are only supported by table-driven parsers"
+                );
+            }
+        }
+
+        if let Some(no_std) = self.grammar.annotations.iter().find(|a| a.id == *NO_STD) {
+            match no_std.arg {
+                Some((ref name, ref value)) if name == "stack_depth" => {
+                    if value.parse::<usize>().map_or(true, |depth| depth == 0) {
+                        return_err!(
+                            no_std.id_span,
+                            "could not parse the stack depth `{}`, expected a positive integer",
+                            value
+                        );
+                    }
+                }
+                _ => return_err!(
+                    no_std.id_span,
+                    r#"`no_std` annotations must have a `stack_depth = "N"` argument"#
+                ),
+            }
+
+            let mut algorithm = r::Algorithm::default();
+            read_algorithm(&self.grammar.annotations, &mut algorithm);
+            if algorithm.construction == r::LrConstruction::Glr {
+                return_err!(
+                    no_std.id_span,
+                    "a bounded stack cannot be used in ambiguous grammars"
+                );
+            }
+            if algorithm.codegen != r::LrCodeGeneration::TableDriven {
+                return_err!(
+                    no_std.id_span,
+                    "a bounded stack is only supported by table-driven parsers"
+                );
+            }
+        }
+
+        let token_source = self
+            .grammar
+            .annotations
+            .iter()
+            .find(|a| a.id == *TOKEN_SOURCE);
+        if let Some(token_source) = token_source {
+            match token_source.arg {
+                Some((ref name, ref value)) if name.is_empty() && value == PROC_MACRO2 => {}
+                _ => return_err!(
+                    token_source.id_span,
+                    r#"`token_source` annotations must be `#[token_source = "proc_macro2"]`"#
+                ),
+            }
+            // the tokens are those of the source, so the grammar cannot
+            // declare any of its own
+            if let Some(extern_token) = self.extern_token {
+                return_err!(
+                    extern_token.span,
+                    "a grammar with a `#[token_source]` cannot have an `extern` block"
+                );
+            }
+            if let Some(match_token) = self.match_token {
+                return_err!(
+                    match_token.span,
+                    "a grammar with a `#[token_source]` cannot have a `match` block"
+                );
+            }
+
+            let mut algorithm = r::Algorithm::default();
+            read_algorithm(&self.grammar.annotations, &mut algorithm);
+            if algorithm.codegen != r::LrCodeGeneration::TableDriven {
+                return_err!(
+                    token_source.id_span,
+                    "`#[token_source]` is only supported by table-driven parsers"
+                );
+            }
         }
 
+        if let Some(incremental) = self
+            .grammar
+            .annotations
+            .iter()
+            .find(|a| a.id == *INCREMENTAL)
+        {
+            if self.extern_token.is_some_and(|t| t.enum_token.is_some()) || token_source.is_some() {
+                return_err!(
+                    incremental.id_span,
+                    "incremental parsers require the built-in lexer"
+                );
+            }
+
+            let mut algorithm = r::Algorithm::default();
+            read_algorithm(&self.grammar.annotations, &mut algorithm);
+            if algorithm.construction == r::LrConstruction::Glr {
+                return_err!(
+                    incremental.id_span,
+                    "incremental parsers cannot be generated for ambiguous grammars"
+                );
+            }
+            if algorithm.codegen != r::LrCodeGeneration::TableDriven {
+                return_err!(
+                    incremental.id_span,
+                    "incremental parsers are only supported by table-driven parsers"
+                );
+            }
+        }
+
+        if let Some(async_parser) = self.grammar.annotations.iter().find(|a| a.id == *ASYNC) {
+            // the tokens come from a stream, not from text to be lexed
+            if !self.extern_token.is_some_and(|t| t.enum_token.is_some()) && token_source.is_none()
+            {
+                return_err!(
+                    async_parser.id_span,
+                    "async parsers require an external tokenizer"
+                );
+            }
+
+            let mut algorithm = r::Algorithm::default();
+            read_algorithm(&self.grammar.annotations, &mut algorithm);
+            if algorithm.construction == r::LrConstruction::Glr {
+                return_err!(
+                    async_parser.id_span,
+                    "async parsers cannot be generated for ambiguous grammars"
+                );
+            }
+            if algorithm.codegen != r::LrCodeGeneration::TableDriven {
+                return_err!(
+                    async_parser.id_span,
+                    "async parsers are only supported by table-driven parsers"
+                );
+            }
+        }
+
+        if let Some(enum_token) = self
+            .extern_token
+            .and_then(|t| t.enum_token.as_ref())
+            .filter(|enum_token| enum_token.match_by_str)
+        {
+            let mut algorithm = r::Algorithm::default();
+            read_algorithm(&self.grammar.annotations, &mut algorithm);
+            if algorithm.codegen != r::LrCodeGeneration::TableDriven {
+                return_err!(
+                    enum_token.type_span,
+                    "`extern token` is only supported by table-driven parsers"
+                );
+            }
+        }
+
+        let mut precedence_terminals = set();
         for item in &self.grammar.items {
             match *item {
                 GrammarItem::Use(..) => {}
 
+                GrammarItem::Import(ref data) => {
+                    return_err!(
+                        data.span,
+                        "imports can only be used in grammar files processed by a build"
+                    );
+                }
+
                 GrammarItem::MatchToken(ref data) => {
                     if data.span != self.match_token.unwrap().span {
                         return_err!(data.span, "multiple match definitions are not permitted");
@@ -93,9 +281,17 @@ impl<'grammar> Validator<'grammar> {
                         }
                     }
 
+                    let token_trait = data.enum_token.as_ref().is_some_and(|t| t.token_trait);
                     let allowed_names = vec![Atom::from(LOCATION), Atom::from(ERROR)];
                     let mut new_names = set();
                     for associated_type in &data.associated_types {
+                        if token_trait && *associated_type.type_name == *LOCATION {
+                            return_err!(
+                                associated_type.type_span,
+                                "the locations of a `Token` are given by its `span`, \
+                                 which are always `usize`"
+                            );
+                        }
                         if !allowed_names.contains(&associated_type.type_name) {
                             return_err!(
                                 associated_type.type_span,
@@ -110,17 +306,49 @@ impl<'grammar> Validator<'grammar> {
                                 "associated type `{}` already specified",
                                 associated_type.type_name
                             );
+                        } else if *associated_type.type_name == *ERROR
+                            && self.grammar.error_type.is_some()
+                        {
+                            return_err!(
+                                associated_type.type_span,
+                                "the error type is already given by `grammar<E = ...>`"
+                            );
+                        }
+                    }
+                }
+                GrammarItem::PrecedenceDecl(ref data) => {
+                    self.reject_lalr(data.span, "precedence declarations")?;
+                    for terminal in &data.terminals {
+                        if !precedence_terminals.insert(terminal) {
+                            return_err!(
+                                data.span,
+                                "the precedence of `{}` is already declared",
+                                terminal
+                            );
                         }
                     }
                 }
+
                 GrammarItem::Nonterminal(ref data) => {
                     if data.visibility.is_pub() && !data.args.is_empty() {
                         return_err!(data.span, "macros cannot be marked public");
                     }
                     let inline_annotation = Atom::from(INLINE);
                     let cfg_annotation = Atom::from(CFG);
-                    let known_annotations = [inline_annotation.clone(), cfg_annotation.clone()];
+                    let display_annotation = Atom::from(DISPLAY);
+                    let shared_action_annotation = Atom::from(SHARED_ACTION);
+                    let allow_annotation = Atom::from(ALLOW);
+                    let deprecated_annotation = Atom::from(DEPRECATED);
+                    let known_annotations = [
+                        inline_annotation.clone(),
+                        cfg_annotation.clone(),
+                        display_annotation.clone(),
+                        shared_action_annotation.clone(),
+                        allow_annotation.clone(),
+                        deprecated_annotation.clone(),
+                    ];
                     let mut found_annotations = set();
+                    let mut deprecated_args = set();
                     for annotation in &data.annotations {
                         if !known_annotations.contains(&annotation.id) {
                             return_err!(
@@ -128,12 +356,28 @@ impl<'grammar> Validator<'grammar> {
                                 "unrecognized annotation `{}`",
                                 annotation.id
                             );
+                        } else if annotation.id == allow_annotation {
+                            validate_allow(annotation)?;
+                        } else if annotation.id == deprecated_annotation {
+                            validate_deprecated(data, annotation, &mut deprecated_args)?;
                         } else if !found_annotations.insert(annotation.id.clone()) {
                             return_err!(
                                 annotation.id_span,
                                 "duplicate annotation `{}`",
                                 annotation.id
                             );
+                        } else if annotation.id == display_annotation && annotation.arg.is_some() {
+                            return_err!(
+                                annotation.id_span,
+                                "`#[display]` on a nonterminal takes no argument"
+                            );
+                        } else if annotation.id == shared_action_annotation
+                            && data.shared_action.is_none()
+                        {
+                            return_err!(
+                                annotation.id_span,
+                                "expected code for `shared_action`, like `#[shared_action => Box::new(<>)]`"
+                            );
                         } else if annotation.id == inline_annotation && data.visibility.is_pub() {
                             return_err!(
                                 annotation.id_span,
@@ -159,16 +403,99 @@ impl<'grammar> Validator<'grammar> {
 
                     self.validate_precedence(&data.alternatives)?;
 
+                    let mut variant_names = set();
                     for alternative in &data.alternatives {
                         self.validate_alternative(alternative)?;
+                        if let Some(rename) =
+                            alternative.annotations.iter().find(|a| a.id == *RENAME)
+                        {
+                            let (_, name) = rename.arg.as_ref().unwrap();
+                            if !variant_names.insert(name) {
+                                return_err!(
+                                    rename.id_span,
+                                    "`{}` already has a variant named `{}`",
+                                    data.name,
+                                    name
+                                );
+                            }
+                        }
+                        if found_annotations.contains(&inline_annotation) {
+                            if let Some(cut) = alternative.expr.symbols.iter().find(|s| s.is_cut())
+                            {
+                                return_err!(
+                                    cut.span,
+                                    "a cut (`^`) cannot be used in an `#[inline]` nonterminal"
+                                );
+                            }
+                        }
                     }
                 }
                 GrammarItem::InternToken(..) => {}
+                GrammarItem::TypeDecl(..) => {}
+                GrammarItem::LeftRecursiveDecl(ref data) => {
+                    self.reject_lalr(data.span, "`%left_recursive` declarations")?;
+                }
+                GrammarItem::StartDecl(..) => {}
+                GrammarItem::Test(ref data) => self.validate_test(data)?,
             }
         }
         Ok(())
     }
 
+    fn validate_test(&self, data: &TestData) -> NormResult<()> {
+        match &data.annotations[..] {
+            [annotation] if annotation.id == *TEST && annotation.arg.is_none() => {}
+            [] => return_err!(data.span, "grammar tests must be annotated with `#[test]`"),
+            [annotation, ..] => return_err!(
+                annotation.id_span,
+                "unrecognized annotation `{}`",
+                annotation.id
+            ),
+        }
+
+        if self.extern_token.is_some_and(|t| t.enum_token.is_some()) {
+            return_err!(data.span, "grammar tests require the built-in lexer");
+        }
+        if !self.grammar.parameters.is_empty()
+            || self
+                .grammar
+                .type_parameters
+                .iter()
+                .any(|tp| matches!(tp, TypeParameter::Id(_)))
+        {
+            return_err!(
+                data.span,
+                "grammar tests cannot be used in grammars with parameters"
+            );
+        }
+
+        let is_public = self
+            .grammar
+            .items
+            .iter()
+            .filter_map(GrammarItem::as_nonterminal)
+            .any(|nt| nt.name == data.nonterminal && nt.visibility.is_pub());
+        if !is_public {
+            return_err!(
+                data.span,
+                "`{}` is not a public nonterminal",
+                data.nonterminal
+            );
+        }
+        Ok(())
+    }
+
+    /// Reports `what` as unsupported if the grammar is marked `#[LALR]`:
+    /// it needs the exact lookahead of canonical LR(1) states.
+    fn reject_lalr(&self, span: Span, what: &str) -> NormResult<()> {
+        let mut algorithm = r::Algorithm::default();
+        read_algorithm(&self.grammar.annotations, &mut algorithm);
+        if algorithm.construction == r::LrConstruction::Lalr {
+            return_err!(span, "{} are not supported by LALR parsers", what);
+        }
+        Ok(())
+    }
+
     fn validate_precedence(&self, alternatives: &[Alternative]) -> NormResult<()> {
         let with_precedence = alternatives.iter().any(|alt| {
             alt.annotations
@@ -258,12 +585,19 @@ impl<'grammar> Validator<'grammar> {
 
     fn validate_alternative(&self, alternative: &Alternative) -> NormResult<()> {
         self.validate_expr(&alternative.expr)?;
+        self.validate_cut(&alternative.expr)?;
 
         let allowed_names = vec![
             Atom::from(precedence::PREC_ANNOT),
             Atom::from(precedence::ASSOC_ANNOT),
+            Atom::from(INLINE),
+            Atom::from(COLD),
+            Atom::from(DISPLAY),
+            Atom::from(RENAME),
+            Atom::from(CFG),
         ];
 
+        let mut found_annotations = set();
         for annotation in &alternative.annotations {
             if !allowed_names.contains(&annotation.id) {
                 return_err!(
@@ -272,6 +606,76 @@ impl<'grammar> Validator<'grammar> {
                     annotation.id
                 );
             }
+
+            // all the `#[cfg]`s of an alternative must hold
+            if &*annotation.id == CFG {
+                if !matches!(annotation.arg, Some((ref key, _)) if !key.is_empty()) {
+                    return_err!(
+                        annotation.id_span,
+                        r#"`cfg` annotations must have an argument, like `#[cfg(target_os = "windows")]`"#
+                    );
+                }
+                continue;
+            }
+
+            if !found_annotations.insert(annotation.id.clone()) {
+                return_err!(
+                    annotation.id_span,
+                    "duplicate annotation `{}`",
+                    annotation.id
+                );
+            }
+
+            if &*annotation.id == DISPLAY {
+                if !matches!(annotation.arg, Some((ref name, _)) if name.is_empty()) {
+                    return_err!(
+                        annotation.id_span,
+                        r#"expected a format for `display`, like `#[display = "{{l}} + {{r}}"]`"#
+                    );
+                }
+                continue;
+            }
+
+            if &*annotation.id == RENAME {
+                let valid = match annotation.arg {
+                    Some((ref name, ref value)) => name.is_empty() && is_identifier(value),
+                    None => false,
+                };
+                if !valid {
+                    return_err!(
+                        annotation.id_span,
+                        r#"expected a variant name for `rename`, like `#[rename = "Add"]`"#
+                    );
+                }
+                continue;
+            }
+
+            // `#[inline]`, `#[inline(always)]`, `#[inline(never)]` and
+            // `#[cold]` are passed on to the action fn.
+            let valid_arg = match (&*annotation.id, &annotation.arg) {
+                (INLINE, Some((name, value))) => {
+                    value.is_empty() && (name == "always" || name == "never")
+                }
+                (COLD, Some(_)) => false,
+                _ => true,
+            };
+            if !valid_arg {
+                return_err!(
+                    annotation.id_span,
+                    "invalid argument for `{}` annotation, expected `#[inline]`, \
+                     `#[inline(always)]`, `#[inline(never)]` or `#[cold]`",
+                    annotation.id
+                );
+            }
+        }
+
+        if let Some(Condition::Arbitrary(_)) = alternative.condition {
+            if let Some(ActionKind::Lookahead | ActionKind::Lookbehind) = alternative.action {
+                return_err!(
+                    alternative.span,
+                    "`#[if]` guards cannot be used with `=>@L` or `=>@R`"
+                );
+            }
         }
 
         match norm_util::analyze_expr(&alternative.expr) {
@@ -305,6 +709,36 @@ impl<'grammar> Validator<'grammar> {
         Ok(())
     }
 
+    /// A cut (`^`) commits to an alternative once the symbols before
+    /// it are parsed, so there is at most one, and not at the start.
+    fn validate_cut(&self, expr: &ExprSymbol) -> NormResult<()> {
+        let mut cuts = expr
+            .symbols
+            .iter()
+            .enumerate()
+            .filter(|(_, symbol)| symbol.is_cut());
+        if let Some((index, cut)) = cuts.next() {
+            if index == 0 {
+                return_err!(
+                    cut.span,
+                    "a cut (`^`) cannot be the first symbol of an alternative"
+                );
+            }
+            if let Some((_, cut)) = cuts.next() {
+                return_err!(cut.span, "an alternative can only have one cut (`^`)");
+            }
+            let mut algorithm = r::Algorithm::default();
+            read_algorithm(&self.grammar.annotations, &mut algorithm);
+            if algorithm.codegen == r::LrCodeGeneration::RecursiveAscent {
+                return_err!(
+                    cut.span,
+                    "cuts (`^`) are only supported by table-driven parsers"
+                );
+            }
+        }
+        Ok(())
+    }
+
     fn validate_expr(&self, expr: &ExprSymbol) -> NormResult<()> {
         for symbol in &expr.symbols {
             self.validate_symbol(symbol)?;
@@ -350,8 +784,20 @@ impl<'grammar> Validator<'grammar> {
     fn validate_symbol(&self, symbol: &Symbol) -> NormResult<()> {
         match symbol.kind {
             SymbolKind::Expr(ref expr) => {
+                for symbol in &expr.symbols {
+                    reject_nested_cut(symbol)?;
+                }
                 self.validate_expr(expr)?;
             }
+            SymbolKind::Choice(ref exprs) => {
+                for expr in exprs {
+                    for symbol in &expr.symbols {
+                        reject_nested_cut(symbol)?;
+                    }
+                    self.validate_expr(expr)?;
+                }
+            }
+            SymbolKind::Cut => { /* see validate_cut */ }
             SymbolKind::AmbiguousId(_) => { /* see resolve */ }
             SymbolKind::Terminal(_) => { /* see postvalidate! */ }
             SymbolKind::Nonterminal(_) => { /* see resolve */ }
@@ -364,25 +810,57 @@ impl<'grammar> Validator<'grammar> {
                         "error recovery is not yet supported by recursive ascent parsers"
                     );
                 }
+                if algorithm.construction == r::LrConstruction::Glr {
+                    return_err!(
+                        symbol.span,
+                        "error recovery is not supported in ambiguous grammars"
+                    );
+                }
             }
             SymbolKind::Macro(ref msym) => {
-                if msym.args.is_empty() {
-                    return_err!(symbol.span, "macros must have at least one argument")
-                }
+                // the arguments are all symbols until `resolve`
                 for arg in &msym.args {
-                    self.validate_symbol(arg)?;
+                    if let MacroArg::Symbol(ref arg) = *arg {
+                        reject_nested_cut(arg)?;
+                        self.validate_symbol(arg)?;
+                    }
                 }
             }
             SymbolKind::Repeat(ref repeat) => {
+                if let RepeatOp::Count {
+                    min,
+                    max: Some(max),
+                } = repeat.op
+                {
+                    if max < min {
+                        return_err!(
+                            symbol.span,
+                            "the maximum of `{}` is less than its minimum",
+                            repeat
+                        );
+                    }
+                }
+                reject_nested_cut(&repeat.symbol)?;
                 self.validate_symbol(&repeat.symbol)?;
             }
-            SymbolKind::Choose(ref sym) | SymbolKind::Name(_, ref sym) => {
+            SymbolKind::Choose(ref sym)
+            | SymbolKind::Name(_, ref sym)
+            | SymbolKind::DocName(_, ref sym) => {
+                reject_nested_cut(sym)?;
+                self.validate_symbol(sym)?;
+            }
+            SymbolKind::PosLookahead(ref sym) | SymbolKind::NegLookahead(ref sym) => {
+                self.reject_lalr(symbol.span, "lookahead assertions")?;
                 self.validate_symbol(sym)?;
             }
             SymbolKind::Lookahead | SymbolKind::Lookbehind => {
                 // if using an internal tokenizer, lookahead/lookbehind are ok.
                 if let Some(extern_token) = self.extern_token {
-                    if extern_token.enum_token.is_some() {
+                    if extern_token
+                        .enum_token
+                        .as_ref()
+                        .is_some_and(|t| !t.token_trait)
+                    {
                         // otherwise, the Location type must be specified.
                         let loc = Atom::from(LOCATION);
                         if self.extern_token.unwrap().associated_type(loc).is_none() {
@@ -402,3 +880,78 @@ impl<'grammar> Validator<'grammar> {
         Ok(())
     }
 }
+
+/// A cut (`^`) commits to an alternative, so it can only be one of
+/// the symbols of the alternative, not part of one.
+fn reject_nested_cut(symbol: &Symbol) -> NormResult<()> {
+    if symbol.is_cut() {
+        return_err!(
+            symbol.span,
+            "a cut (`^`) can only appear directly in an alternative"
+        );
+    }
+    Ok(())
+}
+
+/// `#[allow(name)]` must name one of the lints.
+fn validate_allow(annotation: &Annotation) -> NormResult<()> {
+    match annotation.arg {
+        Some((ref name, ref value)) if value.is_empty() => {
+            if LintKind::from_name(name).is_none() {
+                return_err!(
+                    annotation.id_span,
+                    "unknown lint `{}`, expected one of {}",
+                    name,
+                    Sep(
+                        ", ",
+                        &LintKind::ALL
+                            .iter()
+                            .map(|kind| format!("`{}`", kind.name()))
+                            .collect::<Vec<_>>()
+                    )
+                );
+            }
+            Ok(())
+        }
+        _ => return_err!(
+            annotation.id_span,
+            "`allow` annotations must name a lint, like `#[allow(unreachable_alternatives)]`"
+        ),
+    }
+}
+
+/// Whether `value` can be used as the name of an enum variant.
+fn is_identifier(value: &str) -> bool {
+    let mut chars = value.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// `#[deprecated]` can be written `#[deprecated]`,
+/// `#[deprecated = "note"]` or `#[deprecated(since = "1.2.0", note = "...")]`,
+/// as in Rust. Only public nonterminals have a parser to carry it.
+fn validate_deprecated<'a>(
+    data: &NonterminalData,
+    annotation: &'a Annotation,
+    seen: &mut Set<Option<&'a str>>,
+) -> NormResult<()> {
+    if !data.visibility.is_pub() {
+        return_err!(
+            annotation.id_span,
+            "only public nonterminals can be marked `#[deprecated]`"
+        );
+    }
+    let name = annotation.arg.as_ref().map(|(name, _)| &**name);
+    if !matches!(name, None | Some("" | "since" | "note")) {
+        return_err!(
+            annotation.id_span,
+            "unknown argument `{}` to `deprecated`, expected `since` or `note`",
+            name.unwrap()
+        );
+    }
+    // `#[deprecated = "..."]` gives the note
+    if !seen.insert(name.map(|name| if name.is_empty() { "note" } else { name })) {
+        return_err!(annotation.id_span, "duplicate annotation `deprecated`");
+    }
+    Ok(())
+}
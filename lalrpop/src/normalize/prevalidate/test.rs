@@ -16,6 +16,15 @@ fn named_symbols() {
     );
 }
 
+#[test]
+fn bare_named_symbols() {
+    check_err(
+        r#"named symbols \(like `"Num"`\) require a custom action"#,
+        r#"grammar; Term = { n:"Num" };"#,
+        r#"                    ~~~~~    "#,
+    );
+}
+
 #[test]
 fn bad_assoc_type() {
     check_err(
@@ -61,6 +70,33 @@ fn unrecognized_annotation() {
     );
 }
 
+#[test]
+fn conflicting_construction_annotations() {
+    check_err(
+        r"`#\[LR1\]` conflicts with the earlier `#\[LALR\]` annotation",
+        r#"#[LALR] #[LR1] grammar; Term = ();"#,
+        r#"          ~~~                     "#,
+    );
+}
+
+#[test]
+fn ambiguous_recursive_ascent() {
+    check_err(
+        r"ambiguous grammars are only supported by table-driven parsers",
+        r#"#[ambiguous] #[recursive_ascent] grammar; Term = ();"#,
+        r#"  ~~~~~~~~~                                        "#,
+    );
+}
+
+#[test]
+fn ambiguous_error_recovery() {
+    check_err(
+        r"error recovery is not supported in ambiguous grammars",
+        r#"#[ambiguous] grammar; Term = { "a" => (), ! => () };"#,
+        r#"                                          ~         "#,
+    );
+}
+
 #[test]
 fn duplicate_annotation() {
     check_err(
@@ -79,6 +115,15 @@ fn pub_inline_annotation() {
     );
 }
 
+#[test]
+fn unresolved_import() {
+    check_err(
+        r"imports can only be used in grammar files processed by a build",
+        r#"grammar; import "other.lalrpop"; Term = ();"#,
+        r#"         ~~~~~~~~~~~~~~~~~~~~~~            "#,
+    );
+}
+
 #[test]
 fn multiple_match_token() {
     check_err(
@@ -215,10 +260,370 @@ fn first_level_assoc() {
 }
 
 #[test]
-fn missing_macro_arg() {
+fn lalr_lookahead_assertion() {
+    check_err(
+        r"lookahead assertions are not supported by LALR parsers",
+        r#"#[LALR] grammar; Term = { "a" (?="b") "b" => () };"#,
+        r#"                              ~~~~~~~              "#,
+    );
+}
+
+#[test]
+fn lalr_precedence_declaration() {
+    check_err(
+        r"precedence declarations are not supported by LALR parsers",
+        r#"#[LALR] grammar; %left "+"; Term = { "a" => () };"#,
+        r#"                 ~~~~~~~~~                       "#,
+    );
+}
+
+#[test]
+fn lalr_left_recursive_declaration() {
+    check_err(
+        r"`%left_recursive` declarations are not supported by LALR parsers",
+        r#"#[LALR] grammar; %left_recursive Term; Term = { "a" => () };"#,
+        r#"                 ~~~~~~~~~~~~~~~~~~~~                       "#,
+    );
+}
+
+#[test]
+fn invalid_inline_alternative_arg() {
+    check_err(
+        r"invalid argument for `inline` annotation, expected `#\[inline\]`, `#\[inline\(always\)\]`, `#\[inline\(never\)\]` or `#\[cold\]`",
+        r#"grammar; Term = { #[inline(sometimes)] "a" => () };"#,
+        r#"                    ~~~~~~~~~~~~~~~~~             "#,
+    );
+}
+
+#[test]
+fn cold_alternative_arg() {
+    check_err(
+        r"invalid argument for `cold` annotation",
+        r#"grammar; Term = { #[cold(always)] "a" => () };"#,
+        r#"                    ~~~~~~~~~~~~             "#,
+    );
+}
+
+#[test]
+fn no_std_without_stack_depth() {
+    check_err(
+        r#"`no_std` annotations must have a `stack_depth = "N"` argument"#,
+        r#"#[no_std] grammar; Term = ();"#,
+        r#"  ~~~~~~                     "#,
+    );
+}
+
+#[test]
+fn no_std_bad_stack_depth() {
+    check_err(
+        r"could not parse the stack depth `0`, expected a positive integer",
+        r#"#[no_std(stack_depth="0")] grammar; Term = ();"#,
+        r#"  ~~~~~~~~~~~~~~~~~~~~~~~                     "#,
+    );
+}
+
+#[test]
+fn no_std_recursive_ascent() {
+    check_err(
+        r"a bounded stack is only supported by table-driven parsers",
+        r#"#[no_std(stack_depth="64")] #[recursive_ascent] grammar; Term = ();"#,
+        r#"  ~~~~~~~~~~~~~~~~~~~~~~~~                                        "#,
+    );
+}
+
+#[test]
+fn duplicate_precedence_declaration() {
+    check_err(
+        r#"the precedence of `"\+"` is already declared"#,
+        r#"grammar; %left "+" "-"; %right "^" "+"; Term = ();"#,
+        r#"                        ~~~~~~~~~~~~~~          "#,
+    );
+}
+
+#[test]
+fn incremental_extern_token() {
+    check_err(
+        r"incremental parsers require the built-in lexer",
+        r#"#[incremental] grammar; extern { enum Tok { } } Term = ();"#,
+        r#"  ~~~~~~~~~~~                                            "#,
+    );
+}
+
+#[test]
+fn incremental_recursive_ascent() {
+    check_err(
+        r"incremental parsers are only supported by table-driven parsers",
+        r#"#[incremental] #[recursive_ascent] grammar; Term = ();"#,
+        r#"  ~~~~~~~~~~~                                        "#,
+    );
+}
+
+#[test]
+fn async_without_extern_token() {
+    check_err(
+        r"async parsers require an external tokenizer",
+        r#"#[async] grammar; Term = "x";"#,
+        r#"  ~~~~~                     "#,
+    );
+}
+
+#[test]
+fn async_ambiguous() {
     check_err(
-        r#"macros must have at least one argument"#,
-        r#"grammar; Macro<Smth>: String = { Smth => <>.to_string() } pub Root: String = { Macro<>}"#,
-        r#"                                                                               ~~~~~~~"#,
-    )
+        r"async parsers cannot be generated for ambiguous grammars",
+        r#"#[async] #[ambiguous] grammar; extern { enum Tok { "x" => Tok::X } } Term = "x";"#,
+        r#"  ~~~~~                                                                          "#,
+    );
+}
+
+#[test]
+fn async_recursive_ascent() {
+    check_err(
+        r"async parsers are only supported by table-driven parsers",
+        r#"#[async] #[recursive_ascent] grammar; extern { enum Tok { "x" => Tok::X } } Term = "x";"#,
+        r#"  ~~~~~                                                                                 "#,
+    );
+}
+
+#[test]
+fn token_source_unknown() {
+    check_err(
+        r#"`token_source` annotations must be `#\[token_source = "proc_macro2"\]`"#,
+        r#"#[token_source = "syn"] grammar; Term = "x";"#,
+        r#"  ~~~~~~~~~~~~~~~~~~~~                        "#,
+    );
+}
+
+#[test]
+fn token_source_extern_token() {
+    check_err(
+        r"a grammar with a `#\[token_source\]` cannot have an `extern` block",
+        r#"#[token_source = "proc_macro2"] grammar; extern { type Error = (); } Term = "x";"#,
+        r#"                                         ~~~~~~                                 "#,
+    );
+}
+
+#[test]
+fn token_source_recursive_ascent() {
+    check_err(
+        r"`#\[token_source\]` is only supported by table-driven parsers",
+        r#"#[token_source = "proc_macro2"] #[recursive_ascent] grammar; Term = "x";"#,
+        r#"  ~~~~~~~~~~~~~~~~~~~~~~~~~~~~                                         "#,
+    );
+}
+
+#[test]
+fn incremental_token_source() {
+    check_err(
+        r"incremental parsers require the built-in lexer",
+        r#"#[incremental] #[token_source = "proc_macro2"] grammar; Term = "x";"#,
+        r#"  ~~~~~~~~~~~                                                    "#,
+    );
+}
+
+#[test]
+fn extern_token_recursive_ascent() {
+    check_err(
+        r"`extern token` is only supported by table-driven parsers",
+        r#"#[recursive_ascent] grammar; extern token Tok; Term = ();"#,
+        r#"                                          ~~~            "#,
+    );
+}
+
+#[test]
+fn cut_first() {
+    check_err(
+        r"a cut \(`\^`\) cannot be the first symbol of an alternative",
+        r#"grammar; Term = ^ "x";"#,
+        r#"                ~     "#,
+    );
+}
+
+#[test]
+fn cut_twice() {
+    check_err(
+        r"an alternative can only have one cut \(`\^`\)",
+        r#"grammar; Term = "x" ^ "y" ^ "z";"#,
+        r#"                          ~     "#,
+    );
+}
+
+#[test]
+fn cut_nested() {
+    check_err(
+        r"a cut \(`\^`\) can only appear directly in an alternative",
+        r#"grammar; Term = "x" ("y" ^ "z")*;"#,
+        r#"                         ~       "#,
+    );
+}
+
+#[test]
+fn cut_inline() {
+    check_err(
+        r"a cut \(`\^`\) cannot be used in an `#\[inline\]` nonterminal",
+        r#"grammar; #[inline] Term = "x" ^ "y";"#,
+        r#"                              ~     "#,
+    );
+}
+
+#[test]
+fn cut_recursive_ascent() {
+    check_err(
+        r"cuts \(`\^`\) are only supported by table-driven parsers",
+        r#"#[recursive_ascent] grammar; Term = "x" ^ "y";"#,
+        r#"                                        ~     "#,
+    );
+}
+
+#[test]
+fn extern_token_trait_location() {
+    check_err(
+        r"the locations of a `Token` are given by its `span`, which are always `usize`",
+        r#"grammar; extern token Tok: Token { type Location = u32; } Term = ();"#,
+        r#"                                        ~~~~~~~~                    "#,
+    );
+}
+
+#[test]
+fn display_without_format() {
+    check_err(
+        r"expected a format for `display`",
+        r#"grammar; #[display] Term = { #[display] "x" };"#,
+        r#"                               ~~~~~~~       "#,
+    );
+}
+
+#[test]
+fn display_nonterminal_argument() {
+    check_err(
+        r"`#\[display\]` on a nonterminal takes no argument",
+        r#"grammar; #[display = "x"] Term = "x";"#,
+        r#"           ~~~~~~~~~~~~~             "#,
+    );
+}
+
+#[test]
+fn alternative_cfg_without_argument() {
+    check_err(
+        r"`cfg` annotations must have an argument, like .*",
+        r#"grammar; Term = { #[cfg = "unix"] "x" };"#,
+        r#"                    ~~~~~~~~~~~~       "#,
+    );
+}
+
+#[test]
+fn rename_not_identifier() {
+    check_err(
+        r"expected a variant name for `rename`, like .*",
+        r#"grammar; Term = { #[rename = "1x"] "x" };"#,
+        r#"                    ~~~~~~~~~~~~~       "#,
+    );
+}
+
+#[test]
+fn rename_duplicate() {
+    check_err(
+        r"`Term` already has a variant named `X`",
+        r#"grammar; Term = { #[rename = "X"] "x", #[rename = "X"] "y" };"#,
+        r#"                                         ~~~~~~~~~~~~        "#,
+    );
+}
+
+#[test]
+fn shared_action_without_code() {
+    check_err(
+        r"expected code for `shared_action`",
+        r#"grammar; #[shared_action = "x"] Term: u32 = "x" => 1;"#,
+        r#"           ~~~~~~~~~~~~~~~~~~~                        "#,
+    );
+}
+
+#[test]
+fn guard_lookahead() {
+    check_err(
+        r"`#\[if\]` guards cannot be used with `=>@L` or `=>@R`",
+        r#"grammar; Term = { #[if { true }] "x" =>@L };"#,
+        r#"                                 ~~~~~~~~   "#,
+    );
+}
+
+#[test]
+fn repeat_count_max_below_min() {
+    check_err(
+        r"the maximum of `.x.\{3,2\}` is less than its minimum",
+        r#"grammar; Term = "x"{3,2};"#,
+        r#"                ~~~~~~~~ "#,
+    );
+}
+
+#[test]
+fn test_missing_annotation() {
+    check_err(
+        r"grammar tests must be annotated with `#\[test\]`",
+        r#"grammar; parse "x" as Term => (); pub Term = "x";"#,
+        r#"         ~~~~~~~~~~~~~~~~~                       "#,
+    );
+}
+
+#[test]
+fn test_private_nonterminal() {
+    check_err(
+        r"`Term` is not a public nonterminal",
+        r#"grammar; #[test] parse "x" as Term => (); Term = "x";"#,
+        r#"                 ~~~~~~~~~~~~~~~~~                   "#,
+    );
+}
+
+#[test]
+fn test_extern_token() {
+    check_err(
+        r"grammar tests require the built-in lexer",
+        r#"grammar; extern { enum Tok { } } #[test] parse "x" as Term => (); pub Term = ();"#,
+        r#"                                         ~~~~~~~~~~~~~~~~~                      "#,
+    );
+}
+
+#[test]
+fn test_grammar_parameters() {
+    check_err(
+        r"grammar tests cannot be used in grammars with parameters",
+        r#"grammar(x: u32); #[test] parse "" as Term => (); pub Term = ();"#,
+        r#"                         ~~~~~~~~~~~~~~~~                      "#,
+    );
+}
+
+#[test]
+fn error_type_given_twice() {
+    check_err(
+        r#"the error type is already given by `grammar<E = ...>`"#,
+        r#"grammar<E = MyError>; extern { type Error = (); } Term = "x";"#,
+        r#"                                    ~~~~~               "#,
+    );
+}
+
+#[test]
+fn unknown_lint() {
+    check_err(
+        r"unknown lint `unused`, expected one of `ambiguous_terminals`, .*",
+        r#"grammar; #[allow(unused)] Term = ();"#,
+        r#"           ~~~~~~~~~~~~~            "#,
+    );
+}
+
+#[test]
+fn deprecated_private() {
+    check_err(
+        r"only public nonterminals can be marked `#\[deprecated\]`",
+        r#"grammar; #[deprecated] Term = ();"#,
+        r#"           ~~~~~~~~~~            "#,
+    );
+}
+
+#[test]
+fn deprecated_unknown_argument() {
+    check_err(
+        r"unknown argument `reason` to `deprecated`, expected `since` or `note`",
+        r#"grammar; #[deprecated(since = "1.2.0", reason = "x")] pub Term = ();"#,
+        r#"           ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~                   "#,
+    );
 }
@@ -1,6 +1,7 @@
 //! Resolves identifiers to decide if they are macros, terminals, or
 //! nonterminals. Rewrites the parse tree accordingly.
 
+use super::norm_util::{analyze_expr, Symbols};
 use super::{NormError, NormResult};
 
 use crate::collections::{map, Map};
@@ -16,6 +17,8 @@ pub fn resolve(mut grammar: Grammar) -> NormResult<Grammar> {
 }
 
 fn resolve_in_place(grammar: &mut Grammar) -> NormResult<()> {
+    declare_types(grammar)?;
+
     let globals = {
         let nonterminal_identifiers = grammar
             .items
@@ -89,6 +92,58 @@ fn resolve_in_place(grammar: &mut Grammar) -> NormResult<()> {
     validator.validate(grammar)
 }
 
+/// Moves the types given by `%type` declarations onto the nonterminals
+/// they name.
+fn declare_types(grammar: &mut Grammar) -> NormResult<()> {
+    let mut declared_types: Map<NonterminalString, (Span, TypeRef)> = map();
+    for data in grammar.items.iter().filter_map(|item| match *item {
+        GrammarItem::TypeDecl(ref data) => Some(data),
+        _ => None,
+    }) {
+        for nonterminal in &data.nonterminals {
+            let declared = (data.span, data.type_ref.clone());
+            if declared_types
+                .insert(nonterminal.clone(), declared)
+                .is_some()
+            {
+                return_err!(data.span, "type of `{}` declared twice", nonterminal);
+            }
+        }
+    }
+
+    for item in &mut grammar.items {
+        if let GrammarItem::Nonterminal(ref mut data) = *item {
+            if let Some((span, type_ref)) = declared_types.remove(&data.name) {
+                if data.is_macro_def() {
+                    return_err!(
+                        span,
+                        "`%type` cannot declare the type of macro `{}`",
+                        data.name
+                    );
+                }
+                if data.type_decl.is_some() {
+                    return_err!(
+                        span,
+                        "type of `{}` declared twice, by `%type` and at its definition",
+                        data.name
+                    );
+                }
+                data.declared_type = Some(type_ref);
+            }
+        }
+    }
+
+    if let Some((nonterminal, (span, _))) = declared_types.into_iter().next() {
+        return_err!(
+            span,
+            "no definition found for nonterminal `{}`",
+            nonterminal
+        );
+    }
+
+    Ok(())
+}
+
 struct Validator {
     globals: ScopeChain<'static>,
 }
@@ -122,9 +177,27 @@ impl Validator {
         for item in &mut grammar.items {
             match *item {
                 GrammarItem::Use(..) => {}
+                GrammarItem::Import(..) => {}
                 GrammarItem::MatchToken(..) => {}
                 GrammarItem::InternToken(..) => {}
                 GrammarItem::ExternToken(..) => {}
+                GrammarItem::PrecedenceDecl(..) => {}
+                GrammarItem::TypeDecl(..) => {}
+                GrammarItem::StartDecl(..) => {}
+                GrammarItem::LeftRecursiveDecl(ref data) => {
+                    for nonterminal in &data.nonterminals {
+                        match self.validate_id(&self.globals, data.span, &nonterminal.0)? {
+                            Def::Nonterminal(0) => {}
+                            def => return_err!(
+                                data.span,
+                                "`%left_recursive` expects nonterminals, not {}s like `{}`",
+                                def.description(),
+                                nonterminal
+                            ),
+                        }
+                    }
+                }
+                GrammarItem::Test(..) => {}
                 GrammarItem::Nonterminal(ref mut data) => {
                     let identifiers = self.validate_macro_args(data.span, &data.args)?;
                     let locals = ScopeChain {
@@ -143,10 +216,10 @@ impl Validator {
     fn validate_macro_args(
         &self,
         span: Span,
-        args: &[NonterminalString],
+        args: &[(NonterminalString, Option<TypeRef>)],
     ) -> NormResult<Map<Atom, Def>> {
-        for (index, arg) in args.iter().enumerate() {
-            if args[..index].contains(arg) {
+        for (index, (arg, _)) in args.iter().enumerate() {
+            if args[..index].iter().any(|(a, _)| a == arg) {
                 return_err!(
                     span,
                     "multiple macro arguments declared with the name `{}`",
@@ -156,7 +229,7 @@ impl Validator {
         }
         Ok(args
             .iter()
-            .map(|nt| (nt.0.clone(), Def::MacroArg))
+            .map(|(nt, _)| (nt.0.clone(), Def::MacroArg))
             .collect())
     }
 
@@ -165,21 +238,30 @@ impl Validator {
         scope: &ScopeChain,
         alternative: &mut Alternative,
     ) -> NormResult<()> {
-        if let Some(ref condition) = alternative.condition {
-            let def = self.validate_id(scope, condition.span, &condition.lhs.0)?;
-            match def {
-                Def::MacroArg => { /* OK */ }
-                _ => {
-                    return_err!(
-                        condition.span,
-                        "only macro arguments can be used in conditions, \
-                         not {}s like `{}`",
-                        def.description(),
-                        condition.lhs
-                    );
+        let mut guard = None;
+        if let Some(Condition::Macro(ref condition)) = alternative.condition {
+            let is_macro_arg = matches!(scope.def(&condition.lhs.0), Some(Def::MacroArg));
+            if !is_macro_arg && binds_name(&alternative.expr, &condition.lhs.0) {
+                guard = Some(bound_name_guard(condition)?);
+            } else {
+                let def = self.validate_id(scope, condition.span, &condition.lhs.0)?;
+                match def {
+                    Def::MacroArg => { /* OK */ }
+                    _ => {
+                        return_err!(
+                            condition.span,
+                            "only macro arguments and the names bound by the alternative \
+                             can be used in conditions, not {}s like `{}`",
+                            def.description(),
+                            condition.lhs
+                        );
+                    }
                 }
             }
         }
+        if let Some(guard) = guard {
+            alternative.condition = Some(Condition::Arbitrary(guard));
+        }
 
         self.validate_expr(scope, &mut alternative.expr)?;
 
@@ -199,6 +281,11 @@ impl Validator {
             SymbolKind::Expr(ref mut expr) => {
                 self.validate_expr(scope, expr)?;
             }
+            SymbolKind::Choice(ref mut exprs) => {
+                for expr in exprs {
+                    self.validate_expr(scope, expr)?;
+                }
+            }
             SymbolKind::AmbiguousId(_) => {
                 self.rewrite_ambiguous_id(scope, symbol)?;
             }
@@ -221,8 +308,27 @@ impl Validator {
                     }
                 }
             }
+            SymbolKind::Macro(ref msym) if msym.args.is_empty() => {
+                // `X<>` is the same as `X`
+                let def = self.validate_id(scope, symbol.span, &msym.name.0)?;
+                match def {
+                    Def::Nonterminal(0) | Def::MacroArg => {
+                        symbol.kind = SymbolKind::Nonterminal(msym.name.clone());
+                    }
+                    Def::Terminal => return_err!(
+                        symbol.span,
+                        "`{}` is a terminal, not a nonterminal",
+                        msym.name
+                    ),
+                    Def::Nonterminal(arity) => return_err!(
+                        symbol.span,
+                        "wrong number of arguments to `{}`: expected {}, found 0",
+                        msym.name,
+                        arity
+                    ),
+                }
+            }
             SymbolKind::Macro(ref mut msym) => {
-                debug_assert!(!msym.args.is_empty());
                 let def = self.validate_id(scope, symbol.span, &msym.name.0)?;
                 match def {
                     Def::Nonterminal(0) | Def::Terminal | Def::MacroArg => return_err!(
@@ -246,21 +352,55 @@ impl Validator {
                 }
 
                 for arg in &mut msym.args {
-                    self.validate_symbol(scope, arg)?;
+                    self.validate_macro_arg(scope, arg)?;
                 }
             }
             SymbolKind::Repeat(ref mut repeat) => {
                 self.validate_symbol(scope, &mut repeat.symbol)?;
             }
-            SymbolKind::Choose(ref mut sym) | SymbolKind::Name(_, ref mut sym) => {
+            SymbolKind::Choose(ref mut sym)
+            | SymbolKind::Name(_, ref mut sym)
+            | SymbolKind::PosLookahead(ref mut sym)
+            | SymbolKind::NegLookahead(ref mut sym) => {
                 self.validate_symbol(scope, sym)?;
             }
-            SymbolKind::Lookahead | SymbolKind::Lookbehind | SymbolKind::Error => {}
+            SymbolKind::DocName(ref name, ref mut sym) => {
+                // the errors about `X` in `@x:X` mention the name too
+                self.validate_symbol(scope, sym).map_err(|mut error| {
+                    error.message = format!("{} (called `{}`)", error.message, name);
+                    error
+                })?;
+            }
+            SymbolKind::Lookahead
+            | SymbolKind::Lookbehind
+            | SymbolKind::Error
+            | SymbolKind::Cut => {}
         }
 
         Ok(())
     }
 
+    /// Validates an argument in a use of a macro, turning it into a type
+    /// if it names nothing the grammar defines, like `u32`, or is a use
+    /// of such a name, like `Vec<T>`.
+    fn validate_macro_arg(&self, scope: &ScopeChain, arg: &mut MacroArg) -> NormResult<()> {
+        let symbol = match *arg {
+            MacroArg::Symbol(ref mut symbol) => symbol,
+            MacroArg::Type(_) => return Ok(()),
+        };
+        let undefined = match symbol.kind {
+            SymbolKind::AmbiguousId(ref id) => scope.def(id).is_none(),
+            SymbolKind::Macro(ref msym) => scope.def(&msym.name.0).is_none(),
+            _ => false,
+        };
+        if undefined {
+            *arg = MacroArg::Type(symbol_type(symbol)?);
+            Ok(())
+        } else {
+            self.validate_symbol(scope, symbol)
+        }
+    }
+
     fn rewrite_ambiguous_id(&self, scope: &ScopeChain, symbol: &mut Symbol) -> NormResult<()> {
         let id = if let SymbolKind::AmbiguousId(ref name) = symbol.kind {
             name.clone()
@@ -283,6 +423,38 @@ impl Validator {
     }
 }
 
+/// Whether `expr` binds `name`, as in `<name:Ident>`.
+fn binds_name(expr: &ExprSymbol, name: &Atom) -> bool {
+    match analyze_expr(expr) {
+        Symbols::Named(names) => names.iter().any(|(_, n, _)| n.name == *name),
+        Symbols::Anon(_) => false,
+    }
+}
+
+/// The `#[if { ... }]` guard that checks a condition like `if cmd ==
+/// "let"` on the value of a name bound by the alternative, when the
+/// alternative is reduced.
+fn bound_name_guard(condition: &MacroCondition) -> NormResult<String> {
+    let op = match condition.op {
+        ConditionOp::Equals => "==",
+        ConditionOp::NotEquals => "!=",
+        ConditionOp::Match | ConditionOp::NotMatch => return_err!(
+            condition.span,
+            "conditions on `{}`, a name bound by the alternative, can only use `==` and `!=`",
+            condition.lhs
+        ),
+    };
+    match condition.rhs {
+        ConditionRhs::Literal(ref text) => Ok(format!("{} {} {:?}", condition.lhs, op, &**text)),
+        ConditionRhs::Regex(_) => return_err!(
+            condition.span,
+            "conditions on `{}`, a name bound by the alternative, must compare it with a \
+             string literal",
+            condition.lhs
+        ),
+    }
+}
+
 impl<'scope> ScopeChain<'scope> {
     fn def(&self, id: &Atom) -> Option<Def> {
         self.identifiers
@@ -291,3 +463,27 @@ impl<'scope> ScopeChain<'scope> {
             .or_else(|| self.previous.and_then(|s| s.def(id)))
     }
 }
+
+/// The type written as `symbol`. Names are left as `TypeRef::Id`,
+/// like in a type the parser reads, so that macro expansion
+/// substitutes the arguments of the macro being defined, like `T`
+/// in `Vec<T>`.
+fn symbol_type(symbol: &Symbol) -> NormResult<TypeRef> {
+    match symbol.kind {
+        SymbolKind::AmbiguousId(ref id) => Ok(TypeRef::Id(id.clone())),
+        SymbolKind::Macro(ref msym) => {
+            let mut types = vec![];
+            for arg in &msym.args {
+                types.push(match *arg {
+                    MacroArg::Symbol(ref symbol) => symbol_type(symbol)?,
+                    MacroArg::Type(ref type_ref) => type_ref.clone(),
+                });
+            }
+            Ok(TypeRef::Nominal {
+                path: Path::from_id(msym.name.0.clone()),
+                types,
+            })
+        }
+        _ => return_err!(symbol.span, "expected a type, found `{}`", symbol),
+    }
+}
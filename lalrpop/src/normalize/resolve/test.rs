@@ -1,4 +1,4 @@
-use crate::grammar::parse_tree::Span;
+use crate::grammar::parse_tree::{Condition, Span};
 use crate::parser;
 use regex::Regex;
 
@@ -40,7 +40,7 @@ fn unknown_nonterminal() {
 fn unknown_nonterminal_in_macro_arg() {
     check_err(
         "no definition found for `Y`",
-        r#"grammar; X = X Id<>>>Y<<<>; Id<T> = T;"#,
+        r#"grammar; X = X Id<>>>Y<<<?>; Id<T> = T;"#,
     );
 }
 
@@ -80,3 +80,87 @@ fn overlapping_terminal_and_nonterminal() {
         r#"grammar; A = "Foo"; extern { enum Foo { >>>A => Foo::A(..) <<<} }"#,
     );
 }
+
+#[test]
+fn type_declared_twice() {
+    check_err(
+        "type of `X` declared twice, by `%type` and at its definition",
+        r#"grammar; >>>%type<u32> X<<<; X: u32 = "x";"#,
+    );
+}
+
+#[test]
+fn type_of_unknown_nonterminal() {
+    check_err(
+        "no definition found for nonterminal `Y`",
+        r#"grammar; >>>%type<u32> X Y<<<; X = "x";"#,
+    );
+}
+
+#[test]
+fn left_recursive_unknown_nonterminal() {
+    check_err(
+        "no definition found for `Y`",
+        r#"grammar; >>>%left_recursive X Y<<<; X = "x";"#,
+    );
+}
+
+#[test]
+fn left_recursive_macro() {
+    check_err(
+        "`%left_recursive` expects nonterminals, not macros like `X`",
+        r#"grammar; >>>%left_recursive X<<<; X<T> = T;"#,
+    );
+}
+
+#[test]
+fn unknown_nonterminal_doc_name() {
+    check_err(
+        "no definition found for `Y` \\(called `y`\\)",
+        r#"grammar; X = "x" @y:>>>Y<<<;"#,
+    );
+}
+
+#[test]
+fn empty_macro_args() {
+    let grammar = parser::parse_grammar(r#"grammar; X = Y<> "x"; Y = "y";"#);
+    let grammar = super::resolve(grammar.unwrap()).unwrap();
+    let alternative = &grammar.items[0].as_nonterminal().unwrap().alternatives[0];
+    assert_eq!(alternative.expr.symbols[0].to_string(), "Y");
+}
+
+#[test]
+fn empty_macro_args_of_macro() {
+    check_err(
+        "wrong number of arguments to `Id`: expected 1, found 0",
+        r#"grammar; X = >>>Id<><<<; Id<T> = T;"#,
+    );
+}
+
+#[test]
+fn bound_name_condition() {
+    let grammar = parser::parse_grammar(r#"grammar; X = <cmd:r"[a-z]+"> if cmd != "let" => cmd;"#);
+    let grammar = super::resolve(grammar.unwrap()).unwrap();
+    let alternative = &grammar.items[0].as_nonterminal().unwrap().alternatives[0];
+    assert_eq!(
+        alternative.condition,
+        Some(Condition::Arbitrary(r#"cmd != "let""#.to_string()))
+    );
+}
+
+#[test]
+fn bound_name_condition_regex() {
+    check_err(
+        "conditions on `cmd`, a name bound by the alternative, can only use `==` and `!=`",
+        r#"grammar; X = <cmd:r"[a-z]+"> if >>>cmd ~~ "l.*"<<< => cmd;"#,
+    );
+}
+
+#[test]
+fn unbound_name_condition() {
+    check_err(
+        "only macro arguments and the names bound by the alternative can be used in \
+         conditions, not nonterminals like `Y`",
+        r#"grammar; X = <cmd:Y> if >>>Y == "a"<<< => cmd; Y = "y";"#,
+    );
+}
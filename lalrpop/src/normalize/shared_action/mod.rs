@@ -0,0 +1,65 @@
+//! Applies the code of `#[shared_action => code]` to the value of
+//! every alternative of its nonterminal: the code of an alternative
+//! becomes
+//!
+//!     { let __alternative = code of the alternative; code }
+//!
+//! where `<>` in `code` stands for `__alternative`. Alternatives
+//! without an action get their default one first.
+
+use super::norm_util::{self, Symbols};
+use crate::grammar::parse_tree::*;
+
+#[cfg(test)]
+mod test;
+
+pub fn apply(mut grammar: Grammar) -> Grammar {
+    let prefix = grammar.prefix.clone();
+    for item in &mut grammar.items {
+        if let GrammarItem::Nonterminal(ref mut data) = *item {
+            if let Some(shared) = data.shared_action.take() {
+                for alternative in &mut data.alternatives {
+                    apply_to_alternative(&prefix, &shared, alternative);
+                }
+            }
+        }
+    }
+    grammar
+}
+
+fn apply_to_alternative(prefix: &str, shared: &str, alternative: &mut Alternative) {
+    let value = format!("{}alternative", prefix);
+    let shared = shared.replace("<>", &value);
+    let wrap = |code: &str| format!("{{ let {} = {}; {} }}", value, code, shared);
+    let wrap_fallible = |code: &str| {
+        format!(
+            "match {c} {{ Ok({v}) => Ok({s}), Err({p}error) => Err({p}error) }}",
+            c = code,
+            v = value,
+            s = shared,
+            p = prefix
+        )
+    };
+    alternative.action = match alternative.action.take() {
+        None => Some(ActionKind::User(wrap(default_action(&alternative.expr)))),
+        Some(ActionKind::User(code)) => Some(ActionKind::User(wrap(&code))),
+        Some(ActionKind::Fallible(code)) => Some(ActionKind::Fallible(wrap_fallible(&code))),
+        Some(ActionKind::ExternalFn(path)) => {
+            Some(ActionKind::User(wrap(&format!("{}(<>)", path))))
+        }
+        Some(ActionKind::FallibleExternalFn(path)) => {
+            Some(ActionKind::Fallible(wrap_fallible(&format!("{}(<>)", path))))
+        }
+        // `=>@L` and `=>@R` produce locations, not values
+        action @ Some(ActionKind::Lookahead | ActionKind::Lookbehind) => action,
+    };
+}
+
+/// The code that lowering supplies for an alternative without action;
+/// named symbols always need an action.
+fn default_action(expr: &ExprSymbol) -> &'static str {
+    match norm_util::analyze_expr(expr) {
+        Symbols::Anon(ref symbols) if symbols.len() == 1 => "<>",
+        _ => "(<>)",
+    }
+}
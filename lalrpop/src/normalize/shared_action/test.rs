@@ -0,0 +1,71 @@
+use crate::grammar::parse_tree::{ActionKind, GrammarItem};
+use crate::normalize::shared_action::apply;
+use crate::parser;
+
+fn actions(text: &str) -> Vec<Option<ActionKind>> {
+    let grammar = apply(parser::parse_grammar(text).unwrap());
+    grammar
+        .items
+        .iter()
+        .filter_map(GrammarItem::as_nonterminal)
+        .inspect(|data| assert_eq!(data.shared_action, None))
+        .flat_map(|data| data.alternatives.iter().map(|alt| alt.action.clone()))
+        .collect()
+}
+
+fn user(code: &str) -> Option<ActionKind> {
+    Some(ActionKind::User(code.to_string()))
+}
+
+#[test]
+fn wraps_actions() {
+    assert_eq!(
+        actions(
+            r#"
+grammar;
+    #[shared_action => Box::new(<>)]
+    Expr: Box<Expr> = {
+        <l:Expr> "+" <r:"Num"> => Expr::Add(l, r),
+        "Num" => fn Expr::Num,
+        "(" <Expr> ")",
+        "-" <"Num"> =>? Ok(Expr::Neg(<>)),
+    };
+    Num = "Num";
+"#
+        ),
+        vec![
+            user("{ let __alternative = Expr::Add(l, r); Box::new(__alternative) }"),
+            user("{ let __alternative = Expr::Num(<>); Box::new(__alternative) }"),
+            user("{ let __alternative = <>; Box::new(__alternative) }"),
+            Some(ActionKind::Fallible(
+                "match Ok(Expr::Neg(<>)) { \
+                 Ok(__alternative) => Ok(Box::new(__alternative)), \
+                 Err(__error) => Err(__error) }"
+                    .to_string()
+            )),
+            None,
+        ]
+    );
+}
+
+#[test]
+fn keeps_lookarounds() {
+    assert_eq!(
+        actions(
+            r#"
+grammar;
+    #[shared_action => <> + 1]
+    Loc: usize = {
+        =>@L,
+        "Num" "Num" => 0,
+        "(" ")",
+    };
+"#
+        ),
+        vec![
+            Some(ActionKind::Lookahead),
+            user("{ let __alternative = 0; __alternative + 1 }"),
+            user("{ let __alternative = (<>); __alternative + 1 }"),
+        ]
+    );
+}
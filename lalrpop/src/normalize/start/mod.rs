@@ -0,0 +1,49 @@
+//! Makes the nonterminals named by `%start` declarations public, so that
+//! each of them gets a parser like any `pub` nonterminal.
+
+use super::{NormError, NormResult};
+
+use crate::collections::{map, Map};
+use crate::grammar::parse_tree::*;
+
+#[cfg(test)]
+mod test;
+
+pub fn apply(mut grammar: Grammar) -> NormResult<Grammar> {
+    let mut start_symbols: Map<NonterminalString, Span> = map();
+    for data in grammar.items.iter().filter_map(|item| match *item {
+        GrammarItem::StartDecl(ref data) => Some(data),
+        _ => None,
+    }) {
+        for nonterminal in &data.nonterminals {
+            start_symbols.insert(nonterminal.clone(), data.span);
+        }
+    }
+
+    for item in &mut grammar.items {
+        if let GrammarItem::Nonterminal(ref mut data) = *item {
+            if let Some(span) = start_symbols.remove(&data.name) {
+                if data.is_macro_def() {
+                    return_err!(
+                        span,
+                        "`%start` expects nonterminals, not macros like `{}`",
+                        data.name
+                    );
+                }
+                if !data.visibility.is_pub() {
+                    data.visibility = Visibility::Pub(None);
+                }
+            }
+        }
+    }
+
+    if let Some((nonterminal, span)) = start_symbols.into_iter().next() {
+        return_err!(
+            span,
+            "no definition found for nonterminal `{}`",
+            nonterminal
+        );
+    }
+
+    Ok(grammar)
+}
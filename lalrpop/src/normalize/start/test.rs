@@ -0,0 +1,49 @@
+use crate::grammar::parse_tree::{Grammar, GrammarItem};
+use crate::parser;
+use crate::test_util;
+
+/// The names of the public nonterminals once `%start` is applied.
+fn public_nonterminals(text: &str) -> Vec<String> {
+    let grammar: Grammar = super::apply(parser::parse_grammar(text).unwrap()).unwrap();
+    grammar
+        .items
+        .iter()
+        .filter_map(GrammarItem::as_nonterminal)
+        .filter(|data| data.visibility.is_pub())
+        .map(|data| data.name.to_string())
+        .collect()
+}
+
+fn check_err(expected_err: &str, grammar: &str, span: &str) {
+    let parsed_grammar = parser::parse_grammar(grammar).unwrap();
+    let err = super::apply(parsed_grammar).unwrap_err();
+    test_util::check_norm_err(expected_err, span, err);
+}
+
+#[test]
+fn start_symbols() {
+    assert_eq!(
+        public_nonterminals(
+            r#"grammar; %start Expr Stmt; Stmt = Expr ";"; Expr = "x"; Other = "y"; pub Top = Stmt;"#
+        ),
+        vec!["Stmt", "Expr", "Top"]
+    );
+}
+
+#[test]
+fn unknown_start_symbol() {
+    check_err(
+        "no definition found for nonterminal `Y`",
+        r#"grammar; %start X Y; X = "x";"#,
+        r#"         ~~~~~~~~~~         "#,
+    );
+}
+
+#[test]
+fn macro_start_symbol() {
+    check_err(
+        "`%start` expects nonterminals, not macros like `X`",
+        r#"grammar; %start X; X<T> = T;"#,
+        r#"         ~~~~~~~~           "#,
+    );
+}
@@ -1,6 +1,6 @@
 //! If an extern token is provided, then this pass validates that
-//! terminal IDs have conversions. Otherwise, it generates a
-//! tokenizer. This can only be done after macro expansion because
+//! terminal IDs have conversions, or for `extern token T;` creates
+//! them. Otherwise, it generates a tokenizer. This can only be done after macro expansion because
 //! some macro arguments never make it into an actual production and
 //! are only used in `if` conditions; we use string literals for
 //! those, but they do not have to have a defined conversion.
@@ -10,6 +10,7 @@ use super::{NormError, NormResult};
 use crate::collections::{Map, Set};
 use crate::grammar::consts::*;
 use crate::grammar::parse_tree::*;
+use crate::grammar::pattern::{Pattern, PatternKind};
 use crate::lexer::dfa::{self, DfaConstructionError, Precedence};
 use crate::lexer::nfa::NfaConstructionError::*;
 use crate::lexer::re;
@@ -26,12 +27,18 @@ pub fn validate(mut grammar: Grammar) -> NormResult<Grammar> {
                 "validator permitted both an extern/match section"
             );
 
-            TokenMode::Extern {
-                conversions: enum_token
-                    .conversions
-                    .iter()
-                    .map(|conversion| conversion.from.clone())
-                    .collect(),
+            if enum_token.match_by_str {
+                TokenMode::ExternStr {
+                    terminals: Map::new(),
+                }
+            } else {
+                TokenMode::Extern {
+                    conversions: enum_token
+                        .conversions
+                        .iter()
+                        .map(|conversion| conversion.from.clone())
+                        .collect(),
+                }
             }
         } else {
             TokenMode::Internal {
@@ -53,6 +60,30 @@ pub fn validate(mut grammar: Grammar) -> NormResult<Grammar> {
         TokenMode::Extern { .. } => {
             // If using an external tokenizer, we're all done at this point.
         }
+        TokenMode::ExternStr { terminals } => {
+            // Each terminal is the whole token; which token it is gets
+            // decided by the generated code, by comparing strings.
+            let enum_token = grammar
+                .items
+                .iter_mut()
+                .filter_map(|item| match item {
+                    GrammarItem::ExternToken(data) => data.enum_token.as_mut(),
+                    _ => None,
+                })
+                .next()
+                .unwrap();
+            let type_name = enum_token.type_name.clone();
+            enum_token
+                .conversions
+                .extend(terminals.into_iter().map(|(from, span)| Conversion {
+                    span,
+                    from,
+                    to: Pattern {
+                        span,
+                        kind: PatternKind::Choose(type_name.clone()),
+                    },
+                }));
+        }
         TokenMode::Internal { match_block } => {
             // Otherwise, construct the `InternToken` item.
             construct(&mut grammar, match_block)?;
@@ -80,6 +111,13 @@ enum TokenMode {
     /// user should be using.
     Extern { conversions: Set<TerminalString> },
 
+    /// With `extern token T;`, any quoted terminal can be used, and
+    /// matches the tokens whose `AsRef<str>` is its text. This maps
+    /// the terminals used to where they first appear.
+    ExternStr {
+        terminals: Map<TerminalString, Span>,
+    },
+
     /// Otherwise, we are synthesizing the tokenizer. In that case,
     /// `match_block` summarizes the data from the `match { ... }`
     /// section, if any. If there was no `match` section, or the
@@ -216,9 +254,19 @@ impl<'grammar> Validator<'grammar> {
         for item in &self.grammar.items {
             match *item {
                 GrammarItem::Use(..) => {}
+                GrammarItem::Import(..) => {}
                 GrammarItem::MatchToken(..) => {}
                 GrammarItem::ExternToken(_) => {}
                 GrammarItem::InternToken(_) => {}
+                GrammarItem::Test(_) => {}
+                GrammarItem::TypeDecl(_) => {}
+                GrammarItem::LeftRecursiveDecl(_) => {}
+                GrammarItem::StartDecl(_) => {}
+                GrammarItem::PrecedenceDecl(ref data) => {
+                    for terminal in &data.terminals {
+                        self.validate_declared_terminal(data.span, terminal)?;
+                    }
+                }
                 GrammarItem::Nonterminal(ref data) => {
                     for alternative in &data.alternatives {
                         self.validate_alternative(alternative)?;
@@ -230,7 +278,8 @@ impl<'grammar> Validator<'grammar> {
     }
 
     fn validate_alternative(&mut self, alternative: &Alternative) -> NormResult<()> {
-        assert!(alternative.condition.is_none()); // macro expansion should have removed these
+        // macro expansion should have removed the macro conditions
+        assert!(!matches!(alternative.condition, Some(Condition::Macro(_))));
         self.validate_expr(&alternative.expr)?;
         Ok(())
     }
@@ -247,6 +296,11 @@ impl<'grammar> Validator<'grammar> {
             SymbolKind::Expr(ref expr) => {
                 self.validate_expr(expr)?;
             }
+            SymbolKind::Choice(ref exprs) => {
+                for expr in exprs {
+                    self.validate_expr(expr)?;
+                }
+            }
             SymbolKind::Terminal(ref term) => {
                 self.validate_terminal(symbol.span, term)?;
             }
@@ -254,10 +308,25 @@ impl<'grammar> Validator<'grammar> {
             SymbolKind::Repeat(ref repeat) => {
                 self.validate_symbol(&repeat.symbol)?;
             }
-            SymbolKind::Choose(ref sym) | SymbolKind::Name(_, ref sym) => {
+            SymbolKind::Choose(ref sym)
+            | SymbolKind::Name(_, ref sym)
+            | SymbolKind::DocName(_, ref sym) => {
                 self.validate_symbol(sym)?;
             }
-            SymbolKind::Lookahead | SymbolKind::Lookbehind | SymbolKind::Error => {}
+            SymbolKind::PosLookahead(ref sym) | SymbolKind::NegLookahead(ref sym) => {
+                match sym.kind {
+                    SymbolKind::Terminal(ref term) => self.validate_terminal(sym.span, term)?,
+                    _ => return_err!(
+                        sym.span,
+                        "lookahead assertions can only test a terminal, but `{}` is not one",
+                        sym
+                    ),
+                }
+            }
+            SymbolKind::Lookahead
+            | SymbolKind::Lookbehind
+            | SymbolKind::Error
+            | SymbolKind::Cut => {}
             SymbolKind::AmbiguousId(ref id) => {
                 panic!("ambiguous id `{}` encountered after name resolution", id)
             }
@@ -269,6 +338,24 @@ impl<'grammar> Validator<'grammar> {
         Ok(())
     }
 
+    /// Terminals named in a `%left` (etc) declaration are not parsed
+    /// as symbols, so a bare name there might not be a terminal at all.
+    fn validate_declared_terminal(&mut self, span: Span, term: &TerminalString) -> NormResult<()> {
+        if let TerminalString::Bare(_) = *term {
+            let is_terminal = match self.mode {
+                TokenMode::Internal { ref match_block } => {
+                    match_block.match_user_names.contains(term)
+                }
+                TokenMode::ExternStr { .. } => false,
+                TokenMode::Extern { .. } => true,
+            };
+            if !is_terminal {
+                return_err!(span, "`{}` is not a terminal", term);
+            }
+        }
+        self.validate_terminal(span, term)
+    }
+
     fn validate_terminal(&mut self, span: Span, term: &TerminalString) -> NormResult<()> {
         match self.mode {
             // If there is an extern token definition, validate that
@@ -283,6 +370,22 @@ impl<'grammar> Validator<'grammar> {
                 }
             }
 
+            TokenMode::ExternStr { ref mut terminals } => match *term {
+                TerminalString::Literal(TerminalLiteral::Quoted(_)) => {
+                    terminals.entry(term.clone()).or_insert(span);
+                }
+                TerminalString::Literal(TerminalLiteral::Regex(_)) => {
+                    return_err!(
+                        span,
+                        "terminal `{}` cannot be matched by an `extern token`; \
+                         write the name of the token as a string instead",
+                        term
+                    );
+                }
+                TerminalString::Bare(_) => panic!("bare terminal with `extern token`: {}", term),
+                TerminalString::Error => (),
+            },
+
             // If there is no extern token definition, then collect
             // the terminal literals ("class", r"[a-z]+") into a set.
             TokenMode::Internal {
@@ -320,8 +423,10 @@ fn construct(grammar: &mut Grammar, match_block: MatchBlock) -> NormResult<()> {
         ..
     } = match_block;
 
-    // Sort match entries by order of increasing precedence.
-    match_entries.sort();
+    // Sort match entries by order of increasing precedence, and those
+    // of the same precedence by where they appear, so that the Dfa
+    // prefers the first of any two that match the same input.
+    match_entries.sort_by_key(|entry| (entry.precedence, spans[&entry.match_literal]));
 
     // Build up two vectors, one of parsed regular expressions and
     // one of precedences, that are parallel with `literals`.
@@ -364,22 +469,27 @@ fn construct(grammar: &mut Grammar, match_block: MatchBlock) -> NormResult<()> {
                 feature
             )
         }
-        Err(DfaConstructionError::Ambiguity { match0, match1 }) => {
-            let literal0 = &match_entries[match0.index()].match_literal;
-            let literal1 = &match_entries[match1.index()].match_literal;
-            // FIXME(#88) -- it'd be nice to give an example here
-            return_err!(
-                spans[literal0],
-                "ambiguity detected between the terminal `{}` and the terminal `{}`",
-                literal0,
-                literal1
-            )
-        }
     };
 
-    grammar
-        .items
-        .push(GrammarItem::InternToken(InternToken { match_entries, dfa }));
+    let ambiguities = dfa
+        .ambiguities
+        .iter()
+        .map(|ambiguity| {
+            let preferred = &match_entries[ambiguity.preferred.index()].match_literal;
+            let other = &match_entries[ambiguity.other.index()].match_literal;
+            TerminalAmbiguity {
+                preferred: preferred.clone(),
+                other: other.clone(),
+                span: spans[other],
+            }
+        })
+        .collect();
+
+    grammar.items.push(GrammarItem::InternToken(InternToken {
+        match_entries,
+        dfa,
+        ambiguities,
+    }));
 
     // we need to inject a `'input` lifetime and `input: &'input str` parameter as well:
 
@@ -1,4 +1,4 @@
-use crate::grammar::parse_tree::Grammar;
+use crate::grammar::parse_tree::{Grammar, Span};
 use crate::lexer::dfa::interpret;
 use crate::normalize::resolve::resolve;
 use crate::normalize::NormResult;
@@ -217,16 +217,47 @@ fn complex_match() {
     assert!(validate_grammar(grammar).is_ok())
 }
 
-/// Test that overlapping regular expressions are still forbidden within one level
-/// of a match declaration.
+/// Test that overlapping regular expressions within one level of a
+/// match declaration are resolved in favor of the first one.
 #[test]
 // This test requires regex's unicode case support
 #[cfg_attr(not(feature = "unicode"), ignore)]
 fn ambiguity_within_match() {
-    check_err(
-        r##"ambiguity detected between the terminal `r#"b"#` and the terminal `r#"\(\?i\)b"#`"##,
-        r#"grammar; match { r"(?i)b" => "B", r"b" => "b" }"#,
-        r#"                                  ~~~~~~~~~~~~ "#,
+    let grammar = r#"grammar; match { r"(?i)b" => "B", r"b" => "b" }"#;
+    let parsed_grammar = validate_grammar(grammar).expect("validate");
+    let intern_token = parsed_grammar.intern_token().expect("intern_token");
+    let ambiguities: Vec<_> = intern_token
+        .ambiguities
+        .iter()
+        .map(|a| (a.preferred.to_string(), a.other.to_string()))
+        .collect();
+    assert_eq!(
+        ambiguities,
+        vec![(r##"r#"(?i)b"#"##.to_string(), r##"r#"b"#"##.to_string())]
+    );
+
+    check_intern_token(
+        grammar,
+        vec![("b", r#"Some(("B", "b"))"#), ("B", r#"Some(("B", "B"))"#)],
+    );
+}
+
+/// Test that terminals from the grammar of the same precedence are
+/// ordered by where they first appear.
+#[test]
+fn ambiguity_in_grammar() {
+    let grammar = r#"grammar; X = { r"[0-9]+", r"\w+" };"#;
+    let parsed_grammar = validate_grammar(grammar).expect("validate");
+    let intern_token = parsed_grammar.intern_token().expect("intern_token");
+    assert_eq!(intern_token.ambiguities.len(), 1);
+    assert_eq!(intern_token.ambiguities[0].span, Span(26, 32));
+
+    check_intern_token(
+        grammar,
+        vec![
+            ("123", r##"Some((r#"[0-9]+"#, "123"))"##),
+            ("abc", r##"Some((r#"\\w+"#, "abc"))"##),
+        ],
     );
 }
 
@@ -241,3 +272,39 @@ fn same_literal_twice() {
         r#"                                         ~~~~~~~~~~~~~~~ "#,
     );
 }
+
+#[test]
+fn lookahead_assertion_on_nonterminal() {
+    check_err(
+        r"lookahead assertions can only test a terminal, but `Y` is not one",
+        r#"grammar; X = "x" (?=Y) Y; Y = "y";"#,
+        r#"                    ~         "#,
+    );
+}
+
+#[test]
+fn precedence_of_nonterminal() {
+    check_err(
+        r"`Y` is not a terminal",
+        r#"grammar; %left "+" Y; X = X "+" Y; Y = "y";"#,
+        r#"         ~~~~~~~~~~~                        "#,
+    );
+}
+
+#[test]
+fn precedence_without_pattern() {
+    check_err(
+        r#"terminal `"\*"` does not have a pattern defined for it"#,
+        r#"grammar; extern { enum Tok { "+" => .. } } %left "+" "*"; X = X "+" X;"#,
+        r#"                                           ~~~~~~~~~~~~~             "#,
+    );
+}
+
+#[test]
+fn extern_token_regex() {
+    check_err(
+        r".*cannot be matched by an `extern token`; write the name of the token as a string instead",
+        r#"grammar; extern token Tok; X = X "+" r"[0-9]+";"#,
+        r#"                                     ~~~~~~~~~ "#,
+    );
+}
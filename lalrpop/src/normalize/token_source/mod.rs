@@ -0,0 +1,40 @@
+//! Turns `#[token_source = "proc_macro2"]` into the `extern` block it
+//! stands for. The tokens are the `lalrpop_procmacro::Token`s, which
+//! give their own locations like those of `extern token T: Token;`, so
+//! from here on the grammar is one with an external tokenizer whose
+//! terminals are all quoted strings.
+
+use crate::grammar::consts::TOKEN_SOURCE;
+use crate::grammar::parse_tree::*;
+use string_cache::DefaultAtom as Atom;
+
+#[cfg(test)]
+mod test;
+
+pub fn apply(mut grammar: Grammar) -> Grammar {
+    // `prevalidate` made sure that the only source is `proc_macro2`, and
+    // that the grammar has no `extern` block of its own
+    let Some(token_source) = grammar.annotations.iter().find(|a| a.id == *TOKEN_SOURCE) else {
+        return grammar;
+    };
+    let span = token_source.id_span;
+    let type_name = TypeRef::Nominal {
+        path: Path {
+            absolute: true,
+            ids: vec![Atom::from("lalrpop_procmacro"), Atom::from("Token")],
+        },
+        types: vec![],
+    };
+    grammar.items.push(GrammarItem::ExternToken(ExternToken {
+        span,
+        associated_types: vec![],
+        enum_token: Some(EnumToken {
+            type_name,
+            type_span: span,
+            conversions: vec![],
+            match_by_str: true,
+            token_trait: true,
+        }),
+    }));
+    grammar
+}
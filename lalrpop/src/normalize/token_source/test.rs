@@ -0,0 +1,27 @@
+use crate::test_util::normalized_grammar;
+
+#[test]
+fn proc_macro2_tokens() {
+    let grammar = normalized_grammar(
+        r#"
+#[token_source = "proc_macro2"]
+grammar;
+    pub Item: () = "fn" "Ident" "(" ")" "{" "}" => ();
+"#,
+    );
+    assert!(grammar.match_terminals_by_str);
+    assert!(grammar.match_terminals_by_token_trait);
+    assert!(grammar.match_terminals_by_proc_macro2);
+    assert_eq!(
+        grammar.types.terminal_token_type().to_string(),
+        "::lalrpop_procmacro::Token"
+    );
+    assert_eq!(grammar.types.terminal_loc_type().to_string(), "usize");
+}
+
+#[test]
+fn no_token_source() {
+    let grammar = normalized_grammar(r#"grammar; pub Item: () = "fn" => ();"#);
+    assert!(grammar.intern_token.is_some());
+    assert!(!grammar.match_terminals_by_proc_macro2);
+}
@@ -4,7 +4,7 @@ use super::{NormError, NormResult};
 use crate::grammar::consts::{ERROR, LOCATION};
 use crate::grammar::parse_tree::{
     ActionKind, Alternative, Grammar, GrammarItem, Lifetime, MatchMapping, NonterminalData,
-    NonterminalString, Path, Span, SymbolKind, TypeParameter, TypeRef,
+    NonterminalString, Path, Span, Symbol, SymbolKind, TypeParameter, TypeRef,
 };
 use crate::grammar::repr::{NominalTypeRepr, TypeRepr, Types};
 use std::collections::{HashMap, HashSet};
@@ -29,6 +29,8 @@ struct TypeInferencer<'grammar> {
 struct Nt<'grammar> {
     span: Span,
     type_decl: &'grammar Option<TypeRef>,
+    declared_type: &'grammar Option<TypeRef>,
+    arg_bounds: &'grammar Vec<(Symbol, TypeRef)>,
     alternatives: &'grammar Vec<Alternative>,
 }
 
@@ -66,12 +68,15 @@ impl<'grammar> TypeInferencer<'grammar> {
     fn make_types(grammar: &Grammar) -> Types {
         let opt_extern_token = grammar.extern_token();
 
-        // Determine error type (if any).
-        let error_type = opt_extern_token.and_then(|extern_token| {
-            extern_token
-                .associated_type(Atom::from(ERROR))
-                .map(|tr| tr.type_ref.type_repr())
-        });
+        // Determine error type (if any), from the `extern` block or
+        // from `grammar<E = ...>`.
+        let error_type = opt_extern_token
+            .and_then(|extern_token| {
+                extern_token
+                    .associated_type(Atom::from(ERROR))
+                    .map(|tr| tr.type_ref.type_repr())
+            })
+            .or_else(|| grammar.error_type.as_ref().map(TypeRef::type_repr));
 
         // Determine location type and enum type. If using an internal
         // token, that's specified by us, not user.
@@ -104,15 +109,15 @@ impl<'grammar> TypeInferencer<'grammar> {
             types
         } else {
             let extern_token = opt_extern_token.unwrap();
-            let loc_type = extern_token
-                .associated_type(Atom::from(LOCATION))
-                .map(|tr| tr.type_ref.type_repr());
-            let enum_type = extern_token
-                .enum_token
-                .as_ref()
-                .unwrap()
-                .type_name
-                .type_repr();
+            let enum_token = extern_token.enum_token.as_ref().unwrap();
+            let loc_type = if enum_token.token_trait {
+                Some(TypeRepr::usize())
+            } else {
+                extern_token
+                    .associated_type(Atom::from(LOCATION))
+                    .map(|tr| tr.type_ref.type_repr())
+            };
+            let enum_type = enum_token.type_name.type_repr();
             let mut types = Types::new(&grammar.prefix, loc_type, error_type, enum_type);
 
             // For each defined conversion, figure out the type of the
@@ -152,6 +157,25 @@ impl<'grammar> TypeInferencer<'grammar> {
             debug_assert!(self.types.lookup_nonterminal_type(&id).is_some());
         }
 
+        // Only now that every type is known can the arguments given to
+        // bounded macro arguments be checked.
+        let nonterminals: Vec<Nt> = self.nonterminals.values().cloned().collect();
+        for nt in nonterminals {
+            for (symbol, bound) in nt.arg_bounds {
+                let actual = self.symbol_type(&symbol.kind)?;
+                let expected = self.type_ref(bound)?;
+                if actual != expected {
+                    return_err!(
+                        symbol.span,
+                        "type of macro argument `{}` is `{}`, but it must be `{}`",
+                        symbol,
+                        actual,
+                        expected
+                    );
+                }
+            }
+        }
+
         Ok(self.types)
     }
 
@@ -170,7 +194,7 @@ impl<'grammar> TypeInferencer<'grammar> {
         }
 
         let ty = self.push(id, |this| {
-            if let Some(ref type_decl) = nt.type_decl {
+            if let Some(type_decl) = nt.type_decl.as_ref().or(nt.declared_type.as_ref()) {
                 return this.type_ref(type_decl);
             }
 
@@ -321,7 +345,9 @@ impl<'grammar> TypeInferencer<'grammar> {
     fn alternative_type(&mut self, alt: &Alternative) -> NormResult<TypeRepr> {
         match norm_util::analyze_action(alt) {
             AlternativeAction::User(&ActionKind::User(_))
-            | AlternativeAction::User(&ActionKind::Fallible(_)) => {
+            | AlternativeAction::User(&ActionKind::Fallible(_))
+            | AlternativeAction::User(&ActionKind::ExternalFn(_))
+            | AlternativeAction::User(&ActionKind::FallibleExternalFn(_)) => {
                 return_err!(
                     alt.span,
                     "cannot infer types if there is custom action code"
@@ -357,15 +383,20 @@ impl<'grammar> TypeInferencer<'grammar> {
             SymbolKind::Terminal(ref id) => Ok(self.types.terminal_type(id).clone()),
             SymbolKind::Nonterminal(ref id) => self.nonterminal_type(id),
             SymbolKind::Choose(ref s) => self.symbol_type(&s.kind),
-            SymbolKind::Name(_, ref s) => self.symbol_type(&s.kind),
+            SymbolKind::Name(_, ref s) | SymbolKind::DocName(_, ref s) => self.symbol_type(&s.kind),
             SymbolKind::Error => Ok(self.types.error_recovery_type().clone()),
+            SymbolKind::PosLookahead(_) | SymbolKind::NegLookahead(_) => {
+                Ok(TypeRepr::Tuple(vec![]))
+            }
 
             SymbolKind::Repeat(..)
             | SymbolKind::Expr(..)
+            | SymbolKind::Choice(..)
             | SymbolKind::Macro(..)
             | SymbolKind::AmbiguousId(..)
             | SymbolKind::Lookahead
-            | SymbolKind::Lookbehind => {
+            | SymbolKind::Lookbehind
+            | SymbolKind::Cut => {
                 unreachable!("symbol `{:?}` should have been expanded away", symbol)
             }
         }
@@ -377,6 +408,8 @@ impl<'grammar> Nt<'grammar> {
         Nt {
             span: data.span,
             type_decl: &data.type_decl,
+            declared_type: &data.declared_type,
+            arg_bounds: &data.arg_bounds,
             alternatives: &data.alternatives,
         }
     }
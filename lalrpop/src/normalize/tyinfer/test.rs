@@ -242,3 +242,73 @@ grammar;
         )],
     )
 }
+
+#[test]
+fn error_type_parameter() {
+    compare(
+        r#"
+grammar<E = MyError>;
+    Z = !;
+"#,
+        vec![(
+            "Z",
+            "__lalrpop_util::ErrorRecovery<usize, Token<'input>, MyError>",
+        )],
+    )
+}
+
+#[test]
+fn test_declared_type() {
+    // without the `%type`, the type of `X` references itself
+    let grammar = parser::parse_grammar(
+        r#"
+grammar;
+    extern { enum Tok { "Hi" => Hi(..), "Ho" => Ho(..) } }
+    %type<Vec<Tok>> X;
+    X = {
+        X "Hi",
+        "Ho" => vec![<>]
+    };
+"#,
+    )
+    .unwrap();
+    let grammar = crate::normalize::resolve::resolve(grammar).unwrap();
+    let grammar = expand_macros(grammar).unwrap();
+    let types = infer_types(&grammar).unwrap();
+    let id = NonterminalString(Atom::from("X"));
+    assert_eq!(types.nonterminal_type(&id), &type_repr("Vec<Tok>"));
+}
+
+#[test]
+fn test_bounded_macro_arg() {
+    compare(
+        r#"
+grammar;
+    extern { enum Tok { "Hi" => Hi(..), "Ho" => Ho(..) } }
+    Two<E: Foo>: (Foo, Foo) = E E;
+    X = Two<Y>;
+    Y: Foo = "Hi";
+"#,
+        vec![("X", "(Foo, Foo)")],
+    )
+}
+
+#[test]
+fn test_bounded_macro_arg_mismatch() {
+    let grammar = parser::parse_grammar(
+        r#"
+grammar;
+    extern { enum Tok { "Hi" => Hi(..), "Ho" => Ho(..) } }
+    Two<E: Foo>: (Foo, Foo) = E E;
+    X = Two<"Ho">;
+"#,
+    )
+    .unwrap();
+    let grammar = expand_macros(grammar).unwrap();
+    let grammar = token_check::validate(grammar).unwrap();
+    let err = infer_types(&grammar).unwrap_err();
+    assert_eq!(
+        err.message,
+        "type of macro argument `\"Ho\"` is `Tok`, but it must be `Foo`"
+    );
+}
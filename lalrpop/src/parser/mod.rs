@@ -47,7 +47,13 @@ macro_rules! parser {
 }
 
 pub fn parse_grammar(input: &str) -> Result<Grammar, ParseError<'_>> {
-    let mut grammar = parser!(input, 0, Grammar, StartGrammar)?;
+    parse_grammar_at(input, 0)
+}
+
+/// Parses a grammar whose spans all start at `offset`; used for
+/// imported files, whose spans must not overlap the importing file's.
+pub fn parse_grammar_at(input: &str, offset: usize) -> Result<Grammar, ParseError<'_>> {
+    let mut grammar = parser!(input, offset, Grammar, StartGrammar)?;
 
     // find a unique prefix that does not appear anywhere in the input
     while input.contains(&grammar.prefix) {
@@ -1,5 +1,6 @@
-use crate::grammar::parse_tree::{GrammarItem, MatchItem};
+use crate::grammar::parse_tree::{Condition, GrammarItem, MatchItem, SymbolKind};
 use crate::parser;
+use crate::test_util::compare;
 
 #[test]
 fn match_block() {
@@ -151,6 +152,26 @@ grammar<F>(logger: &mut F) where F: for<'a> FnMut(&'a str);
     }
 }
 
+#[test]
+fn error_type_parameter() {
+    let g = parser::parse_grammar("grammar<'input, E = MyError<'input>>;").unwrap();
+    assert_eq!(g.type_parameters.len(), 1);
+    assert!(g.error_type.is_some());
+
+    // only `E` names the error type
+    assert!(parser::parse_grammar("grammar<T = MyError>;").is_err());
+}
+
+#[test]
+fn line_continuation() {
+    let text = "grammar; X = \"a\" \\\n    \"b\" => ();";
+    let g = parser::parse_grammar(text).unwrap();
+    let alternative = &g.items[0].as_nonterminal().unwrap().alternatives[0];
+    assert_eq!(alternative.expr.symbols.len(), 2);
+    let span = alternative.span;
+    assert_eq!(&text[span.0..span.1], "\"a\" \\\n    \"b\" => ()");
+}
+
 #[test]
 fn optional_semicolon() {
     // Semi after block is optional
@@ -169,3 +190,124 @@ Bar: () = "bar"
 "#;
     assert!(parser::parse_grammar(g).is_err());
 }
+
+#[test]
+fn bare_named_symbols() {
+    let symbols = |g: &str| -> Vec<String> {
+        let parsed = parser::parse_grammar(g).unwrap();
+        let nt = parsed.items[0].as_nonterminal().unwrap();
+        nt.alternatives[0]
+            .expr
+            .symbols
+            .iter()
+            .map(|sym| {
+                assert!(matches!(
+                    sym.kind,
+                    SymbolKind::Name(..) | SymbolKind::Terminal(..)
+                ));
+                sym.to_string()
+            })
+            .collect()
+    };
+
+    // `x:Expr` means the same as `<x:Expr>`
+    assert_eq!(
+        symbols(r#"grammar; Foo = { l:Bar "+" mut r:Baz* => (l, r) };"#),
+        symbols(r#"grammar; Foo = { <l:Bar> "+" <mut r:Baz*> => (l, r) };"#),
+    );
+}
+
+#[test]
+fn doc_named_symbols() {
+    let g = r#"grammar; Foo = { <l:Bar> "+" @rhs:Baz* => l };"#;
+    let parsed = parser::parse_grammar(g).unwrap();
+    let nt = parsed.items[0].as_nonterminal().unwrap();
+    let symbol = &nt.alternatives[0].expr.symbols[2];
+    assert!(matches!(symbol.kind, SymbolKind::DocName(..)));
+    assert_eq!(symbol.to_string(), "@rhs:Baz*");
+}
+
+#[test]
+fn grammar_tests() {
+    let g = r#"grammar; #[test] parse "1, 2" as Pair => (1, 2); pub Pair = Num "," Num;"#;
+    let parsed = parser::parse_grammar(g).unwrap();
+    match parsed.items[0] {
+        GrammarItem::Test(ref data) => {
+            assert_eq!(data.nonterminal.to_string(), "Pair");
+            assert_eq!(data.input, "1, 2");
+            assert_eq!(data.pattern, "(1, 2)");
+        }
+        ref item => panic!("expected a test, found {:?}", item),
+    }
+
+    // `parse` and `as` are ordinary identifiers, checked by the action
+    let g = r#"grammar; #[test] parse "1" is Num => 1; Num = "1";"#;
+    assert!(parser::parse_grammar(g).is_err());
+    let g = r#"grammar; #[test] prase "1" as Num => 1; Num = "1";"#;
+    assert!(parser::parse_grammar(g).is_err());
+}
+
+#[test]
+fn repeat_counts() {
+    let g = r#"grammar; X = "a"{2} "b"{1,} ("c" "d"){0, 3};"#;
+    let parsed = parser::parse_grammar(g).unwrap();
+    let alternative = match parsed.items[0] {
+        GrammarItem::Nonterminal(ref data) => &data.alternatives[0],
+        ref item => panic!("expected a nonterminal, found {:?}", item),
+    };
+    assert_eq!(
+        alternative.expr.to_string(),
+        r#"("a"{2} "b"{1,} ("c" "d"){0,3})"#
+    );
+}
+
+#[test]
+fn precedence_with_assoc() {
+    let combined = parser::parse_grammar(
+        r#"grammar; E = { #[precedence(level=2, assoc="left")] E "*" E => () };"#,
+    )
+    .unwrap();
+    let separate = parser::parse_grammar(
+        r#"grammar; E = { #[precedence(level="2")] #[assoc(side="left")] E "*" E => () };"#,
+    )
+    .unwrap();
+    compare(combined, separate);
+
+    // only `precedence` takes a second argument
+    let g = r#"grammar; E = { #[cold(level=2, assoc="left")] E => () };"#;
+    assert!(parser::parse_grammar(g).is_err());
+}
+
+#[test]
+fn alternative_guards() {
+    let g = r#"grammar; X = { #[if { a != "b" }] #[cold] #[if { c }] <a:"a"> <c:"c"> => () };"#;
+    let parsed = parser::parse_grammar(g).unwrap();
+    let alternative = match parsed.items[0] {
+        GrammarItem::Nonterminal(ref data) => &data.alternatives[0],
+        ref item => panic!("expected a nonterminal, found {:?}", item),
+    };
+    assert_eq!(
+        alternative.condition,
+        Some(Condition::Arbitrary(r#"(a != "b") && (c)"#.to_string()))
+    );
+    assert_eq!(alternative.annotations.len(), 1);
+
+    // a guard cannot be combined with a macro condition
+    let g = r#"grammar; X<T> = { #[if { true }] T if T == "a" => () };"#;
+    assert!(parser::parse_grammar(g).is_err());
+}
+
+#[test]
+fn triple_quoted_terminals() {
+    // the content is taken as it is, without escapes
+    let triple = parser::parse_grammar(r#"grammar; X = """say "hi"\n""";"#).unwrap();
+    let single = parser::parse_grammar(r#"grammar; X = "say \"hi\"\\n";"#).unwrap();
+    compare(triple, single);
+
+    let g = parser::parse_grammar(r#"grammar; X = """a"b"""";"#).unwrap();
+    let alternative = match g.items[0] {
+        GrammarItem::Nonterminal(ref data) => &data.alternatives[0],
+        ref item => panic!("expected a nonterminal, found {:?}", item),
+    };
+    assert_eq!(alternative.expr.symbols[0].canonical_form(), r#""a\"b\"""#);
+}
@@ -0,0 +1,475 @@
+//! Writes a grammar as railroad diagrams: an HTML page with an SVG
+//! drawing of each nonterminal, read by following the lines from left
+//! to right. Terminals are drawn in rounded boxes, and nonterminals in
+//! square ones that link to their own diagram. The uses of macros are
+//! expected to have been expanded (see `normalize::expand_macro_uses`),
+//! so `X*`, `X+` and `X?` are drawn as loops and bypasses around `X`.
+
+use crate::collections::Map;
+use crate::ebnf::nonterminal_idents;
+use crate::grammar::parse_tree::{
+    Grammar, GrammarItem, NonterminalString, RepeatOp, Symbol, SymbolKind, TerminalLiteral,
+    TerminalString,
+};
+use std::cmp;
+use std::io::{self, Write};
+
+/// The width of a character of the monospace font of the labels.
+const CHAR_WIDTH: usize = 8;
+const BOX_HEIGHT: usize = 22;
+/// The space between the label of a box and its sides.
+const PADDING: usize = 10;
+/// The length of the line between two boxes in a sequence.
+const GAP: usize = 10;
+/// The space between two branches of a choice, or a loop.
+const BRANCH_GAP: usize = 8;
+/// The radius of the curves where lines branch off.
+const ARC: usize = 10;
+/// The space around a diagram.
+const MARGIN: usize = 10;
+/// The length of the lines that enter and leave a diagram.
+const END: usize = 20;
+
+pub fn write_railroad(grammar: &Grammar, out: &mut dyn Write) -> io::Result<()> {
+    let nonterminals: Vec<_> = grammar
+        .items
+        .iter()
+        .filter_map(GrammarItem::as_nonterminal)
+        .collect();
+    let idents = nonterminal_idents(&nonterminals);
+
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html>")?;
+    writeln!(out, "<head>")?;
+    writeln!(out, "<meta charset=\"utf-8\">")?;
+    writeln!(out, "<style>")?;
+    writeln!(
+        out,
+        "svg path {{ fill: none; stroke: black; stroke-width: 2; }}"
+    )?;
+    writeln!(
+        out,
+        "svg rect {{ fill: #ffffe0; stroke: black; stroke-width: 2; }}"
+    )?;
+    writeln!(out, "svg rect.nonterminal {{ fill: #e0f0ff; }}")?;
+    writeln!(
+        out,
+        "svg text {{ font-family: monospace; font-size: 13px; text-anchor: middle; }}"
+    )?;
+    writeln!(out, "</style>")?;
+    writeln!(out, "</head>")?;
+    writeln!(out, "<body>")?;
+    for data in nonterminals {
+        let node = Node::choice(
+            data.alternatives
+                .iter()
+                .map(|alternative| sequence(&idents, &alternative.expr.symbols))
+                .collect(),
+        );
+        writeln!(
+            out,
+            "<h2 id=\"{}\">{}</h2>",
+            idents[&data.name],
+            escape(&data.name.0)
+        )?;
+        writeln!(out, "{}", svg(&node))?;
+    }
+    writeln!(out, "</body>")?;
+    writeln!(out, "</html>")?;
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Terminal(String),
+    /// The label and the `id` of the diagram it links to.
+    Nonterminal(String, String),
+    /// A line that reads nothing.
+    Skip,
+    Sequence(Vec<Node>),
+    /// The first choice is on the line the others branch off.
+    Choice(Vec<Node>),
+    /// The node, then as many more times as wanted.
+    Loop(Box<Node>),
+}
+
+fn sequence(idents: &Map<NonterminalString, String>, symbols: &[Symbol]) -> Node {
+    let mut nodes: Vec<_> = symbols
+        .iter()
+        .filter_map(|symbol| node(idents, symbol))
+        .collect();
+    match nodes.len() {
+        0 => Node::Skip,
+        1 => nodes.pop().unwrap(),
+        _ => Node::Sequence(nodes),
+    }
+}
+
+/// The node for `symbol`, or `None` if it matches no input, like `@L`
+/// or a lookahead assertion.
+fn node(idents: &Map<NonterminalString, String>, symbol: &Symbol) -> Option<Node> {
+    match symbol.kind {
+        SymbolKind::Expr(ref expr) => Some(sequence(idents, &expr.symbols)),
+        SymbolKind::Choice(ref exprs) => Some(Node::choice(
+            exprs
+                .iter()
+                .map(|expr| sequence(idents, &expr.symbols))
+                .collect(),
+        )),
+        SymbolKind::Terminal(ref terminal) => Some(Node::Terminal(terminal_label(terminal))),
+        SymbolKind::Nonterminal(ref name) => {
+            let ident = idents.get(name).cloned().unwrap_or_default();
+            Some(Node::Nonterminal(name.to_string(), ident))
+        }
+        SymbolKind::Repeat(ref repeat) => {
+            let inner = node(idents, &repeat.symbol)?;
+            let many = |inner: Node| Node::Choice(vec![Node::Skip, Node::Loop(Box::new(inner))]);
+            Some(match repeat.op {
+                RepeatOp::Star => many(inner),
+                RepeatOp::Plus => Node::Loop(Box::new(inner)),
+                RepeatOp::Question => Node::Choice(vec![Node::Skip, inner]),
+                RepeatOp::Count { min, max } => {
+                    let mut nodes = vec![inner.clone(); min];
+                    match max {
+                        None => nodes.push(many(inner)),
+                        Some(max) => nodes.extend(
+                            (min..max).map(|_| Node::Choice(vec![Node::Skip, inner.clone()])),
+                        ),
+                    }
+                    Node::Sequence(nodes)
+                }
+            })
+        }
+        SymbolKind::Choose(ref symbol)
+        | SymbolKind::Name(_, ref symbol)
+        | SymbolKind::DocName(_, ref symbol) => node(idents, symbol),
+        SymbolKind::Error => Some(Node::Terminal("error".to_string())),
+        SymbolKind::Lookahead
+        | SymbolKind::Lookbehind
+        | SymbolKind::PosLookahead(_)
+        | SymbolKind::NegLookahead(_)
+        | SymbolKind::Cut => None,
+        SymbolKind::Macro(_) | SymbolKind::AmbiguousId(_) => {
+            unreachable!("symbol `{}` should have been expanded away", symbol)
+        }
+    }
+}
+
+fn terminal_label(terminal: &TerminalString) -> String {
+    match terminal {
+        TerminalString::Literal(TerminalLiteral::Quoted(s)) => s.to_string(),
+        TerminalString::Literal(TerminalLiteral::Regex(s)) => format!("r\"{}\"", s),
+        TerminalString::Bare(s) => s.to_string(),
+        TerminalString::Error => "error".to_string(),
+    }
+}
+
+impl Node {
+    fn choice(mut nodes: Vec<Node>) -> Node {
+        match nodes.len() {
+            0 => Node::Skip,
+            1 => nodes.pop().unwrap(),
+            _ => Node::Choice(nodes),
+        }
+    }
+
+    fn width(&self) -> usize {
+        match self {
+            Node::Terminal(label) | Node::Nonterminal(label, _) => {
+                label.chars().count() * CHAR_WIDTH + 2 * PADDING
+            }
+            Node::Skip => 0,
+            Node::Sequence(nodes) => {
+                nodes.iter().map(Node::width).sum::<usize>() + GAP * (nodes.len().max(1) - 1)
+            }
+            Node::Choice(nodes) => nodes.iter().map(Node::width).max().unwrap_or(0) + 4 * ARC,
+            Node::Loop(node) => node.width() + 2 * ARC,
+        }
+    }
+
+    /// How far the node reaches above the line it is entered on.
+    fn up(&self) -> usize {
+        match self {
+            Node::Terminal(_) | Node::Nonterminal(..) => BOX_HEIGHT / 2,
+            Node::Skip => 0,
+            Node::Sequence(nodes) => nodes.iter().map(Node::up).max().unwrap_or(0),
+            Node::Choice(nodes) => nodes.first().map_or(0, Node::up),
+            Node::Loop(node) => node.up(),
+        }
+    }
+
+    /// How far the node reaches below the line it is entered on.
+    fn down(&self) -> usize {
+        match self {
+            Node::Terminal(_) | Node::Nonterminal(..) => BOX_HEIGHT / 2,
+            Node::Skip => 0,
+            Node::Sequence(nodes) => nodes.iter().map(Node::down).max().unwrap_or(0),
+            Node::Choice(nodes) => {
+                let last = nodes.len() - 1;
+                self.branch_offsets()[last] + nodes[last].down()
+            }
+            Node::Loop(node) => self.loop_offset(node),
+        }
+    }
+
+    /// How far below the line of a choice each of its branches is.
+    fn branch_offsets(&self) -> Vec<usize> {
+        let Node::Choice(nodes) = self else {
+            unreachable!()
+        };
+        let mut offsets = vec![0];
+        let mut bottom = nodes[0].down();
+        for node in &nodes[1..] {
+            // leave room for the curves down to the branch
+            let offset = cmp::max(bottom + BRANCH_GAP + node.up(), 2 * ARC);
+            offsets.push(offset);
+            bottom = offset + node.down();
+        }
+        offsets
+    }
+
+    /// How far below its line the way back of a loop is.
+    fn loop_offset(&self, node: &Node) -> usize {
+        cmp::max(node.down() + BRANCH_GAP, 2 * ARC)
+    }
+
+    /// Draws the node from `x` on, entering and leaving it on the line
+    /// at `y`.
+    fn draw(&self, x: usize, y: usize, out: &mut String) {
+        match self {
+            Node::Terminal(label) => {
+                out.push_str(&text_box(x, y, self.width(), label, BOX_HEIGHT / 2, None));
+            }
+            Node::Nonterminal(label, ident) => {
+                let text_box = text_box(x, y, self.width(), label, 0, Some("nonterminal"));
+                out.push_str(&format!("<a href=\"#{}\">{}</a>", ident, text_box));
+            }
+            Node::Skip => {}
+            Node::Sequence(nodes) => {
+                let mut x = x;
+                for (index, node) in nodes.iter().enumerate() {
+                    if index > 0 {
+                        out.push_str(&line(x, y, x + GAP));
+                        x += GAP;
+                    }
+                    node.draw(x, y, out);
+                    x += node.width();
+                }
+            }
+            Node::Choice(nodes) => {
+                let inner = self.width() - 4 * ARC;
+                let (left, right) = (x + 2 * ARC, x + 2 * ARC + inner);
+                for (node, offset) in nodes.iter().zip(self.branch_offsets()) {
+                    let branch = y + offset;
+                    if offset == 0 {
+                        out.push_str(&line(x, y, left));
+                    } else {
+                        out.push_str(&path(format!(
+                            "M{} {} Q{} {} {} {} V{} Q{} {} {} {}",
+                            x,
+                            y,
+                            x + ARC,
+                            y,
+                            x + ARC,
+                            y + ARC,
+                            branch - ARC,
+                            x + ARC,
+                            branch,
+                            left,
+                            branch
+                        )));
+                    }
+                    node.draw(left, branch, out);
+                    out.push_str(&line(left + node.width(), branch, right));
+                    if offset == 0 {
+                        out.push_str(&line(right, y, right + 2 * ARC));
+                    } else {
+                        out.push_str(&path(format!(
+                            "M{} {} Q{} {} {} {} V{} Q{} {} {} {}",
+                            right,
+                            branch,
+                            right + ARC,
+                            branch,
+                            right + ARC,
+                            branch - ARC,
+                            y + ARC,
+                            right + ARC,
+                            y,
+                            right + 2 * ARC,
+                            y
+                        )));
+                    }
+                }
+            }
+            Node::Loop(node) => {
+                let (left, right) = (x + ARC, x + ARC + node.width());
+                let back = y + self.loop_offset(node);
+                out.push_str(&line(x, y, left));
+                node.draw(left, y, out);
+                out.push_str(&line(right, y, right + ARC));
+                out.push_str(&path(format!(
+                    "M{} {} Q{} {} {} {} V{} Q{} {} {} {} H{} Q{} {} {} {} V{} Q{} {} {} {}",
+                    right,
+                    y,
+                    right + ARC,
+                    y,
+                    right + ARC,
+                    y + ARC,
+                    back - ARC,
+                    right + ARC,
+                    back,
+                    right,
+                    back,
+                    left,
+                    x,
+                    back,
+                    x,
+                    back - ARC,
+                    y + ARC,
+                    x,
+                    y,
+                    left,
+                    y
+                )));
+            }
+        }
+    }
+}
+
+/// The SVG drawing of a whole diagram, with a bar at each end.
+fn svg(node: &Node) -> String {
+    let width = node.width() + 2 * (MARGIN + END);
+    let height = node.up() + node.down() + 2 * MARGIN;
+    let y = MARGIN + node.up();
+    let (start, end) = (MARGIN, MARGIN + END + node.width() + END);
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n",
+        width, height
+    );
+    out.push_str(&path(format!(
+        "M{} {} V{} M{} {} H{}",
+        start,
+        y - ARC,
+        y + ARC,
+        start,
+        y,
+        start + END
+    )));
+    node.draw(start + END, y, &mut out);
+    out.push_str(&path(format!(
+        "M{} {} H{} M{} {} V{}",
+        end - END,
+        y,
+        end,
+        end,
+        y - ARC,
+        y + ARC
+    )));
+    out.push_str("</svg>");
+    out
+}
+
+fn text_box(
+    x: usize,
+    y: usize,
+    width: usize,
+    label: &str,
+    radius: usize,
+    class: Option<&str>,
+) -> String {
+    let class = class.map_or(String::new(), |class| format!(" class=\"{}\"", class));
+    format!(
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"{}\"{}/>\
+         <text x=\"{}\" y=\"{}\">{}</text>\n",
+        x,
+        y - BOX_HEIGHT / 2,
+        width,
+        BOX_HEIGHT,
+        radius,
+        class,
+        x + width / 2,
+        y + 4,
+        escape(label)
+    )
+}
+
+fn line(x0: usize, y: usize, x1: usize) -> String {
+    if x0 == x1 {
+        String::new()
+    } else {
+        path(format!("M{} {} H{}", x0, y, x1))
+    }
+}
+
+fn path(d: String) -> String {
+    format!("<path d=\"{}\"/>\n", d)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{write_railroad, Node};
+    use crate::normalize;
+    use crate::parser;
+
+    fn railroad(text: &str) -> String {
+        let grammar = parser::parse_grammar(text).unwrap();
+        let grammar = normalize::expand_macro_uses(grammar).unwrap();
+        let mut out = vec![];
+        write_railroad(&grammar, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn diagrams() {
+        let html = railroad(
+            r#"
+grammar;
+pub Exprs = Comma<Expr>;
+Comma<T>: Vec<T> = {
+    <mut v:(<T> ",")*> <e:T?> => v,
+};
+Expr: () = {
+    "(" Expr+ ")" => (),
+    r"[0-9]+" => (),
+};
+"#,
+        );
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains(r#"<h2 id="Exprs">Exprs</h2>"#));
+        assert!(html.contains(r#"<h2 id="Comma_Expr">Comma&lt;Expr&gt;</h2>"#));
+        // nonterminals link to their diagram
+        assert!(html.contains(r##"<a href="#Comma_Expr">"##));
+        assert!(html.contains(r##"<a href="#Expr">"##));
+        assert!(html.contains(r#">r&quot;[0-9]+&quot;</text>"#));
+        assert_eq!(html.matches("<svg ").count(), 3);
+    }
+
+    #[test]
+    fn layout() {
+        let terminal = Node::Terminal("ab".to_string());
+        assert_eq!(terminal.width(), 36);
+        assert_eq!((terminal.up(), terminal.down()), (11, 11));
+
+        // `"ab"?`: the box is on a branch below the line
+        let optional = Node::Choice(vec![Node::Skip, terminal.clone()]);
+        assert_eq!(optional.width(), 76);
+        assert_eq!(optional.branch_offsets(), vec![0, 20]);
+        assert_eq!((optional.up(), optional.down()), (0, 31));
+
+        // `"ab"+`: the way back is below the box
+        let repeat = Node::Loop(Box::new(terminal.clone()));
+        assert_eq!(repeat.width(), 56);
+        assert_eq!((repeat.up(), repeat.down()), (11, 20));
+
+        let sequence = Node::Sequence(vec![terminal, optional]);
+        assert_eq!(sequence.width(), 122);
+        assert_eq!((sequence.up(), sequence.down()), (11, 31));
+    }
+}
@@ -2,11 +2,14 @@
 //! to `configuration::Configuration`, but it is not exported outside the
 //! crate. Note that all fields are public and so forth for convenience.
 
-use crate::log::{Level, Log};
+use crate::log::{Level, Log, Timings};
 use crate::style::{self, Style};
+use std::cell::RefCell;
 use std::collections::BTreeSet;
 use std::default::Default;
+use std::fmt::Display;
 use std::path;
+use std::time::Duration;
 
 // These two, ubiquitous types are defined here so that their fields can be private
 // across crate, but visible within the crate:
@@ -38,6 +41,10 @@ pub struct Session {
 
     pub out_dir: Option<path::PathBuf>,
 
+    /// Name of the generated module, instead of the stem of the
+    /// grammar file
+    pub module_name: Option<String>,
+
     /// Emit `rerun-if-changed` directives for Cargo
     pub emit_rerun_directives: bool,
 
@@ -48,9 +55,49 @@ pub struct Session {
     /// Emit whitespace in the generated code to improve readability.
     pub emit_whitespace: bool,
 
+    /// Format the generated code with rustfmt, if it can be run
+    pub format_generated_code: bool,
+
+    /// A comment to write at the top of the generated code
+    pub module_comment: Option<String>,
+
     /// Emit report file about generated code
     pub emit_report: bool,
 
+    /// Emit a Graphviz DOT file of the LR automaton
+    pub emit_dot: bool,
+
+    /// Emit a file of AST type definitions inferred from the grammar
+    pub emit_rust_types: bool,
+
+    /// Emit a JSON file describing the conflicts of the grammar
+    pub emit_conflicts_json: bool,
+
+    /// Emit a file with the grammar in EBNF notation
+    pub emit_ebnf: bool,
+
+    /// Emit a file with the grammar in BNF notation
+    pub emit_bnf: bool,
+
+    /// Emit an HTML file with a railroad diagram of each nonterminal
+    pub emit_railroad: bool,
+
+    /// Emit `tables()` and `load_tables_from_path` for table-driven parsers
+    pub serialize_tables: bool,
+
+    /// Only check grammars for errors and conflicts, writing no files
+    pub check_only: bool,
+
+    /// Warn about alternatives that no state of the LR tables reduces
+    pub warn_unreachable: bool,
+
+    /// List all the items of the state of a conflict, with their
+    /// lookaheads, when reporting it
+    pub verbose_conflicts: bool,
+
+    /// How long each phase took, if they are to be printed
+    pub timings: Option<RefCell<Timings>>,
+
     pub color_config: ColorConfig,
 
     /// Stop after you find `max_errors` errors. If this value is 0,
@@ -89,6 +136,11 @@ pub struct Session {
 
     /// Features used for conditional compilation
     pub features: Option<BTreeSet<String>>,
+
+    /// The other configuration options used for conditional
+    /// compilation, as `(key, value)`: `("target_os", "linux")`, or
+    /// `("unix", "")` for options without a value
+    pub cfg: Option<BTreeSet<(String, String)>>,
 }
 
 impl Session {
@@ -97,11 +149,25 @@ impl Session {
             log: Log::new(Level::Informative),
             in_dir: None,
             out_dir: None,
+            module_name: None,
             force_build: false,
             emit_rerun_directives: false,
             emit_comments: false,
             emit_whitespace: true,
+            format_generated_code: false,
+            module_comment: None,
             emit_report: false,
+            emit_dot: false,
+            emit_rust_types: false,
+            emit_conflicts_json: false,
+            emit_ebnf: false,
+            emit_bnf: false,
+            emit_railroad: false,
+            serialize_tables: false,
+            check_only: false,
+            warn_unreachable: false,
+            verbose_conflicts: false,
+            timings: None,
             color_config: ColorConfig::default(),
             max_errors: 1,
             heading: style::FG_WHITE.with(style::BOLD),
@@ -114,6 +180,7 @@ impl Session {
             hint_text: style::FG_BRIGHT_MAGENTA.with(style::BOLD),
             unit_test: false,
             features: Default::default(),
+            cfg: Default::default(),
         }
     }
 
@@ -124,11 +191,25 @@ impl Session {
             log: Log::new(Level::Debug),
             in_dir: None,
             out_dir: None,
+            module_name: None,
             force_build: false,
             emit_rerun_directives: false,
             emit_comments: false,
             emit_whitespace: true,
+            format_generated_code: false,
+            module_comment: None,
             emit_report: false,
+            emit_dot: false,
+            emit_rust_types: false,
+            emit_conflicts_json: false,
+            emit_ebnf: false,
+            emit_bnf: false,
+            emit_railroad: false,
+            serialize_tables: false,
+            check_only: false,
+            warn_unreachable: false,
+            verbose_conflicts: false,
+            timings: None,
             color_config: ColorConfig::IfTty,
             max_errors: 1,
             heading: Style::new(),
@@ -141,6 +222,7 @@ impl Session {
             hint_text: Style::new(),
             unit_test: true,
             features: Default::default(),
+            cfg: Default::default(),
         }
     }
 
@@ -157,6 +239,19 @@ impl Session {
         self.log.log(level, message)
     }
 
+    /// Starts timing the phase `name`, if phases are being timed.
+    pub fn begin_phase<N: Display>(&self, name: N) -> Option<usize> {
+        self.timings
+            .as_ref()
+            .map(|timings| timings.borrow_mut().begin(name.to_string()))
+    }
+
+    pub fn end_phase(&self, index: Option<usize>, elapsed: Duration) {
+        if let (Some(timings), Some(index)) = (&self.timings, index) {
+            timings.borrow_mut().end(index, elapsed);
+        }
+    }
+
     pub fn emit_rerun_directive(&self, path: &path::Path) {
         if self.emit_rerun_directives {
             if let Some(display) = path.to_str() {
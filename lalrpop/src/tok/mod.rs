@@ -41,6 +41,7 @@ pub enum Tok<'input> {
     Enum,
     Extern,
     Grammar,
+    Import,
     Match,
     Else,
     If,
@@ -52,6 +53,14 @@ pub enum Tok<'input> {
     For,
     Dyn,
 
+    // Precedence declarations:
+    PercentLeft,          // %left
+    PercentRight,         // %right
+    PercentNonassoc,      // %nonassoc
+    PercentType,          // %type
+    PercentLeftRecursive, // %left_recursive
+    PercentStart,         // %start
+
     // Special keywords: these are accompanied by a series of
     // uninterpreted strings representing imports and stuff.
     Use(&'input str),
@@ -59,11 +68,14 @@ pub enum Tok<'input> {
     // Identifiers of various kinds:
     Escape(&'input str),
     Id(&'input str),
-    MacroId(&'input str),       // identifier followed immediately by `<`
-    Lifetime(&'input str),      // includes the `'`
-    StringLiteral(&'input str), // excludes the `"`
-    CharLiteral(&'input str),   // excludes the `'`
-    RegexLiteral(&'input str),  // excludes the `r"` and `"`
+    MacroId(&'input str),             // identifier followed immediately by `<`
+    Lifetime(&'input str),            // includes the `'`
+    StringLiteral(&'input str),       // excludes the `"`
+    TripleStringLiteral(&'input str), // excludes the `"""`, has no escapes
+    CharLiteral(&'input str),         // excludes the `'`
+    RegexLiteral(&'input str),        // excludes the `r"` and `"`
+    Integer(&'input str),             // always fits in a `usize`
+    DocName(&'input str),             // `@x`, excludes the `@`
 
     // Symbols:
     Ampersand,
@@ -79,6 +91,8 @@ pub enum Tok<'input> {
     EqualsGreaterThanQuestionCode(&'input str),
     EqualsGreaterThanLookahead,
     EqualsGreaterThanLookbehind,
+    EqualsGreaterThanFn,         // => fn
+    EqualsGreaterThanQuestionFn, // =>? fn
     Hash,
     GreaterThan,
     LeftBrace,
@@ -98,7 +112,10 @@ pub enum Tok<'input> {
     TildeTilde,
     Underscore,
     Bang,
+    Caret,
+    Pipe,
     ShebangAttribute(&'input str), // #![...]
+    IfCode(&'input str),           // if { expr }, excludes the braces
 
     // Dummy tokens for parser sharing
     StartGrammar,
@@ -123,6 +140,7 @@ const KEYWORDS: &[(&str, Tok<'static>)] = &[
     ("enum", Enum),
     ("extern", Extern),
     ("grammar", Grammar),
+    ("import", Import),
     ("match", Match),
     ("else", Else),
     ("if", If),
@@ -135,6 +153,15 @@ const KEYWORDS: &[(&str, Tok<'static>)] = &[
     ("dyn", Dyn),
 ];
 
+const DIRECTIVES: &[(&str, Tok<'static>)] = &[
+    ("left", PercentLeft),
+    ("right", PercentRight),
+    ("nonassoc", PercentNonassoc),
+    ("type", PercentType),
+    ("left_recursive", PercentLeftRecursive),
+    ("start", PercentStart),
+];
+
 // Helper for backtracking.
 macro_rules! first {
     ($this:expr, $action:expr, $fallback:expr) => {{
@@ -291,14 +318,11 @@ impl<'input> Tokenizer<'input> {
                     Some(Ok((idx0, LessThan, idx0 + 1)))
                 }
                 Some((idx0, '@')) => match self.bump() {
-                    Some((idx1, 'L')) => {
-                        self.bump();
-                        Some(Ok((idx0, Lookahead, idx1 + 1)))
-                    }
-                    Some((idx1, 'R')) => {
-                        self.bump();
-                        Some(Ok((idx0, Lookbehind, idx1 + 1)))
-                    }
+                    Some((idx1, c)) if is_identifier_start(c) => Some(self.at_word(idx0, idx1)),
+                    _ => Some(error(UnrecognizedToken, idx0)),
+                },
+                Some((idx0, '%')) => match self.bump() {
+                    Some((idx1, c)) if is_identifier_start(c) => Some(self.directive(idx0, idx1)),
                     _ => Some(error(UnrecognizedToken, idx0)),
                 },
                 Some((idx0, '+')) => {
@@ -309,6 +333,14 @@ impl<'input> Tokenizer<'input> {
                     self.bump();
                     Some(Ok((idx0, Question, idx0 + 1)))
                 }
+                Some((idx0, '^')) => {
+                    self.bump();
+                    Some(Ok((idx0, Caret, idx0 + 1)))
+                }
+                Some((idx0, '|')) => {
+                    self.bump();
+                    Some(Ok((idx0, Pipe, idx0 + 1)))
+                }
                 Some((idx0, '}')) => {
                     self.bump();
                     Some(Ok((idx0, RightBrace, idx0 + 1)))
@@ -379,10 +411,23 @@ impl<'input> Tokenizer<'input> {
                         Some(self.identifierish(idx0))
                     }
                 }
+                Some((idx0, c)) if c.is_ascii_digit() => Some(self.integer(idx0)),
                 Some((_, c)) if c.is_whitespace() => {
                     self.bump();
                     continue;
                 }
+                Some((idx0, '\\')) => {
+                    // a `\` at the end of a line continues it, and is
+                    // read as a space
+                    self.bump();
+                    while let Some((_, ' ' | '\t' | '\r')) = self.lookahead {
+                        self.bump();
+                    }
+                    match self.lookahead {
+                        Some((_, '\n')) => continue,
+                        _ => Some(error(UnrecognizedToken, idx0)),
+                    }
+                }
                 Some((idx, _)) => Some(error(UnrecognizedToken, idx)),
                 None => None,
             };
@@ -399,6 +444,8 @@ impl<'input> Tokenizer<'input> {
         //
         // => code
         // =>? code
+        // => fn path
+        // =>? fn path
         // =>@L
         // =>@R
 
@@ -417,12 +464,18 @@ impl<'input> Tokenizer<'input> {
 
             Some((idx1, '?')) => {
                 self.bump();
+                if let Some(idx2) = self.fn_keyword() {
+                    return Ok((idx0, EqualsGreaterThanQuestionFn, idx2));
+                }
                 let idx2 = self.code(idx0, "([{", "}])")?;
                 let code = &self.text[idx1 + 1..idx2];
                 Ok((idx0, EqualsGreaterThanQuestionCode(code), idx2))
             }
 
             Some((idx1, _)) => {
+                if let Some(idx2) = self.fn_keyword() {
+                    return Ok((idx0, EqualsGreaterThanFn, idx2));
+                }
                 let idx2 = self.code(idx0, "([{", "}])")?;
                 let code = &self.text[idx1..idx2];
                 Ok((idx0, EqualsGreaterThanCode(code), idx2))
@@ -432,6 +485,32 @@ impl<'input> Tokenizer<'input> {
         }
     }
 
+    /// Consumes the `fn` of `=> fn path`, returning the index after it,
+    /// if that is what follows the arrow. Code that merely starts with
+    /// `fn`, like `=> fn foo() {}`, is still code.
+    fn fn_keyword(&mut self) -> Option<usize> {
+        let (idx0, _) = self.lookahead?;
+        let rest = &self.text[idx0..];
+        let trimmed = rest.trim_start();
+        let path = match trimmed.strip_prefix("fn") {
+            Some(path) if path.starts_with(char::is_whitespace) => path.trim_start(),
+            _ => return None,
+        };
+        let after_path = path
+            .trim_start_matches(|c: char| is_identifier_continue(c) || c == ':')
+            .trim_start();
+        if after_path.len() == path.len()
+            || !after_path.is_empty() && !after_path.starts_with([',', ';', '}', ']', ')'])
+        {
+            return None;
+        }
+        let idx1 = idx0 + (rest.len() - trimmed.len()) + 2;
+        while self.lookahead.is_some_and(|(idx, _)| idx < idx1) {
+            self.bump();
+        }
+        Some(idx1)
+    }
+
     fn code(&mut self, idx0: usize, open_delims: &str, close_delims: &str) -> Result<usize, Error> {
         // This is the interesting case. To find the end of the code,
         // we have to scan ahead, matching (), [], and {}, and looking
@@ -558,12 +637,46 @@ impl<'input> Tokenizer<'input> {
     }
 
     fn string_literal(&mut self, idx0: usize) -> Result<Spanned<Tok<'input>>, Error> {
+        if self.text[idx0..].starts_with(r#"""""#) {
+            return self.triple_string_literal(idx0);
+        }
         match self.string_or_char_literal(idx0, '"', StringLiteral) {
             Some(x) => Ok(x),
             None => error(UnterminatedStringLiteral, idx0),
         }
     }
 
+    // parses `"""..."""`, starts after the first `"` has been consumed;
+    // idx0 points at it
+    fn triple_string_literal(&mut self, idx0: usize) -> Result<Spanned<Tok<'input>>, Error> {
+        self.bump();
+        self.bump();
+        let mut quotes = 0;
+        let end_of_string = |c: char| {
+            if c == '"' {
+                quotes += 1;
+            } else {
+                quotes = 0;
+            }
+            quotes == 3
+        };
+        match self.take_until(end_of_string) {
+            Some(mut idx1) => {
+                // like in Kotlin, a run of more than three quotes ends
+                // with the last three, so the text can end with a quote
+                self.bump();
+                while let Some((idx2, '"')) = self.lookahead {
+                    idx1 = idx2;
+                    self.bump();
+                }
+                // idx1 is the last closing quote
+                let text = &self.text[idx0 + 3..idx1 - 2];
+                Ok((idx0, TripleStringLiteral(text), idx1 + 1))
+            }
+            None => error(UnterminatedStringLiteral, idx0),
+        }
+    }
+
     // parses `r#"..."#` (for some number of #), starts after the `r`
     // has been consumed; idx0 points at the `r`
     fn regex_literal(&mut self, idx0: usize) -> Result<Spanned<Tok<'input>>, Error> {
@@ -653,6 +766,24 @@ impl<'input> Tokenizer<'input> {
             return Ok((start, Tok::Use(code), code_end));
         }
 
+        if word == "if" {
+            // `if { expr }` is the guard of `#[if { expr }]`, while a
+            // macro condition is `if X == "Foo"`
+            while self.lookahead.is_some_and(|(_, c)| c.is_whitespace()) {
+                self.bump();
+            }
+            if let Some((idx1, '{')) = self.lookahead {
+                self.bump();
+                let code_end = self.code(idx1, "([{", "}])")?;
+                if !matches!(self.lookahead, Some((_, '}'))) {
+                    return error(UnterminatedCode, idx1);
+                }
+                self.bump();
+                let code = self.text[idx1 + 1..code_end].trim();
+                return Ok((start, Tok::IfCode(code), code_end + 1));
+            }
+        }
+
         let tok =
             // search for a keyword first; if none are found, this is
             // either a MacroId or an Id, depending on whether there
@@ -671,6 +802,36 @@ impl<'input> Tokenizer<'input> {
         Ok((start, tok, end))
     }
 
+    fn directive(&mut self, idx0: usize, idx1: usize) -> Result<Spanned<Tok<'input>>, Error> {
+        let (_, word, end) = self.word(idx1);
+        match DIRECTIVES.iter().find(|&&(w, _)| w == word) {
+            Some((_, tok)) => Ok((idx0, tok.clone(), end)),
+            None => error(UnrecognizedToken, idx0),
+        }
+    }
+
+    /// `@L`, `@R`, or the `@x` of a documentation name `@x:X`.
+    fn at_word(&mut self, idx0: usize, idx1: usize) -> Result<Spanned<Tok<'input>>, Error> {
+        let (_, word, end) = self.word(idx1);
+        let tok = match word {
+            "L" => Lookahead,
+            "R" => Lookbehind,
+            _ => DocName(word),
+        };
+        Ok((idx0, tok, end))
+    }
+
+    fn integer(&mut self, idx0: usize) -> Result<Spanned<Tok<'input>>, Error> {
+        let end = self
+            .take_while(|c| c.is_ascii_digit())
+            .unwrap_or(self.text.len());
+        let text = &self.text[idx0..end];
+        match text.parse::<usize>() {
+            Ok(_) => Ok((idx0, Integer(text), end)),
+            Err(_) => error(UnrecognizedToken, idx0),
+        }
+    }
+
     fn word(&mut self, idx0: usize) -> Spanned<&'input str> {
         match self.take_while(is_identifier_continue) {
             Some(end) => (idx0, &self.text[idx0..end], end),
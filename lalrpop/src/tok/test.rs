@@ -58,6 +58,21 @@ fn basic() {
     );
 }
 
+#[test]
+fn unicode_identifiers() {
+    // the spans count bytes, and `ö`, `ß`, `É`, `é` and `ñ` take two
+    test(
+        "Größe<Élément> ñ",
+        vec![
+            ("~~~~~~~              ", MacroId("Größe")),
+            ("       ~             ", LessThan),
+            ("        ~~~~~~~~~    ", Id("Élément")),
+            ("                 ~   ", GreaterThan),
+            ("                   ~~", Id("ñ")),
+        ],
+    );
+}
+
 #[test]
 fn eol_comment() {
     test(
@@ -69,6 +84,107 @@ fn eol_comment() {
     );
 }
 
+#[test]
+fn line_continuation() {
+    test(
+        r#"A "+" \  $  B"#,
+        vec![
+            (r#"~            "#, Id("A")),
+            (r#"  ~~~        "#, StringLiteral("+")),
+            (r#"            ~"#, Id("B")),
+        ],
+    );
+}
+
+#[test]
+fn backslash_not_at_end_of_line() {
+    test_err(r#"\ "#, (r#"~ "#, ErrorCode::UnrecognizedToken));
+}
+
+#[test]
+fn doc_names() {
+    test(
+        r#"@x:Expr @L @Left"#,
+        vec![
+            (r#"~~              "#, DocName("x")),
+            (r#"  ~             "#, Colon),
+            (r#"   ~~~~         "#, Id("Expr")),
+            (r#"        ~~      "#, Lookahead),
+            (r#"           ~~~~~"#, DocName("Left")),
+        ],
+    );
+}
+
+#[test]
+fn doc_names_starting_with_location() {
+    // only a bare `@L`/`@R` is a location; any longer name is a doc name
+    test(
+        r#"@Lfoo @R @Rfoo"#,
+        vec![
+            (r#"~~~~~         "#, DocName("Lfoo")),
+            (r#"      ~~      "#, Lookbehind),
+            (r#"         ~~~~~"#, DocName("Rfoo")),
+        ],
+    );
+}
+
+#[test]
+fn precedence_declarations() {
+    test(
+        r#"%left "+"; %right %nonassoc"#,
+        vec![
+            (r#"~~~~~                      "#, PercentLeft),
+            (r#"      ~~~                  "#, StringLiteral("+")),
+            (r#"         ~                 "#, Semi),
+            (r#"           ~~~~~~          "#, PercentRight),
+            (r#"                  ~~~~~~~~~"#, PercentNonassoc),
+        ],
+    );
+}
+
+#[test]
+fn type_declaration() {
+    test(
+        r#"%type<Expr> X;"#,
+        vec![
+            (r#"~~~~~         "#, PercentType),
+            (r#"     ~        "#, LessThan),
+            (r#"      ~~~~    "#, Id("Expr")),
+            (r#"          ~   "#, GreaterThan),
+            (r#"            ~ "#, Id("X")),
+            (r#"             ~"#, Semi),
+        ],
+    );
+}
+
+#[test]
+fn repeat_count() {
+    test(
+        r#"X{2,10}"#,
+        vec![
+            (r#"~      "#, Id("X")),
+            (r#" ~     "#, LeftBrace),
+            (r#"  ~    "#, Integer("2")),
+            (r#"   ~   "#, Comma),
+            (r#"    ~~ "#, Integer("10")),
+            (r#"      ~"#, RightBrace),
+        ],
+    );
+}
+
+#[test]
+fn integer_overflow() {
+    test_err(
+        "99999999999999999999999",
+        ("~                      ", ErrorCode::UnrecognizedToken),
+    );
+}
+
+#[test]
+fn unknown_precedence_declaration() {
+    test_err("%lefty", ("~     ", ErrorCode::UnrecognizedToken));
+}
+
 #[test]
 fn code1() {
     test(
@@ -80,6 +196,30 @@ fn code1() {
     );
 }
 
+#[test]
+fn external_fn_action() {
+    test(
+        "=> fn a::b, =>? fn c; => fnord",
+        vec![
+            ("~~~~~                         ", EqualsGreaterThanFn),
+            ("      ~                       ", Id("a")),
+            ("       ~~                     ", ColonColon),
+            ("         ~                    ", Id("b")),
+            ("          ~                   ", Comma),
+            (
+                "            ~~~~~~            ",
+                EqualsGreaterThanQuestionFn,
+            ),
+            ("                   ~          ", Id("c")),
+            ("                    ~         ", Semi),
+            (
+                "                      ~~~~~~~~",
+                EqualsGreaterThanCode(" fnord"),
+            ),
+        ],
+    );
+}
+
 #[test]
 fn rule_id_then_equalsgreaterthancode_functioncall() {
     test(
@@ -578,6 +718,26 @@ fn use2() {
     );
 }
 
+#[test]
+fn if_code() {
+    test(
+        r#"#[if { f(a, "}") }] if X"#,
+        vec![
+            (r#"~                       "#, Hash),
+            (r#" ~                      "#, LeftBracket),
+            (r#"  ~~~~~~~~~~~~~~~~      "#, IfCode(r#"f(a, "}")"#)),
+            (r#"                  ~     "#, RightBracket),
+            (r#"                    ~~  "#, If),
+            (r#"                       ~"#, Id("X")),
+        ],
+    );
+}
+
+#[test]
+fn if_code_error_unterminated() {
+    test_err(r#"if { (a"#, (r#"   ~   "#, ErrorCode::UnterminatedCode))
+}
+
 #[test]
 fn where1() {
     test(
@@ -685,6 +845,30 @@ fn regex2() {
     test(r#"r"(123""#, vec![(r#"~~~~~~~"#, RegexLiteral(r"(123"))]);
 }
 
+#[test]
+fn triple_string_literals() {
+    test(
+        r#""""a "b" \n""" "" """""" x"#,
+        vec![
+            (
+                r#"~~~~~~~~~~~~~~            "#,
+                TripleStringLiteral(r#"a "b" \n"#),
+            ),
+            (r#"               ~~         "#, StringLiteral("")),
+            (r#"                  ~~~~~~  "#, TripleStringLiteral("")),
+            (r#"                         ~"#, Id("x")),
+        ],
+    );
+}
+
+#[test]
+fn triple_string_literal_unterminated() {
+    test_err(
+        r#"""" "x" "#,
+        ("~       ", ErrorCode::UnterminatedStringLiteral),
+    );
+}
+
 #[test]
 fn char_literals() {
     test(
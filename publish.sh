@@ -38,6 +38,7 @@ function publish {
 publish lalrpop-util
 sleep 25 # Wait for lalrpop-util to be available on crates.io
 publish lalrpop
+publish lalrpop-procmacro
 
 git tag $VERSION
 git push origin tag $VERSION
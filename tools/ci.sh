@@ -12,3 +12,6 @@ cargo test --workspace
 cargo check -p calculator
 cargo check -p pascal
 cargo check -p whitespace
+# `lalrpop-procmacro` is not part of the workspace
+cargo test --manifest-path lalrpop-procmacro/Cargo.toml
+cargo test --manifest-path lalrpop-procmacro-test/Cargo.toml